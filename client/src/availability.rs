@@ -0,0 +1,420 @@
+//! Typed access to the `availability` API module.
+
+use anyhow::Context;
+use espresso_types::{
+    Header, NamespaceId, NamespaceProofQueryData, NamespaceStreamEntry, SeqTypes,
+    TransactionProofQueryData,
+};
+use futures::stream::BoxStream;
+use hotshot_query_service::availability::{
+    BlockHash, BlockQueryData, BlockSummaryQueryData, LeafHash, LeafQueryData, Limits,
+    PayloadQueryData, StateCertQueryData, TransactionHash, TransactionQueryData,
+    VidCommonQueryData,
+};
+use hotshot_types::data::VidCommitment;
+use surf_disco::{
+    error::ClientError,
+    socket::{Connection, Unsupported},
+};
+use vid::avid_m::proofs::NsAvidMBadEncodingProof;
+
+use crate::{SequencerApiVersion, SequencerClient};
+
+impl SequencerClient {
+    /// Get the leaf at the given height.
+    pub async fn get_leaf(&self, height: u64) -> anyhow::Result<LeafQueryData<SeqTypes>> {
+        self.get(&format!("availability/leaf/{height}"))
+            .await
+            .with_context(|| format!("getting leaf {height}"))
+    }
+
+    /// Get the leaf with the given hash.
+    pub async fn get_leaf_by_hash(
+        &self,
+        hash: LeafHash<SeqTypes>,
+    ) -> anyhow::Result<LeafQueryData<SeqTypes>> {
+        self.get(&format!("availability/leaf/hash/{hash}"))
+            .await
+            .with_context(|| format!("getting leaf {hash}"))
+    }
+
+    /// Get the leaves in the range `[from, until)`.
+    ///
+    /// The size of the range is limited by the server (see
+    /// [`get_availability_limits`](Self::get_availability_limits)).
+    pub async fn get_leaf_range(
+        &self,
+        from: u64,
+        until: u64,
+    ) -> anyhow::Result<Vec<LeafQueryData<SeqTypes>>> {
+        self.get(&format!("availability/leaf/{from}/{until}"))
+            .await
+            .with_context(|| format!("getting leaves {from}..{until}"))
+    }
+
+    /// Stream leaves, starting at the given height, loading them a page at a time.
+    ///
+    /// Unlike [`subscribe_leaves`](Self::subscribe_leaves), the stream ends once it catches up with
+    /// the chain. It fails if it reaches leaves which the server has pruned.
+    pub fn paginate_leaves(
+        &self,
+        from: u64,
+    ) -> BoxStream<'static, anyhow::Result<LeafQueryData<SeqTypes>>> {
+        self.paginate(format!("availability/page/leaf/from/{from}"), |cursor| {
+            format!("availability/page/leaf/{cursor}")
+        })
+    }
+
+    /// Subscribe to a stream of leaves, starting at the given height.
+    pub async fn subscribe_leaves(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<BoxStream<'static, Result<LeafQueryData<SeqTypes>, ClientError>>> {
+        self.subscribe(&format!("availability/stream/leaves/{height}"))
+            .await
+            .context("subscribing to Espresso leaves")
+    }
+
+    /// Get the header at the given height.
+    pub async fn get_header(&self, height: u64) -> anyhow::Result<Header> {
+        self.get(&format!("availability/header/{height}"))
+            .await
+            .with_context(|| format!("getting header {height}"))
+    }
+
+    /// Get the header with the given hash.
+    pub async fn get_header_by_hash(&self, hash: BlockHash<SeqTypes>) -> anyhow::Result<Header> {
+        self.get(&format!("availability/header/hash/{hash}"))
+            .await
+            .with_context(|| format!("getting header {hash}"))
+    }
+
+    /// Get the header of the block with the given payload commitment.
+    pub async fn get_header_by_payload_hash(
+        &self,
+        payload_hash: VidCommitment,
+    ) -> anyhow::Result<Header> {
+        self.get(&format!("availability/header/payload-hash/{payload_hash}"))
+            .await
+            .with_context(|| format!("getting header with payload {payload_hash}"))
+    }
+
+    /// Get the first header with a timestamp at or after `timestamp`, in seconds.
+    pub async fn get_header_by_timestamp(&self, timestamp: u64) -> anyhow::Result<Header> {
+        self.get(&format!("availability/header/timestamp/{timestamp}"))
+            .await
+            .with_context(|| format!("getting header at timestamp {timestamp}"))
+    }
+
+    /// Get the first header whose L1 head is at or after L1 block `number`.
+    pub async fn get_header_by_l1_block(&self, number: u64) -> anyhow::Result<Header> {
+        self.get(&format!("availability/header/l1-block/{number}"))
+            .await
+            .with_context(|| format!("getting header at L1 block {number}"))
+    }
+
+    /// Get the headers in the range `[from, until)`.
+    pub async fn get_header_range(&self, from: u64, until: u64) -> anyhow::Result<Vec<Header>> {
+        self.get(&format!("availability/header/{from}/{until}"))
+            .await
+            .with_context(|| format!("getting headers {from}..{until}"))
+    }
+
+    /// Stream headers, starting at the given height, loading them a page at a time.
+    ///
+    /// The stream ends once it catches up with the chain.
+    pub fn paginate_headers(&self, from: u64) -> BoxStream<'static, anyhow::Result<Header>> {
+        self.paginate(format!("availability/page/header/from/{from}"), |cursor| {
+            format!("availability/page/header/{cursor}")
+        })
+    }
+
+    /// Subscribe to a stream of Block Headers
+    pub async fn subscribe_headers(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<BoxStream<'static, Result<Header, ClientError>>> {
+        self.subscribe(&format!("availability/stream/headers/{height}"))
+            .await
+            .context("subscribing to Espresso headers")
+    }
+
+    /// Get the block at the given height.
+    pub async fn get_block(&self, height: u64) -> anyhow::Result<BlockQueryData<SeqTypes>> {
+        self.get(&format!("availability/block/{height}"))
+            .await
+            .with_context(|| format!("getting block {height}"))
+    }
+
+    /// Get the block with the given hash.
+    pub async fn get_block_by_hash(
+        &self,
+        hash: BlockHash<SeqTypes>,
+    ) -> anyhow::Result<BlockQueryData<SeqTypes>> {
+        self.get(&format!("availability/block/hash/{hash}"))
+            .await
+            .with_context(|| format!("getting block {hash}"))
+    }
+
+    /// Get the blocks in the range `[from, until)`.
+    pub async fn get_block_range(
+        &self,
+        from: u64,
+        until: u64,
+    ) -> anyhow::Result<Vec<BlockQueryData<SeqTypes>>> {
+        self.get(&format!("availability/block/{from}/{until}"))
+            .await
+            .with_context(|| format!("getting blocks {from}..{until}"))
+    }
+
+    /// Stream blocks, starting at the given height, loading them a page at a time.
+    ///
+    /// The stream ends once it catches up with the chain.
+    pub fn paginate_blocks(
+        &self,
+        from: u64,
+    ) -> BoxStream<'static, anyhow::Result<BlockQueryData<SeqTypes>>> {
+        self.paginate(format!("availability/page/block/from/{from}"), |cursor| {
+            format!("availability/page/block/{cursor}")
+        })
+    }
+
+    /// Subscribe to a stream of Block Headers
+    pub async fn subscribe_blocks(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<Connection<Header, Unsupported, ClientError, SequencerApiVersion>> {
+        self.inner
+            .socket(&format!("availability/stream/blocks/{height}"))
+            .subscribe()
+            .await
+            .context("subscribing to Espresso Blocks")
+    }
+
+    /// Subscribe to a stream of blocks, starting at the given height.
+    ///
+    /// Unlike [`subscribe_blocks`](Self::subscribe_blocks), this retries transient failures to
+    /// connect, and yields full blocks rather than headers.
+    pub async fn subscribe_block_data(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<BoxStream<'static, Result<BlockQueryData<SeqTypes>, ClientError>>> {
+        self.subscribe(&format!("availability/stream/blocks/{height}"))
+            .await
+            .context("subscribing to Espresso Blocks")
+    }
+
+    /// Get the payload of the block at the given height.
+    pub async fn get_payload(&self, height: u64) -> anyhow::Result<PayloadQueryData<SeqTypes>> {
+        self.get(&format!("availability/payload/{height}"))
+            .await
+            .with_context(|| format!("getting payload {height}"))
+    }
+
+    /// Get the payloads of the blocks in the range `[from, until)`.
+    pub async fn get_payload_range(
+        &self,
+        from: u64,
+        until: u64,
+    ) -> anyhow::Result<Vec<PayloadQueryData<SeqTypes>>> {
+        self.get(&format!("availability/payload/{from}/{until}"))
+            .await
+            .with_context(|| format!("getting payloads {from}..{until}"))
+    }
+
+    /// Stream payloads, starting at the given height, loading them a page at a time.
+    ///
+    /// The stream ends once it catches up with the chain.
+    pub fn paginate_payloads(
+        &self,
+        from: u64,
+    ) -> BoxStream<'static, anyhow::Result<PayloadQueryData<SeqTypes>>> {
+        self.paginate(format!("availability/page/payload/from/{from}"), |cursor| {
+            format!("availability/page/payload/{cursor}")
+        })
+    }
+
+    /// Subscribe to a stream of payloads, starting at the given height.
+    pub async fn subscribe_payloads(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<BoxStream<'static, Result<PayloadQueryData<SeqTypes>, ClientError>>> {
+        self.subscribe(&format!("availability/stream/payloads/{height}"))
+            .await
+            .context("subscribing to Espresso payloads")
+    }
+
+    /// Get the VID common data for the block at the given height.
+    pub async fn get_vid_common(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<VidCommonQueryData<SeqTypes>> {
+        self.get(&format!("availability/vid/common/{height}"))
+            .await
+            .with_context(|| format!("getting VID common {height}"))
+    }
+
+    /// Stream VID common data, starting at the given height, loading it a page at a time.
+    ///
+    /// The stream ends once it catches up with the chain.
+    pub fn paginate_vid_common(
+        &self,
+        from: u64,
+    ) -> BoxStream<'static, anyhow::Result<VidCommonQueryData<SeqTypes>>> {
+        self.paginate(
+            format!("availability/page/vid/common/from/{from}"),
+            |cursor| format!("availability/page/vid/common/{cursor}"),
+        )
+    }
+
+    /// Subscribe to a stream of VID common data, starting at the given height.
+    pub async fn subscribe_vid_common(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<BoxStream<'static, Result<VidCommonQueryData<SeqTypes>, ClientError>>> {
+        self.subscribe(&format!("availability/stream/vid/common/{height}"))
+            .await
+            .context("subscribing to Espresso VID common data")
+    }
+
+    /// Get the `index`-th transaction in the block at the given height.
+    pub async fn get_transaction(
+        &self,
+        height: u64,
+        index: u64,
+    ) -> anyhow::Result<TransactionQueryData<SeqTypes>> {
+        self.get(&format!("availability/transaction/{height}/{index}"))
+            .await
+            .with_context(|| format!("getting transaction {index} in block {height}"))
+    }
+
+    /// Get the transaction with the given hash.
+    pub async fn get_transaction_by_hash(
+        &self,
+        hash: TransactionHash<SeqTypes>,
+    ) -> anyhow::Result<TransactionQueryData<SeqTypes>> {
+        self.get(&format!("availability/transaction/hash/{hash}"))
+            .await
+            .with_context(|| format!("getting transaction {hash}"))
+    }
+
+    /// Subscribe to a stream of transactions, starting at the given height.
+    ///
+    /// If `namespace` is provided, only transactions in that namespace are included.
+    pub async fn subscribe_transactions(
+        &self,
+        height: u64,
+        namespace: Option<NamespaceId>,
+    ) -> anyhow::Result<BoxStream<'static, Result<TransactionQueryData<SeqTypes>, ClientError>>>
+    {
+        let path = match namespace {
+            Some(ns) => format!("availability/stream/transactions/{height}/namespace/{ns}"),
+            None => format!("availability/stream/transactions/{height}"),
+        };
+        self.subscribe(&path)
+            .await
+            .context("subscribing to Espresso transactions")
+    }
+
+    /// Get a summary of the block at the given height.
+    pub async fn get_block_summary(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<BlockSummaryQueryData<SeqTypes>> {
+        self.get(&format!("availability/block/summary/{height}"))
+            .await
+            .with_context(|| format!("getting block summary {height}"))
+    }
+
+    /// Get summaries of the blocks in the range `[from, until)`.
+    pub async fn get_block_summary_range(
+        &self,
+        from: u64,
+        until: u64,
+    ) -> anyhow::Result<Vec<BlockSummaryQueryData<SeqTypes>>> {
+        self.get(&format!("availability/block/summaries/{from}/{until}"))
+            .await
+            .with_context(|| format!("getting block summaries {from}..{until}"))
+    }
+
+    /// Stream block summaries, starting at the given height, loading them a page at a time.
+    ///
+    /// The stream ends once it catches up with the chain.
+    pub fn paginate_block_summaries(
+        &self,
+        from: u64,
+    ) -> BoxStream<'static, anyhow::Result<BlockSummaryQueryData<SeqTypes>>> {
+        self.paginate(
+            format!("availability/page/block/summaries/from/{from}"),
+            |cursor| format!("availability/page/block/summaries/{cursor}"),
+        )
+    }
+
+    /// Get the limits the server places on range queries in the availability API.
+    pub async fn get_availability_limits(&self) -> anyhow::Result<Limits> {
+        self.get("availability/limits")
+            .await
+            .context("getting availability limits")
+    }
+
+    /// Get the light client state update certificate for the given epoch.
+    pub async fn get_state_cert(&self, epoch: u64) -> anyhow::Result<StateCertQueryData<SeqTypes>> {
+        self.get(&format!("availability/state-cert/{epoch}"))
+            .await
+            .with_context(|| format!("getting state cert for epoch {epoch}"))
+    }
+
+    /// Get the transactions in the given namespace of a block, along with a namespace proof.
+    pub async fn get_namespace_proof(
+        &self,
+        height: u64,
+        namespace: NamespaceId,
+    ) -> anyhow::Result<NamespaceProofQueryData> {
+        self.get(&format!(
+            "availability/block/{height}/namespace/{namespace}"
+        ))
+        .await
+        .with_context(|| format!("getting namespace {namespace} proof for block {height}"))
+    }
+
+    /// Get the `index`th transaction in the block at the given height, along with an inclusion
+    /// proof.
+    ///
+    /// This only works for blocks dispersed with ADVZ. AVID-M blocks cannot prove a single
+    /// transaction; use [`get_namespace_proof`](Self::get_namespace_proof) for those.
+    pub async fn get_transaction_proof(
+        &self,
+        height: u64,
+        index: u64,
+    ) -> anyhow::Result<TransactionProofQueryData> {
+        self.get(&format!("availability/transaction/{height}/{index}/proof"))
+            .await
+            .with_context(|| format!("getting proof for transaction {index} in block {height}"))
+    }
+
+    /// Subscribe to the transactions in the given namespace, starting at the given height.
+    ///
+    /// The stream yields one entry per block, with a namespace proof and VID common data for each
+    /// block containing the namespace.
+    pub async fn subscribe_namespace(
+        &self,
+        namespace: NamespaceId,
+        height: u64,
+    ) -> anyhow::Result<BoxStream<'static, Result<NamespaceStreamEntry, ClientError>>> {
+        self.subscribe(&format!(
+            "availability/stream/namespace/{namespace}/{height}"
+        ))
+        .await
+        .with_context(|| format!("subscribing to namespace {namespace}"))
+    }
+
+    /// Get a proof that the block at the given height was incorrectly encoded.
+    pub async fn get_incorrect_encoding_proof(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<NsAvidMBadEncodingProof> {
+        self.get(&format!("availability/incorrect-encoding-proof/{height}"))
+            .await
+            .with_context(|| format!("getting incorrect encoding proof for block {height}"))
+    }
+}
@@ -0,0 +1,99 @@
+//! Typed access to the `catchup` API module.
+
+use alloy::primitives::Address;
+use anyhow::Context;
+use committable::Commitment;
+use espresso_types::{
+    v0_1::{RewardAccount, RewardAccountQueryData, RewardMerkleTree},
+    v0_3::ChainConfig,
+    AccountQueryData, FeeAccount, FeeMerkleTree, Leaf2,
+};
+
+use crate::{BlockMerkleProof, SequencerClient};
+
+impl SequencerClient {
+    /// Get the balance of a fee account, with proof, as of the given block height and view.
+    ///
+    /// `height` and `view` must correspond to the same decided leaf.
+    pub async fn get_catchup_account(
+        &self,
+        height: u64,
+        view: u64,
+        address: Address,
+    ) -> anyhow::Result<AccountQueryData> {
+        self.get(&format!("catchup/{height}/{view}/account/{address:#x}"))
+            .await
+            .with_context(|| format!("catching up fee account {address} at {height}/{view}"))
+    }
+
+    /// Get a fee Merkle tree containing paths for each of `accounts`, as of the given block height
+    /// and view.
+    pub async fn get_catchup_accounts(
+        &self,
+        height: u64,
+        view: u64,
+        accounts: &[FeeAccount],
+    ) -> anyhow::Result<FeeMerkleTree> {
+        self.post(&format!("catchup/{height}/{view}/accounts"), &accounts)
+            .await
+            .with_context(|| format!("catching up fee accounts at {height}/{view}"))
+    }
+
+    /// Get the balance of a reward account, with proof, as of the given block height and view.
+    pub async fn get_catchup_reward_account(
+        &self,
+        height: u64,
+        view: u64,
+        address: Address,
+    ) -> anyhow::Result<RewardAccountQueryData> {
+        self.get(&format!(
+            "catchup/{height}/{view}/reward-account/{address:#x}"
+        ))
+        .await
+        .with_context(|| format!("catching up reward account {address} at {height}/{view}"))
+    }
+
+    /// Get a reward Merkle tree containing paths for each of `accounts`, as of the given block
+    /// height and view.
+    pub async fn get_catchup_reward_accounts(
+        &self,
+        height: u64,
+        view: u64,
+        accounts: &[RewardAccount],
+    ) -> anyhow::Result<RewardMerkleTree> {
+        self.post(
+            &format!("catchup/{height}/{view}/reward-accounts"),
+            &accounts,
+        )
+        .await
+        .with_context(|| format!("catching up reward accounts at {height}/{view}"))
+    }
+
+    /// Get the frontier of the blocks Merkle tree as of the given block height and view.
+    pub async fn get_catchup_blocks_frontier(
+        &self,
+        height: u64,
+        view: u64,
+    ) -> anyhow::Result<BlockMerkleProof> {
+        self.get(&format!("catchup/{height}/{view}/blocks"))
+            .await
+            .with_context(|| format!("catching up blocks frontier at {height}/{view}"))
+    }
+
+    /// Get the chain config with the given commitment.
+    pub async fn get_chain_config(
+        &self,
+        commitment: Commitment<ChainConfig>,
+    ) -> anyhow::Result<ChainConfig> {
+        self.get(&format!("catchup/chain-config/{commitment}"))
+            .await
+            .with_context(|| format!("getting chain config {commitment}"))
+    }
+
+    /// Get a chain of leaves proving that the block at `height` was decided.
+    pub async fn get_leaf_chain(&self, height: u64) -> anyhow::Result<Vec<Leaf2>> {
+        self.get(&format!("catchup/{height}/leafchain"))
+            .await
+            .with_context(|| format!("getting leaf chain for {height}"))
+    }
+}
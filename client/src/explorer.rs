@@ -0,0 +1,164 @@
+//! Typed access to the `explorer` API module.
+
+use anyhow::Context;
+use espresso_types::SeqTypes;
+use futures::stream::BoxStream;
+use hotshot_query_service::{
+    availability::BlockHash,
+    explorer::{
+        BlockDetailResponse, BlockIdentifier, BlockSummary, BlockSummaryResponse,
+        ExplorerSummaryResponse, NamespaceCursor, NamespaceSummary, SearchResultResponse,
+        TransactionDetailResponse, TransactionIdentifier, TransactionSummariesResponse,
+        TransactionSummary, TransactionSummaryFilter,
+    },
+};
+use tagged_base64::TaggedBase64;
+
+use crate::SequencerClient;
+
+impl SequencerClient {
+    /// Get the explorer's detailed view of the block at the given height.
+    pub async fn get_explorer_block_detail(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<BlockDetailResponse<SeqTypes>> {
+        self.get(&format!("explorer/block/{height}"))
+            .await
+            .with_context(|| format!("getting explorer block detail {height}"))
+    }
+
+    /// Get the explorer's detailed view of the block with the given hash.
+    pub async fn get_explorer_block_detail_by_hash(
+        &self,
+        hash: BlockHash<SeqTypes>,
+    ) -> anyhow::Result<BlockDetailResponse<SeqTypes>> {
+        self.get(&format!("explorer/block/hash/{hash}"))
+            .await
+            .with_context(|| format!("getting explorer block detail {hash}"))
+    }
+
+    /// Get summaries of up to `limit` blocks, descending from `target`.
+    ///
+    /// Blocks may be identified by height or [`Latest`](BlockIdentifier::Latest).
+    pub async fn get_explorer_block_summaries(
+        &self,
+        target: BlockIdentifier<SeqTypes>,
+        limit: usize,
+    ) -> anyhow::Result<BlockSummaryResponse<SeqTypes>> {
+        let path = match &target {
+            BlockIdentifier::Latest => format!("explorer/blocks/latest/{limit}"),
+            BlockIdentifier::Height(height) => format!("explorer/blocks/{height}/{limit}"),
+            BlockIdentifier::Hash(_) => {
+                anyhow::bail!("block summaries cannot be queried by hash")
+            },
+        };
+        self.get(&path)
+            .await
+            .with_context(|| format!("getting explorer block summaries from {target}"))
+    }
+
+    /// Stream the explorer's summaries of every block, from the latest block back to genesis,
+    /// loading them a page at a time.
+    ///
+    /// The stream fails if it reaches blocks which the server has pruned.
+    pub fn paginate_explorer_block_summaries(
+        &self,
+    ) -> BoxStream<'static, anyhow::Result<BlockSummary<SeqTypes>>> {
+        self.paginate("explorer/page/blocks/latest".into(), |cursor| {
+            format!("explorer/page/blocks/{cursor}")
+        })
+    }
+
+    /// Get the explorer's detailed view of a transaction.
+    pub async fn get_explorer_transaction_detail(
+        &self,
+        target: TransactionIdentifier<SeqTypes>,
+    ) -> anyhow::Result<TransactionDetailResponse<SeqTypes>> {
+        let path = match &target {
+            TransactionIdentifier::Latest => {
+                anyhow::bail!("transaction detail must be queried by position or hash")
+            },
+            TransactionIdentifier::HeightAndOffset(height, offset) => {
+                format!("explorer/transaction/{height}/{offset}")
+            },
+            TransactionIdentifier::Hash(hash) => format!("explorer/transaction/hash/{hash}"),
+        };
+        self.get(&path)
+            .await
+            .with_context(|| format!("getting explorer transaction detail {target}"))
+    }
+
+    /// Get summaries of up to `limit` transactions, descending from `target` and matching
+    /// `filter`.
+    pub async fn get_explorer_transaction_summaries(
+        &self,
+        target: TransactionIdentifier<SeqTypes>,
+        limit: usize,
+        filter: TransactionSummaryFilter<SeqTypes>,
+    ) -> anyhow::Result<TransactionSummariesResponse<SeqTypes>> {
+        let mut path = match &target {
+            TransactionIdentifier::Latest => format!("explorer/transactions/latest/{limit}"),
+            TransactionIdentifier::HeightAndOffset(height, offset) => {
+                format!("explorer/transactions/from/{height}/{offset}/{limit}")
+            },
+            TransactionIdentifier::Hash(hash) => {
+                format!("explorer/transactions/hash/{hash}/{limit}")
+            },
+        };
+        match filter {
+            TransactionSummaryFilter::None => {},
+            TransactionSummaryFilter::Block(block) => path += &format!("/block/{block}"),
+            TransactionSummaryFilter::RollUp(ns) => path += &format!("/namespace/{ns}"),
+        }
+        self.get(&path)
+            .await
+            .with_context(|| format!("getting explorer transaction summaries from {target}"))
+    }
+
+    /// Stream the explorer's summaries of every transaction matching `filter`, from the latest
+    /// transaction back to genesis, loading them a page at a time.
+    ///
+    /// The stream fails if it reaches blocks which the server has pruned.
+    pub fn paginate_explorer_transaction_summaries(
+        &self,
+        filter: TransactionSummaryFilter<SeqTypes>,
+    ) -> BoxStream<'static, anyhow::Result<TransactionSummary<SeqTypes>>> {
+        let suffix = match filter {
+            TransactionSummaryFilter::None => String::new(),
+            TransactionSummaryFilter::Block(block) => format!("/block/{block}"),
+            TransactionSummaryFilter::RollUp(ns) => format!("/namespace/{ns}"),
+        };
+        self.paginate(
+            format!("explorer/page/transactions/latest{suffix}"),
+            move |cursor| format!("explorer/page/transactions/{cursor}{suffix}"),
+        )
+    }
+
+    /// Get the summary shown on the explorer's landing page.
+    pub async fn get_explorer_summary(&self) -> anyhow::Result<ExplorerSummaryResponse<SeqTypes>> {
+        self.get("explorer/explorer-summary")
+            .await
+            .context("getting explorer summary")
+    }
+
+    /// Stream the explorer's summaries of every namespace, from most to least recently seen,
+    /// loading them a page at a time.
+    pub fn paginate_explorer_namespace_summaries(
+        &self,
+    ) -> BoxStream<'static, anyhow::Result<NamespaceSummary<SeqTypes>>> {
+        self.paginate_with(
+            "explorer/page/namespaces/latest".into(),
+            |cursor: NamespaceCursor| format!("explorer/page/namespaces/{cursor}"),
+        )
+    }
+
+    /// Search for blocks and transactions matching `query`.
+    pub async fn explorer_search(
+        &self,
+        query: &TaggedBase64,
+    ) -> anyhow::Result<SearchResultResponse<SeqTypes>> {
+        self.get(&format!("explorer/search/{query}"))
+            .await
+            .with_context(|| format!("searching explorer for {query}"))
+    }
+}
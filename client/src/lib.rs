@@ -1,76 +1,241 @@
-use std::time::Duration;
+use std::{cmp::min, future::Future, sync::Arc, time::Duration};
 
 use alloy::primitives::Address;
 use anyhow::Context;
-use espresso_types::{FeeAccount, FeeAmount, FeeMerkleTree, Header};
-use futures::{stream::BoxStream, StreamExt};
+use espresso_types::{
+    v0_1::{RewardAccount, RewardAmount, RewardMerkleTree},
+    FeeAccount, FeeAmount, FeeMerkleTree,
+};
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt, TryStreamExt,
+};
+use hotshot_query_service::availability::{Cursor, Page};
 use jf_merkle_tree::{
     prelude::{MerkleProof, Sha3Node},
     MerkleTreeScheme,
 };
-use surf_disco::{
-    error::ClientError,
-    socket::{Connection, Unsupported},
-    Url,
-};
+use serde::{de::DeserializeOwned, Serialize};
+use surf_disco::{error::ClientError, Error as _, StatusCode, Url};
 use tokio::time::sleep;
 use vbs::version::StaticVersion;
 
+mod availability;
+mod catchup;
+mod explorer;
+mod node;
+mod state;
+mod submit;
+mod verify;
+
+pub use state::BlockMerkleProof;
+pub use submit::TransactionInclusion;
+pub use verify::VerifiedSequencerClient;
+
 pub type SequencerApiVersion = StaticVersion<0, 1>;
 
 #[derive(Clone, Debug)]
-pub struct SequencerClient(surf_disco::Client<ClientError, SequencerApiVersion>);
+pub struct SequencerClient {
+    inner: surf_disco::Client<ClientError, SequencerApiVersion>,
+    retry: RetryOptions,
+}
 
 pub type FeeMerkleProof = MerkleProof<FeeAmount, FeeAccount, Sha3Node, { FeeMerkleTree::ARITY }>;
+pub type RewardMerkleProof =
+    MerkleProof<RewardAmount, RewardAccount, Sha3Node, { RewardMerkleTree::ARITY }>;
+
+/// Retry and backoff settings shared by all requests made through a [`SequencerClient`].
+///
+/// Requests which fail with a transient error (a server error, a connection error, or a 404 for
+/// data which may not be available yet) are retried with exponential backoff until they succeed or
+/// `max_attempts` is reached. Requests which fail because they are malformed are never retried.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryOptions {
+    /// Maximum number of attempts for a single request, including the first one.
+    pub max_attempts: usize,
+    /// Delay before the first retry.
+    pub base_delay: Duration,
+    /// Upper bound on the delay between consecutive attempts.
+    pub max_delay: Duration,
+    /// Factor by which the delay grows after each failed attempt.
+    pub factor: u32,
+}
+
+impl Default for RetryOptions {
+    fn default() -> Self {
+        Self {
+            max_attempts: 10,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            factor: 2,
+        }
+    }
+}
+
+impl RetryOptions {
+    /// Fail after the first unsuccessful attempt.
+    pub fn disabled() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    fn backoff(&self, delay: Duration) -> Duration {
+        min(delay.saturating_mul(self.factor), self.max_delay)
+    }
+}
+
+/// Whether a failed request is worth trying again.
+fn is_transient(err: &ClientError) -> bool {
+    let status = err.status();
+    !status.is_client_error() || status == StatusCode::NOT_FOUND
+}
 
 impl SequencerClient {
     pub fn new(provider: Url) -> Self {
-        Self(surf_disco::Client::new(provider))
+        Self {
+            inner: surf_disco::Client::new(provider),
+            retry: RetryOptions::default(),
+        }
+    }
+
+    /// Use the given retry settings for all requests made by this client.
+    pub fn with_retry_options(mut self, retry: RetryOptions) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// The retry settings used by this client.
+    pub fn retry_options(&self) -> RetryOptions {
+        self.retry
+    }
+
+    /// Run `f` until it succeeds, fails with a permanent error, or we run out of attempts.
+    async fn retry<T, F, Fut>(&self, path: &str, f: F) -> Result<T, ClientError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, ClientError>>,
+    {
+        let mut delay = self.retry.base_delay;
+        let mut attempt = 1;
+        loop {
+            match f().await {
+                Ok(res) => return Ok(res),
+                Err(err) if attempt >= self.retry.max_attempts || !is_transient(&err) => {
+                    return Err(err)
+                },
+                Err(err) => {
+                    tracing::warn!(
+                        path,
+                        attempt,
+                        "request failed, will retry after {delay:?}: {err:#}"
+                    );
+                    sleep(delay).await;
+                    delay = self.retry.backoff(delay);
+                    attempt += 1;
+                },
+            }
+        }
+    }
+
+    /// GET a resource, retrying transient failures.
+    pub(crate) async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ClientError> {
+        self.retry(path, || self.inner.get::<T>(path).send()).await
+    }
+
+    /// POST a JSON body and read the response, retrying transient failures.
+    ///
+    /// This should only be used for routes which are idempotent.
+    pub(crate) async fn post<T: DeserializeOwned, B: Serialize>(
+        &self,
+        path: &str,
+        body: &B,
+    ) -> Result<T, ClientError> {
+        self.retry(path, || async {
+            self.inner.post::<T>(path).body_json(body)?.send().await
+        })
+        .await
+    }
+
+    /// Open a websocket subscription, retrying transient failures to connect.
+    pub(crate) async fn subscribe<T: DeserializeOwned + Send + 'static>(
+        &self,
+        path: &str,
+    ) -> Result<BoxStream<'static, Result<T, ClientError>>, ClientError> {
+        self.retry(path, || async {
+            Ok(self.inner.socket(path).subscribe::<T>().await?.boxed())
+        })
+        .await
+    }
+
+    /// Stream objects from a paginated route, following cursors from one page to the next.
+    ///
+    /// The first page is loaded from `first`, and each later page from the path which `next` builds
+    /// from its cursor. The stream ends after an empty page, or a page with no cursor for the next
+    /// one.
+    pub(crate) fn paginate<T>(
+        &self,
+        first: String,
+        next: impl Fn(Cursor) -> String + Send + Sync + 'static,
+    ) -> BoxStream<'static, anyhow::Result<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.paginate_with(first, next)
+    }
+
+    /// Stream objects from a paginated route whose pages use the cursor type `C`.
+    ///
+    /// This behaves like [`paginate`](Self::paginate), for routes which do not use [`Cursor`].
+    pub(crate) fn paginate_with<T, C>(
+        &self,
+        first: String,
+        next: impl Fn(C) -> String + Send + Sync + 'static,
+    ) -> BoxStream<'static, anyhow::Result<T>>
+    where
+        T: DeserializeOwned + Send + 'static,
+        C: DeserializeOwned + Send + 'static,
+    {
+        let client = self.clone();
+        let next = Arc::new(next);
+        stream::try_unfold(Some(first), move |path| {
+            let client = client.clone();
+            let next = next.clone();
+            async move {
+                let Some(path) = path else {
+                    return Ok(None);
+                };
+                let page: Page<T, C> = client
+                    .get(&path)
+                    .await
+                    .with_context(|| format!("getting page {path}"))?;
+                if page.items.is_empty() {
+                    return Ok(None);
+                }
+                let path = page.next.map(&*next);
+                let items = stream::iter(page.items.into_iter().map(Ok::<_, anyhow::Error>));
+                Ok(Some((items, path)))
+            }
+        })
+        .try_flatten()
+        .boxed()
     }
 
     /// GET Block Height from the node
     pub async fn get_height(&self) -> anyhow::Result<u64> {
-        self.0
-            .get::<u64>("node/block-height")
-            .send()
+        self.get::<u64>("node/block-height")
             .await
             .context("getting Espresso block height")
     }
 
     /// Get the Number of Transactions
     pub async fn get_transaction_count(&self) -> anyhow::Result<u64> {
-        self.0
-            .get::<u64>("node/transactions/count")
-            .send()
+        self.get::<u64>("node/transactions/count")
             .await
             .context("getting Espresso transaction count")
     }
 
-    /// Subscribe to a stream of Block Headers
-    pub async fn subscribe_headers(
-        &self,
-        height: u64,
-    ) -> anyhow::Result<BoxStream<'static, Result<Header, ClientError>>> {
-        self.0
-            .socket(&format!("availability/stream/headers/{height}"))
-            .subscribe::<Header>()
-            .await
-            .context("subscribing to Espresso headers")
-            .map(|s| s.boxed())
-    }
-
-    /// Subscribe to a stream of Block Headers
-    pub async fn subscribe_blocks(
-        &self,
-        height: u64,
-    ) -> anyhow::Result<Connection<Header, Unsupported, ClientError, SequencerApiVersion>> {
-        self.0
-            .socket(&format!("availability/stream/blocks/{height}"))
-            .subscribe()
-            .await
-            .context("subscribing to Espresso Blocks")
-    }
-
     /// Get the balance for a given account at a given block height, defaulting to current balance.
     pub async fn get_espresso_balance(
         &self,
@@ -88,35 +253,18 @@ impl SequencerClient {
         }
         // Block is non-zero, we can safely decrement to query the state as of the previous block.
         block -= 1;
-        // Download the Merkle path for this fee account at the specified block height. Transient errors
-        // are possible (for example, if we are fetching from the latest block, the block height might
-        // get incremented shortly before the state becomes available) so retry a few times.
-        let mut retry = 0;
-        let max_retries = 10;
-        let proof = loop {
-            tracing::debug!(%address, block, retry, "fetching Espresso balance");
-            match self
-                .0
-                .get::<FeeMerkleProof>(&format!("fee-state/{block}/{address:#x}"))
-                .send()
-                .await
-            {
-                Ok(proof) => break proof,
-                Err(err) => {
-                    tracing::warn!("error getting account balance: {err:#}");
-                    retry += 1;
-
-                    if retry == max_retries {
-                        return Err(err).context("getting account balance");
-                    } else {
-                        sleep(Duration::from_millis(200)).await;
-                    }
-                },
-            }
-        };
+        // Download the Merkle path for this fee account at the specified block height. Transient
+        // errors are possible (for example, if we are fetching from the latest block, the block
+        // height might get incremented shortly before the state becomes available) so the request
+        // is retried according to the client's retry settings.
+        tracing::debug!(%address, block, "fetching Espresso balance");
+        let proof = self
+            .get_fee_state_proof(block, address)
+            .await
+            .context("getting account balance")?;
 
-        // If the element in the Merkle path is missing -- there is no account with this address -- the
-        // balance is defined to be 0.
+        // If the element in the Merkle path is missing -- there is no account with this address --
+        // the balance is defined to be 0.
         let balance = proof.elem().copied().unwrap_or(0.into());
         Ok(balance)
     }
@@ -124,6 +272,8 @@ impl SequencerClient {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Instant;
+
     use super::*;
     // Regression test for a bug where the block number underflowed. This test would panic
     // on the previous implementation, as long as overflow checks are enabled.
@@ -138,4 +288,31 @@ mod tests {
             0.into()
         )
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_retry_gives_up() {
+        let retry = RetryOptions {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(150),
+            factor: 2,
+        };
+        let client = SequencerClient::new("http://dummy-url:3030".parse().unwrap())
+            .with_retry_options(retry);
+
+        let start = Instant::now();
+        client.get_height().await.unwrap_err();
+        // We should have waited between each pair of attempts, with the second delay capped.
+        assert!(start.elapsed() >= Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let retry = RetryOptions::default();
+        assert_eq!(
+            retry.backoff(Duration::from_millis(200)),
+            Duration::from_millis(400)
+        );
+        assert_eq!(retry.backoff(Duration::from_secs(4)), retry.max_delay);
+    }
 }
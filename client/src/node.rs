@@ -0,0 +1,160 @@
+//! Typed access to the `node` and `status` API modules.
+
+use std::ops::RangeInclusive;
+
+use anyhow::Context;
+use espresso_types::{
+    v0_1::RewardAmount, Header, NamespaceId, SeqTypes, StakeTableWithEpochNumber, ValidatorMap,
+};
+use futures::stream::BoxStream;
+use hotshot_query_service::node::{self, SyncStatus, TimeWindowQueryData};
+use hotshot_types::{data::VidShare, PeerConfig};
+
+use crate::SequencerClient;
+
+/// Build the path for one of the node API's aggregate routes (`transactions/count` or
+/// `payloads/size`), which share the same optional namespace and range suffixes.
+fn aggregate_path(
+    base: &str,
+    namespace: Option<NamespaceId>,
+    range: Option<RangeInclusive<u64>>,
+) -> String {
+    let mut path = format!("node/{base}");
+    if let Some(ns) = namespace {
+        path += &format!("/namespace/{ns}");
+    }
+    if let Some(range) = range {
+        path += &format!("/{}/{}", range.start(), range.end());
+    }
+    path
+}
+
+impl SequencerClient {
+    /// Count the transactions in the blocks in `range`, optionally restricted to one namespace.
+    ///
+    /// If `range` is `None`, all blocks known to the node are counted.
+    pub async fn count_transactions(
+        &self,
+        namespace: Option<NamespaceId>,
+        range: Option<RangeInclusive<u64>>,
+    ) -> anyhow::Result<u64> {
+        self.get(&aggregate_path("transactions/count", namespace, range))
+            .await
+            .context("counting Espresso transactions")
+    }
+
+    /// Get the total size in bytes of the payloads in `range`, optionally restricted to one
+    /// namespace.
+    ///
+    /// If `range` is `None`, all blocks known to the node are included.
+    pub async fn get_payload_size(
+        &self,
+        namespace: Option<NamespaceId>,
+        range: Option<RangeInclusive<u64>>,
+    ) -> anyhow::Result<u64> {
+        self.get(&aggregate_path("payloads/size", namespace, range))
+            .await
+            .context("getting Espresso payload size")
+    }
+
+    /// Get the node's own VID share for the block at the given height.
+    pub async fn get_vid_share(&self, height: u64) -> anyhow::Result<VidShare> {
+        self.get(&format!("node/vid/share/{height}"))
+            .await
+            .with_context(|| format!("getting VID share {height}"))
+    }
+
+    /// Get the node's progress in syncing with the latest state of the chain.
+    pub async fn get_sync_status(&self) -> anyhow::Result<SyncStatus> {
+        self.get("node/sync-status")
+            .await
+            .context("getting sync status")
+    }
+
+    /// Get the headers whose timestamps fall in the window `[start, end)`.
+    pub async fn get_header_window(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> anyhow::Result<TimeWindowQueryData<Header>> {
+        self.get(&format!("node/header/window/{start}/{end}"))
+            .await
+            .with_context(|| format!("getting header window {start}..{end}"))
+    }
+
+    /// Continue a header window query, starting from the block at `height`.
+    pub async fn get_header_window_from(
+        &self,
+        height: u64,
+        end: u64,
+    ) -> anyhow::Result<TimeWindowQueryData<Header>> {
+        self.get(&format!("node/header/window/from/{height}/{end}"))
+            .await
+            .with_context(|| format!("getting header window from {height} to {end}"))
+    }
+
+    /// Stream the headers whose timestamps fall in the window `[start, end)`, loading them a page
+    /// at a time.
+    ///
+    /// The stream ends once the window is complete, or once it catches up with the chain. It fails
+    /// if it reaches headers which the server has pruned.
+    pub fn paginate_header_window(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> BoxStream<'static, anyhow::Result<Header>> {
+        self.paginate(
+            format!("node/page/header/window/{start}/{end}"),
+            move |cursor| format!("node/page/header/window/from/{cursor}/{end}"),
+        )
+    }
+
+    /// Get the limits the server places on queries in the node API.
+    pub async fn get_node_limits(&self) -> anyhow::Result<node::Limits> {
+        self.get("node/limits").await.context("getting node limits")
+    }
+
+    /// Get the stake table for the given epoch.
+    pub async fn get_stake_table(&self, epoch: u64) -> anyhow::Result<Vec<PeerConfig<SeqTypes>>> {
+        self.get(&format!("node/stake-table/{epoch}"))
+            .await
+            .with_context(|| format!("getting stake table for epoch {epoch}"))
+    }
+
+    /// Get the stake table for the current epoch.
+    pub async fn get_stake_table_current(
+        &self,
+    ) -> anyhow::Result<StakeTableWithEpochNumber<SeqTypes>> {
+        self.get("node/stake-table/current")
+            .await
+            .context("getting current stake table")
+    }
+
+    /// Get the validators for the given epoch.
+    pub async fn get_validators(&self, epoch: u64) -> anyhow::Result<ValidatorMap> {
+        self.get(&format!("node/validators/{epoch}"))
+            .await
+            .with_context(|| format!("getting validators for epoch {epoch}"))
+    }
+
+    /// Get the reward paid out for each block.
+    pub async fn get_block_reward(&self) -> anyhow::Result<RewardAmount> {
+        self.get("node/block-reward")
+            .await
+            .context("getting block reward")
+    }
+
+    /// Get the fraction of views which successfully decided a block, as observed by this node.
+    pub async fn get_success_rate(&self) -> anyhow::Result<f64> {
+        self.get("status/success-rate")
+            .await
+            .context("getting success rate")
+    }
+
+    /// Get the time in seconds since the node last saw a decide.
+    pub async fn get_time_since_last_decide(&self) -> anyhow::Result<u64> {
+        self.get("status/time-since-last-decide")
+            .await
+            .context("getting time since last decide")
+    }
+}
@@ -0,0 +1,166 @@
+//! Typed access to the merklized state API modules (`fee-state`, `reward-state` and
+//! `block-state`).
+
+use std::ops::RangeInclusive;
+
+use alloy::primitives::Address;
+use anyhow::Context;
+use espresso_types::{
+    v0_1::RewardAmount, BalanceChange, BlockMerkleTree, FeeAmount, NamespaceFinalityProof,
+    NamespaceId,
+};
+use jf_merkle_tree::MerkleTreeScheme;
+
+use crate::{FeeMerkleProof, RewardMerkleProof, SequencerClient};
+
+/// A Merkle path from the block Merkle tree root to a single header commitment.
+pub type BlockMerkleProof = <BlockMerkleTree as MerkleTreeScheme>::MembershipProof;
+
+impl SequencerClient {
+    /// Get the Merkle path for a fee account in the fee state as of the given block height.
+    pub async fn get_fee_state_proof(
+        &self,
+        height: u64,
+        address: Address,
+    ) -> anyhow::Result<FeeMerkleProof> {
+        self.get(&format!("fee-state/{height}/{address:#x}"))
+            .await
+            .with_context(|| format!("getting fee state for {address} at height {height}"))
+    }
+
+    /// Get the latest block height for which the fee state is available.
+    pub async fn get_fee_state_height(&self) -> anyhow::Result<u64> {
+        self.get("fee-state/block-height")
+            .await
+            .context("getting fee state height")
+    }
+
+    /// Get the balance of a fee account in the latest fee state.
+    ///
+    /// Returns `None` if the account is not present in the state.
+    pub async fn get_latest_fee_balance(
+        &self,
+        address: Address,
+    ) -> anyhow::Result<Option<FeeAmount>> {
+        self.get(&format!("fee-state/fee-balance/latest/{address:#x}"))
+            .await
+            .with_context(|| format!("getting latest fee balance for {address}"))
+    }
+
+    /// Get every change to the balance of a fee account in blocks `range`, defaulting to the most
+    /// recent blocks for which the fee state is available.
+    pub async fn get_fee_balance_history(
+        &self,
+        address: Address,
+        range: Option<RangeInclusive<u64>>,
+    ) -> anyhow::Result<Vec<BalanceChange<FeeAmount>>> {
+        self.get(&balance_history_path("fee-state", address, range))
+            .await
+            .with_context(|| format!("getting fee balance history for {address}"))
+    }
+
+    /// Get the Merkle path for a reward account in the reward state as of the given block height.
+    pub async fn get_reward_state_proof(
+        &self,
+        height: u64,
+        address: Address,
+    ) -> anyhow::Result<RewardMerkleProof> {
+        self.get(&format!("reward-state/{height}/{address:#x}"))
+            .await
+            .with_context(|| format!("getting reward state for {address} at height {height}"))
+    }
+
+    /// Get the latest block height for which the reward state is available.
+    pub async fn get_reward_state_height(&self) -> anyhow::Result<u64> {
+        self.get("reward-state/block-height")
+            .await
+            .context("getting reward state height")
+    }
+
+    /// Get the balance of a reward account, either in the latest reward state or as of the given
+    /// block height.
+    ///
+    /// Returns `None` if the account is not present in the state.
+    pub async fn get_reward_balance(
+        &self,
+        address: Address,
+        height: Option<u64>,
+    ) -> anyhow::Result<Option<RewardAmount>> {
+        let path = match height {
+            Some(height) => format!("reward-state/reward-balance/{height}/{address:#x}"),
+            None => format!("reward-state/reward-balance/latest/{address:#x}"),
+        };
+        self.get(&path)
+            .await
+            .with_context(|| format!("getting reward balance for {address}"))
+    }
+
+    /// Get every change to the balance of a reward account in blocks `range`, defaulting to the
+    /// most recent blocks for which the reward state is available.
+    pub async fn get_reward_balance_history(
+        &self,
+        address: Address,
+        range: Option<RangeInclusive<u64>>,
+    ) -> anyhow::Result<Vec<BalanceChange<RewardAmount>>> {
+        self.get(&balance_history_path("reward-state", address, range))
+            .await
+            .with_context(|| format!("getting reward balance history for {address}"))
+    }
+
+    /// Get the Merkle path for the header of block `block` in the block Merkle tree as of the given
+    /// block height.
+    ///
+    /// The block Merkle tree at height `height` contains all blocks before `height`, so `block`
+    /// must be less than `height`.
+    pub async fn get_block_state_proof(
+        &self,
+        height: u64,
+        block: u64,
+    ) -> anyhow::Result<BlockMerkleProof> {
+        self.get(&format!("block-state/{height}/{block}"))
+            .await
+            .with_context(|| format!("getting block state for {block} at height {height}"))
+    }
+
+    /// Get the latest block height for which the block Merkle tree is available.
+    pub async fn get_block_state_height(&self) -> anyhow::Result<u64> {
+        self.get("block-state/block-height")
+            .await
+            .context("getting block state height")
+    }
+
+    /// Get a bundle proving that namespace `namespace` of block `height` was sequenced, anchored
+    /// at the light client state with block height `anchor`.
+    ///
+    /// Check the result with [`NamespaceFinalityProof::verify`] against the block commitment root
+    /// of that light client state.
+    pub async fn get_namespace_finality_proof(
+        &self,
+        height: u64,
+        namespace: NamespaceId,
+        anchor: u64,
+    ) -> anyhow::Result<NamespaceFinalityProof> {
+        self.get(&format!(
+            "block-state/finality-proof/{height}/namespace/{namespace}/anchor/{anchor}"
+        ))
+        .await
+        .with_context(|| {
+            format!("getting finality proof for namespace {namespace} of block {height}")
+        })
+    }
+}
+
+fn balance_history_path(
+    module: &str,
+    address: Address,
+    range: Option<RangeInclusive<u64>>,
+) -> String {
+    match range {
+        Some(range) => format!(
+            "{module}/history/{address:#x}/{}/{}",
+            range.start(),
+            range.end()
+        ),
+        None => format!("{module}/history/{address:#x}"),
+    }
+}
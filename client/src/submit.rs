@@ -0,0 +1,145 @@
+//! Typed access to the `submit`, `config` and `state-signature` API modules.
+
+use anyhow::{bail, ensure, Context};
+use committable::Commitment;
+use espresso_types::{
+    config::PublicNetworkConfig, NamespaceProofQueryData, SubmitResult, Transaction,
+    TransactionPosition, TransactionStatus,
+};
+use futures::stream::{BoxStream, StreamExt};
+use hotshot_types::light_client::StateSignatureRequestBody;
+use surf_disco::error::ClientError;
+
+use crate::SequencerClient;
+
+/// Evidence that a submitted transaction was decided.
+#[derive(Clone, Debug)]
+pub struct TransactionInclusion {
+    /// Commitment of the transaction.
+    pub hash: Commitment<Transaction>,
+    /// Where the transaction was sequenced.
+    pub position: TransactionPosition,
+    /// Namespace proof for the transaction's namespace in the block which included it.
+    pub proof: NamespaceProofQueryData,
+}
+
+impl SequencerClient {
+    /// Submit a transaction to the sequencer.
+    ///
+    /// Returns the commitment of the submitted transaction. Submission is not retried, since the
+    /// server may have accepted the transaction even if we fail to receive the response.
+    pub async fn submit_transaction(
+        &self,
+        tx: &Transaction,
+    ) -> anyhow::Result<Commitment<Transaction>> {
+        self.inner
+            .post::<Commitment<Transaction>>("submit/submit")
+            .body_json(tx)
+            .context("serializing transaction")?
+            .send()
+            .await
+            .context("submitting transaction")
+    }
+
+    /// Submit a batch of transactions to the sequencer.
+    ///
+    /// The server validates each transaction independently, and returns one result per
+    /// transaction, in order. Like [`submit_transaction`](Self::submit_transaction), submission is
+    /// not retried.
+    pub async fn submit_batch(&self, txs: &[Transaction]) -> anyhow::Result<Vec<SubmitResult>> {
+        self.inner
+            .post::<Vec<SubmitResult>>("submit/batch")
+            .body_json(&txs)
+            .context("serializing transactions")?
+            .send()
+            .await
+            .context("submitting transaction batch")
+    }
+
+    /// Get the status of a transaction submitted through the node.
+    pub async fn get_transaction_status(
+        &self,
+        hash: Commitment<Transaction>,
+    ) -> anyhow::Result<TransactionStatus> {
+        self.get(&format!("submit/status/{hash}"))
+            .await
+            .with_context(|| format!("getting status of transaction {hash}"))
+    }
+
+    /// Subscribe to status updates for a transaction submitted through the node.
+    ///
+    /// The stream ends after the transaction is decided, or once the node stops tracking a
+    /// transaction it has reported as dropped.
+    pub async fn subscribe_transaction_status(
+        &self,
+        hash: Commitment<Transaction>,
+    ) -> anyhow::Result<BoxStream<'static, Result<TransactionStatus, ClientError>>> {
+        self.subscribe(&format!("submit/stream/status/{hash}"))
+            .await
+            .with_context(|| format!("subscribing to status of transaction {hash}"))
+    }
+
+    /// Submit a transaction and wait for it to be decided.
+    ///
+    /// A transaction reported as dropped may still be decided late, so this keeps waiting until the
+    /// node stops tracking it, and only then fails. On success, returns the position of the
+    /// transaction along with a namespace proof for the block which included it.
+    pub async fn submit_and_wait(&self, tx: &Transaction) -> anyhow::Result<TransactionInclusion> {
+        let hash = self.submit_transaction(tx).await?;
+        let mut updates = self.subscribe_transaction_status(hash).await?;
+        let mut dropped = false;
+        let position = loop {
+            match updates.next().await {
+                Some(Ok(TransactionStatus::Decided(position))) => break position,
+                Some(Ok(status)) => {
+                    dropped = status == TransactionStatus::Dropped;
+                    tracing::debug!(%hash, ?status, "waiting for transaction to be decided");
+                },
+                Some(Err(err)) => {
+                    return Err(err).context(format!("waiting for transaction {hash}"));
+                },
+                None if dropped => bail!("transaction {hash} was dropped"),
+                None => bail!("status stream for transaction {hash} ended before it was decided"),
+            }
+        };
+
+        let proof = self
+            .get_namespace_proof(position.height, position.namespace)
+            .await?;
+        ensure!(
+            proof.transactions.get(position.offset as usize) == Some(tx),
+            "transaction {hash} not found in namespace {} of block {}",
+            position.namespace,
+            position.height
+        );
+        Ok(TransactionInclusion {
+            hash,
+            position,
+            proof,
+        })
+    }
+
+    /// Get the HotShot configuration of the node.
+    pub async fn get_hotshot_config(&self) -> anyhow::Result<PublicNetworkConfig> {
+        self.get("config/hotshot")
+            .await
+            .context("getting HotShot config")
+    }
+
+    /// Get the public `ESPRESSO_*` environment variables set for the node, as `KEY=value` strings.
+    pub async fn get_env_config(&self) -> anyhow::Result<Vec<String>> {
+        self.get("config/env")
+            .await
+            .context("getting environment config")
+    }
+
+    /// Get the node's signature on the light client state for the given block height.
+    pub async fn get_state_signature(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<StateSignatureRequestBody> {
+        self.get(&format!("state-signature/block/{height}"))
+            .await
+            .with_context(|| format!("getting state signature for block {height}"))
+    }
+}
@@ -0,0 +1,559 @@
+//! Verified access to a sequencer query node.
+//!
+//! A [`SequencerClient`] trusts whatever the node it is connected to returns. A
+//! [`VerifiedSequencerClient`] instead anchors trust in the finalized state of the `LightClient`
+//! contract on L1, and checks every response against that anchor before returning it:
+//!
+//! * headers are checked against the block Merkle tree root committed to by the light client,
+//! * namespace payloads are checked with a namespace proof against a verified header, and
+//! * fee and reward balances are checked with a Merkle proof against a verified header.
+//!
+//! Only blocks finalized by the light client can be verified. Data for more recent blocks becomes
+//! available after calling [`refresh_anchor`](VerifiedSequencerClient::refresh_anchor) once the
+//! light client contract has been updated.
+
+use alloy::{primitives::Address, providers::Provider};
+use anyhow::{ensure, Context};
+use ark_serialize::CanonicalSerialize;
+use committable::Committable;
+use espresso_types::{
+    v0_1::{
+        RewardAccountProof, RewardAmount, RewardMerkleCommitment,
+        RewardMerkleProof as RewardAccountMerkleProof,
+    },
+    BlockMerkleCommitment, BlockMerkleTree, FeeAccountProof, FeeAmount, FeeMerkleCommitment,
+    FeeMerkleProof as AccountMerkleProof, Header, NamespaceId, NsProof, Transaction,
+};
+use hotshot_contract_adapter::sol_types::{LightClientStateSol, LightClientV2};
+use hotshot_query_service::VidCommon;
+use hotshot_types::light_client::{hash_bytes_to_field, LightClientState};
+use jf_merkle_tree::{MerkleCommitment, MerkleTreeScheme};
+
+use crate::{BlockMerkleProof, FeeMerkleProof, RewardMerkleProof, SequencerClient};
+
+/// A [`SequencerClient`] which verifies responses against the state of the light client contract.
+#[derive(Clone, Debug)]
+pub struct VerifiedSequencerClient<P> {
+    client: SequencerClient,
+    provider: P,
+    light_client: Address,
+    anchor: LightClientState,
+    block_merkle_tree_root: BlockMerkleCommitment,
+}
+
+impl SequencerClient {
+    /// Switch to verified mode, anchoring trust in the `LightClient` contract at `light_client`.
+    ///
+    /// The finalized light client state is read from L1 using `provider`.
+    pub async fn verified<P: Provider>(
+        self,
+        provider: P,
+        light_client: Address,
+    ) -> anyhow::Result<VerifiedSequencerClient<P>> {
+        VerifiedSequencerClient::new(self, provider, light_client).await
+    }
+}
+
+impl<P: Provider> VerifiedSequencerClient<P> {
+    /// Create a verified client, reading the initial anchor from the light client contract.
+    pub async fn new(
+        client: SequencerClient,
+        provider: P,
+        light_client: Address,
+    ) -> anyhow::Result<Self> {
+        let anchor = read_finalized_state(&provider, light_client).await?;
+        let block_merkle_tree_root = fetch_block_merkle_tree_root(&client, &anchor).await?;
+        Ok(Self {
+            client,
+            provider,
+            light_client,
+            anchor,
+            block_merkle_tree_root,
+        })
+    }
+
+    /// Re-read the finalized state of the light client contract.
+    ///
+    /// After this, blocks finalized by the light client since the last refresh can be verified.
+    pub async fn refresh_anchor(&mut self) -> anyhow::Result<()> {
+        let anchor = read_finalized_state(&self.provider, self.light_client).await?;
+        if anchor == self.anchor {
+            return Ok(());
+        }
+        check_anchor_update(&self.anchor, &anchor)?;
+        self.block_merkle_tree_root = fetch_block_merkle_tree_root(&self.client, &anchor).await?;
+        self.anchor = anchor;
+        Ok(())
+    }
+}
+
+impl<P> VerifiedSequencerClient<P> {
+    /// The finalized light client state all responses are verified against.
+    pub fn anchor(&self) -> LightClientState {
+        self.anchor
+    }
+
+    /// The underlying, unverified client.
+    pub fn inner(&self) -> &SequencerClient {
+        &self.client
+    }
+
+    /// Get the header at the given height, verified against the anchor.
+    ///
+    /// Fails if the block has not yet been finalized by the light client.
+    pub async fn get_header(&self, height: u64) -> anyhow::Result<Header> {
+        ensure!(
+            height < self.anchor.block_height,
+            "block {height} is not yet finalized by the light client (finalized height {})",
+            self.anchor.block_height
+        );
+        let header = self.client.get_header(height).await?;
+        let proof = self
+            .client
+            .get_block_state_proof(self.anchor.block_height, height)
+            .await?;
+        verify_header(&self.block_merkle_tree_root, height, &header, &proof)?;
+        Ok(header)
+    }
+
+    /// Get the transactions in the given namespace of a block, verified against the anchor.
+    ///
+    /// Returns an empty list if the namespace is not present in the block.
+    pub async fn get_namespace(
+        &self,
+        height: u64,
+        namespace: NamespaceId,
+    ) -> anyhow::Result<Vec<Transaction>> {
+        let header = self.get_header(height).await?;
+        let res = self.client.get_namespace_proof(height, namespace).await?;
+        let Some(proof) = res.proof else {
+            // The server claims the namespace is absent, which we can check against the verified
+            // namespace table.
+            verify_namespace_absent(&header, namespace)?;
+            return Ok(vec![]);
+        };
+        let common = self.client.get_vid_common(height).await?;
+        verify_namespace_proof(&header, namespace, &proof, common.common())
+    }
+
+    /// Get the balance of a fee account as of the given block height, verified against the anchor.
+    pub async fn get_fee_balance(
+        &self,
+        address: Address,
+        height: u64,
+    ) -> anyhow::Result<FeeAmount> {
+        let header = self.get_header(height).await?;
+        let proof = self.client.get_fee_state_proof(height, address).await?;
+        verify_fee_proof(&header.fee_merkle_tree_root(), address, &proof)
+            .with_context(|| format!("verifying fee balance for {address} at height {height}"))
+    }
+
+    /// Get the balance of a reward account as of the given block height, verified against the
+    /// anchor.
+    pub async fn get_reward_balance(
+        &self,
+        address: Address,
+        height: u64,
+    ) -> anyhow::Result<RewardAmount> {
+        let header = self.get_header(height).await?;
+        let proof = self.client.get_reward_state_proof(height, address).await?;
+        verify_reward_proof(&header.reward_merkle_tree_root(), address, &proof)
+            .with_context(|| format!("verifying reward balance for {address} at height {height}"))
+    }
+}
+
+/// Read the finalized state of the light client contract.
+async fn read_finalized_state(
+    provider: impl Provider,
+    light_client: Address,
+) -> anyhow::Result<LightClientState> {
+    let contract = LightClientV2::new(light_client, &provider);
+    let state: LightClientStateSol = contract
+        .finalizedState()
+        .call()
+        .await
+        .context("reading finalized light client state")?
+        .into();
+    Ok(state.into())
+}
+
+/// Get the block Merkle tree root committed to by a light client state.
+///
+/// The light client only stores a hash of the root, so we fetch the root from the header at the
+/// finalized height and check it against the hash.
+async fn fetch_block_merkle_tree_root(
+    client: &SequencerClient,
+    anchor: &LightClientState,
+) -> anyhow::Result<BlockMerkleCommitment> {
+    let header = client.get_header(anchor.block_height).await?;
+    check_block_merkle_tree_root(anchor, &header)
+}
+
+/// Check that an updated light client state does not go back in the chain.
+fn check_anchor_update(current: &LightClientState, new: &LightClientState) -> anyhow::Result<()> {
+    ensure!(
+        new.block_height >= current.block_height,
+        "light client state regressed from block {} to {}",
+        current.block_height,
+        new.block_height
+    );
+    Ok(())
+}
+
+/// Check the block Merkle tree root of a header against the hash stored in a light client state.
+fn check_block_merkle_tree_root(
+    anchor: &LightClientState,
+    header: &Header,
+) -> anyhow::Result<BlockMerkleCommitment> {
+    let root = header.block_merkle_tree_root();
+    let mut root_bytes = vec![];
+    root.serialize_compressed(&mut root_bytes)?;
+    ensure!(
+        hash_bytes_to_field(&root_bytes)? == anchor.block_comm_root,
+        "block Merkle tree root of header {} does not match light client state",
+        anchor.block_height
+    );
+    Ok(root)
+}
+
+/// Check a header from the server against a verified block Merkle tree root.
+fn verify_header(
+    root: &BlockMerkleCommitment,
+    height: u64,
+    header: &Header,
+    proof: &BlockMerkleProof,
+) -> anyhow::Result<()> {
+    ensure!(
+        header.height() == height,
+        "requested header {height} but got header {}",
+        header.height()
+    );
+    ensure!(
+        BlockMerkleTree::verify(root.digest(), height, proof)?.is_ok(),
+        "invalid block Merkle proof for header {height}"
+    );
+    let proven = proof
+        .elem()
+        .context("block Merkle proof is missing header commitment")?;
+    ensure!(
+        *proven == header.commit(),
+        "header commitment mismatch: proven {proven} != header {}",
+        header.commit()
+    );
+    Ok(())
+}
+
+/// Check a claim from the server that a verified block does not contain `namespace`.
+fn verify_namespace_absent(header: &Header, namespace: NamespaceId) -> anyhow::Result<()> {
+    ensure!(
+        header.ns_table().find_ns_id(&namespace).is_none(),
+        "missing proof for namespace {namespace} in block {}",
+        header.height()
+    );
+    Ok(())
+}
+
+/// Check a namespace proof from the server against a verified header.
+fn verify_namespace_proof(
+    header: &Header,
+    namespace: NamespaceId,
+    proof: &NsProof,
+    common: &VidCommon,
+) -> anyhow::Result<Vec<Transaction>> {
+    let (transactions, ns) = proof
+        .verify(header.ns_table(), &header.payload_commitment(), common)
+        .with_context(|| {
+            format!(
+                "invalid proof for namespace {namespace} in block {}",
+                header.height()
+            )
+        })?;
+    ensure!(
+        ns == namespace,
+        "requested namespace {namespace} but got proof for namespace {ns}"
+    );
+    Ok(transactions)
+}
+
+/// Check a fee balance proof from the server, using the same verification as
+/// [`FeeAccountProof::verify`].
+fn verify_fee_proof(
+    root: &FeeMerkleCommitment,
+    address: Address,
+    proof: &FeeMerkleProof,
+) -> anyhow::Result<FeeAmount> {
+    let proof = FeeAccountProof {
+        account: address,
+        proof: match proof.elem() {
+            Some(_) => AccountMerkleProof::Presence(proof.clone()),
+            None => AccountMerkleProof::Absence(proof.clone()),
+        },
+    };
+    Ok(FeeAmount(proof.verify(root)?))
+}
+
+/// Check a reward balance proof from the server, using the same verification as
+/// [`RewardAccountProof::verify`].
+fn verify_reward_proof(
+    root: &RewardMerkleCommitment,
+    address: Address,
+    proof: &RewardMerkleProof,
+) -> anyhow::Result<RewardAmount> {
+    let proof = RewardAccountProof {
+        account: address,
+        proof: match proof.elem() {
+            Some(_) => RewardAccountMerkleProof::Presence(proof.clone()),
+            None => RewardAccountMerkleProof::Absence(proof.clone()),
+        },
+    };
+    Ok(RewardAmount(proof.verify(root)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use espresso_types::{
+        v0_1::{RewardAccount, RewardMerkleTree, REWARD_MERKLE_TREE_HEIGHT},
+        v0_3::FEE_MERKLE_TREE_HEIGHT,
+        FeeAccount, FeeMerkleTree, NodeState, Payload, ValidatedState, BLOCK_MERKLE_TREE_HEIGHT,
+    };
+    use hotshot_query_service::testing::mocks::MockVersions;
+    use hotshot_types::{
+        data::VidCommitment,
+        light_client::CircuitField,
+        traits::{
+            block_contents::BlockHeader, states::ValidatedState as _, BlockPayload, EncodeBytes,
+        },
+        vid::avidm::{init_avidm_param, AvidMScheme},
+    };
+    use jf_merkle_tree::{AppendableMerkleTreeScheme, LookupResult, UniversalMerkleTreeScheme};
+
+    use super::*;
+
+    /// Block 1, containing namespaces 1 and 2, along with the data a server would return to prove
+    /// it against a light client state for block 2.
+    struct TestBlock {
+        header: Header,
+        payload: Payload,
+        common: VidCommon,
+        /// Proof of `header` in the block Merkle tree of `anchor_header`.
+        proof: BlockMerkleProof,
+        anchor_header: Header,
+        anchor: LightClientState,
+    }
+
+    async fn test_block() -> TestBlock {
+        let instance = NodeState::mock();
+        let validated = ValidatedState::genesis(&instance).0;
+        let txs = vec![
+            Transaction::new(1u32.into(), vec![1, 2, 3]),
+            Transaction::new(2u32.into(), vec![4, 5]),
+            Transaction::new(1u32.into(), vec![6]),
+        ];
+        let (payload, ns_table) = Payload::from_transactions(txs, &validated, &instance)
+            .await
+            .unwrap();
+        let genesis = Header::genesis::<MockVersions>(&instance, payload.clone(), &ns_table);
+
+        let param = init_avidm_param(10).unwrap();
+        let payload_byte_len = payload.byte_len();
+        let ns_ranges = ns_table
+            .iter()
+            .map(|index| {
+                ns_table
+                    .ns_range(&index, &payload_byte_len)
+                    .as_block_range()
+            })
+            .collect::<Vec<_>>();
+        let payload_commitment = AvidMScheme::commit(&param, &payload.encode(), ns_ranges).unwrap();
+        let mut header = genesis.clone();
+        *header.height_mut() = 1;
+        *header.payload_commitment_mut() = VidCommitment::V1(payload_commitment);
+
+        let mut tree = BlockMerkleTree::new(BLOCK_MERKLE_TREE_HEIGHT);
+        tree.push(genesis.commit()).unwrap();
+        tree.push(header.commit()).unwrap();
+        let (_, proof) = tree.lookup(1).expect_ok().unwrap();
+
+        let mut anchor_header = header.clone();
+        *anchor_header.height_mut() = 2;
+        *anchor_header.block_merkle_tree_root_mut() = tree.commitment();
+        let mut root_bytes = vec![];
+        tree.commitment()
+            .serialize_compressed(&mut root_bytes)
+            .unwrap();
+        let anchor = LightClientState {
+            view_number: 2,
+            block_height: 2,
+            block_comm_root: hash_bytes_to_field::<CircuitField>(&root_bytes).unwrap(),
+        };
+
+        TestBlock {
+            header,
+            payload,
+            common: VidCommon::V1(param),
+            proof,
+            anchor_header,
+            anchor,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_check_block_merkle_tree_root() {
+        let block = test_block().await;
+        assert_eq!(
+            check_block_merkle_tree_root(&block.anchor, &block.anchor_header).unwrap(),
+            block.anchor_header.block_merkle_tree_root()
+        );
+
+        // A header whose root the light client did not commit to is rejected.
+        check_block_merkle_tree_root(&block.anchor, &block.header).unwrap_err();
+
+        // So is the right header against a different light client state.
+        let mut anchor = block.anchor;
+        anchor.block_comm_root += CircuitField::from(1u64);
+        check_block_merkle_tree_root(&anchor, &block.anchor_header).unwrap_err();
+    }
+
+    #[test]
+    fn test_check_anchor_update() {
+        let anchor = LightClientState {
+            view_number: 10,
+            block_height: 10,
+            block_comm_root: CircuitField::from(1u64),
+        };
+        let mut next = anchor;
+        next.block_height = 11;
+        check_anchor_update(&anchor, &next).unwrap();
+        check_anchor_update(&anchor, &anchor).unwrap();
+
+        // A light client state for an earlier block is a regression, even if it is otherwise valid.
+        let mut prev = anchor;
+        prev.block_height = 9;
+        check_anchor_update(&anchor, &prev).unwrap_err();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verify_header() {
+        let block = test_block().await;
+        let root = block.anchor_header.block_merkle_tree_root();
+        verify_header(&root, 1, &block.header, &block.proof).unwrap();
+
+        // A header for a different height than requested.
+        verify_header(&root, 2, &block.header, &block.proof).unwrap_err();
+
+        // A header which does not match the commitment in the block Merkle tree.
+        let mut tampered = block.header.clone();
+        *tampered.block_merkle_tree_root_mut() = root;
+        verify_header(&root, 1, &tampered, &block.proof).unwrap_err();
+
+        // A proof for a different position in the block Merkle tree.
+        let mut proof = block.proof.clone();
+        proof.pos = 0;
+        verify_header(&root, 1, &block.header, &proof).unwrap_err();
+
+        // A proof against a different root.
+        verify_header(
+            &block.header.block_merkle_tree_root(),
+            1,
+            &block.header,
+            &block.proof,
+        )
+        .unwrap_err();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verify_namespace() {
+        let block = test_block().await;
+        let ns_index = block.payload.ns_table().find_ns_id(&1u32.into()).unwrap();
+        let proof = NsProof::new(&block.payload, &ns_index, &block.common).unwrap();
+        assert_eq!(
+            verify_namespace_proof(&block.header, 1u32.into(), &proof, &block.common).unwrap(),
+            vec![
+                Transaction::new(1u32.into(), vec![1, 2, 3]),
+                Transaction::new(1u32.into(), vec![6]),
+            ]
+        );
+
+        // A valid proof for a different namespace than requested.
+        verify_namespace_proof(&block.header, 2u32.into(), &proof, &block.common).unwrap_err();
+
+        // A proof against a header with a different payload.
+        let mut tampered = block.header.clone();
+        *tampered.payload_commitment_mut() = VidCommitment::V1(
+            AvidMScheme::commit(&init_avidm_param(10).unwrap(), &[0; 32], [0..32]).unwrap(),
+        );
+        verify_namespace_proof(&tampered, 1u32.into(), &proof, &block.common).unwrap_err();
+
+        // A namespace which is not in the block can be reported absent without a proof.
+        verify_namespace_absent(&block.header, 3u32.into()).unwrap();
+
+        // But a namespace which is in the block cannot.
+        verify_namespace_absent(&block.header, 1u32.into()).unwrap_err();
+    }
+
+    #[test]
+    fn test_verify_fee_proof() {
+        let present = Address::random();
+        let absent = Address::random();
+        let mut tree = FeeMerkleTree::new(FEE_MERKLE_TREE_HEIGHT);
+        tree.update(FeeAccount(present), FeeAmount::from(100))
+            .unwrap();
+        let root = tree.commitment();
+
+        let LookupResult::Ok(_, proof) = tree.universal_lookup(FeeAccount(present)) else {
+            panic!("account should be present");
+        };
+        assert_eq!(
+            verify_fee_proof(&root, present, &proof).unwrap(),
+            FeeAmount::from(100)
+        );
+
+        let LookupResult::NotFound(proof) = tree.universal_lookup(FeeAccount(absent)) else {
+            panic!("account should be absent");
+        };
+        assert_eq!(
+            verify_fee_proof(&root, absent, &proof).unwrap(),
+            FeeAmount::from(0)
+        );
+
+        // A non-membership proof for one account does not prove the absence of another.
+        verify_fee_proof(&root, present, &proof).unwrap_err();
+    }
+
+    #[test]
+    fn test_verify_reward_proof() {
+        let present = Address::random();
+        let absent = Address::random();
+        let mut tree = RewardMerkleTree::new(REWARD_MERKLE_TREE_HEIGHT);
+        tree.update(RewardAccount(present), RewardAmount::from(100))
+            .unwrap();
+        let root = tree.commitment();
+
+        let LookupResult::Ok(_, proof) = tree.universal_lookup(RewardAccount(present)) else {
+            panic!("account should be present");
+        };
+        assert_eq!(
+            verify_reward_proof(&root, present, &proof).unwrap(),
+            RewardAmount::from(100)
+        );
+
+        let LookupResult::NotFound(proof) = tree.universal_lookup(RewardAccount(absent)) else {
+            panic!("account should be absent");
+        };
+        assert_eq!(
+            verify_reward_proof(&root, absent, &proof).unwrap(),
+            RewardAmount::from(0)
+        );
+
+        // A non-membership proof for one account does not prove the absence of another.
+        verify_reward_proof(&root, present, &proof).unwrap_err();
+
+        // A proof against one root does not verify against another.
+        tree.update(RewardAccount(absent), RewardAmount::from(1))
+            .unwrap();
+        let LookupResult::Ok(_, proof) = tree.universal_lookup(RewardAccount(present)) else {
+            panic!("account should be present");
+        };
+        verify_reward_proof(&root, present, &proof).unwrap_err();
+    }
+}
@@ -0,0 +1,5 @@
+-- Support looking up the first and last blocks containing each namespace.
+CREATE INDEX transactions_ns_id_idx ON transactions (ns_id, block_height);
+
+-- Support looking up the cumulative statistics of a single namespace over a range of blocks.
+CREATE INDEX aggregate_namespace_idx ON aggregate (namespace, height);
@@ -0,0 +1,13 @@
+-- The height of the last pruned block for each kind of data which can be retained separately (see
+-- `PrunedData`). Leaves are still tracked in `pruned_height`, and pruning a leaf also prunes the
+-- payload, VID common data and transactions in the same block, so the effective pruned height for
+-- these is the maximum of the height in this table and the height in `pruned_height`.
+CREATE TABLE pruned_height_by_type (
+    data_type TEXT PRIMARY KEY,
+    last_height BIGINT NOT NULL
+);
+
+-- Merklized state used to be pruned along with leaves.
+INSERT INTO pruned_height_by_type (data_type, last_height)
+    SELECT 'merklized_state', last_height FROM pruned_height
+    WHERE id = (SELECT max(id) FROM pruned_height);
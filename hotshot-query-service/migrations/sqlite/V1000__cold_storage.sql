@@ -0,0 +1,11 @@
+-- Payloads and VID common data which have been moved to cold storage. For these rows, the data
+-- column in the database is cleared (set to NULL for payloads and empty for VID common, which is
+-- not nullable) and `cold_key` is the key of the object in cold storage.
+ALTER TABLE payload ADD COLUMN cold_key TEXT;
+ALTER TABLE vid2 ADD COLUMN cold_key TEXT;
+
+CREATE TABLE cold_height (
+    id INTEGER PRIMARY KEY,
+    -- The height of the last block whose data has been moved to cold storage.
+    last_height BIGINT NOT NULL
+);
@@ -33,7 +33,7 @@ pub(crate) fn load_api<State: 'static, Error: 'static, Ver: StaticVersionType +
     Api::new(toml)
 }
 
-fn merge_toml(into: &mut Value, from: Value) {
+pub(crate) fn merge_toml(into: &mut Value, from: Value) {
     if let (Value::Table(into), Value::Table(from)) = (into, from) {
         for (key, value) in from {
             match into.entry(key) {
@@ -29,7 +29,7 @@
 use std::{fmt::Display, path::PathBuf, time::Duration};
 
 use derive_more::From;
-use futures::{FutureExt, StreamExt, TryFutureExt, TryStreamExt};
+use futures::{future, FutureExt, StreamExt, TryFutureExt, TryStreamExt};
 use hotshot_types::{
     data::{Leaf, Leaf2, QuorumProposal, VidCommitment},
     simple_certificate::QuorumCertificate,
@@ -40,13 +40,18 @@ use snafu::{OptionExt, Snafu};
 use tide_disco::{api::ApiError, method::ReadState, Api, RequestError, StatusCode};
 use vbs::version::StaticVersionType;
 
-use crate::{api::load_api, Header, Payload, QueryError, VidCommon};
+use crate::{
+    api::load_api, data_source::storage::pruning::PrunedData, Header, Payload, QueryError,
+    VidCommon,
+};
 
 pub(crate) mod data_source;
 mod fetch;
+mod page;
 pub(crate) mod query_data;
 pub use data_source::*;
 pub use fetch::Fetch;
+pub use page::*;
 pub use query_data::*;
 
 #[derive(Debug)]
@@ -68,6 +73,8 @@ pub struct Options {
 
     /// The maximum number of small objects which can be loaded in a single range query.
     ///
+    /// This is also the size of each page of small objects returned by paginated routes.
+    ///
     /// Currently small objects include leaves only. In the future this limit will also apply to
     /// headers, block summaries, and VID common, however
     /// * loading of headers and block summaries is currently implemented by loading the entire
@@ -77,6 +84,8 @@ pub struct Options {
 
     /// The maximum number of large objects which can be loaded in a single range query.
     ///
+    /// This is also the size of each page of large objects returned by paginated routes.
+    ///
     /// Large objects include anything that _might_ contain a full payload or an object proportional
     /// in size to a payload. Note that this limit applies to the entire class of objects: we do not
     /// check the size of objects while loading to determine which limit to apply. If an object
@@ -136,6 +145,12 @@ pub enum Error {
         until: usize,
         limit: usize,
     },
+    #[snafu(display("data at height {height} has been pruned (pruned height {pruned_height})"))]
+    #[from(ignore)]
+    Pruned {
+        height: u64,
+        pruned_height: u64,
+    },
     #[snafu(display("{source}"))]
     Query {
         source: QueryError,
@@ -169,6 +184,7 @@ impl Error {
             | Self::FetchHeader { .. }
             | Self::FetchStateCert { .. } => StatusCode::NOT_FOUND,
             Self::InvalidTransactionIndex { .. } | Self::Query { .. } => StatusCode::NOT_FOUND,
+            Self::Pruned { .. } => StatusCode::GONE,
             Self::Custom { status, .. } => *status,
         }
     }
@@ -234,8 +250,11 @@ where
     Header<Types>: QueryableHeader<Types>,
     Payload<Types>: QueryablePayload<Types>,
 {
-    let id = match req.opt_integer_param("height")? {
-        Some(height) => LeafId::Number(height),
+    let id = match req.opt_integer_param::<_, usize>("height")? {
+        Some(height) => {
+            check_pruned::<Types, _>(state, height as u64, PrunedData::Leaf).await?;
+            LeafId::Number(height)
+        },
         None => LeafId::Hash(req.blob_param("hash")?),
     };
     let fetch = state.read(|state| state.get_leaf(id).boxed()).await;
@@ -275,6 +294,30 @@ where
         .await
 }
 
+async fn get_leaf_page_handler<Types, State>(
+    req: tide_disco::RequestParams,
+    state: &State,
+    timeout: Duration,
+    small_object_range_limit: usize,
+) -> Result<Page<LeafQueryData<Types>>, Error>
+where
+    State: 'static + Send + Sync + ReadState,
+    <State as ReadState>::State: Send + Sync + AvailabilityDataSource<Types>,
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    let start = page_start::<Types, _>(&req, state, PrunedData::Leaf).await?;
+    let leaves = state
+        .read(|state| {
+            state
+                .get_leaf_range(start..start + small_object_range_limit)
+                .boxed()
+        })
+        .await;
+    Ok(collect_page(start, leaves, timeout).await)
+}
+
 fn downgrade_vid_common_query_data<Types: NodeType>(
     data: VidCommonQueryData<Types>,
 ) -> Option<ADVZCommonQueryData<Types>> {
@@ -307,7 +350,8 @@ where
     Header<Types>: QueryableHeader<Types>,
     Payload<Types>: QueryablePayload<Types>,
 {
-    let id = if let Some(height) = req.opt_integer_param("height")? {
+    let id = if let Some(height) = req.opt_integer_param::<_, usize>("height")? {
+        check_pruned::<Types, _>(state, height as u64, PrunedData::VidCommon).await?;
         BlockId::Number(height)
     } else if let Some(hash) = req.opt_blob_param("hash")? {
         BlockId::Hash(hash)
@@ -320,6 +364,60 @@ where
     })
 }
 
+async fn get_vid_common_range_handler<Types, State>(
+    req: tide_disco::RequestParams,
+    state: &State,
+    timeout: Duration,
+    large_object_range_limit: usize,
+) -> Result<Vec<VidCommonQueryData<Types>>, Error>
+where
+    State: 'static + Send + Sync + ReadState,
+    <State as ReadState>::State: Send + Sync + AvailabilityDataSource<Types>,
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    let from = req.integer_param::<_, usize>("from")?;
+    let until = req.integer_param("until")?;
+    enforce_range_limit(from, until, large_object_range_limit)?;
+
+    let vid = state
+        .read(|state| state.get_vid_common_range(from..until).boxed())
+        .await;
+    vid.enumerate()
+        .then(|(index, fetch)| async move {
+            fetch.with_timeout(timeout).await.context(FetchBlockSnafu {
+                resource: (index + from).to_string(),
+            })
+        })
+        .try_collect::<Vec<_>>()
+        .await
+}
+
+async fn get_vid_common_page_handler<Types, State>(
+    req: tide_disco::RequestParams,
+    state: &State,
+    timeout: Duration,
+    large_object_range_limit: usize,
+) -> Result<Page<VidCommonQueryData<Types>>, Error>
+where
+    State: 'static + Send + Sync + ReadState,
+    <State as ReadState>::State: Send + Sync + AvailabilityDataSource<Types>,
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    let start = page_start::<Types, _>(&req, state, PrunedData::VidCommon).await?;
+    let vid = state
+        .read(|state| {
+            state
+                .get_vid_common_range(start..start + large_object_range_limit)
+                .boxed()
+        })
+        .await;
+    Ok(collect_page(start, vid, timeout).await)
+}
+
 pub fn define_api<State, Types: NodeType, Ver: StaticVersionType + 'static>(
     options: &Options,
     _: Ver,
@@ -368,6 +466,12 @@ where
                 .boxed()
         })?;
 
+        api.at("get_leaf_page", move |req, state| {
+            get_leaf_page_handler(req, state, timeout, small_object_range_limit)
+                .map(|res| res.map(|page| page.map(downgrade_leaf_query_data)))
+                .boxed()
+        })?;
+
         api.stream("stream_leaves", move |req, state| {
             async move {
                 let height = req.integer_param("height")?;
@@ -395,6 +499,10 @@ where
             get_leaf_range_handler(req, state, timeout, small_object_range_limit).boxed()
         })?;
 
+        api.at("get_leaf_page", move |req, state| {
+            get_leaf_page_handler(req, state, timeout, small_object_range_limit).boxed()
+        })?;
+
         api.stream("stream_leaves", move |req, state| {
             async move {
                 let height = req.integer_param("height")?;
@@ -423,6 +531,41 @@ where
                 })
                 .boxed()
         })?
+        .at("get_vid_common_range", move |req, state| {
+            get_vid_common_range_handler(req, state, timeout, large_object_range_limit)
+                .map(|r| match r {
+                    Ok(data) => data
+                        .into_iter()
+                        .map(downgrade_vid_common_query_data)
+                        .collect::<Option<Vec<_>>>()
+                        .ok_or(Error::Custom {
+                            message: "Incompatible VID version.".to_string(),
+                            status: StatusCode::BAD_REQUEST,
+                        }),
+                    Err(e) => Err(e),
+                })
+                .boxed()
+        })?
+        .at("get_vid_common_page", move |req, state| {
+            get_vid_common_page_handler(req, state, timeout, large_object_range_limit)
+                .map(|r| match r {
+                    Ok(page) => page
+                        .items
+                        .into_iter()
+                        .map(downgrade_vid_common_query_data)
+                        .collect::<Option<Vec<_>>>()
+                        .map(|items| Page {
+                            items,
+                            next: page.next,
+                        })
+                        .ok_or(Error::Custom {
+                            message: "Incompatible VID version.".to_string(),
+                            status: StatusCode::BAD_REQUEST,
+                        }),
+                    Err(e) => Err(e),
+                })
+                .boxed()
+        })?
         .stream("stream_vid_common", move |req, state| {
             async move {
                 let height = req.integer_param("height")?;
@@ -447,6 +590,12 @@ where
         api.at("get_vid_common", move |req, state| {
             get_vid_common_handler(req, state, timeout).boxed().boxed()
         })?
+        .at("get_vid_common_range", move |req, state| {
+            get_vid_common_range_handler(req, state, timeout, large_object_range_limit).boxed()
+        })?
+        .at("get_vid_common_page", move |req, state| {
+            get_vid_common_page_handler(req, state, timeout, large_object_range_limit).boxed()
+        })?
         .stream("stream_vid_common", move |req, state| {
             async move {
                 let height = req.integer_param("height")?;
@@ -463,7 +612,8 @@ where
 
     api.at("get_header", move |req, state| {
         async move {
-            let id = if let Some(height) = req.opt_integer_param("height")? {
+            let id = if let Some(height) = req.opt_integer_param::<_, usize>("height")? {
+                check_pruned::<Types, _>(state, height as u64, PrunedData::Leaf).await?;
                 BlockId::Number(height)
             } else if let Some(hash) = req.opt_blob_param("hash")? {
                 BlockId::Hash(hash)
@@ -498,6 +648,20 @@ where
         }
         .boxed()
     })?
+    .at("get_header_page", move |req, state| {
+        async move {
+            let start = page_start::<Types, _>(&req, state, PrunedData::Leaf).await?;
+            let headers = state
+                .read(|state| {
+                    state
+                        .get_header_range(start..start + large_object_range_limit)
+                        .boxed()
+                })
+                .await;
+            Ok(collect_page(start, headers, timeout).await)
+        }
+        .boxed()
+    })?
     .stream("stream_headers", move |req, state| {
         async move {
             let height = req.integer_param("height")?;
@@ -512,7 +676,8 @@ where
     })?
     .at("get_block", move |req, state| {
         async move {
-            let id = if let Some(height) = req.opt_integer_param("height")? {
+            let id = if let Some(height) = req.opt_integer_param::<_, usize>("height")? {
+                check_pruned::<Types, _>(state, height as u64, PrunedData::Payload).await?;
                 BlockId::Number(height)
             } else if let Some(hash) = req.opt_blob_param("hash")? {
                 BlockId::Hash(hash)
@@ -547,6 +712,20 @@ where
         }
         .boxed()
     })?
+    .at("get_block_page", move |req, state| {
+        async move {
+            let start = page_start::<Types, _>(&req, state, PrunedData::Payload).await?;
+            let blocks = state
+                .read(|state| {
+                    state
+                        .get_block_range(start..start + large_object_range_limit)
+                        .boxed()
+                })
+                .await;
+            Ok(collect_page(start, blocks, timeout).await)
+        }
+        .boxed()
+    })?
     .stream("stream_blocks", move |req, state| {
         async move {
             let height = req.integer_param("height")?;
@@ -561,7 +740,8 @@ where
     })?
     .at("get_payload", move |req, state| {
         async move {
-            let id = if let Some(height) = req.opt_integer_param("height")? {
+            let id = if let Some(height) = req.opt_integer_param::<_, usize>("height")? {
+                check_pruned::<Types, _>(state, height as u64, PrunedData::Payload).await?;
                 BlockId::Number(height)
             } else if let Some(hash) = req.opt_blob_param("hash")? {
                 BlockId::PayloadHash(hash)
@@ -596,6 +776,20 @@ where
         }
         .boxed()
     })?
+    .at("get_payload_page", move |req, state| {
+        async move {
+            let start = page_start::<Types, _>(&req, state, PrunedData::Payload).await?;
+            let payloads = state
+                .read(|state| {
+                    state
+                        .get_payload_range(start..start + large_object_range_limit)
+                        .boxed()
+                })
+                .await;
+            Ok(collect_page(start, payloads, timeout).await)
+        }
+        .boxed()
+    })?
     .stream("stream_payloads", move |req, state| {
         async move {
             let height = req.integer_param("height")?;
@@ -624,6 +818,7 @@ where
                 },
                 None => {
                     let height: u64 = req.integer_param("height")?;
+                    check_pruned::<Types, _>(state, height, PrunedData::Payload).await?;
                     let fetch = state
                         .read(|state| state.get_block(height as usize).boxed())
                         .await;
@@ -698,6 +893,7 @@ where
     .at("get_block_summary", move |req, state| {
         async move {
             let id: usize = req.integer_param("height")?;
+            check_pruned::<Types, _>(state, id as u64, PrunedData::Payload).await?;
 
             let fetch = state.read(|state| state.get_block(id).boxed()).await;
             fetch
@@ -734,6 +930,21 @@ where
         }
         .boxed()
     })?
+    .at("get_block_summary_page", move |req, state| {
+        async move {
+            let start = page_start::<Types, _>(&req, state, PrunedData::Payload).await?;
+            let blocks = state
+                .read(|state| {
+                    state
+                        .get_block_range(start..start + large_object_range_limit)
+                        .boxed()
+                })
+                .await;
+            let page = collect_page(start, blocks, timeout).await;
+            Ok(page.map(BlockSummaryQueryData::from))
+        }
+        .boxed()
+    })?
     .at("get_limits", move |_req, _state| {
         async move {
             Ok(Limits {
@@ -766,6 +977,82 @@ fn enforce_range_limit(from: usize, until: usize, limit: usize) -> Result<(), Er
     Ok(())
 }
 
+/// Get the height of the first object in a page, from either a `cursor` or a `height` parameter.
+///
+/// Fails with [`Error::Pruned`] if `data` has already been pruned at that height.
+async fn page_start<Types, State>(
+    req: &tide_disco::RequestParams,
+    state: &State,
+    data: PrunedData,
+) -> Result<usize, Error>
+where
+    State: 'static + Send + Sync + ReadState,
+    <State as ReadState>::State: Send + Sync + AvailabilityDataSource<Types>,
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    let height = match req.opt_integer_param("height")? {
+        Some(height) => height,
+        None => {
+            let cursor: Cursor = req.blob_param("cursor")?;
+            cursor.height()
+        },
+    };
+    check_pruned::<Types, _>(state, height, data).await?;
+    Ok(height as usize)
+}
+
+/// Fail with [`Error::Pruned`] if `data` has already been pruned at `height`.
+///
+/// Objects requested by hash are not checked, since their height is not known until they are
+/// found, and a pruned object is simply not found. Data sources cache pruned heights in memory, so
+/// this does not normally add a storage query to the request.
+async fn check_pruned<Types, State>(
+    state: &State,
+    height: u64,
+    data: PrunedData,
+) -> Result<(), Error>
+where
+    State: 'static + Send + Sync + ReadState,
+    <State as ReadState>::State: Send + Sync + AvailabilityDataSource<Types>,
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    let pruned_height = state
+        .read(|state| state.pruned_height(data).boxed())
+        .await?;
+    if let Some(pruned_height) = pruned_height {
+        if height <= pruned_height {
+            return Err(Error::Pruned {
+                height,
+                pruned_height,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Collect a page of objects, the first of which is at height `start`.
+///
+/// The page ends early at the first object which is not available within `timeout`. Thus, a client
+/// which has caught up with the chain gets an empty page, with a cursor it can retry later. Since
+/// the chain never ends, the page always has a cursor for the next one.
+async fn collect_page<T>(start: usize, fetches: FetchStream<T>, timeout: Duration) -> Page<T> {
+    let items = fetches
+        .then(|fetch| fetch.with_timeout(timeout))
+        .take_while(|item| future::ready(item.is_some()))
+        .filter_map(future::ready)
+        .collect::<Vec<_>>()
+        .await;
+    let next = Cursor::new((start + items.len()) as u64);
+    Page {
+        items,
+        next: Some(next),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::{fmt::Debug, time::Duration};
@@ -787,13 +1074,21 @@ mod test {
 
     use super::*;
     use crate::{
-        data_source::{storage::AvailabilityStorage, ExtensibleDataSource, VersionedDataSource},
+        data_source::{
+            storage::{
+                pruning::{PrunedHeightStorage, PrunerCfg},
+                sql::testing::TmpDb,
+                AvailabilityStorage,
+            },
+            ExtensibleDataSource, VersionedDataSource,
+        },
+        fetching::provider::NoFetching,
         status::StatusDataSource,
         task::BackgroundTask,
         testing::{
             consensus::{MockDataSource, MockNetwork, MockSqlDataSource},
             mocks::{mock_transaction, MockBase, MockHeader, MockPayload, MockTypes, MockVersions},
-            setup_test,
+            setup_test, sleep,
         },
         types::HeightIndexed,
         ApiState, Error, Header,
@@ -1724,6 +2019,12 @@ mod test {
         check_limit::<BlockQueryData<MockTypes>>(&client, "block", large_object_range_limit).await;
         check_limit::<PayloadQueryData<MockTypes>>(&client, "payload", large_object_range_limit)
             .await;
+        check_limit::<VidCommonQueryData<MockTypes>>(
+            &client,
+            "vid/common",
+            large_object_range_limit,
+        )
+        .await;
         check_limit::<BlockSummaryQueryData<MockTypes>>(
             &client,
             "block/summaries",
@@ -1855,4 +2156,212 @@ mod test {
 
         network.shut_down().await;
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_pruned() {
+        setup_test();
+
+        // Create the consensus network and wait for a few blocks.
+        let mut network = MockNetwork::<MockDataSource, MockVersions>::init().await;
+        network.start().await;
+        let leaves = network
+            .data_source()
+            .subscribe_leaves(1)
+            .await
+            .take(3)
+            .collect::<Vec<_>>()
+            .await;
+        let last_leaf = leaves.last().unwrap();
+
+        // Start a data source which prunes everything it can, and give it the leaves.
+        let db = TmpDb::init().await;
+        let data_source: MockSqlDataSource = db
+            .config()
+            .pruner_cfg(
+                PrunerCfg::new()
+                    .with_target_retention(Duration::from_secs(0))
+                    .with_interval(Duration::from_secs(1)),
+            )
+            .unwrap()
+            .connect(NoFetching)
+            .await
+            .unwrap();
+        for leaf in &leaves {
+            data_source.append(leaf.clone().into()).await.unwrap();
+        }
+
+        // Wait for the pruner to run.
+        loop {
+            let pruned_height = data_source
+                .read()
+                .await
+                .unwrap()
+                .load_pruned_height()
+                .await
+                .unwrap();
+            if pruned_height == Some(last_leaf.height()) {
+                break;
+            }
+            tracing::info!(?pruned_height, "waiting for pruner to run");
+            sleep(Duration::from_secs(1)).await;
+        }
+
+        // Start the web server.
+        let port = pick_unused_port().unwrap();
+        let mut app = App::<_, Error>::with_state(ApiState::from(data_source.clone()));
+        app.register_module(
+            "availability",
+            define_api(
+                &Default::default(),
+                MockBase::instance(),
+                "1.0.0".parse().unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        network.spawn(
+            "server",
+            app.serve(format!("0.0.0.0:{port}"), MockBase::instance()),
+        );
+
+        // Start a client.
+        let client = Client::<Error, MockBase>::new(
+            format!("http://localhost:{port}/availability")
+                .parse()
+                .unwrap(),
+        );
+        assert!(client.connect(Some(Duration::from_secs(60))).await);
+
+        // Requests for pruned objects by height fail immediately, rather than waiting for the
+        // objects to be fetched.
+        let height = last_leaf.height();
+        for route in [
+            format!("leaf/{height}"),
+            format!("header/{height}"),
+            format!("block/{height}"),
+            format!("payload/{height}"),
+            format!("vid/common/{height}"),
+            format!("block/summary/{height}"),
+            format!("transaction/{height}/0"),
+        ] {
+            tracing::info!(route, "checking pruned object");
+            let err = client
+                .get::<serde_json::Value>(&route)
+                .send()
+                .await
+                .unwrap_err();
+            assert_eq!(err.status(), StatusCode::GONE, "{route}: {err}");
+        }
+
+        // So do requests for pages of pruned objects, including pages given by a cursor which was
+        // handed out before the objects were pruned.
+        let cursor = Cursor::new(height);
+        for resource in [
+            "leaf",
+            "header",
+            "block",
+            "payload",
+            "vid/common",
+            "block/summaries",
+        ] {
+            for route in [
+                format!("page/{resource}/from/{height}"),
+                format!("page/{resource}/{cursor}"),
+            ] {
+                tracing::info!(route, "checking pruned page");
+                let err = client
+                    .get::<serde_json::Value>(&route)
+                    .send()
+                    .await
+                    .unwrap_err();
+                assert_eq!(err.status(), StatusCode::GONE, "{route}: {err}");
+            }
+        }
+
+        network.shut_down().await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_pages() {
+        setup_test();
+
+        // Create the consensus network and wait for a few blocks.
+        let mut network = MockNetwork::<MockDataSource, MockVersions>::init().await;
+        network.start().await;
+        let leaves = network
+            .data_source()
+            .subscribe_leaves(0)
+            .await
+            .take(5)
+            .collect::<Vec<_>>()
+            .await;
+
+        // Start a data source with a fixed set of leaves, so that the tip of the chain doesn't move
+        // while we are paging through it.
+        let db = TmpDb::init().await;
+        let data_source: MockSqlDataSource = db.config().connect(NoFetching).await.unwrap();
+        for leaf in &leaves {
+            data_source.append(leaf.clone().into()).await.unwrap();
+        }
+
+        // Start the web server, with a small page size so that we need several pages to reach the
+        // tip.
+        let port = pick_unused_port().unwrap();
+        let mut app = App::<_, Error>::with_state(ApiState::from(data_source.clone()));
+        app.register_module(
+            "availability",
+            define_api(
+                &Options {
+                    fetch_timeout: Duration::from_millis(500),
+                    small_object_range_limit: 2,
+                    ..Default::default()
+                },
+                MockBase::instance(),
+                "1.0.0".parse().unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        network.spawn(
+            "server",
+            app.serve(format!("0.0.0.0:{port}"), MockBase::instance()),
+        );
+
+        // Start a client.
+        let client = Client::<Error, MockBase>::new(
+            format!("http://localhost:{port}/availability")
+                .parse()
+                .unwrap(),
+        );
+        assert!(client.connect(Some(Duration::from_secs(60))).await);
+
+        // Follow cursors until we catch up with the chain, which is signalled by an empty page.
+        let mut items: Vec<LeafQueryData<MockTypes>> = vec![];
+        let mut route = "page/leaf/from/0".to_string();
+        let next = loop {
+            let page: Page<LeafQueryData<MockTypes>> = client.get(&route).send().await.unwrap();
+            tracing::info!(route, items = page.items.len(), "got page");
+            assert!(page.items.len() <= 2);
+            let next = page.next.expect("leaf pages always have a next cursor");
+            if page.items.is_empty() {
+                break next;
+            }
+            items.extend(page.items);
+            route = format!("page/leaf/{next}");
+        };
+        assert_eq!(items, leaves);
+
+        // The final cursor points just past the tip, so a client can resume from it once more
+        // blocks are produced.
+        assert_eq!(next, Cursor::new(leaves.len() as u64));
+
+        // Invalid cursors are rejected.
+        client
+            .get::<Page<LeafQueryData<MockTypes>>>("page/leaf/invalid")
+            .send()
+            .await
+            .unwrap_err();
+
+        network.shut_down().await;
+    }
 }
@@ -36,7 +36,9 @@ use super::{
     },
     StateCertQueryData,
 };
-use crate::{types::HeightIndexed, Header, Payload};
+use crate::{
+    data_source::storage::pruning::PrunedData, types::HeightIndexed, Header, Payload, QueryResult,
+};
 
 #[derive(Derivative, From, Display)]
 #[derivative(Ord = "feature_allow_slow_enum")]
@@ -294,6 +296,14 @@ where
             .then(Fetch::resolve)
             .boxed()
     }
+
+    /// The height of the last block whose `data` has been pruned, if any.
+    ///
+    /// Objects at or below this height are no longer stored, and requests for them will not
+    /// resolve. Data sources which never prune can rely on the default implementation.
+    async fn pruned_height(&self, _data: PrunedData) -> QueryResult<Option<u64>> {
+        Ok(None)
+    }
 }
 
 /// Information about a block.
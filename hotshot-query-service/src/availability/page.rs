@@ -0,0 +1,162 @@
+// Copyright (c) 2022 Espresso Systems (espressosys.com)
+// This file is part of the HotShot Query Service library.
+//
+// This program is free software: you can redistribute it and/or modify it under the terms of the GNU
+// General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+// This program is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without
+// even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+// You should have received a copy of the GNU General Public License along with this program. If not,
+// see <https://www.gnu.org/licenses/>.
+
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+use tagged_base64::TaggedBase64;
+
+const CURSOR_TAG: &str = "CURSOR";
+
+/// An opaque position in a paginated list of objects.
+///
+/// Cursors are returned by the server along with each [`Page`], and passed back to the server to
+/// request the next page. Clients should not try to interpret or construct them.
+///
+/// A cursor remains valid indefinitely. If the objects it points to are pruned, requesting a page
+/// with that cursor fails with a specific "pruned" error, rather than silently skipping ahead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "TaggedBase64", try_from = "TaggedBase64")]
+pub struct Cursor {
+    height: u64,
+    offset: u64,
+}
+
+impl Cursor {
+    pub(crate) fn new(height: u64) -> Self {
+        Self::with_offset(height, 0)
+    }
+
+    pub(crate) fn with_offset(height: u64, offset: u64) -> Self {
+        Self { height, offset }
+    }
+
+    /// The height of the first object in the page this cursor points to.
+    pub(crate) fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// The position of the first object in the page among the objects at the same height.
+    ///
+    /// This is always 0 for lists with at most one object per block.
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl Display for Cursor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", TaggedBase64::from(*self))
+    }
+}
+
+impl From<Cursor> for TaggedBase64 {
+    fn from(cursor: Cursor) -> Self {
+        let mut bytes = cursor.height.to_le_bytes().to_vec();
+        bytes.extend(cursor.offset.to_le_bytes());
+        TaggedBase64::new(CURSOR_TAG, &bytes).unwrap()
+    }
+}
+
+impl TryFrom<&TaggedBase64> for Cursor {
+    type Error = InvalidCursor;
+
+    fn try_from(tb64: &TaggedBase64) -> Result<Self, Self::Error> {
+        if tb64.tag() != CURSOR_TAG {
+            return Err(InvalidCursor);
+        }
+        let bytes: [u8; 16] = tb64.value().try_into().map_err(|_| InvalidCursor)?;
+        let (height, offset) = bytes.split_at(8);
+        Ok(Self::with_offset(
+            u64::from_le_bytes(height.try_into().unwrap()),
+            u64::from_le_bytes(offset.try_into().unwrap()),
+        ))
+    }
+}
+
+impl TryFrom<TaggedBase64> for Cursor {
+    type Error = InvalidCursor;
+
+    fn try_from(tb64: TaggedBase64) -> Result<Self, Self::Error> {
+        (&tb64).try_into()
+    }
+}
+
+/// A string which could not be parsed as a [`Cursor`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidCursor;
+
+impl Display for InvalidCursor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pagination cursor")
+    }
+}
+
+impl std::error::Error for InvalidCursor {}
+
+/// A page of objects from a paginated route.
+///
+/// The number of objects in each page is chosen by the server. Most lists are ordered by block
+/// height and use [`Cursor`], but lists with a different order may have their own cursor type `C`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page<T, C = Cursor> {
+    /// The objects in this page, in order.
+    pub items: Vec<T>,
+    /// A cursor pointing to the page after this one, or [`None`] if the list has ended.
+    ///
+    /// Lists which follow the chain forward never end, since new objects may be added to them at
+    /// any time. Their pages always have a cursor, even if they are empty because the client has
+    /// caught up; the client can retry the cursor later. Only lists with a fixed last object, such
+    /// as lists ordered from the latest block back to genesis, may end.
+    pub next: Option<C>,
+}
+
+impl<T, C> Page<T, C> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U, C> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next: self.next,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cursor_encoding() {
+        let cursor = Cursor::new(1234);
+        let tb64 = TaggedBase64::from(cursor);
+        assert_eq!(tb64.to_string(), cursor.to_string());
+        assert_eq!(Cursor::try_from(&tb64).unwrap(), cursor);
+
+        // The offset is part of the cursor.
+        let with_offset = Cursor::with_offset(1234, 5);
+        assert_ne!(with_offset.to_string(), cursor.to_string());
+        let decoded = Cursor::try_from(&TaggedBase64::from(with_offset)).unwrap();
+        assert_eq!(decoded, with_offset);
+        assert_eq!(decoded.height(), 1234);
+        assert_eq!(decoded.offset(), 5);
+
+        // Cursors round trip through serialization as strings.
+        let json = serde_json::to_string(&cursor).unwrap();
+        assert_eq!(json, format!("\"{cursor}\""));
+        assert_eq!(serde_json::from_str::<Cursor>(&json).unwrap(), cursor);
+
+        // Other tagged blobs are not cursors.
+        let other = TaggedBase64::new("OTHER", &[0; 16]).unwrap();
+        Cursor::try_from(&other).unwrap_err();
+        let short = TaggedBase64::new(CURSOR_TAG, &[0; 4]).unwrap();
+        Cursor::try_from(&short).unwrap_err();
+    }
+}
@@ -873,6 +873,7 @@ pub mod node_tests {
                 missing_vid_shares: 1,
                 missing_leaves: 0,
                 pruned_height: None,
+                backfill: None,
             }
         );
 
@@ -887,6 +888,7 @@ pub mod node_tests {
                 missing_vid_shares: 3,
                 missing_leaves: 1,
                 pruned_height: None,
+                backfill: None,
             }
         );
 
@@ -904,6 +906,7 @@ pub mod node_tests {
                 missing_vid_shares: 3,
                 missing_leaves: 1,
                 pruned_height: None,
+                backfill: None,
             }
         );
 
@@ -945,6 +948,7 @@ pub mod node_tests {
             missing_vid_common: 0,
             missing_vid_shares: expected_missing,
             pruned_height: None,
+            backfill: None,
         };
         assert_eq!(ds.sync_status().await.unwrap(), expected_sync_status);
 
@@ -1032,14 +1036,23 @@ pub mod node_tests {
             for retry in 0..5 {
                 let ds_transactions = ds.count_transactions().await.unwrap();
                 let ds_payload_size = ds.payload_size().await.unwrap();
-                if ds_transactions != total_transactions || ds_payload_size != total_size {
+                // Mock blocks have a single namespace, so its statistics match the totals.
+                let ns_transactions = ds.count_transactions_in_range(.., Some(0)).await.unwrap();
+                let ns_payload_size = ds.payload_size_in_range(.., Some(0)).await.unwrap();
+                if ds_transactions != total_transactions
+                    || ds_payload_size != total_size
+                    || ns_transactions != total_transactions
+                    || ns_payload_size != total_size
+                {
                     tracing::info!(
                         i,
                         retry,
                         total_transactions,
                         ds_transactions,
+                        ns_transactions,
                         total_size,
                         ds_payload_size,
+                        ns_payload_size,
                         "waiting for statistics to update"
                     );
                     sleep(Duration::from_secs(1)).await;
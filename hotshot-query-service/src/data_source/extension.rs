@@ -25,11 +25,11 @@ use crate::{
         QueryablePayload, StateCertQueryData, TransactionHash, TransactionQueryData,
         UpdateAvailabilityData, VidCommonMetadata, VidCommonQueryData,
     },
-    data_source::storage::pruning::PrunedHeightDataSource,
+    data_source::storage::pruning::{PrunedData, PrunedHeightDataSource},
     explorer::{self, ExplorerDataSource, ExplorerHeader, ExplorerTransaction},
     merklized_state::{
-        MerklizedState, MerklizedStateDataSource, MerklizedStateHeightPersistence, Snapshot,
-        UpdateStateData,
+        EntryUpdate, MerklizedState, MerklizedStateDataSource, MerklizedStateHeightPersistence,
+        Snapshot, UpdateStateData,
     },
     metrics::PrometheusMetrics,
     node::{NodeDataSource, SyncStatus, TimeWindowQueryData, WindowStart},
@@ -150,6 +150,10 @@ where
     async fn load_pruned_height(&self) -> anyhow::Result<Option<u64>> {
         self.data_source.load_pruned_height().await
     }
+
+    async fn load_pruned_height_for(&self, data: PrunedData) -> anyhow::Result<Option<u64>> {
+        self.data_source.load_pruned_height_for(data).await
+    }
 }
 
 #[async_trait]
@@ -307,6 +311,10 @@ where
     async fn get_state_cert(&self, epoch: u64) -> Fetch<StateCertQueryData<Types>> {
         self.data_source.get_state_cert(epoch).await
     }
+
+    async fn pruned_height(&self, data: PrunedData) -> QueryResult<Option<u64>> {
+        self.data_source.pruned_height(data).await
+    }
 }
 
 impl<D, U, Types> UpdateAvailabilityData<Types> for ExtensibleDataSource<D, U>
@@ -404,6 +412,15 @@ where
     ) -> QueryResult<MerkleProof<State::Entry, State::Key, State::T, ARITY>> {
         self.data_source.get_path(snapshot, key).await
     }
+
+    async fn get_entry_history(
+        &self,
+        key: State::Key,
+        from: u64,
+        to: u64,
+    ) -> QueryResult<Vec<EntryUpdate<State::Entry>>> {
+        self.data_source.get_entry_history(key, from, to).await
+    }
 }
 
 #[async_trait]
@@ -508,6 +525,26 @@ where
     > {
         self.data_source.get_search_results(query).await
     }
+
+    async fn get_namespace_summaries(
+        &self,
+        request: explorer::query_data::GetNamespaceSummariesRequest,
+    ) -> Result<
+        Vec<explorer::query_data::NamespaceSummary<Types>>,
+        explorer::query_data::GetNamespaceSummariesError,
+    > {
+        self.data_source.get_namespace_summaries(request).await
+    }
+
+    async fn get_namespace_detail(
+        &self,
+        request: explorer::query_data::GetNamespaceDetailRequest<Types>,
+    ) -> Result<
+        explorer::query_data::NamespaceDetail<Types>,
+        explorer::query_data::GetNamespaceDetailError,
+    > {
+        self.data_source.get_namespace_detail(request).await
+    }
 }
 
 #[cfg(any(test, feature = "testing"))]
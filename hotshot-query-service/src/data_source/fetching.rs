@@ -54,6 +54,17 @@
 //! because it is rather unlikely that a major scan will discover any missing blocks that the next
 //! minor scan would have missed, it is ok if major scans run very infrequently.
 //!
+//! A node which is missing a large part of the history (for example, one that has just joined an
+//! existing network) can also [enable a backfill task](Builder::enable_backfill). Instead of
+//! fetching missing objects one at a time, this task requests them in large ranges from several
+//! providers at once, verifies each range against the hash chain, and stores whole ranges in a
+//! single transaction. Its progress is reported in the node's sync status.
+//!
+//! Separately, a node can [enable an integrity scan](Builder::enable_integrity_scan), which
+//! periodically checks the data already in storage against the hash chain and the commitments in
+//! each header, and reports anything which is inconsistent, such as rows left partially written by
+//! a crash.
+//!
 //! # Active Fetching
 //!
 //! Active fetching means reaching out to a remote data availability provider to retrieve a missing
@@ -75,6 +86,7 @@
 
 use std::{
     cmp::{max, min},
+    collections::BTreeMap,
     fmt::{Debug, Display},
     iter::repeat_with,
     marker::PhantomData,
@@ -84,9 +96,10 @@ use std::{
 };
 
 use anyhow::{bail, Context};
-use async_lock::Semaphore;
+use async_lock::{RwLock, Semaphore};
 use async_trait::async_trait;
 use backoff::{backoff::Backoff, ExponentialBackoff, ExponentialBackoffBuilder};
+use committable::Committable;
 use derivative::Derivative;
 use futures::{
     channel::oneshot,
@@ -96,6 +109,7 @@ use futures::{
 use hotshot_types::{
     data::VidShare,
     traits::{
+        block_contents::BlockHeader,
         metrics::{Gauge, Metrics},
         node_implementation::NodeType,
     },
@@ -108,7 +122,7 @@ use tracing::Instrument;
 use super::{
     notifier::Notifier,
     storage::{
-        pruning::{PruneStorage, PrunedHeightDataSource, PrunedHeightStorage},
+        pruning::{PruneStorage, PrunedData, PrunedHeightDataSource, PrunedHeightStorage},
         sql::MigrateTypes,
         Aggregate, AggregatesStorage, AvailabilityStorage, ExplorerStorage,
         MerklizedStateHeightStorage, MerklizedStateStorage, NodeStorage, UpdateAggregatesStorage,
@@ -127,28 +141,33 @@ use crate::{
     fetching::{
         self,
         request::{self, StateCertRequest},
-        Provider,
+        Provider, Request,
     },
     merklized_state::{
-        MerklizedState, MerklizedStateDataSource, MerklizedStateHeightPersistence, Snapshot,
+        EntryUpdate, MerklizedState, MerklizedStateDataSource, MerklizedStateHeightPersistence,
+        Snapshot,
     },
     metrics::PrometheusMetrics,
-    node::{NodeDataSource, SyncStatus, TimeWindowQueryData, WindowStart},
+    node::{BackfillStatus, NodeDataSource, SyncStatus, TimeWindowQueryData, WindowStart},
     status::{HasMetrics, StatusDataSource},
     task::BackgroundTask,
     types::HeightIndexed,
     Header, Payload, QueryError, QueryResult,
 };
 
+mod backfill;
 mod block;
 mod header;
+mod integrity;
 mod leaf;
 mod state_cert;
 mod transaction;
 mod vid;
 
 use self::{
+    backfill::{BackfillCfg, BackfillMetrics},
     block::PayloadFetcher,
+    integrity::{IntegrityCfg, IntegrityMetrics},
     leaf::LeafFetcher,
     transaction::TransactionRequest,
     vid::{VidCommonFetcher, VidCommonRequest},
@@ -172,6 +191,14 @@ pub struct Builder<Types, S, P> {
     aggregator_chunk_size: Option<usize>,
     types_migration_batch_size: u64,
     leaf_only: bool,
+    backfill: bool,
+    backfill_window_size: usize,
+    backfill_request_size: usize,
+    backfill_parallelism: usize,
+    backfill_interval: Duration,
+    integrity_scan: bool,
+    integrity_scan_batch_size: u64,
+    integrity_scan_interval: Duration,
     _types: PhantomData<Types>,
 }
 
@@ -210,6 +237,16 @@ impl<Types, S, P> Builder<Types, S, P> {
             aggregator_chunk_size: None,
             types_migration_batch_size: 10000,
             leaf_only: false,
+            backfill: false,
+            backfill_window_size: 1000,
+            // Match the default range limit for large objects in the availability API.
+            backfill_request_size: 100,
+            backfill_parallelism: 4,
+            backfill_interval: Duration::from_secs(3600),
+            integrity_scan: false,
+            integrity_scan_batch_size: 1000,
+            // A full scan reads the entire database, so by default we only run one a day.
+            integrity_scan_interval: Duration::from_secs(24 * 3600),
             _types: Default::default(),
         }
     }
@@ -379,6 +416,69 @@ impl<Types, S, P> Builder<Types, S, P> {
         self
     }
 
+    /// Run a task which backfills missing data in bulk, using range requests.
+    ///
+    /// This is much faster than [proactive fetching](self#proactive-fetching) for a node which is
+    /// missing a lot of data, such as a new node joining an existing network, provided the
+    /// configured providers support range requests. Proactive fetching still runs alongside the
+    /// backfill, and picks up anything the backfill was unable to fetch.
+    pub fn enable_backfill(mut self) -> Self {
+        self.backfill = true;
+        self
+    }
+
+    /// Set the number of blocks to backfill at a time.
+    ///
+    /// All the objects fetched for a window of this many blocks are verified together and written
+    /// to storage in a single transaction.
+    pub fn with_backfill_window_size(mut self, window_size: usize) -> Self {
+        self.backfill_window_size = window_size;
+        self
+    }
+
+    /// Set the number of objects to request from a provider at a time when backfilling.
+    ///
+    /// This should not exceed the range limits of the providers.
+    pub fn with_backfill_request_size(mut self, request_size: usize) -> Self {
+        self.backfill_request_size = request_size;
+        self
+    }
+
+    /// Set the number of simultaneous range requests when backfilling.
+    pub fn with_backfill_parallelism(mut self, parallelism: usize) -> Self {
+        self.backfill_parallelism = parallelism;
+        self
+    }
+
+    /// Set how often to check for missing data to backfill.
+    pub fn with_backfill_interval(mut self, interval: Duration) -> Self {
+        self.backfill_interval = interval;
+        self
+    }
+
+    /// Run a task which periodically checks the integrity of data in storage.
+    ///
+    /// Each scan checks every stored leaf, payload and VID common object against the hash chain and
+    /// the commitments in the corresponding headers. Inconsistencies are logged and reported in
+    /// metrics, but are not repaired automatically; see
+    /// [`integrity::repair`](super::storage::integrity::repair).
+    pub fn enable_integrity_scan(mut self) -> Self {
+        self.integrity_scan = true;
+        self
+    }
+
+    /// Set the number of blocks to check in each transaction during an integrity scan.
+    pub fn with_integrity_scan_batch_size(mut self, batch_size: u64) -> Self {
+        self.integrity_scan_batch_size = batch_size;
+        self
+    }
+
+    /// Set how often to run an integrity scan.
+    pub fn with_integrity_scan_interval(mut self, interval: Duration) -> Self {
+        self.integrity_scan_interval = interval;
+        self
+    }
+
     pub fn is_leaf_only(&self) -> bool {
         self.leaf_only
     }
@@ -434,7 +534,52 @@ where
     scanner: Option<BackgroundTask>,
     // The aggregator task, which derives aggregate statistics from a block stream.
     aggregator: Option<BackgroundTask>,
+    // The backfill task, which fetches missing data in bulk.
+    backfill: Option<BackgroundTask>,
+    // The integrity scanner, which checks data already in storage.
+    integrity_scan: Option<BackgroundTask>,
     pruner: Pruner<Types, S>,
+    pruned_heights: Arc<PrunedHeights>,
+}
+
+/// The pruned height of each kind of data, cached in memory.
+///
+/// Pruned heights only change when the pruner runs, so requests can check whether an object has
+/// been pruned without a storage query each time. The pruner invalidates the cache after each batch
+/// it prunes.
+#[derive(Debug, Default)]
+struct PrunedHeights(parking_lot::Mutex<PrunedHeightsInner>);
+
+#[derive(Debug, Default)]
+struct PrunedHeightsInner {
+    /// Incremented each time the cache is invalidated, so that a height loaded from storage before
+    /// the pruner ran is not cached after it.
+    generation: u64,
+    heights: BTreeMap<PrunedData, Option<u64>>,
+}
+
+impl PrunedHeights {
+    /// Get the cached pruned height for `data`.
+    ///
+    /// On a cache miss, returns the current generation, which must be passed to
+    /// [`insert`](Self::insert) along with the height loaded from storage.
+    fn get(&self, data: PrunedData) -> Result<Option<u64>, u64> {
+        let inner = self.0.lock();
+        inner.heights.get(&data).copied().ok_or(inner.generation)
+    }
+
+    fn insert(&self, generation: u64, data: PrunedData, height: Option<u64>) {
+        let mut inner = self.0.lock();
+        if inner.generation == generation {
+            inner.heights.insert(data, height);
+        }
+    }
+
+    fn invalidate(&self) {
+        let mut inner = self.0.lock();
+        inner.generation += 1;
+        inner.heights.clear();
+    }
 }
 
 #[derive(Derivative)]
@@ -454,7 +599,7 @@ where
     Payload<Types>: QueryablePayload<Types>,
     S: PruneStorage + Send + Sync + 'static,
 {
-    async fn new(storage: Arc<S>) -> Self {
+    async fn new(storage: Arc<S>, pruned_heights: Arc<PrunedHeights>) -> Self {
         let cfg = storage.get_pruning_config();
         let Some(cfg) = cfg else {
             return Self {
@@ -466,7 +611,7 @@ where
         let future = async move {
             for i in 1.. {
                 tracing::warn!("starting pruner run {i} ");
-                Self::prune(storage.clone()).await;
+                Self::prune(storage.clone(), &pruned_heights).await;
                 sleep(cfg.interval()).await;
             }
         };
@@ -479,11 +624,13 @@ where
         }
     }
 
-    async fn prune(storage: Arc<S>) {
+    async fn prune(storage: Arc<S>, pruned_heights: &PrunedHeights) {
         // We loop until the whole run pruner run is complete
         let mut pruner = S::Pruner::default();
         loop {
-            match storage.prune(&mut pruner).await {
+            let res = storage.prune(&mut pruner).await;
+            pruned_heights.invalidate();
+            match res {
                 Ok(Some(height)) => {
                     tracing::warn!("Pruned to height {height}");
                 },
@@ -534,6 +681,20 @@ where
         let migration_batch_size = builder.types_migration_batch_size;
         let scanner_metrics = ScannerMetrics::new(builder.storage.metrics());
         let aggregator_metrics = AggregatorMetrics::new(builder.storage.metrics());
+        let backfill = builder.backfill;
+        let backfill_cfg = BackfillCfg {
+            window_size: builder.backfill_window_size,
+            request_size: builder.backfill_request_size,
+            parallelism: builder.backfill_parallelism,
+            interval: builder.backfill_interval,
+        };
+        let backfill_metrics = BackfillMetrics::new(builder.storage.metrics());
+        let integrity_scan = builder.integrity_scan;
+        let integrity_cfg = IntegrityCfg {
+            batch_size: builder.integrity_scan_batch_size,
+            interval: builder.integrity_scan_interval,
+        };
+        let integrity_metrics = IntegrityMetrics::new(builder.storage.metrics());
 
         let fetcher = Arc::new(Fetcher::new(builder).await?);
 
@@ -569,14 +730,38 @@ where
             None
         };
 
+        let backfill = if backfill {
+            Some(BackgroundTask::spawn(
+                "backfill",
+                fetcher.clone().backfill(backfill_cfg, backfill_metrics),
+            ))
+        } else {
+            None
+        };
+
+        let integrity_scan = if integrity_scan && !leaf_only {
+            Some(BackgroundTask::spawn(
+                "integrity scan",
+                fetcher
+                    .clone()
+                    .integrity_scan(integrity_cfg, integrity_metrics),
+            ))
+        } else {
+            None
+        };
+
         let storage = fetcher.storage.clone();
 
-        let pruner = Pruner::new(storage).await;
+        let pruned_heights = Arc::new(PrunedHeights::default());
+        let pruner = Pruner::new(storage, pruned_heights.clone()).await;
         let ds = Self {
             fetcher,
             scanner,
             pruner,
+            pruned_heights,
             aggregator,
+            backfill,
+            integrity_scan,
         };
 
         Ok(ds)
@@ -636,6 +821,11 @@ where
         let mut tx = self.read().await?;
         tx.load_pruned_height().await
     }
+
+    async fn load_pruned_height_for(&self, data: PrunedData) -> anyhow::Result<Option<u64>> {
+        let mut tx = self.read().await?;
+        tx.load_pruned_height_for(data).await
+    }
 }
 
 #[async_trait]
@@ -815,6 +1005,24 @@ where
     async fn get_state_cert(&self, epoch: u64) -> Fetch<StateCertQueryData<Types>> {
         self.fetcher.get(StateCertRequest::from(epoch)).await
     }
+
+    async fn pruned_height(&self, data: PrunedData) -> QueryResult<Option<u64>> {
+        let generation = match self.pruned_heights.get(data) {
+            Ok(height) => return Ok(height),
+            Err(generation) => generation,
+        };
+        let mut tx = self.read().await.map_err(|err| QueryError::Error {
+            message: err.to_string(),
+        })?;
+        let height = tx
+            .load_pruned_height_for(data)
+            .await
+            .map_err(|err| QueryError::Error {
+                message: err.to_string(),
+            })?;
+        self.pruned_heights.insert(generation, data, height);
+        Ok(height)
+    }
 }
 
 impl<Types, S, P> UpdateAvailabilityData<Types> for FetchingDataSource<Types, S, P>
@@ -906,6 +1114,8 @@ where
     // retry failed loads.
     retry_semaphore: Arc<Semaphore>,
     leaf_only: bool,
+    // Progress of the backfill task, if it is running.
+    backfill_status: RwLock<Option<BackfillStatus>>,
 }
 
 impl<Types, S, P> VersionedDataSource for Fetcher<Types, S, P>
@@ -974,6 +1184,7 @@ where
             backoff,
             retry_semaphore,
             leaf_only,
+            backfill_status: Default::default(),
         })
     }
 }
@@ -1416,7 +1627,7 @@ where
         tracing::debug!("fetching resource {req:?}");
 
         // Trigger an active fetch from a remote provider if possible.
-        let heights = Heights::load(tx)
+        let heights = Heights::load(tx, T::pruned_data())
             .await
             .context("failed to load heights; cannot definitively say object might exist")?;
         if req.might_exist(heights) {
@@ -1453,9 +1664,9 @@ where
                 let max_backoff = Duration::from_secs(60);
                 metrics.backoff.set(backoff.as_secs() as usize);
 
-                // We can't start the scan until we know the current block height and pruned height,
-                // so we know which blocks to scan. Thus we retry until this succeeds.
-                let heights = loop {
+                // We can't start the scan until we know the current block height and pruned
+                // heights, so we know which blocks to scan. Thus we retry until this succeeds.
+                let (heights, vid_heights) = loop {
                     let mut tx = match self.read().await {
                         Ok(tx) => tx,
                         Err(err) => {
@@ -1470,7 +1681,14 @@ where
                             continue;
                         },
                     };
-                    let heights = match Heights::load(&mut tx).await {
+                    let heights = async {
+                        Ok::<_, anyhow::Error>((
+                            Heights::load(&mut tx, PrunedData::Payload).await?,
+                            Heights::load(&mut tx, PrunedData::VidCommon).await?,
+                        ))
+                    }
+                    .await;
+                    let heights = match heights {
                         Ok(heights) => heights,
                         Err(err) => {
                             tracing::error!(?backoff, "unable to load heights: {err:#}");
@@ -1486,8 +1704,10 @@ where
                 };
 
                 // Get the pruned height or default to 0 if it is not set. We will not look for
-                // blocks older than the pruned height.
+                // blocks older than the pruned height. Payloads and VID may have been pruned
+                // separately, so each has its own minimum height.
                 let minimum_block_height = heights.pruned_height.unwrap_or(0) as usize;
+                let minimum_vid_height = vid_heights.pruned_height.unwrap_or(0) as usize;
                 // Get the block height; we will look for any missing blocks up to `block_height`.
                 let block_height = heights.height as usize;
 
@@ -1513,6 +1733,11 @@ where
                     tracing::info!(start = prev_height, block_height, "starting minor scan");
                     prev_height
                 };
+                let vid_start = if major {
+                    minimum_vid_height
+                } else {
+                    max(start, minimum_vid_height)
+                };
                 prev_height = block_height;
                 metrics.current_start.set(start);
                 metrics.current_end.set(block_height);
@@ -1558,7 +1783,7 @@ where
                     .clone()
                     .get_range_with_chunk_size_rev::<VidCommonMetadata<Types>>(
                         chunk_size,
-                        Bound::Included(start),
+                        Bound::Included(vid_start),
                         block_height.saturating_sub(1),
                     );
                 let mut missing_vid = 0;
@@ -1786,7 +2011,8 @@ struct Heights {
 }
 
 impl Heights {
-    async fn load<Types, T>(tx: &mut T) -> anyhow::Result<Self>
+    /// Load the block height and the pruned height for `data`.
+    async fn load<Types, T>(tx: &mut T, data: PrunedData) -> anyhow::Result<Self>
     where
         Types: NodeType,
         Header<Types>: QueryableHeader<Types>,
@@ -1794,7 +2020,7 @@ impl Heights {
     {
         let height = tx.block_height().await.context("loading block height")? as u64;
         let pruned_height = tx
-            .load_pruned_height()
+            .load_pruned_height_for(data)
             .await
             .context("loading pruned height")?;
         Ok(Self {
@@ -1829,6 +2055,18 @@ where
         })?;
         tx.get_path(snapshot, key).await
     }
+
+    async fn get_entry_history(
+        &self,
+        key: State::Key,
+        from: u64,
+        to: u64,
+    ) -> QueryResult<Vec<EntryUpdate<State::Entry>>> {
+        let mut tx = self.read().await.map_err(|err| QueryError::Error {
+            message: err.to_string(),
+        })?;
+        tx.get_entry_history(key, from, to).await
+    }
 }
 
 #[async_trait]
@@ -1901,7 +2139,11 @@ where
         let mut tx = self.read().await.map_err(|err| QueryError::Error {
             message: err.to_string(),
         })?;
-        tx.sync_status().await
+        let status = tx.sync_status().await?;
+        Ok(SyncStatus {
+            backfill: self.fetcher.backfill_status.read().await.clone(),
+            ..status
+        })
     }
 
     async fn get_header_window(
@@ -2002,9 +2244,41 @@ where
         })?;
         tx.get_search_results(query).await
     }
+
+    async fn get_namespace_summaries(
+        &self,
+        request: explorer::query_data::GetNamespaceSummariesRequest,
+    ) -> Result<
+        Vec<explorer::query_data::NamespaceSummary<Types>>,
+        explorer::query_data::GetNamespaceSummariesError,
+    > {
+        let mut tx = self.read().await.map_err(|err| QueryError::Error {
+            message: err.to_string(),
+        })?;
+        tx.get_namespace_summaries(request).await
+    }
+
+    async fn get_namespace_detail(
+        &self,
+        request: explorer::query_data::GetNamespaceDetailRequest<Types>,
+    ) -> Result<
+        explorer::query_data::NamespaceDetail<Types>,
+        explorer::query_data::GetNamespaceDetailError,
+    > {
+        let mut tx = self.read().await.map_err(|err| QueryError::Error {
+            message: err.to_string(),
+        })?;
+        tx.get_namespace_detail(request).await
+    }
 }
 
 /// A provider which can be used as a fetcher by the availability service.
+///
+/// Besides fetching individual objects, an availability provider can fetch whole ranges of objects,
+/// which is used by the [backfill](Builder::enable_backfill) task. The default implementations of
+/// the range methods fall back to requesting each object individually, so a provider only needs to
+/// override them if it can serve ranges more efficiently.
+#[async_trait]
 pub trait AvailabilityProvider<Types: NodeType>:
     Provider<Types, request::LeafRequest<Types>>
     + Provider<Types, request::PayloadRequest>
@@ -2012,14 +2286,98 @@ pub trait AvailabilityProvider<Types: NodeType>:
     + Sync
     + 'static
 {
-}
-impl<Types: NodeType, P> AvailabilityProvider<Types> for P where
-    P: Provider<Types, request::LeafRequest<Types>>
-        + Provider<Types, request::PayloadRequest>
-        + Provider<Types, request::VidCommonRequest>
-        + Sync
-        + 'static
-{
+    /// Fetch a range of leaves.
+    ///
+    /// `last`, if known, is a request for the last leaf in the range. The default implementation
+    /// needs it to fetch the leaves one at a time, following the chain of parent hashes down from
+    /// `last`, and fails without it.
+    ///
+    /// The response is not fully verified. The caller is responsible for checking that the leaves
+    /// form a chain ending in a known leaf.
+    async fn fetch_leaf_range(
+        &self,
+        req: request::LeafRangeRequest,
+        last: Option<request::LeafRequest<Types>>,
+    ) -> Option<Vec<LeafQueryData<Types>>> {
+        let mut next = last.filter(|last| last.height + 1 == req.end)?;
+        let mut leaves = vec![];
+        loop {
+            let leaf = self.fetch(next).await?;
+            if !next.is_valid_response(&leaf) {
+                return None;
+            }
+            if leaf.height() <= req.start {
+                leaves.push(leaf);
+                break;
+            }
+            next = request::LeafRequest::new(
+                leaf.height() - 1,
+                leaf.leaf().parent_commitment(),
+                leaf.leaf().justify_qc().commit(),
+            );
+            leaves.push(leaf);
+        }
+        leaves.reverse();
+        Some(leaves)
+    }
+
+    /// Fetch a range of blocks.
+    ///
+    /// `headers` are the headers of the requested blocks, in order. The default implementation
+    /// needs them to fetch each payload individually.
+    ///
+    /// The response is not fully verified. The caller is responsible for checking the blocks
+    /// against known headers.
+    async fn fetch_block_range(
+        &self,
+        req: request::BlockRangeRequest,
+        headers: &[Header<Types>],
+    ) -> Option<Vec<BlockQueryData<Types>>>
+    where
+        Header<Types>: QueryableHeader<Types>,
+        Payload<Types>: QueryablePayload<Types>,
+    {
+        if headers.len() as u64 != req.end.saturating_sub(req.start) {
+            return None;
+        }
+        join_all(headers.iter().map(|header| async move {
+            let payload = self
+                .fetch(request::PayloadRequest(header.payload_commitment()))
+                .await?;
+            Some(BlockQueryData::new(header.clone(), payload))
+        }))
+        .await
+        .into_iter()
+        .collect()
+    }
+
+    /// Fetch VID common data for a range of blocks.
+    ///
+    /// `headers` are the headers of the requested blocks, in order. The default implementation
+    /// needs them to fetch the VID common data for each block individually.
+    ///
+    /// The response is not fully verified. The caller is responsible for checking it against known
+    /// headers.
+    async fn fetch_vid_common_range(
+        &self,
+        req: request::VidCommonRangeRequest,
+        headers: &[Header<Types>],
+    ) -> Option<Vec<VidCommonQueryData<Types>>> {
+        if headers.len() as u64 != req.end.saturating_sub(req.start) {
+            return None;
+        }
+        join_all(headers.iter().map(|header| async move {
+            let req = request::VidCommonRequest(header.payload_commitment());
+            let common = self.fetch(req).await?;
+            if !<request::VidCommonRequest as Request<Types>>::is_valid_response(&req, &common) {
+                return None;
+            }
+            Some(VidCommonQueryData::new(header.clone(), common))
+        }))
+        .await
+        .into_iter()
+        .collect()
+    }
 }
 
 trait FetchRequest: Copy + Debug + Send + Sync + 'static {
@@ -2056,6 +2414,13 @@ where
     /// Does this object satisfy the given request?
     fn satisfies(&self, req: Self::Request) -> bool;
 
+    /// The kind of data this object is pruned with.
+    ///
+    /// Objects below the pruned height for this kind of data are never fetched.
+    fn pruned_data() -> PrunedData {
+        PrunedData::Leaf
+    }
+
     /// Spawn a task to fetch the object from a remote provider, if possible.
     ///
     /// An active fetch will only be triggered if:
@@ -2458,4 +2823,24 @@ mod test {
             [3..5, 1..3]
         );
     }
+
+    #[test]
+    fn test_pruned_heights_cache() {
+        let cache = PrunedHeights::default();
+        let generation = cache.get(PrunedData::Payload).unwrap_err();
+        cache.insert(generation, PrunedData::Payload, None);
+        assert_eq!(cache.get(PrunedData::Payload), Ok(None));
+
+        // Once the pruner runs, heights must be loaded again.
+        cache.invalidate();
+        let generation = cache.get(PrunedData::Payload).unwrap_err();
+
+        // A height which was loaded before the pruner ran is not cached.
+        cache.invalidate();
+        cache.insert(generation, PrunedData::Payload, Some(5));
+        let generation = cache.get(PrunedData::Payload).unwrap_err();
+        cache.insert(generation, PrunedData::Payload, Some(10));
+        assert_eq!(cache.get(PrunedData::Payload), Ok(Some(10)));
+        cache.get(PrunedData::Leaf).unwrap_err();
+    }
 }
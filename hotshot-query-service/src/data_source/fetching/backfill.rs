@@ -0,0 +1,663 @@
+// Copyright (c) 2022 Espresso Systems (espressosys.com)
+// This file is part of the HotShot Query Service library.
+//
+// This program is free software: you can redistribute it and/or modify it under the terms of the GNU
+// General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+// This program is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without
+// even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+// You should have received a copy of the GNU General Public License along with this program. If not,
+// see <https://www.gnu.org/licenses/>.
+
+//! Bulk backfill of missing data using range requests.
+//!
+//! The [proactive scanner](super#proactive-fetching) fetches missing objects one at a time, which
+//! is slow when a node is missing a large part of the history. The backfill task instead walks the
+//! chain backwards from the current block height in large windows. For each window, it finds the
+//! objects missing from storage and requests them in ranges, with several range requests in flight
+//! at once. When the provider is an [`AnyProvider`](crate::fetching::provider::AnyProvider), these
+//! requests are spread over all of its underlying providers. Providers which cannot serve ranges
+//! fall back to fetching each object individually (see [`AvailabilityProvider`]).
+//!
+//! Responses to range requests are not trusted. Leaves are checked in bulk against the hash chain
+//! ending in a leaf we already have, and VID common data and payloads are checked against the
+//! verified headers. Anything that fails these checks is requested again (usually from a different
+//! provider), and if it still cannot be verified it is left for the proactive scanner. All objects
+//! fetched for a window are then written in a single transaction.
+
+use std::{
+    cmp::max,
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use backoff::backoff::Backoff;
+use committable::Committable;
+use futures::{
+    future::Future,
+    stream::{self, StreamExt},
+};
+use hotshot_types::traits::{
+    block_contents::BlockHeader,
+    metrics::{Counter, Gauge},
+    node_implementation::NodeType,
+};
+use tokio::time::sleep;
+
+use super::{AvailabilityProvider, Fetcher, Heights, Storable};
+use crate::{
+    availability::{
+        BlockId, BlockQueryData, LeafHash, LeafQueryData, QueryableHeader, QueryablePayload,
+        VidCommonQueryData,
+    },
+    data_source::{
+        storage::{
+            archive::{verify_payload, verify_vid_common},
+            pruning::{PrunedData, PrunedHeightStorage},
+            AvailabilityStorage, NodeStorage, UpdateAvailabilityStorage,
+        },
+        Transaction, VersionedDataSource,
+    },
+    fetching::request::LeafRequest,
+    metrics::PrometheusMetrics,
+    node::BackfillStatus,
+    types::HeightIndexed,
+    Header, Payload, VidCommon,
+};
+
+/// The number of times to request objects which are missing or fail verification.
+const FETCH_ATTEMPTS: usize = 3;
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct BackfillCfg {
+    pub(super) window_size: usize,
+    pub(super) request_size: usize,
+    pub(super) parallelism: usize,
+    pub(super) interval: Duration,
+}
+
+/// Objects fetched and verified for a single window.
+struct Window<Types: NodeType> {
+    leaves: Vec<LeafQueryData<Types>>,
+    vid_common: Vec<VidCommonQueryData<Types>>,
+    blocks: Vec<BlockQueryData<Types>>,
+}
+
+impl<Types: NodeType> Window<Types> {
+    fn len(&self) -> usize {
+        self.leaves.len() + self.vid_common.len() + self.blocks.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<Types, S, P> Fetcher<Types, S, P>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    S: VersionedDataSource + 'static,
+    for<'a> S::Transaction<'a>: UpdateAvailabilityStorage<Types>,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<Types> + NodeStorage<Types> + PrunedHeightStorage,
+    P: AvailabilityProvider<Types>,
+{
+    /// Repeatedly backfill all missing data.
+    ///
+    /// This function will run until cancelled, thus, it is meant to be spawned as a background
+    /// task rather than called synchronously.
+    pub(super) async fn backfill(self: Arc<Self>, cfg: BackfillCfg, metrics: BackfillMetrics) {
+        loop {
+            match self.backfill_pass(&cfg, &metrics).await {
+                Ok(()) => {
+                    tracing::info!("backfill complete, will check again in {:?}", cfg.interval)
+                },
+                Err(err) => {
+                    tracing::warn!("backfill failed, will retry in {:?}: {err:#}", cfg.interval)
+                },
+            }
+            *self.backfill_status.write().await = None;
+            metrics.running.set(0);
+            sleep(cfg.interval).await;
+        }
+    }
+
+    async fn backfill_pass(
+        &self,
+        cfg: &BackfillCfg,
+        metrics: &BackfillMetrics,
+    ) -> anyhow::Result<()> {
+        let (heights, sync_status) = {
+            let mut tx = self.read().await.context("opening transaction")?;
+            let heights = Heights::load(&mut tx, PrunedData::Leaf).await?;
+            let sync_status = tx.sync_status().await.context("loading sync status")?;
+            (heights, sync_status)
+        };
+        // Don't bother scanning the whole chain if nothing is missing. We cannot fetch VID shares,
+        // so don't count those.
+        let missing = if self.leaf_only {
+            sync_status.missing_leaves
+        } else {
+            sync_status.missing_leaves + sync_status.missing_blocks + sync_status.missing_vid_common
+        };
+        if missing == 0 {
+            return Ok(());
+        }
+
+        // We will not look for blocks older than the pruned height.
+        let start = heights.pruned_height.map(|h| h + 1).unwrap_or(0);
+        let end = heights.height;
+        if end <= start {
+            return Ok(());
+        }
+
+        tracing::warn!(start, end, missing, "starting backfill");
+        metrics.running.set(1);
+        metrics.start.set(start as usize);
+        metrics.end.set(end as usize);
+        metrics.processed.set(0);
+        let mut status = BackfillStatus {
+            start,
+            end,
+            processed: 0,
+            fetched: 0,
+            eta_secs: None,
+        };
+        *self.backfill_status.write().await = Some(status.clone());
+
+        // Work backwards from the end of the chain, since we need each leaf's child to tell us what
+        // its hash should be. `next` is the request for the leaf just below the window.
+        let started = Instant::now();
+        let mut next = None;
+        let mut window_end = end;
+        while window_end > start {
+            let window = max(start, window_end.saturating_sub(cfg.window_size as u64))..window_end;
+            next = self
+                .backfill_window(window.clone(), next, cfg, metrics, &mut status)
+                .await
+                .with_context(|| format!("backfilling blocks {window:?}"))?;
+            window_end = window.start;
+
+            // Estimate the remaining time based on the rate of progress so far.
+            status.processed = end - window_end;
+            status.eta_secs =
+                Some(started.elapsed().as_secs() * (window_end - start) / status.processed);
+            metrics.processed.set(status.processed as usize);
+            metrics.eta.set(status.eta_secs.unwrap_or(0) as usize);
+            *self.backfill_status.write().await = Some(status.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Fetch, verify, and store missing objects in `range`.
+    ///
+    /// `next` is a request for the leaf at the end of `range`, if known. Returns a request for the
+    /// leaf just below `range`, if known.
+    async fn backfill_window(
+        &self,
+        range: Range<u64>,
+        next: Option<LeafRequest<Types>>,
+        cfg: &BackfillCfg,
+        metrics: &BackfillMetrics,
+        status: &mut BackfillStatus,
+    ) -> anyhow::Result<Option<LeafRequest<Types>>> {
+        // Find out what we already have, and what we don't need because it has been pruned.
+        let (mut leaves, have_vid, have_payloads, vid_pruned_height, payload_pruned_height) = {
+            let mut tx = self.read().await.context("opening transaction")?;
+            let heights = range.start as usize..range.end as usize;
+            let leaves = tx
+                .get_leaf_range(heights.clone())
+                .await
+                .context("loading leaves")?
+                .into_iter()
+                .filter_map(Result::ok)
+                .map(|leaf| (leaf.height(), leaf))
+                .collect::<BTreeMap<_, _>>();
+            if self.leaf_only {
+                (leaves, BTreeSet::new(), BTreeSet::new(), None, None)
+            } else {
+                let vid_pruned_height = tx
+                    .load_pruned_height_for(PrunedData::VidCommon)
+                    .await
+                    .context("loading VID pruned height")?;
+                let payload_pruned_height = tx
+                    .load_pruned_height_for(PrunedData::Payload)
+                    .await
+                    .context("loading payload pruned height")?;
+                let vid = tx
+                    .get_vid_common_metadata_range(heights.clone())
+                    .await
+                    .context("loading VID common metadata")?
+                    .into_iter()
+                    .filter_map(Result::ok)
+                    .map(|vid| vid.height())
+                    .collect::<BTreeSet<_>>();
+                let payloads = tx
+                    .get_payload_metadata_range(heights)
+                    .await
+                    .context("loading payload metadata")?
+                    .into_iter()
+                    .filter_map(Result::ok)
+                    .map(|payload| payload.height())
+                    .collect::<BTreeSet<_>>();
+                (
+                    leaves,
+                    vid,
+                    payloads,
+                    vid_pruned_height,
+                    payload_pruned_height,
+                )
+            }
+        };
+
+        // Fetch missing leaves, checking that they form a chain with the leaves we already have.
+        // The leaf just above each requested range, if we have it, tells us what the last leaf in
+        // the range should be, which providers that cannot serve ranges need in order to fetch the
+        // leaves one at a time.
+        let missing = range.clone().filter(|h| !leaves.contains_key(h)).collect();
+        let new_leaves = self
+            .fetch_verified(
+                cfg,
+                metrics,
+                missing,
+                &mut leaves,
+                |leaves, missing| {
+                    let last = match leaves.get(&missing.end) {
+                        Some(leaf) => parent_request(leaf),
+                        None if missing.end == range.end => next,
+                        None => None,
+                    };
+                    self.provider.fetch_leaf_range(missing.into(), last)
+                },
+                |leaves, fetched| {
+                    verify_leaves(
+                        range.clone(),
+                        next.map(|req| req.expected_leaf),
+                        leaves,
+                        fetched,
+                    )
+                },
+            )
+            .await;
+
+        // The request for the leaf below this window comes from the lowest leaf, if we have it.
+        let next = leaves.get(&range.start).and_then(parent_request);
+
+        let mut window = Window {
+            leaves: new_leaves,
+            vid_common: vec![],
+            blocks: vec![],
+        };
+        if !self.leaf_only {
+            // Fetch missing VID common data for blocks whose headers we know.
+            let missing = leaves
+                .keys()
+                .filter(|&&h| !have_vid.contains(&h) && vid_pruned_height.is_none_or(|ph| h > ph))
+                .copied()
+                .collect();
+            window.vid_common = self
+                .fetch_verified(
+                    cfg,
+                    metrics,
+                    missing,
+                    &mut leaves,
+                    |leaves, missing| {
+                        let headers = headers(leaves, missing.clone());
+                        let provider = &self.provider;
+                        async move {
+                            provider
+                                .fetch_vid_common_range(missing.into(), &headers)
+                                .await
+                        }
+                    },
+                    |leaves, fetched| verify_vid(leaves, fetched),
+                )
+                .await;
+
+            // To check a payload, we need the corresponding VID common data. Use the data we just
+            // fetched, and load the rest from storage.
+            let mut commons = window
+                .vid_common
+                .iter()
+                .map(|vid| (vid.height(), vid.common().clone()))
+                .collect::<BTreeMap<_, _>>();
+            let missing_payloads = leaves
+                .keys()
+                .filter(|&&h| {
+                    !have_payloads.contains(&h) && payload_pruned_height.is_none_or(|ph| h > ph)
+                })
+                .copied()
+                .collect::<Vec<_>>();
+            if !missing_payloads.is_empty() {
+                let mut tx = self.read().await.context("opening transaction")?;
+                for &h in &missing_payloads {
+                    if commons.contains_key(&h) || !have_vid.contains(&h) {
+                        continue;
+                    }
+                    match tx.get_vid_common(BlockId::Number(h as usize)).await {
+                        Ok(vid) => {
+                            commons.insert(h, vid.common().clone());
+                        },
+                        Err(err) => {
+                            tracing::info!(h, "unable to load VID common data: {err:#}");
+                        },
+                    }
+                }
+            }
+
+            let missing = missing_payloads
+                .into_iter()
+                .filter(|h| commons.contains_key(h))
+                .collect();
+            window.blocks = self
+                .fetch_verified(
+                    cfg,
+                    metrics,
+                    missing,
+                    &mut leaves,
+                    |leaves, missing| {
+                        let headers = headers(leaves, missing.clone());
+                        let provider = &self.provider;
+                        async move { provider.fetch_block_range(missing.into(), &headers).await }
+                    },
+                    |leaves, fetched| verify_blocks(leaves, &commons, fetched),
+                )
+                .await;
+        }
+
+        metrics.fetched_leaves.add(window.leaves.len());
+        metrics.fetched_vid.add(window.vid_common.len());
+        metrics.fetched_blocks.add(window.blocks.len());
+        status.fetched += window.len() as u64;
+        if !window.is_empty() {
+            tracing::info!(
+                ?range,
+                leaves = window.leaves.len(),
+                vid_common = window.vid_common.len(),
+                blocks = window.blocks.len(),
+                "backfilled window"
+            );
+            self.store_and_notify_window(window).await;
+        }
+
+        Ok(next)
+    }
+
+    /// Fetch the objects at the `missing` heights in ranges, keeping only those accepted by
+    /// `verify`.
+    ///
+    /// `fetch` requests a range of objects, and `verify` checks the fetched objects. Both have
+    /// access to `state`, which `verify` may update with what it learns from the objects it
+    /// accepts. Objects which are not returned by any provider or which are rejected by `verify`
+    /// are requested again, up to [`FETCH_ATTEMPTS`] times.
+    async fn fetch_verified<T, St, F>(
+        &self,
+        cfg: &BackfillCfg,
+        metrics: &BackfillMetrics,
+        mut missing: BTreeSet<u64>,
+        state: &mut St,
+        fetch: impl Fn(&St, Range<u64>) -> F,
+        mut verify: impl FnMut(&mut St, BTreeMap<u64, T>) -> Vec<T>,
+    ) -> Vec<T>
+    where
+        F: Future<Output = Option<Vec<T>>>,
+        T: HeightIndexed + Send,
+    {
+        let mut verified = vec![];
+        for _ in 0..FETCH_ATTEMPTS {
+            if missing.is_empty() {
+                break;
+            }
+
+            let requests = missing_ranges(&missing, cfg.request_size as u64)
+                .into_iter()
+                .map(|range| fetch(state, range))
+                .collect::<Vec<_>>();
+            let fetched = stream::iter(requests)
+                .buffer_unordered(cfg.parallelism)
+                .flat_map(|objs| stream::iter(objs.unwrap_or_default()))
+                .filter(|obj| futures::future::ready(missing.contains(&obj.height())))
+                .map(|obj| (obj.height(), obj))
+                .collect::<BTreeMap<_, _>>()
+                .await;
+            let num_fetched = fetched.len();
+
+            let objs = verify(state, fetched);
+            metrics.invalid.add(num_fetched.saturating_sub(objs.len()));
+            for obj in &objs {
+                missing.remove(&obj.height());
+            }
+            verified.extend(objs);
+        }
+        verified
+    }
+
+    /// Store all the objects fetched for a window in a single transaction and notify anyone
+    /// waiting for them.
+    ///
+    /// This follows the same rules as [`store_and_notify`](Self::store_and_notify): we notify
+    /// regardless of whether storing succeeded, and only after trying to store.
+    async fn store_and_notify_window(&self, window: Window<Types>) {
+        let try_store = || async {
+            let mut tx = self.storage.write().await?;
+            // Store leaves first, since other objects refer to them.
+            for leaf in &window.leaves {
+                leaf.clone().store(&mut tx, self.leaf_only).await?;
+            }
+            for common in &window.vid_common {
+                common.clone().store(&mut tx, self.leaf_only).await?;
+            }
+            for block in &window.blocks {
+                block.clone().store(&mut tx, self.leaf_only).await?;
+            }
+            tx.commit().await
+        };
+
+        let mut backoff = self.backoff.clone();
+        backoff.reset();
+        loop {
+            let Err(err) = try_store().await else {
+                break;
+            };
+            tracing::warn!(
+                objects = window.len(),
+                "failed to store backfilled window: {err:#}"
+            );
+
+            let Some(delay) = backoff.next_backoff() else {
+                break;
+            };
+            tracing::info!(?delay, "retrying failed operation");
+            sleep(delay).await;
+        }
+
+        for leaf in &window.leaves {
+            leaf.notify(&self.notifiers).await;
+        }
+        for common in &window.vid_common {
+            common.notify(&self.notifiers).await;
+        }
+        for block in &window.blocks {
+            block.notify(&self.notifiers).await;
+        }
+    }
+}
+
+/// Check fetched leaves against the hash chain.
+///
+/// Walks `range` backwards, starting from a leaf whose hash is `parent`, and accepts each fetched
+/// leaf whose hash matches the parent commitment of the leaf above it. Leaves already in storage
+/// are trusted, and re-anchor the chain if it was broken by a leaf we could not fetch. Accepted
+/// leaves are added to `leaves` and returned.
+fn verify_leaves<Types: NodeType>(
+    range: Range<u64>,
+    mut parent: Option<LeafHash<Types>>,
+    leaves: &mut BTreeMap<u64, LeafQueryData<Types>>,
+    mut fetched: BTreeMap<u64, LeafQueryData<Types>>,
+) -> Vec<LeafQueryData<Types>> {
+    let mut verified = vec![];
+    for h in range.rev() {
+        if let Some(leaf) = leaves.get(&h) {
+            parent = Some(leaf.leaf().parent_commitment());
+            continue;
+        }
+        let Some(leaf) = fetched.remove(&h) else {
+            parent = None;
+            continue;
+        };
+        if parent != Some(leaf.hash()) {
+            tracing::warn!(h, ?parent, hash = %leaf.hash(), "fetched leaf is not in the chain");
+            parent = None;
+            continue;
+        }
+        if let Err(err) = LeafQueryData::new(leaf.leaf().clone(), leaf.qc().clone()) {
+            tracing::warn!(h, "fetched leaf is not certified by its QC: {err:#}");
+            parent = None;
+            continue;
+        }
+
+        parent = Some(leaf.leaf().parent_commitment());
+        leaves.insert(h, leaf.clone());
+        verified.push(leaf);
+    }
+    verified
+}
+
+/// A request for the parent of `leaf`, if it has one.
+fn parent_request<Types: NodeType>(leaf: &LeafQueryData<Types>) -> Option<LeafRequest<Types>> {
+    let height = leaf.height().checked_sub(1)?;
+    Some(LeafRequest::new(
+        height,
+        leaf.leaf().parent_commitment(),
+        leaf.leaf().justify_qc().commit(),
+    ))
+}
+
+/// The headers of the verified leaves in `range`.
+fn headers<Types: NodeType>(
+    leaves: &BTreeMap<u64, LeafQueryData<Types>>,
+    range: Range<u64>,
+) -> Vec<Header<Types>> {
+    range
+        .filter_map(|h| Some(leaves.get(&h)?.header().clone()))
+        .collect()
+}
+
+/// Check fetched VID common data against verified headers.
+fn verify_vid<Types: NodeType>(
+    leaves: &BTreeMap<u64, LeafQueryData<Types>>,
+    fetched: BTreeMap<u64, VidCommonQueryData<Types>>,
+) -> Vec<VidCommonQueryData<Types>> {
+    fetched
+        .into_iter()
+        .filter_map(|(h, vid)| {
+            let leaf = leaves.get(&h)?;
+            let header = leaf.header();
+            if vid.block_hash() != leaf.block_hash()
+                || vid.payload_hash() != header.payload_commitment()
+            {
+                tracing::warn!(h, "fetched VID common data is for the wrong block");
+                return None;
+            }
+            if let Err(err) = verify_vid_common::<Types>(header, vid.common()) {
+                tracing::warn!(h, "fetched VID common data is invalid: {err:#}");
+                return None;
+            }
+            Some(vid)
+        })
+        .collect()
+}
+
+/// Check fetched blocks against verified headers and VID common data.
+fn verify_blocks<Types: NodeType>(
+    leaves: &BTreeMap<u64, LeafQueryData<Types>>,
+    commons: &BTreeMap<u64, VidCommon>,
+    fetched: BTreeMap<u64, BlockQueryData<Types>>,
+) -> Vec<BlockQueryData<Types>> {
+    fetched
+        .into_iter()
+        .filter_map(|(h, block)| {
+            let leaf = leaves.get(&h)?;
+            let common = commons.get(&h)?;
+            if block.hash() != leaf.block_hash() {
+                tracing::warn!(h, "fetched block has the wrong header");
+                return None;
+            }
+            if let Err(err) = verify_payload::<Types>(leaf.header(), block.payload(), common) {
+                tracing::warn!(h, "fetched payload is invalid: {err:#}");
+                return None;
+            }
+            Some(block)
+        })
+        .collect()
+}
+
+/// Group `missing` heights into contiguous ranges of at most `max_len` heights each.
+fn missing_ranges(missing: &BTreeSet<u64>, max_len: u64) -> Vec<Range<u64>> {
+    let mut ranges: Vec<Range<u64>> = vec![];
+    for &h in missing {
+        match ranges.last_mut() {
+            Some(range) if range.end == h && range.end - range.start < max_len => range.end += 1,
+            _ => ranges.push(h..h + 1),
+        }
+    }
+    ranges
+}
+
+#[derive(Debug)]
+pub(super) struct BackfillMetrics {
+    /// Whether a backfill is currently running (1) or not (0).
+    running: Box<dyn Gauge>,
+    /// Block height where the current backfill started.
+    start: Box<dyn Gauge>,
+    /// Block height where the current backfill will end.
+    end: Box<dyn Gauge>,
+    /// Number of blocks processed in the current backfill.
+    processed: Box<dyn Gauge>,
+    /// Estimated time remaining in the current backfill (s).
+    eta: Box<dyn Gauge>,
+    /// Total number of leaves fetched.
+    fetched_leaves: Box<dyn Counter>,
+    /// Total number of blocks fetched.
+    fetched_blocks: Box<dyn Counter>,
+    /// Total number of VID common entries fetched.
+    fetched_vid: Box<dyn Counter>,
+    /// Total number of fetched objects which failed verification.
+    invalid: Box<dyn Counter>,
+}
+
+impl BackfillMetrics {
+    pub(super) fn new(metrics: &PrometheusMetrics) -> Self {
+        let group = metrics.subgroup("backfill".into());
+        Self {
+            running: group.create_gauge("running".into(), None),
+            start: group.create_gauge("start".into(), None),
+            end: group.create_gauge("end".into(), None),
+            processed: group.create_gauge("processed".into(), None),
+            eta: group.create_gauge("eta".into(), Some("s".into())),
+            fetched_leaves: group.create_counter("fetched_leaves".into(), None),
+            fetched_blocks: group.create_counter("fetched_blocks".into(), None),
+            fetched_vid: group.create_counter("fetched_vid".into(), None),
+            invalid: group.create_counter("invalid".into(), None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_missing_ranges() {
+        let missing = [1, 2, 3, 5, 6, 10].into_iter().collect();
+        assert_eq!(missing_ranges(&missing, 2), vec![1..3, 3..4, 5..7, 10..11]);
+        assert_eq!(missing_ranges(&missing, 10), vec![1..4, 5..7, 10..11]);
+        assert_eq!(missing_ranges(&BTreeSet::new(), 10), vec![]);
+    }
+}
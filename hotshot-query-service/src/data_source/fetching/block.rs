@@ -32,8 +32,8 @@ use crate::{
     },
     data_source::{
         storage::{
-            pruning::PrunedHeightStorage, AvailabilityStorage, NodeStorage,
-            UpdateAvailabilityStorage,
+            pruning::{PrunedData, PrunedHeightStorage},
+            AvailabilityStorage, NodeStorage, UpdateAvailabilityStorage,
         },
         VersionedDataSource,
     },
@@ -78,6 +78,10 @@ where
         }
     }
 
+    fn pruned_data() -> PrunedData {
+        PrunedData::Payload
+    }
+
     async fn passive_fetch(
         notifiers: &Notifiers<Types>,
         req: Self::Request,
@@ -212,6 +216,10 @@ where
         }
     }
 
+    fn pruned_data() -> PrunedData {
+        PrunedData::Payload
+    }
+
     async fn passive_fetch(
         notifiers: &Notifiers<Types>,
         req: Self::Request,
@@ -329,6 +337,10 @@ where
         }
     }
 
+    fn pruned_data() -> PrunedData {
+        PrunedData::Payload
+    }
+
     async fn passive_fetch(
         notifiers: &Notifiers<Types>,
         req: Self::Request,
@@ -0,0 +1,122 @@
+// Copyright (c) 2022 Espresso Systems (espressosys.com)
+// This file is part of the HotShot Query Service library.
+//
+// This program is free software: you can redistribute it and/or modify it under the terms of the GNU
+// General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+// This program is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without
+// even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+// You should have received a copy of the GNU General Public License along with this program. If not,
+// see <https://www.gnu.org/licenses/>.
+
+//! Periodic integrity scans of the local database.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Context;
+use hotshot_types::traits::{metrics::Gauge, node_implementation::NodeType};
+use tokio::time::sleep;
+
+use super::{AvailabilityProvider, Fetcher, Heights};
+use crate::{
+    availability::{QueryableHeader, QueryablePayload},
+    data_source::{
+        storage::{
+            integrity::{self, Inconsistency},
+            pruning::{PrunedData, PrunedHeightStorage},
+            AvailabilityStorage, NodeStorage, UpdateAvailabilityStorage,
+        },
+        VersionedDataSource,
+    },
+    metrics::PrometheusMetrics,
+    Header, Payload,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct IntegrityCfg {
+    pub(super) batch_size: u64,
+    pub(super) interval: Duration,
+}
+
+impl<Types, S, P> Fetcher<Types, S, P>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    S: VersionedDataSource + 'static,
+    for<'a> S::Transaction<'a>: UpdateAvailabilityStorage<Types>,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<Types> + NodeStorage<Types> + PrunedHeightStorage,
+    P: AvailabilityProvider<Types>,
+{
+    /// Repeatedly check the integrity of all data in storage.
+    ///
+    /// Inconsistencies are logged and counted in metrics, but not repaired, since deleting data
+    /// automatically could make a bad situation worse. They can be repaired offline using
+    /// [`integrity::repair`].
+    ///
+    /// This function will run until cancelled, thus, it is meant to be spawned as a background
+    /// task rather than called synchronously.
+    pub(super) async fn integrity_scan(
+        self: Arc<Self>,
+        cfg: IntegrityCfg,
+        metrics: IntegrityMetrics,
+    ) {
+        loop {
+            metrics.running.set(1);
+            match self.integrity_scan_pass(&cfg).await {
+                Ok(problems) => {
+                    for problem in &problems {
+                        tracing::error!(%problem, "inconsistent data in storage");
+                    }
+                    metrics.inconsistencies.set(problems.len());
+                    tracing::info!(
+                        inconsistencies = problems.len(),
+                        "integrity scan complete, will check again in {:?}",
+                        cfg.interval
+                    );
+                },
+                Err(err) => {
+                    tracing::warn!(
+                        "integrity scan failed, will retry in {:?}: {err:#}",
+                        cfg.interval
+                    );
+                },
+            }
+            metrics.running.set(0);
+            sleep(cfg.interval).await;
+        }
+    }
+
+    async fn integrity_scan_pass(&self, cfg: &IntegrityCfg) -> anyhow::Result<Vec<Inconsistency>> {
+        let heights = {
+            let mut tx = self.read().await.context("opening transaction")?;
+            Heights::load(&mut tx, PrunedData::Leaf).await?
+        };
+        let start = heights.pruned_height.map(|h| h + 1).unwrap_or(0);
+        let end = heights.height;
+        if end <= start {
+            return Ok(vec![]);
+        }
+
+        tracing::info!(start, end, "starting integrity scan");
+        integrity::scan(&*self.storage, start..end, cfg.batch_size).await
+    }
+}
+
+pub(super) struct IntegrityMetrics {
+    /// Whether an integrity scan is currently running (1) or not (0).
+    running: Box<dyn Gauge>,
+    /// Number of inconsistencies found by the last complete scan.
+    inconsistencies: Box<dyn Gauge>,
+}
+
+impl IntegrityMetrics {
+    pub(super) fn new(metrics: &PrometheusMetrics) -> Self {
+        let group = metrics.subgroup("integrity".into());
+        Self {
+            running: group.create_gauge("running".into(), None),
+            inconsistencies: group.create_gauge("inconsistencies".into(), None),
+        }
+    }
+}
@@ -34,8 +34,8 @@ use crate::{
     },
     data_source::{
         storage::{
-            pruning::PrunedHeightStorage, AvailabilityStorage, NodeStorage,
-            UpdateAvailabilityStorage,
+            pruning::{PrunedData, PrunedHeightStorage},
+            AvailabilityStorage, NodeStorage, UpdateAvailabilityStorage,
         },
         VersionedDataSource,
     },
@@ -82,6 +82,10 @@ where
         }
     }
 
+    fn pruned_data() -> PrunedData {
+        PrunedData::VidCommon
+    }
+
     async fn passive_fetch(
         notifiers: &Notifiers<Types>,
         req: Self::Request,
@@ -275,6 +279,10 @@ where
         }
     }
 
+    fn pruned_data() -> PrunedData {
+        PrunedData::VidCommon
+    }
+
     async fn passive_fetch(
         notifiers: &Notifiers<Types>,
         req: Self::Request,
@@ -10,16 +10,50 @@
 // You should have received a copy of the GNU General Public License along with this program. If not,
 // see <https://www.gnu.org/licenses/>.
 
-#![cfg(feature = "metrics-data-source")]
+//! Metrics maintained by data sources, and a data source backed entirely by metrics.
 
+#[cfg(feature = "metrics-data-source")]
 use async_trait::async_trait;
+use hotshot_types::traits::metrics::{Counter, Gauge, Metrics, MetricsFamily};
 
+#[cfg(feature = "metrics-data-source")]
 use crate::{
     metrics::PrometheusMetrics,
     status::{HasMetrics, StatusDataSource},
     QueryError, QueryResult,
 };
 
+/// Metrics for an in-memory cache of objects.
+///
+/// Hits and misses are counted separately for each kind of cached object, in the order in which
+/// the kinds were given to [`CacheMetrics::new`].
+#[derive(Debug)]
+pub(crate) struct CacheMetrics {
+    pub(crate) hits: Vec<Box<dyn Counter>>,
+    pub(crate) misses: Vec<Box<dyn Counter>>,
+    pub(crate) objects: Box<dyn Gauge>,
+    pub(crate) bytes: Box<dyn Gauge>,
+}
+
+impl CacheMetrics {
+    pub(crate) fn new(metrics: &(impl Metrics + ?Sized), kinds: &[&str]) -> Self {
+        let hits = metrics.counter_family("hits".into(), vec!["kind".into()]);
+        let misses = metrics.counter_family("misses".into(), vec!["kind".into()]);
+        Self {
+            hits: kinds
+                .iter()
+                .map(|kind| hits.create(vec![kind.to_string()]))
+                .collect(),
+            misses: kinds
+                .iter()
+                .map(|kind| misses.create(vec![kind.to_string()]))
+                .collect(),
+            objects: metrics.create_gauge("objects".into(), None),
+            bytes: metrics.create_gauge("bytes".into(), Some("bytes".into())),
+        }
+    }
+}
+
 /// A minimal data source for the status API provided in this crate, with no persistent storage.
 ///
 /// [`MetricsDataSource`] uses the metrics provided by HotShot to implement [`StatusDataSource`]. It
@@ -56,17 +90,20 @@ use crate::{
 /// # Ok(())
 /// # }
 /// ```
+#[cfg(feature = "metrics-data-source")]
 #[derive(Clone, Debug, Default)]
 pub struct MetricsDataSource {
     metrics: PrometheusMetrics,
 }
 
+#[cfg(feature = "metrics-data-source")]
 impl HasMetrics for MetricsDataSource {
     fn metrics(&self) -> &PrometheusMetrics {
         &self.metrics
     }
 }
 
+#[cfg(feature = "metrics-data-source")]
 #[async_trait]
 impl StatusDataSource for MetricsDataSource {
     async fn block_height(&self) -> QueryResult<usize> {
@@ -81,7 +118,7 @@ impl StatusDataSource for MetricsDataSource {
     }
 }
 
-#[cfg(any(test, feature = "testing"))]
+#[cfg(all(feature = "metrics-data-source", any(test, feature = "testing")))]
 mod impl_testable_data_source {
     use hotshot::types::Event;
 
@@ -112,7 +149,7 @@ mod impl_testable_data_source {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "metrics-data-source"))]
 mod test {
     use super::{super::status_tests, MetricsDataSource};
     // For some reason this is the only way to import the macro defined in another module of this
@@ -311,7 +311,8 @@ where
     /// [`build`](fetching::Builder::build). For a convenient constructor that uses the default
     /// fetching options, see [`Config::connect`].
     pub async fn connect(config: Config, provider: P) -> Result<Builder<Types, P>, Error> {
-        Ok(Self::builder(SqlStorage::connect(config).await?, provider))
+        let storage = SqlStorage::connect(config).await?.spawn_offloader();
+        Ok(Self::builder(storage, provider))
     }
 }
 
@@ -56,7 +56,7 @@
 //! [`AvailabilityDataSource`](crate::availability::AvailabilityDataSource) in fallibility.
 //!
 
-use std::ops::RangeBounds;
+use std::ops::{Range, RangeBounds};
 
 use alloy::primitives::map::HashMap;
 use async_trait::async_trait;
@@ -75,20 +75,25 @@ use crate::{
         query_data::{
             BlockDetail, BlockIdentifier, BlockSummary, ExplorerSummary, GetBlockDetailError,
             GetBlockSummariesError, GetBlockSummariesRequest, GetExplorerSummaryError,
-            GetSearchResultsError, GetTransactionDetailError, GetTransactionSummariesError,
-            GetTransactionSummariesRequest, SearchResult, TransactionDetailResponse,
-            TransactionIdentifier, TransactionSummary,
+            GetNamespaceDetailError, GetNamespaceDetailRequest, GetNamespaceSummariesError,
+            GetNamespaceSummariesRequest, GetSearchResultsError, GetTransactionDetailError,
+            GetTransactionSummariesError, GetTransactionSummariesRequest, NamespaceDetail,
+            NamespaceSummary, SearchResult, TransactionDetailResponse, TransactionIdentifier,
+            TransactionSummary,
         },
         traits::{ExplorerHeader, ExplorerTransaction},
     },
-    merklized_state::{MerklizedState, Snapshot},
+    merklized_state::{EntryUpdate, MerklizedState, Snapshot},
     node::{SyncStatus, TimeWindowQueryData, WindowStart},
-    Header, Payload, QueryResult, Transaction,
+    Header, Payload, QueryError, QueryResult, Transaction,
 };
 
+pub mod archive;
 pub mod fail_storage;
 pub mod fs;
+pub mod integrity;
 mod ledger_log;
+pub mod migration;
 pub mod pruning;
 pub mod sql;
 
@@ -242,6 +247,22 @@ where
     async fn vid_share<ID>(&mut self, id: ID) -> QueryResult<VidShare>
     where
         ID: Into<BlockId<Types>> + Send + Sync;
+
+    /// Get the VID shares for the blocks in `range`, in order of height.
+    ///
+    /// Blocks for which no share is available are skipped. The default implementation loads each
+    /// share separately.
+    async fn vid_share_range(&mut self, range: Range<usize>) -> QueryResult<Vec<(u64, VidShare)>> {
+        let mut shares = vec![];
+        for height in range {
+            match self.vid_share(height).await {
+                Ok(share) => shares.push((height as u64, share)),
+                Err(QueryError::NotFound | QueryError::Missing) => {},
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(shares)
+    }
     async fn get_header_window(
         &mut self,
         start: impl Into<WindowStart<Types>> + Send + Sync,
@@ -353,6 +374,23 @@ where
         &mut self,
         query: TaggedBase64,
     ) -> Result<SearchResult<Types>, GetSearchResultsError>;
+
+    /// `get_namespace_summaries` is a method that retrieves a
+    /// [NamespaceSummary] for each namespace which has appeared in the
+    /// blockchain, ordered from most to least recently seen.  The range of
+    /// summaries is given by the [GetNamespaceSummariesRequest].
+    async fn get_namespace_summaries(
+        &mut self,
+        request: GetNamespaceSummariesRequest,
+    ) -> Result<Vec<NamespaceSummary<Types>>, GetNamespaceSummariesError>;
+
+    /// `get_namespace_detail` is a method that retrieves the summary and a
+    /// histogram of recent activity for a single namespace.  The namespace
+    /// and histogram dimensions are given by the [GetNamespaceDetailRequest].
+    async fn get_namespace_detail(
+        &mut self,
+        request: GetNamespaceDetailRequest<Types>,
+    ) -> Result<NamespaceDetail<Types>, GetNamespaceDetailError>;
 }
 
 /// This trait defines methods that a data source should implement
@@ -368,6 +406,35 @@ where
         snapshot: Snapshot<Types, State, ARITY>,
         key: State::Key,
     ) -> QueryResult<MerkleProof<State::Entry, State::Key, State::T, ARITY>>;
+
+    /// Get every change to the entry at `key` in blocks `from..=to`, in order of block height.
+    ///
+    /// The default implementation fails, for storage which does not keep the history of the state.
+    async fn get_entry_history(
+        &mut self,
+        _key: State::Key,
+        _from: u64,
+        _to: u64,
+    ) -> QueryResult<Vec<EntryUpdate<State::Entry>>> {
+        Err(QueryError::Error {
+            message: "entry history is not supported by this storage".into(),
+        })
+    }
+
+    /// Get the root of the tree as of block `height`, along with the state commitment in the header
+    /// at that height.
+    ///
+    /// If the storage is consistent, the root is the digest of the commitment. Fails with
+    /// [`QueryError::NotFound`] if the state at `height` is not available, including when the
+    /// header has no commitment to this state.
+    ///
+    /// The default implementation fails, for storage which cannot load the root of the tree
+    /// directly.
+    async fn get_root(&mut self, _height: u64) -> QueryResult<(State::T, State::Commit)> {
+        Err(QueryError::Error {
+            message: "loading the root of the state is not supported by this storage".into(),
+        })
+    }
 }
 
 #[async_trait]
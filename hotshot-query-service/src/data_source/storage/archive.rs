@@ -0,0 +1,684 @@
+// Copyright (c) 2022 Espresso Systems (espressosys.com)
+// This file is part of the HotShot Query Service library.
+//
+// This program is free software: you can redistribute it and/or modify it under the terms of the GNU
+// General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+// This program is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without
+// even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+// You should have received a copy of the GNU General Public License along with this program. If not,
+// see <https://www.gnu.org/licenses/>.
+
+//! Portable archives of chain data.
+//!
+//! An archive holds a contiguous range of the chain (leaves, which include headers, as well as
+//! payloads, VID common data and light client state certificates) in a form that is independent of
+//! any particular storage backend. Archives can be [exported](export) from any storage and
+//! [imported](import) into any storage, which makes it possible to bootstrap a new archival node
+//! from a published snapshot instead of fetching the entire history from its peers.
+//!
+//! An archive is a directory containing a `manifest.json` file and a number of chunk files, each
+//! covering a sub-range of the archive. The manifest records a SHA-256 checksum of each chunk,
+//! which is checked before the chunk is loaded. Since an archive may come from an untrusted source,
+//! the whole archive is verified before anything is imported: every leaf must be certified by its QC
+//! and extend the previous leaf, and every payload and VID common object must match the commitment
+//! in the corresponding header.
+//!
+//! Linking the leaves to each other only proves that they form a chain, not that it is the chain
+//! finalized by consensus, since anyone can build a chain of leaves on top of a real one. Instead of
+//! checking signatures against the stake table, which would require the full history of stake
+//! table updates, the importer anchors the archive to a leaf it already trusts: the last leaf in the
+//! archive must either have a hash given by the operator, or be the parent of a leaf already in
+//! storage. Since each leaf commits to its parent, this authenticates every leaf in the archive,
+//! and with them every header, payload and VID common object.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    ops::Range,
+    path::Path,
+};
+
+use anyhow::{bail, ensure, Context};
+use hotshot_types::{
+    data::{ns_table, VidCommitment},
+    traits::{
+        block_contents::BlockHeader,
+        node_implementation::{ConsensusTime, NodeType},
+        EncodeBytes,
+    },
+    vid::{
+        advz::{advz_scheme, ADVZScheme},
+        avidm::{init_avidm_param, AvidMScheme},
+    },
+};
+use jf_vid::VidScheme;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{AvailabilityStorage, UpdateAvailabilityStorage};
+use crate::{
+    availability::{
+        BlockQueryData, LeafHash, LeafId, LeafQueryData, QueryableHeader, QueryablePayload,
+        StateCertQueryData, VidCommonQueryData,
+    },
+    data_source::{Transaction, VersionedDataSource},
+    types::HeightIndexed,
+    Header, Payload, QueryError, VidCommon,
+};
+
+/// The version of the archive format produced by this module.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Name of the manifest file within an archive directory.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Description of the contents of an archive.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Manifest {
+    /// Version of the archive format.
+    pub version: u32,
+    /// The first block height in the archive.
+    pub start: u64,
+    /// One past the last block height in the archive.
+    pub end: u64,
+    /// The chunks making up the archive, in order of height.
+    pub chunks: Vec<ChunkInfo>,
+}
+
+/// Description of a single chunk of an archive.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ChunkInfo {
+    /// Name of the chunk file, relative to the archive directory.
+    pub file: String,
+    /// The first block height in the chunk.
+    pub start: u64,
+    /// One past the last block height in the chunk.
+    pub end: u64,
+    /// Hex-encoded SHA-256 digest of the chunk file.
+    pub sha256: String,
+}
+
+/// The data for a single block height.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(bound = "")]
+struct Entry<Types: NodeType> {
+    leaf: LeafQueryData<Types>,
+    payload: Option<Payload<Types>>,
+    vid_common: Option<VidCommon>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(bound = "")]
+struct Chunk<Types: NodeType> {
+    entries: Vec<Entry<Types>>,
+    state_certs: Vec<StateCertQueryData<Types>>,
+}
+
+/// Export the blocks in `range` from `storage` into an archive in `dir`.
+///
+/// The archive is split into chunks of at most `chunk_size` blocks each. Every leaf in `range` must
+/// be present in `storage`. Payloads and VID common data which are missing (for example, because
+/// they have been pruned or not yet fetched) are left out of the archive, and can be fetched as
+/// usual by the node which imports it.
+pub async fn export<Types, S>(
+    storage: &S,
+    dir: &Path,
+    range: Range<u64>,
+    chunk_size: u64,
+) -> anyhow::Result<Manifest>
+where
+    Types: NodeType,
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<Types>,
+{
+    ensure!(chunk_size > 0, "chunk size must be positive");
+    ensure!(!range.is_empty(), "cannot export empty range {range:?}");
+    fs::create_dir_all(dir).with_context(|| format!("creating archive directory {dir:?}"))?;
+
+    let mut manifest = Manifest {
+        version: ARCHIVE_VERSION,
+        start: range.start,
+        end: range.end,
+        chunks: vec![],
+    };
+    // State certificates are indexed by epoch, and an epoch may span several chunks. Keep track of
+    // the ones we have already exported so each is only included once.
+    let mut epochs = BTreeSet::new();
+
+    for start in range.clone().step_by(chunk_size as usize) {
+        let end = (start + chunk_size).min(range.end);
+        let chunk = load_chunk(storage, start..end, &mut epochs).await?;
+        let bytes = bincode::serialize(&chunk).context("serializing chunk")?;
+
+        let file = format!("chunk-{start:012}-{end:012}.bin");
+        fs::write(dir.join(&file), &bytes).with_context(|| format!("writing {file}"))?;
+        tracing::info!(start, end, size = bytes.len(), "exported chunk");
+
+        manifest.chunks.push(ChunkInfo {
+            file,
+            start,
+            end,
+            sha256: hex::encode(Sha256::digest(&bytes)),
+        });
+    }
+
+    fs::write(
+        dir.join(MANIFEST_FILE),
+        serde_json::to_vec_pretty(&manifest)?,
+    )
+    .context("writing manifest")?;
+    Ok(manifest)
+}
+
+/// Import the archive in `dir` into `storage`.
+///
+/// The archive must end with the leaf whose hash is `trusted`, or with the parent of a leaf which
+/// is already in `storage`; without either anchor, the import fails. If `storage` already contains
+/// the leaf immediately before the start of the archive, the first leaf in the archive must also
+/// extend it, so that an archive can only be used to fill in a chain, not replace it.
+///
+/// The whole archive is verified before anything is written. Each chunk is then written to
+/// `storage` in its own transaction, so an interrupted import can be resumed simply by importing
+/// the same archive again.
+pub async fn import<Types, S>(
+    storage: &S,
+    dir: &Path,
+    trusted: Option<LeafHash<Types>>,
+) -> anyhow::Result<Manifest>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    S: VersionedDataSource,
+    for<'a> S::Transaction<'a>: UpdateAvailabilityStorage<Types>,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<Types>,
+{
+    let manifest = read_manifest(dir)?;
+
+    let mut tx = storage.read().await?;
+    let parent = if manifest.start > 0 {
+        get_leaf_if_present(&mut tx, manifest.start - 1)
+            .await
+            .context("loading parent of archive")?
+            .map(|leaf| leaf.hash())
+    } else {
+        None
+    };
+    let child = get_leaf_if_present(&mut tx, manifest.end)
+        .await
+        .context("loading child of archive")?
+        .map(|leaf| leaf.leaf().parent_commitment());
+    drop(tx);
+    let anchor = match (trusted, child) {
+        (Some(trusted), Some(child)) => {
+            ensure!(
+                trusted == child,
+                "trusted leaf {trusted} is not the parent of leaf {} in storage",
+                manifest.end
+            );
+            trusted
+        },
+        (Some(anchor), None) | (None, Some(anchor)) => anchor,
+        (None, None) => bail!(
+            "cannot authenticate archive: no trusted leaf hash was given and leaf {} is not in \
+             storage",
+            manifest.end
+        ),
+    };
+
+    // Verify the whole archive before writing any of it.
+    let mut last = parent;
+    for info in &manifest.chunks {
+        let chunk = read_chunk::<Types>(dir, info)?;
+        verify_chunk(&chunk, info, &mut last)
+            .with_context(|| format!("verifying {}", info.file))?;
+    }
+    ensure!(
+        last == Some(anchor),
+        "archive does not end with trusted leaf {anchor}"
+    );
+
+    for info in &manifest.chunks {
+        // The checksum ensures we import exactly the chunk we verified.
+        let chunk = read_chunk::<Types>(dir, info)?;
+        let mut tx = storage.write().await?;
+        for entry in chunk.entries {
+            let header = entry.leaf.header().clone();
+            tx.insert_leaf(entry.leaf).await?;
+            if let Some(payload) = entry.payload {
+                tx.insert_block(BlockQueryData::new(header.clone(), payload))
+                    .await?;
+            }
+            if let Some(common) = entry.vid_common {
+                tx.insert_vid(VidCommonQueryData::new(header, common), None)
+                    .await?;
+            }
+        }
+        for cert in chunk.state_certs {
+            tx.insert_state_cert(cert).await?;
+        }
+        tx.commit().await?;
+        tracing::info!(start = info.start, end = info.end, "imported chunk");
+    }
+
+    Ok(manifest)
+}
+
+/// Read a chunk of the archive in `dir`, checking it against its checksum.
+fn read_chunk<Types: NodeType>(dir: &Path, info: &ChunkInfo) -> anyhow::Result<Chunk<Types>> {
+    let bytes = fs::read(dir.join(&info.file)).with_context(|| format!("reading {}", info.file))?;
+    let digest = hex::encode(Sha256::digest(&bytes));
+    ensure!(
+        digest == info.sha256,
+        "checksum mismatch for {}: expected {}, got {digest}",
+        info.file,
+        info.sha256
+    );
+    bincode::deserialize(&bytes).with_context(|| format!("decoding {}", info.file))
+}
+
+async fn get_leaf_if_present<Types, Tx>(
+    tx: &mut Tx,
+    height: u64,
+) -> anyhow::Result<Option<LeafQueryData<Types>>>
+where
+    Types: NodeType,
+    Tx: AvailabilityStorage<Types>,
+{
+    match tx.get_leaf(LeafId::Number(height as usize)).await {
+        Ok(leaf) => Ok(Some(leaf)),
+        Err(QueryError::NotFound | QueryError::Missing) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Read and sanity check the manifest of the archive in `dir`.
+pub fn read_manifest(dir: &Path) -> anyhow::Result<Manifest> {
+    let bytes = fs::read(dir.join(MANIFEST_FILE)).context("reading manifest")?;
+    let manifest: Manifest = serde_json::from_slice(&bytes).context("decoding manifest")?;
+    ensure!(
+        manifest.version == ARCHIVE_VERSION,
+        "unsupported archive version {}",
+        manifest.version
+    );
+
+    // The chunks must exactly cover the range of the archive, in order.
+    let mut height = manifest.start;
+    for info in &manifest.chunks {
+        ensure!(
+            info.start == height && info.end > info.start,
+            "chunk {} covers {}..{}, expected a chunk starting at {height}",
+            info.file,
+            info.start,
+            info.end
+        );
+        height = info.end;
+    }
+    ensure!(
+        height == manifest.end,
+        "chunks end at {height}, but archive ends at {}",
+        manifest.end
+    );
+
+    Ok(manifest)
+}
+
+async fn load_chunk<Types, S>(
+    storage: &S,
+    range: Range<u64>,
+    epochs: &mut BTreeSet<u64>,
+) -> anyhow::Result<Chunk<Types>>
+where
+    Types: NodeType,
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<Types>,
+{
+    let bounds = range.start as usize..range.end as usize;
+    let mut tx = storage.read().await?;
+
+    let leaves = tx
+        .get_leaf_range(bounds.clone())
+        .await?
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .with_context(|| format!("loading leaves {range:?}"))?;
+    ensure!(
+        leaves.len() == bounds.len(),
+        "missing leaves in {range:?}: found only {}",
+        leaves.len()
+    );
+
+    let mut payloads = tx
+        .get_payload_range(bounds.clone())
+        .await?
+        .into_iter()
+        .filter_map(|res| res.ok())
+        .map(|payload| (payload.height(), payload.data))
+        .collect::<HashMap<_, _>>();
+    let mut commons = tx
+        .get_vid_common_range(bounds)
+        .await?
+        .into_iter()
+        .filter_map(|res| res.ok())
+        .map(|common| (common.height(), common.common))
+        .collect::<HashMap<_, _>>();
+
+    let mut state_certs = vec![];
+    for epoch in leaves.iter().filter_map(|leaf| leaf.qc().data.epoch) {
+        let epoch = epoch.u64();
+        if !epochs.insert(epoch) {
+            continue;
+        }
+        match tx.get_state_cert(epoch).await {
+            Ok(cert) => state_certs.push(cert),
+            // Not every epoch has a state certificate in storage.
+            Err(QueryError::NotFound | QueryError::Missing) => {},
+            Err(err) => return Err(err).with_context(|| format!("loading state cert {epoch}")),
+        }
+    }
+
+    let entries = leaves
+        .into_iter()
+        .map(|leaf| {
+            let height = leaf.height();
+            let payload = payloads.remove(&height);
+            // AVID-M common data can only be verified along with its payload, so an importer would
+            // reject it on its own.
+            let vid_common = commons
+                .remove(&height)
+                .filter(|common| payload.is_some() || matches!(common, VidCommon::V0(_)));
+            Entry {
+                leaf,
+                payload,
+                vid_common,
+            }
+        })
+        .collect();
+    Ok(Chunk {
+        entries,
+        state_certs,
+    })
+}
+
+fn verify_chunk<Types>(
+    chunk: &Chunk<Types>,
+    info: &ChunkInfo,
+    parent: &mut Option<LeafHash<Types>>,
+) -> anyhow::Result<()>
+where
+    Types: NodeType,
+{
+    ensure!(
+        chunk.entries.len() as u64 == info.end - info.start,
+        "chunk has {} entries, expected {}",
+        chunk.entries.len(),
+        info.end - info.start
+    );
+
+    for (height, entry) in (info.start..).zip(&chunk.entries) {
+        let leaf = &entry.leaf;
+        ensure!(
+            leaf.height() == height,
+            "expected leaf {height}, got leaf {}",
+            leaf.height()
+        );
+        LeafQueryData::new(leaf.leaf().clone(), leaf.qc().clone())
+            .with_context(|| format!("leaf {height} is not certified by its QC"))?;
+        if let Some(parent) = parent {
+            ensure!(
+                leaf.leaf().parent_commitment() == *parent,
+                "leaf {height} does not extend its parent"
+            );
+        }
+        *parent = Some(leaf.hash());
+
+        let header = leaf.header();
+        if let Some(common) = &entry.vid_common {
+            verify_vid_common::<Types>(header, common)
+                .with_context(|| format!("invalid VID common for block {height}"))?;
+        }
+        match (&entry.payload, &entry.vid_common) {
+            (Some(payload), Some(common)) => {
+                // Recomputing the payload commitment with the parameters from the VID common data
+                // also verifies those parameters, including for AVID-M.
+                verify_payload::<Types>(header, payload, common)
+                    .with_context(|| format!("invalid payload for block {height}"))?;
+            },
+            (Some(_), None) => {
+                // We need the VID common data to recompute the payload commitment.
+                bail!("payload for block {height} has no VID common data");
+            },
+            (None, Some(VidCommon::V1(_))) => {
+                bail!(
+                    "AVID-M VID common for block {height} cannot be verified without its payload"
+                );
+            },
+            (None, Some(VidCommon::V0(_))) | (None, None) => {},
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that `common` is consistent with the payload commitment in `header`.
+pub(crate) fn verify_vid_common<Types: NodeType>(
+    header: &Header<Types>,
+    common: &VidCommon,
+) -> anyhow::Result<()> {
+    match (header.payload_commitment(), common) {
+        (VidCommitment::V0(commit), VidCommon::V0(common)) => {
+            ADVZScheme::is_consistent(&commit, common)
+                .map_err(|err| anyhow::anyhow!("inconsistent VID common: {err}"))
+        },
+        // AvidM common data does not contain enough information to check it against the
+        // commitment on its own. Callers must check it by using it to verify the payload.
+        (VidCommitment::V1(_), VidCommon::V1(_)) => Ok(()),
+        _ => bail!("VID common version does not match payload commitment"),
+    }
+}
+
+/// Check that `payload` matches the payload commitment in `header`.
+pub(crate) fn verify_payload<Types: NodeType>(
+    header: &Header<Types>,
+    payload: &Payload<Types>,
+    common: &VidCommon,
+) -> anyhow::Result<()> {
+    let bytes = payload.encode();
+    let commit = match common {
+        VidCommon::V0(common) => {
+            let num_storage_nodes = ADVZScheme::get_num_storage_nodes(common) as usize;
+            advz_scheme(num_storage_nodes)
+                .commit_only(bytes)
+                .map(VidCommitment::V0)
+                .map_err(|err| anyhow::anyhow!("failed to compute VID commitment: {err}"))?
+        },
+        VidCommon::V1(common) => {
+            let avidm_param = init_avidm_param(common.total_weights)
+                .map_err(|err| anyhow::anyhow!("failed to initialize AVIDM params: {err}"))?;
+            let metadata = header.metadata().encode();
+            AvidMScheme::commit(
+                &avidm_param,
+                &bytes,
+                ns_table::parse_ns_table(bytes.len(), &metadata),
+            )
+            .map(VidCommitment::V1)
+            .map_err(|err| anyhow::anyhow!("failed to compute AVIDM commitment: {err}"))?
+        },
+    };
+    ensure!(
+        commit == header.payload_commitment(),
+        "payload commitment mismatch: header has {}, payload has {commit}",
+        header.payload_commitment()
+    );
+    Ok(())
+}
+
+// These tests run the `postgres` Docker image, which doesn't work on Windows.
+#[cfg(all(test, not(target_os = "windows")))]
+mod test {
+    use futures::stream::StreamExt;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        availability::AvailabilityDataSource,
+        data_source::storage::FileSystemStorage,
+        testing::{
+            consensus::{MockDataSource, MockNetwork},
+            mocks::{MockTypes, MockVersions},
+            setup_test,
+        },
+    };
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_archive_round_trip() {
+        setup_test();
+
+        let mut network = MockNetwork::<MockDataSource, MockVersions>::init().await;
+        network.start().await;
+        let blocks = network
+            .data_source()
+            .subscribe_blocks(0)
+            .await
+            .take(5)
+            .collect::<Vec<_>>()
+            .await;
+        // Make sure the VID common data has also been stored for all the blocks we're exporting.
+        network
+            .data_source()
+            .subscribe_vid_common(0)
+            .await
+            .take(5)
+            .collect::<Vec<_>>()
+            .await;
+
+        let dir = TempDir::with_prefix("test_archive_round_trip").unwrap();
+        let archive = dir.path().join("archive");
+        let manifest = export::<MockTypes, _>(&network.data_source(), &archive, 0..5, 2)
+            .await
+            .unwrap();
+        assert_eq!(manifest.chunks.len(), 3);
+        assert_eq!(read_manifest(&archive).unwrap(), manifest);
+
+        // Import into a fresh storage of a different kind.
+        let storage = FileSystemStorage::<MockTypes>::create(&dir.path().join("fs"))
+            .await
+            .unwrap();
+        let trusted = network.data_source().get_leaf(4).await.await.hash();
+        import::<MockTypes, _>(&storage, &archive, Some(trusted))
+            .await
+            .unwrap();
+
+        let mut tx = storage.read().await.unwrap();
+        for block in &blocks {
+            let height = block.height() as usize;
+            assert_eq!(
+                tx.get_block(height.into()).await.unwrap().payload(),
+                block.payload()
+            );
+            assert_eq!(
+                tx.get_leaf(height.into()).await.unwrap().hash(),
+                network.data_source().get_leaf(height).await.await.hash()
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_archive_corrupt_chunk() {
+        setup_test();
+
+        let mut network = MockNetwork::<MockDataSource, MockVersions>::init().await;
+        network.start().await;
+        network
+            .data_source()
+            .subscribe_vid_common(0)
+            .await
+            .take(3)
+            .collect::<Vec<_>>()
+            .await;
+
+        let dir = TempDir::with_prefix("test_archive_corrupt_chunk").unwrap();
+        let archive = dir.path().join("archive");
+        let manifest = export::<MockTypes, _>(&network.data_source(), &archive, 0..3, 3)
+            .await
+            .unwrap();
+
+        // Flip a bit in the only chunk.
+        let path = archive.join(&manifest.chunks[0].file);
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(&path, bytes).unwrap();
+
+        let storage = FileSystemStorage::<MockTypes>::create(&dir.path().join("fs"))
+            .await
+            .unwrap();
+        let trusted = network.data_source().get_leaf(2).await.await.hash();
+        let err = import::<MockTypes, _>(&storage, &archive, Some(trusted))
+            .await
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("checksum mismatch"), "{err:#}");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_archive_requires_anchor() {
+        setup_test();
+
+        let mut network = MockNetwork::<MockDataSource, MockVersions>::init().await;
+        network.start().await;
+        let leaves = network
+            .data_source()
+            .subscribe_leaves(0)
+            .await
+            .take(3)
+            .collect::<Vec<_>>()
+            .await;
+
+        let dir = TempDir::with_prefix("test_archive_requires_anchor").unwrap();
+        let archive = dir.path().join("archive");
+        export::<MockTypes, _>(&network.data_source(), &archive, 0..3, 3)
+            .await
+            .unwrap();
+        let storage = FileSystemStorage::<MockTypes>::create(&dir.path().join("fs"))
+            .await
+            .unwrap();
+
+        // Without a trusted leaf, the archive cannot be authenticated.
+        let err = import::<MockTypes, _>(&storage, &archive, None)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("cannot authenticate"), "{err:#}");
+
+        // The trusted leaf must be the end of the archive.
+        let err = import::<MockTypes, _>(&storage, &archive, Some(leaves[1].hash()))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not end with"), "{err:#}");
+
+        // Nothing was written by the failed imports.
+        storage
+            .read()
+            .await
+            .unwrap()
+            .get_leaf(LeafId::Number(0))
+            .await
+            .unwrap_err();
+
+        import::<MockTypes, _>(&storage, &archive, Some(leaves[2].hash()))
+            .await
+            .unwrap();
+        assert_eq!(
+            storage
+                .read()
+                .await
+                .unwrap()
+                .get_leaf(LeafId::Number(2))
+                .await
+                .unwrap()
+                .hash(),
+            leaves[2].hash()
+        );
+    }
+}
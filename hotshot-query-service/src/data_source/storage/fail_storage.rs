@@ -20,7 +20,7 @@ use futures::future::Future;
 use hotshot_types::{data::VidShare, traits::node_implementation::NodeType};
 
 use super::{
-    pruning::{PruneStorage, PrunedHeightStorage, PrunerCfg, PrunerConfig},
+    pruning::{PruneStorage, PrunedData, PrunedHeightStorage, PrunerCfg, PrunerConfig},
     sql::MigrateTypes,
     Aggregate, AggregatesStorage, AvailabilityStorage, NodeStorage, UpdateAggregatesStorage,
     UpdateAvailabilityStorage,
@@ -514,6 +514,11 @@ where
         self.maybe_fail_read(FailableAction::Any).await?;
         self.inner.load_pruned_height().await
     }
+
+    async fn load_pruned_height_for(&mut self, data: PrunedData) -> anyhow::Result<Option<u64>> {
+        self.maybe_fail_read(FailableAction::Any).await?;
+        self.inner.load_pruned_height_for(data).await
+    }
 }
 
 #[async_trait]
@@ -48,8 +48,9 @@ use crate::{
     availability::{
         data_source::{BlockId, LeafId},
         query_data::{
-            BlockHash, BlockQueryData, LeafHash, LeafQueryData, PayloadQueryData, QueryableHeader,
-            QueryablePayload, TransactionHash, TransactionQueryData, VidCommonQueryData,
+            BlockHash, BlockQueryData, LeafHash, LeafQueryData, NamespaceMap, PayloadQueryData,
+            QueryableHeader, QueryablePayload, TransactionHash, TransactionQueryData,
+            VidCommonQueryData,
         },
         NamespaceId, StateCertQueryData,
     },
@@ -80,6 +81,8 @@ where
     index_by_time: BTreeMap<u64, Vec<u64>>,
     num_transactions: usize,
     payload_size: usize,
+    /// Transaction count and payload size for each namespace, summed over all blocks.
+    namespace_stats: NamespaceMap<Types>,
     #[debug(skip)]
     top_storage: Option<AtomicStore>,
     leaf_storage: LedgerLog<LeafQueryData<Types>>,
@@ -213,6 +216,7 @@ where
                 index_by_time: Default::default(),
                 num_transactions: 0,
                 payload_size: 0,
+                namespace_stats: Default::default(),
                 top_storage: None,
                 leaf_storage: LedgerLog::create(loader, "leaves", CACHED_LEAVES_COUNT)?,
                 block_storage: LedgerLog::create(loader, "blocks", CACHED_BLOCKS_COUNT)?,
@@ -275,9 +279,11 @@ where
         let mut index_by_txn_hash = HashMap::new();
         let mut num_transactions = 0;
         let mut payload_size = 0;
+        let mut namespace_stats = NamespaceMap::<Types>::new();
         for block in block_storage.iter().flatten() {
             num_transactions += block.len();
             payload_size += block.size() as usize;
+            update_namespace_stats(&mut namespace_stats, &block);
 
             let height = block.height();
             for (_, txn) in block.enumerate() {
@@ -294,6 +300,7 @@ where
                 index_by_time,
                 num_transactions,
                 payload_size,
+                namespace_stats,
                 leaf_storage,
                 block_storage,
                 vid_storage,
@@ -706,6 +713,7 @@ where
         }
         self.inner.num_transactions += block.len();
         self.inner.payload_size += block.size() as usize;
+        update_namespace_stats(&mut self.inner.namespace_stats, &block);
         for (_, txn) in block.enumerate() {
             update_index_by_hash(
                 &mut self.inner.index_by_txn_hash,
@@ -756,6 +764,19 @@ fn update_index_by_hash<H: Eq + Hash, P: Ord>(index: &mut HashMap<H, P>, hash: H
     }
 }
 
+fn update_namespace_stats<Types>(stats: &mut NamespaceMap<Types>, block: &BlockQueryData<Types>)
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    for (ns, info) in block.namespace_info() {
+        let entry = stats.entry(ns).or_default();
+        entry.num_transactions += info.num_transactions;
+        entry.size += info.size;
+    }
+}
+
 #[async_trait]
 impl<Types, T> NodeStorage<Types> for Transaction<T>
 where
@@ -781,10 +802,13 @@ where
             });
         }
 
-        if namespace.is_some() {
-            return Err(QueryError::Error {
-                message: "file system does not support per-namespace stats".into(),
-            });
+        if let Some(ns) = namespace {
+            return Ok(self
+                .inner
+                .namespace_stats
+                .get(&ns)
+                .map(|info| info.num_transactions as usize)
+                .unwrap_or(0));
         }
 
         Ok(self.inner.num_transactions)
@@ -803,10 +827,13 @@ where
             });
         }
 
-        if namespace.is_some() {
-            return Err(QueryError::Error {
-                message: "file system does not support per-namespace stats".into(),
-            });
+        if let Some(ns) = namespace {
+            return Ok(self
+                .inner
+                .namespace_stats
+                .get(&ns)
+                .map(|info| info.size as usize)
+                .unwrap_or(0));
         }
 
         Ok(self.inner.payload_size)
@@ -846,6 +873,7 @@ where
             missing_vid_common: missing_vid,
             missing_vid_shares: missing_vid + null_vid_shares,
             pruned_height: None,
+            backfill: None,
         })
     }
 
@@ -0,0 +1,546 @@
+// Copyright (c) 2022 Espresso Systems (espressosys.com)
+// This file is part of the HotShot Query Service library.
+//
+// This program is free software: you can redistribute it and/or modify it under the terms of the GNU
+// General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+// This program is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without
+// even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+// You should have received a copy of the GNU General Public License along with this program. If not,
+// see <https://www.gnu.org/licenses/>.
+
+//! Integrity checks for data in storage.
+//!
+//! Data is verified before it is stored, but a crash in the middle of a write, a bug, or a faulty
+//! disk can still leave objects in storage which are unreadable or inconsistent with the rest of
+//! the chain. [`scan`] walks a range of blocks and checks that each stored leaf is certified by its
+//! QC and extends the leaf below it, that each payload and VID common object matches the
+//! commitment in its header, and that the stored payload metadata, including the namespace table
+//! summary, agrees with the payload. [`scan_merklized_state`] checks that the root of each stored
+//! merklized state tree matches the state commitment in each header.
+//!
+//! Problems found by a scan can be fixed with [`repair`], which deletes the affected blocks from
+//! storage so that the fetcher will retrieve them again from its peers. Merklized state cannot be
+//! repaired this way; it must be rebuilt by the application which maintains it.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+    future::Future,
+    ops::Range,
+};
+
+use anyhow::{ensure, Context};
+use hotshot_types::traits::node_implementation::NodeType;
+use jf_merkle_tree::MerkleCommitment;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    archive::{verify_payload, verify_vid_common},
+    AvailabilityStorage, MerklizedStateStorage,
+};
+use crate::{
+    availability::{
+        BlockQueryData, LeafQueryData, PayloadMetadata, QueryableHeader, QueryablePayload,
+        VidCommonQueryData,
+    },
+    data_source::{Transaction, VersionedDataSource},
+    merklized_state::MerklizedState,
+    types::HeightIndexed,
+    Header, Payload, QueryError, QueryResult,
+};
+
+/// The kind of object in which an [`Inconsistency`] was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectKind {
+    Leaf,
+    Payload,
+    PayloadMetadata,
+    VidCommon,
+    MerklizedState,
+}
+
+/// A problem with an object in storage.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Inconsistency {
+    /// The height of the block the bad object belongs to.
+    pub height: u64,
+    /// The kind of object which is bad.
+    pub kind: ObjectKind,
+    /// What is wrong with the object.
+    pub reason: String,
+}
+
+impl Inconsistency {
+    fn new(height: u64, kind: ObjectKind, reason: impl Display) -> Self {
+        Self {
+            height,
+            kind,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl Display for Inconsistency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}: {}", self.kind, self.height, self.reason)
+    }
+}
+
+/// Storage which can delete objects found to be inconsistent.
+pub trait RepairStorage {
+    /// Delete everything stored for the block at `height`.
+    ///
+    /// Afterwards the block is missing, as if it had never been stored, and will be fetched again
+    /// like any other missing block.
+    fn delete_block(&mut self, height: u64) -> impl Send + Future<Output = anyhow::Result<()>>;
+}
+
+/// Check the consistency of the data stored for blocks in `range`.
+///
+/// Objects which are missing from storage are not considered inconsistent; finding those is the
+/// job of the fetcher. Payloads and VID common data can only be checked against a leaf which is
+/// itself valid, so when a leaf is bad, only the leaf is reported.
+///
+/// The range is checked in batches of `batch_size` blocks, each loaded in its own transaction.
+pub async fn scan<Types, S>(
+    storage: &S,
+    range: Range<u64>,
+    batch_size: u64,
+) -> anyhow::Result<Vec<Inconsistency>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<Types>,
+{
+    ensure!(batch_size > 0, "batch size must be positive");
+
+    let mut problems = vec![];
+    // The valid leaf just below the current height, if there is one, for checking the hash chain.
+    let mut prev: Option<LeafQueryData<Types>> = None;
+    for start in range.clone().step_by(batch_size as usize) {
+        let end = (start + batch_size).min(range.end);
+        let mut tx = storage.read().await.context("opening transaction")?;
+        let leaves =
+            load_batch::<Types, LeafQueryData<Types>, _>(&mut tx, start..end, &mut problems)
+                .await?;
+        let blocks =
+            load_batch::<Types, BlockQueryData<Types>, _>(&mut tx, start..end, &mut problems)
+                .await?;
+        let vid =
+            load_batch::<Types, VidCommonQueryData<Types>, _>(&mut tx, start..end, &mut problems)
+                .await?;
+        let metadata =
+            load_batch::<Types, PayloadMetadata<Types>, _>(&mut tx, start..end, &mut problems)
+                .await?;
+        drop(tx);
+
+        for height in start..end {
+            let leaf = leaves.get(&height).and_then(|leaf| {
+                match LeafQueryData::new(leaf.leaf().clone(), leaf.qc().clone()) {
+                    Ok(_) => Some(leaf),
+                    Err(err) => {
+                        problems.push(Inconsistency::new(height, ObjectKind::Leaf, err));
+                        None
+                    },
+                }
+            });
+
+            // Only compare adjacent leaves which are each certified, since an invalid leaf has
+            // already been reported. When two certified leaves do not link up, we cannot tell which
+            // one is wrong, so we report both.
+            if let (Some(parent), Some(child)) = (&prev, leaf) {
+                if child.leaf().parent_commitment() != parent.hash() {
+                    problems.push(Inconsistency::new(
+                        parent.height(),
+                        ObjectKind::Leaf,
+                        format!("not the parent of leaf {height}"),
+                    ));
+                    problems.push(Inconsistency::new(
+                        height,
+                        ObjectKind::Leaf,
+                        format!("does not extend leaf {}", parent.height()),
+                    ));
+                }
+            }
+            prev = leaf.cloned();
+            let Some(leaf) = leaf else {
+                continue;
+            };
+            let header = leaf.header();
+
+            let common = vid.get(&height).and_then(|common| {
+                match verify_vid_common::<Types>(header, common.common()) {
+                    Ok(()) => Some(common.common()),
+                    Err(err) => {
+                        problems.push(Inconsistency::new(height, ObjectKind::VidCommon, err));
+                        None
+                    },
+                }
+            });
+
+            let block = blocks.get(&height);
+            if let Some(block) = block {
+                if block.hash() != leaf.block_hash() {
+                    problems.push(Inconsistency::new(
+                        height,
+                        ObjectKind::Payload,
+                        "block header does not match leaf",
+                    ));
+                } else if let Some(common) = common {
+                    if let Err(err) = verify_payload::<Types>(header, block.payload(), common) {
+                        problems.push(Inconsistency::new(height, ObjectKind::Payload, err));
+                    }
+                }
+            }
+
+            if let (Some(block), Some(metadata)) = (block, metadata.get(&height)) {
+                if *metadata != PayloadMetadata::from(block.clone()) {
+                    problems.push(Inconsistency::new(
+                        height,
+                        ObjectKind::PayloadMetadata,
+                        "payload metadata does not match payload",
+                    ));
+                }
+            }
+        }
+    }
+    Ok(problems)
+}
+
+/// Check that the stored tree for `State` matches the state commitments in `range`.
+///
+/// For each height, the root of the tree as of that height is loaded and checked against the state
+/// commitment in the header. Heights beyond the latest stored state, whose state has been pruned,
+/// or whose header does not commit to `State` are skipped.
+pub async fn scan_merklized_state<Types, State, S, const ARITY: usize>(
+    storage: &S,
+    range: Range<u64>,
+) -> anyhow::Result<Vec<Inconsistency>>
+where
+    Types: NodeType,
+    State: MerklizedState<Types, ARITY>,
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: MerklizedStateStorage<Types, State, ARITY>,
+{
+    let mut problems = vec![];
+    for height in range {
+        let mut tx = storage.read().await.context("opening transaction")?;
+        match tx.get_root(height).await {
+            Ok((root, commit)) => {
+                if root != commit.digest() {
+                    problems.push(Inconsistency::new(
+                        height,
+                        ObjectKind::MerklizedState,
+                        format!(
+                            "{} root {root:?} does not match header commitment {commit}",
+                            State::state_type()
+                        ),
+                    ));
+                }
+            },
+            Err(QueryError::NotFound) => {},
+            Err(err) => problems.push(Inconsistency::new(
+                height,
+                ObjectKind::MerklizedState,
+                format!("{}: {err}", State::state_type()),
+            )),
+        }
+    }
+    Ok(problems)
+}
+
+/// Delete the blocks affected by `problems` from storage.
+///
+/// All of the deletions happen in a single transaction. Problems with merklized state are ignored.
+/// Returns the heights of the blocks which were deleted.
+pub async fn repair<S>(storage: &S, problems: &[Inconsistency]) -> anyhow::Result<Vec<u64>>
+where
+    S: VersionedDataSource,
+    for<'a> S::Transaction<'a>: RepairStorage,
+{
+    let heights = problems
+        .iter()
+        .filter(|problem| problem.kind != ObjectKind::MerklizedState)
+        .map(|problem| problem.height)
+        .collect::<BTreeSet<_>>();
+    if heights.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut tx = storage.write().await.context("opening transaction")?;
+    for &height in &heights {
+        tx.delete_block(height)
+            .await
+            .with_context(|| format!("deleting block {height}"))?;
+    }
+    tx.commit().await.context("committing repair")?;
+    Ok(heights.into_iter().collect())
+}
+
+/// An object which can be loaded from storage and checked by [`scan`].
+trait Stored<Types>: HeightIndexed + Sized
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    const KIND: ObjectKind;
+
+    fn load_range<S>(
+        tx: &mut S,
+        range: Range<usize>,
+    ) -> impl Send + Future<Output = QueryResult<Vec<QueryResult<Self>>>>
+    where
+        S: AvailabilityStorage<Types>;
+
+    fn load<S>(tx: &mut S, height: usize) -> impl Send + Future<Output = QueryResult<Self>>
+    where
+        S: AvailabilityStorage<Types>;
+}
+
+impl<Types> Stored<Types> for LeafQueryData<Types>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    const KIND: ObjectKind = ObjectKind::Leaf;
+
+    fn load_range<S>(
+        tx: &mut S,
+        range: Range<usize>,
+    ) -> impl Send + Future<Output = QueryResult<Vec<QueryResult<Self>>>>
+    where
+        S: AvailabilityStorage<Types>,
+    {
+        tx.get_leaf_range(range)
+    }
+
+    fn load<S>(tx: &mut S, height: usize) -> impl Send + Future<Output = QueryResult<Self>>
+    where
+        S: AvailabilityStorage<Types>,
+    {
+        tx.get_leaf(height.into())
+    }
+}
+
+impl<Types> Stored<Types> for BlockQueryData<Types>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    const KIND: ObjectKind = ObjectKind::Payload;
+
+    fn load_range<S>(
+        tx: &mut S,
+        range: Range<usize>,
+    ) -> impl Send + Future<Output = QueryResult<Vec<QueryResult<Self>>>>
+    where
+        S: AvailabilityStorage<Types>,
+    {
+        tx.get_block_range(range)
+    }
+
+    fn load<S>(tx: &mut S, height: usize) -> impl Send + Future<Output = QueryResult<Self>>
+    where
+        S: AvailabilityStorage<Types>,
+    {
+        tx.get_block(height.into())
+    }
+}
+
+impl<Types> Stored<Types> for VidCommonQueryData<Types>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    const KIND: ObjectKind = ObjectKind::VidCommon;
+
+    fn load_range<S>(
+        tx: &mut S,
+        range: Range<usize>,
+    ) -> impl Send + Future<Output = QueryResult<Vec<QueryResult<Self>>>>
+    where
+        S: AvailabilityStorage<Types>,
+    {
+        tx.get_vid_common_range(range)
+    }
+
+    fn load<S>(tx: &mut S, height: usize) -> impl Send + Future<Output = QueryResult<Self>>
+    where
+        S: AvailabilityStorage<Types>,
+    {
+        tx.get_vid_common(height.into())
+    }
+}
+
+impl<Types> Stored<Types> for PayloadMetadata<Types>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    const KIND: ObjectKind = ObjectKind::PayloadMetadata;
+
+    fn load_range<S>(
+        tx: &mut S,
+        range: Range<usize>,
+    ) -> impl Send + Future<Output = QueryResult<Vec<QueryResult<Self>>>>
+    where
+        S: AvailabilityStorage<Types>,
+    {
+        tx.get_payload_metadata_range(range)
+    }
+
+    fn load<S>(tx: &mut S, height: usize) -> impl Send + Future<Output = QueryResult<Self>>
+    where
+        S: AvailabilityStorage<Types>,
+    {
+        tx.get_payload_metadata(height.into())
+    }
+}
+
+/// Load the objects of type `T` in `range`, indexed by height.
+///
+/// Range queries report objects which cannot be decoded without saying which height they belong
+/// to, and cannot tell us if an object is stored under the wrong height. If a batch has either
+/// problem, we fall back to loading each object individually, reporting the ones which are
+/// unreadable or for the wrong height and leaving them out of the result.
+async fn load_batch<Types, T, S>(
+    tx: &mut S,
+    range: Range<u64>,
+    problems: &mut Vec<Inconsistency>,
+) -> anyhow::Result<BTreeMap<u64, T>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    T: Stored<Types>,
+    S: AvailabilityStorage<Types>,
+{
+    let objects = T::load_range(tx, range.start as usize..range.end as usize)
+        .await
+        .with_context(|| format!("loading {:?} {range:?}", T::KIND))?;
+
+    // Heights in a range query must be strictly increasing and within the range.
+    let mut next = range.start;
+    let mut batch = BTreeMap::new();
+    let mut regular = true;
+    for obj in objects {
+        match obj {
+            Ok(obj) if obj.height() >= next && obj.height() < range.end => {
+                next = obj.height() + 1;
+                batch.insert(obj.height(), obj);
+            },
+            _ => {
+                regular = false;
+                break;
+            },
+        }
+    }
+    if regular {
+        return Ok(batch);
+    }
+
+    let mut batch = BTreeMap::new();
+    for height in range {
+        match T::load(tx, height as usize).await {
+            Ok(obj) if obj.height() == height => {
+                batch.insert(height, obj);
+            },
+            Ok(obj) => problems.push(Inconsistency::new(
+                height,
+                T::KIND,
+                format!("stored object belongs to height {}", obj.height()),
+            )),
+            Err(QueryError::NotFound | QueryError::Missing) => {},
+            Err(err) => problems.push(Inconsistency::new(
+                height,
+                T::KIND,
+                format!("unreadable: {err}"),
+            )),
+        }
+    }
+    Ok(batch)
+}
+
+// These tests run the `postgres` Docker image, which doesn't work on Windows.
+#[cfg(all(test, not(target_os = "windows")))]
+mod test {
+    use futures::stream::StreamExt;
+
+    use super::*;
+    use crate::{
+        availability::{AvailabilityDataSource, LeafId},
+        data_source::storage::sql::{query, Executor},
+        testing::{
+            consensus::{MockNetwork, MockSqlDataSource},
+            mocks::{MockTypes, MockVersions},
+            setup_test,
+        },
+    };
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_integrity_scan_and_repair() {
+        setup_test();
+
+        let mut network = MockNetwork::<MockSqlDataSource, MockVersions>::init().await;
+        network.start().await;
+        let ds = network.data_source();
+        ds.subscribe_blocks(0)
+            .await
+            .take(5)
+            .collect::<Vec<_>>()
+            .await;
+        ds.subscribe_vid_common(0)
+            .await
+            .take(5)
+            .collect::<Vec<_>>()
+            .await;
+
+        // Intact data passes the scan.
+        assert_eq!(scan::<MockTypes, _>(&ds, 0..5, 2).await.unwrap(), vec![]);
+
+        // Overwrite leaf 2 with leaf 3, as if a write had gone to the wrong row.
+        let storage = ds.inner();
+        let mut tx = storage.write().await.unwrap();
+        tx.execute(query(
+            "UPDATE leaf2 SET
+                leaf = (SELECT leaf FROM leaf2 WHERE height = 3),
+                qc = (SELECT qc FROM leaf2 WHERE height = 3)
+              WHERE height = 2",
+        ))
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+
+        let problems = scan::<MockTypes, _>(&ds, 0..5, 2).await.unwrap();
+        tracing::info!(?problems);
+        assert_eq!(
+            problems
+                .iter()
+                .map(|problem| (problem.height, problem.kind))
+                .collect::<Vec<_>>(),
+            vec![(2, ObjectKind::Leaf)]
+        );
+
+        // Repair deletes the bad block, leaving the rest of the database consistent.
+        assert_eq!(repair(&*storage, &problems).await.unwrap(), vec![2]);
+        assert_eq!(scan::<MockTypes, _>(&ds, 0..5, 2).await.unwrap(), vec![]);
+        let mut tx = storage.read().await.unwrap();
+        let err = AvailabilityStorage::<MockTypes>::get_leaf(&mut tx, LeafId::Number(2))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, QueryError::NotFound), "{err:#}");
+        drop(tx);
+
+        network.shut_down().await;
+    }
+}
@@ -0,0 +1,630 @@
+// Copyright (c) 2022 Espresso Systems (espressosys.com)
+// This file is part of the HotShot Query Service library.
+//
+// This program is free software: you can redistribute it and/or modify it under the terms of the GNU
+// General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+// This program is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without
+// even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+// You should have received a copy of the GNU General Public License along with this program. If not,
+// see <https://www.gnu.org/licenses/>.
+
+//! Migration of data between storage backends.
+//!
+//! [`migrate`] copies all of the data in one storage backend into another, for example from a
+//! [`FileSystemStorage`](super::FileSystemStorage) into a [`SqlStorage`](super::SqlStorage), without
+//! having to re-sync the chain from peers. Data is copied in batches, each in its own transaction
+//! on the destination, and progress can be saved to a checkpoint file after each batch, so that an
+//! interrupted migration can be resumed where it left off. Once the migration is complete,
+//! [`verify`] can be used to check that the destination agrees with the source.
+//!
+//! Leaves, blocks, VID common data and shares, and state certificates are copied directly.
+//! Aggregate statistics are recomputed in the destination as blocks are copied. Merklized state
+//! only exists in SQL storage, and is copied separately by [`migrate_merklized_state`], table by
+//! table.
+//!
+//! Data can be read from any [`MigrationSource`]. Every storage backend is a source, as is
+//! [`SqliteSource`](super::sql::SqliteSource) (with the `sqlite-source` feature), which reads a
+//! SQLite database directly. Since the SQL backend of [`SqlStorage`](super::SqlStorage) is fixed
+//! at compile time, this is what allows a Postgres build to migrate data out of SQLite.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{ensure, Context};
+use async_trait::async_trait;
+use hotshot_types::{
+    data::VidShare,
+    traits::node_implementation::{ConsensusTime, NodeType},
+};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "sql-data-source")]
+use sqlx::types::{BitVec, JsonValue};
+
+#[cfg(feature = "sql-data-source")]
+use super::SqlStorage;
+use super::{
+    Aggregate, AggregatesStorage, AvailabilityStorage, NodeStorage, UpdateAggregatesStorage,
+    UpdateAvailabilityStorage,
+};
+use crate::{
+    availability::{
+        BlockHash, BlockQueryData, LeafHash, LeafQueryData, PayloadMetadata, QueryableHeader,
+        QueryablePayload, StateCertQueryData, VidCommonQueryData,
+    },
+    data_source::{Transaction, VersionedDataSource},
+    types::HeightIndexed,
+    Header, Payload, QueryError,
+};
+
+#[derive(Clone, Debug)]
+pub struct MigrationCfg {
+    batch_size: u64,
+    checkpoint: Option<PathBuf>,
+}
+
+impl Default for MigrationCfg {
+    fn default() -> Self {
+        Self {
+            batch_size: 1000,
+            checkpoint: None,
+        }
+    }
+}
+
+impl MigrationCfg {
+    /// Number of blocks to copy in each transaction.
+    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Save progress to a checkpoint file at `path`.
+    ///
+    /// If the file already exists, the migration resumes from the checkpoint it contains.
+    pub fn with_checkpoint(mut self, path: impl Into<PathBuf>) -> Self {
+        self.checkpoint = Some(path.into());
+        self
+    }
+
+    pub fn batch_size(&self) -> u64 {
+        self.batch_size
+    }
+
+    pub fn checkpoint(&self) -> Option<&Path> {
+        self.checkpoint.as_deref()
+    }
+}
+
+/// Progress of a migration, as saved in a checkpoint file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Checkpoint {
+    /// All blocks below this height have been migrated.
+    pub height: u64,
+}
+
+/// Summary of the data copied by a call to [`migrate`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// The range of block heights migrated by this call.
+    pub range: Range<u64>,
+    pub leaves: u64,
+    pub blocks: u64,
+    pub vid_common: u64,
+    pub vid_shares: u64,
+    pub state_certs: u64,
+}
+
+/// Storage which data can be migrated out of.
+#[async_trait]
+pub trait MigrationSource<Types>: Send + Sync
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    /// The number of blocks in storage.
+    async fn block_height(&self) -> anyhow::Result<u64>;
+
+    /// Load the objects in a range of block heights.
+    ///
+    /// Objects which are missing are left out. State certificates are only loaded for epochs which
+    /// are not already in `epochs`, which is updated with the epochs loaded.
+    async fn load_batch(
+        &self,
+        range: Range<u64>,
+        epochs: &mut BTreeSet<u64>,
+    ) -> anyhow::Result<Batch<Types>>;
+
+    /// Summarize the objects present in a range of block heights.
+    async fn load_summary(
+        &self,
+        range: Range<u64>,
+    ) -> anyhow::Result<BTreeMap<u64, Summary<Types>>>;
+}
+
+#[async_trait]
+impl<Types, S> MigrationSource<Types> for S
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<Types> + NodeStorage<Types>,
+{
+    async fn block_height(&self) -> anyhow::Result<u64> {
+        Ok(self.read().await?.block_height().await? as u64)
+    }
+
+    async fn load_batch(
+        &self,
+        range: Range<u64>,
+        epochs: &mut BTreeSet<u64>,
+    ) -> anyhow::Result<Batch<Types>> {
+        load_batch(self, range, epochs).await
+    }
+
+    async fn load_summary(
+        &self,
+        range: Range<u64>,
+    ) -> anyhow::Result<BTreeMap<u64, Summary<Types>>> {
+        load_summary(self, range).await
+    }
+}
+
+/// Copy all the data in `src` into `dst`.
+///
+/// Data is copied up to the block height of `src` at the time this function is called. Objects
+/// which are missing in `src` are skipped, and can be fetched as usual by the node using `dst`.
+pub async fn migrate<Types, Src, Dst>(
+    src: &Src,
+    dst: &Dst,
+    cfg: &MigrationCfg,
+) -> anyhow::Result<MigrationReport>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    Src: MigrationSource<Types>,
+    Dst: VersionedDataSource,
+    for<'a> Dst::Transaction<'a>: UpdateAvailabilityStorage<Types> + UpdateAggregatesStorage<Types>,
+    for<'a> Dst::ReadOnly<'a>: AggregatesStorage<Types>,
+{
+    ensure!(cfg.batch_size > 0, "batch size must be positive");
+
+    let start = match cfg.checkpoint() {
+        Some(path) => load_checkpoint(path)?.height,
+        None => 0,
+    };
+    let end = src.block_height().await?;
+    let mut report = MigrationReport {
+        range: start..end,
+        ..Default::default()
+    };
+    if start >= end {
+        tracing::info!(start, end, "nothing to migrate");
+        return Ok(report);
+    }
+
+    // Aggregates must be computed in order, starting from wherever the destination left off.
+    let (mut aggregate_height, mut aggregate) =
+        match dst.read().await?.load_prev_aggregate().await? {
+            Some(aggregate) => (aggregate.height as u64 + 1, aggregate),
+            None => (0, Aggregate::default()),
+        };
+    // State certificates are indexed by epoch, and an epoch may span several batches. Keep track
+    // of the ones we have already copied so each is only copied once.
+    let mut epochs = BTreeSet::new();
+
+    tracing::info!(start, end, "starting migration");
+    for batch_start in (start..end).step_by(cfg.batch_size as usize) {
+        let batch_end = (batch_start + cfg.batch_size).min(end);
+        let batch = src.load_batch(batch_start..batch_end, &mut epochs).await?;
+
+        report.leaves += batch.leaves.len() as u64;
+        report.blocks += batch.blocks.len() as u64;
+        report.vid_common += batch.vid.len() as u64;
+        report.vid_shares += batch
+            .vid
+            .iter()
+            .filter(|(_, share)| share.is_some())
+            .count() as u64;
+        report.state_certs += batch.state_certs.len() as u64;
+
+        let metadata = batch
+            .blocks
+            .iter()
+            .filter(|block| block.height() >= aggregate_height)
+            .map(|block| PayloadMetadata::from(block.clone()))
+            .collect::<Vec<_>>();
+
+        let mut tx = dst.write().await?;
+        for leaf in batch.leaves {
+            tx.insert_leaf(leaf).await?;
+        }
+        for block in batch.blocks {
+            tx.insert_block(block).await?;
+        }
+        for (common, share) in batch.vid {
+            tx.insert_vid(common, share).await?;
+        }
+        for cert in batch.state_certs {
+            tx.insert_state_cert(cert).await?;
+        }
+        // Aggregates can only be extended over a contiguous run of blocks. If there is a gap, the
+        // destination's own aggregator will fill in the rest once the missing blocks are fetched.
+        let contiguous = metadata
+            .iter()
+            .zip(aggregate_height..)
+            .take_while(|(block, height)| block.height() == *height)
+            .count();
+        if contiguous > 0 {
+            aggregate = tx
+                .update_aggregates(aggregate, &metadata[..contiguous])
+                .await?;
+            aggregate_height += contiguous as u64;
+        }
+        tx.commit().await?;
+
+        if let Some(path) = cfg.checkpoint() {
+            save_checkpoint(path, Checkpoint { height: batch_end })?;
+        }
+        tracing::info!(height = batch_end, end, "migrated batch");
+    }
+
+    Ok(report)
+}
+
+/// Check that `dst` contains everything that `src` does in the given range of block heights.
+///
+/// Returns the heights at which `dst` is missing or disagrees with an object present in `src`.
+pub async fn verify<Types, Src, Dst>(
+    src: &Src,
+    dst: &Dst,
+    range: Range<u64>,
+    batch_size: u64,
+) -> anyhow::Result<Vec<u64>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    Src: MigrationSource<Types>,
+    Dst: MigrationSource<Types>,
+{
+    ensure!(batch_size > 0, "batch size must be positive");
+
+    let mut mismatches = vec![];
+    for start in range.clone().step_by(batch_size as usize) {
+        let end = (start + batch_size).min(range.end);
+        let expected = src.load_summary(start..end).await?;
+        let actual = dst.load_summary(start..end).await?;
+        mismatches.extend(
+            expected
+                .into_iter()
+                .filter(|(height, summary)| summary.differs(actual.get(height)))
+                .map(|(height, _)| height),
+        );
+    }
+    mismatches.sort();
+    mismatches.dedup();
+    Ok(mismatches)
+}
+
+/// Load the migration checkpoint from `path`, if there is one.
+pub fn load_checkpoint(path: &Path) -> anyhow::Result<Checkpoint> {
+    if !path.exists() {
+        return Ok(Checkpoint::default());
+    }
+    let bytes = fs::read(path).with_context(|| format!("reading checkpoint {path:?}"))?;
+    serde_json::from_slice(&bytes).with_context(|| format!("decoding checkpoint {path:?}"))
+}
+
+fn save_checkpoint(path: &Path, checkpoint: Checkpoint) -> anyhow::Result<()> {
+    // Write to a temporary file and rename it, so that a crash in the middle of writing the
+    // checkpoint cannot leave behind a corrupt file.
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, serde_json::to_vec(&checkpoint)?)
+        .with_context(|| format!("writing checkpoint {tmp:?}"))?;
+    fs::rename(&tmp, path).with_context(|| format!("saving checkpoint {path:?}"))?;
+    Ok(())
+}
+
+/// The objects in a range of block heights, as loaded from a [`MigrationSource`].
+pub struct Batch<Types: NodeType> {
+    pub leaves: Vec<LeafQueryData<Types>>,
+    pub blocks: Vec<BlockQueryData<Types>>,
+    pub vid: Vec<(VidCommonQueryData<Types>, Option<VidShare>)>,
+    pub state_certs: Vec<StateCertQueryData<Types>>,
+}
+
+async fn load_batch<Types, S>(
+    storage: &S,
+    range: Range<u64>,
+    epochs: &mut BTreeSet<u64>,
+) -> anyhow::Result<Batch<Types>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<Types> + NodeStorage<Types>,
+{
+    let bounds = range.start as usize..range.end as usize;
+    let mut tx = storage.read().await?;
+
+    let leaves = tx
+        .get_leaf_range(bounds.clone())
+        .await?
+        .into_iter()
+        .filter_map(|res| res.ok())
+        .collect::<Vec<_>>();
+    let blocks = tx
+        .get_block_range(bounds.clone())
+        .await?
+        .into_iter()
+        .filter_map(|res| res.ok())
+        .collect::<Vec<_>>();
+
+    let mut shares = tx
+        .vid_share_range(bounds.clone())
+        .await?
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+    let vid = tx
+        .get_vid_common_range(bounds)
+        .await?
+        .into_iter()
+        .filter_map(|res| res.ok())
+        .map(|common| {
+            let share = shares.remove(&common.height());
+            (common, share)
+        })
+        .collect();
+
+    let mut state_certs = vec![];
+    for epoch in leaves.iter().filter_map(|leaf| leaf.qc().data.epoch) {
+        let epoch = epoch.u64();
+        if !epochs.insert(epoch) {
+            continue;
+        }
+        match tx.get_state_cert(epoch).await {
+            Ok(cert) => state_certs.push(cert),
+            // Not every epoch has a state certificate in storage.
+            Err(QueryError::NotFound | QueryError::Missing) => {},
+            Err(err) => return Err(err).with_context(|| format!("loading state cert {epoch}")),
+        }
+    }
+
+    Ok(Batch {
+        leaves,
+        blocks,
+        vid,
+        state_certs,
+    })
+}
+
+/// The identifying hashes of the objects stored at a single height.
+pub struct Summary<Types: NodeType> {
+    pub leaf: Option<LeafHash<Types>>,
+    pub block: Option<BlockHash<Types>>,
+    pub vid_common: Option<BlockHash<Types>>,
+}
+
+impl<Types: NodeType> Default for Summary<Types> {
+    fn default() -> Self {
+        Self {
+            leaf: None,
+            block: None,
+            vid_common: None,
+        }
+    }
+}
+
+impl<Types: NodeType> Summary<Types> {
+    /// Whether `other` is missing or disagrees with any object present in `self`.
+    fn differs(&self, other: Option<&Self>) -> bool {
+        let Some(other) = other else {
+            return true;
+        };
+        (self.leaf.is_some() && self.leaf != other.leaf)
+            || (self.block.is_some() && self.block != other.block)
+            || (self.vid_common.is_some() && self.vid_common != other.vid_common)
+    }
+}
+
+async fn load_summary<Types, S>(
+    storage: &S,
+    range: Range<u64>,
+) -> anyhow::Result<BTreeMap<u64, Summary<Types>>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<Types>,
+{
+    let bounds = range.start as usize..range.end as usize;
+    let mut tx = storage.read().await?;
+    let mut summaries = BTreeMap::<u64, Summary<Types>>::new();
+
+    for leaf in tx
+        .get_leaf_range(bounds.clone())
+        .await?
+        .into_iter()
+        .filter_map(|res| res.ok())
+    {
+        summaries.entry(leaf.height()).or_default().leaf = Some(leaf.hash());
+    }
+    for meta in tx
+        .get_payload_metadata_range(bounds.clone())
+        .await?
+        .into_iter()
+        .filter_map(|res| res.ok())
+    {
+        summaries.entry(meta.height()).or_default().block = Some(meta.block_hash);
+    }
+    for meta in tx
+        .get_vid_common_metadata_range(bounds)
+        .await?
+        .into_iter()
+        .filter_map(|res| res.ok())
+    {
+        summaries.entry(meta.height()).or_default().vid_common = Some(meta.block_hash);
+    }
+
+    Ok(summaries)
+}
+
+/// A node of a Merkle tree, as stored in the table for some merklized state.
+///
+/// Hashes are given by value, rather than by their IDs in the `hash` table, since IDs differ
+/// between databases.
+#[cfg(feature = "sql-data-source")]
+#[derive(Clone, Debug)]
+pub struct MerkleNodeRow {
+    pub path: JsonValue,
+    pub created: i64,
+    pub hash: Vec<u8>,
+    pub children: Option<Vec<Vec<u8>>>,
+    pub children_bitvec: Option<BitVec>,
+    pub idx: Option<JsonValue>,
+    pub entry: Option<JsonValue>,
+}
+
+/// SQL storage which merklized state can be migrated out of.
+#[cfg(feature = "sql-data-source")]
+#[async_trait]
+pub trait MerklizedStateSource: Send + Sync {
+    /// The height of the last block for which merklized state is stored.
+    async fn last_state_height(&self) -> anyhow::Result<u64>;
+
+    /// Load the nodes of the tree stored in `table` which were created in blocks in `created`.
+    async fn load_merkle_nodes(
+        &self,
+        table: &str,
+        created: Range<u64>,
+    ) -> anyhow::Result<Vec<MerkleNodeRow>>;
+}
+
+/// Copy the merklized state stored in `tables` from `src` into `dst`.
+///
+/// State is copied in batches of `batch_size` blocks, each in its own transaction, up to the last
+/// block for which `src` has merklized state, and then the merklized state height of `dst` is set
+/// to match. Since copying a node which is already in `dst` has no effect, an interrupted
+/// migration can simply be run again.
+///
+/// Returns the number of nodes copied.
+#[cfg(feature = "sql-data-source")]
+pub async fn migrate_merklized_state<Src>(
+    src: &Src,
+    dst: &SqlStorage,
+    tables: &[&str],
+    batch_size: u64,
+) -> anyhow::Result<u64>
+where
+    Src: MerklizedStateSource + ?Sized,
+{
+    ensure!(batch_size > 0, "batch size must be positive");
+
+    let height = src.last_state_height().await?;
+    let mut nodes = 0;
+    tracing::info!(height, ?tables, "migrating merklized state");
+    for start in (0..=height).step_by(batch_size as usize) {
+        let end = (start + batch_size).min(height + 1);
+        let mut tx = dst.write().await?;
+        for table in tables {
+            let rows = src
+                .load_merkle_nodes(table, start..end)
+                .await
+                .with_context(|| format!("loading {table} nodes in blocks {start}..{end}"))?;
+            nodes += rows.len() as u64;
+            tx.insert_merkle_node_rows(table, rows).await?;
+        }
+        tx.commit().await?;
+        tracing::info!(height = end, nodes, "migrated merklized state batch");
+    }
+
+    let mut tx = dst.write().await?;
+    tx.set_merklized_state_height(height).await?;
+    tx.commit().await?;
+    Ok(nodes)
+}
+
+// These tests run the `postgres` Docker image, which doesn't work on Windows.
+#[cfg(all(test, not(target_os = "windows")))]
+mod test {
+    use futures::stream::StreamExt;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        availability::AvailabilityDataSource,
+        data_source::storage::FileSystemStorage,
+        testing::{
+            consensus::{MockDataSource, MockNetwork},
+            mocks::{MockTypes, MockVersions},
+            setup_test,
+        },
+    };
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_migrate_resume_and_verify() {
+        setup_test();
+
+        let mut network = MockNetwork::<MockDataSource, MockVersions>::init().await;
+        network.start().await;
+        network
+            .data_source()
+            .subscribe_vid_common(0)
+            .await
+            .take(5)
+            .collect::<Vec<_>>()
+            .await;
+        // The source may keep growing while we migrate, but the migration only copies up to the
+        // height at which it started.
+        let src = network.data_source();
+
+        let dir = TempDir::with_prefix("test_migrate_resume_and_verify").unwrap();
+        let dst = FileSystemStorage::<MockTypes>::create(&dir.path().join("fs"))
+            .await
+            .unwrap();
+        let checkpoint = dir.path().join("checkpoint.json");
+        let cfg = MigrationCfg::default()
+            .with_batch_size(2)
+            .with_checkpoint(&checkpoint);
+
+        let report = migrate::<MockTypes, _, _>(&src, &dst, &cfg).await.unwrap();
+        let end = report.range.end;
+        assert_eq!(report.range.start, 0);
+        assert!(end >= 5);
+        assert_eq!(report.leaves, end);
+        assert_eq!(load_checkpoint(&checkpoint).unwrap().height, end);
+        assert_eq!(
+            verify::<MockTypes, _, _>(&src, &dst, 0..end, 2)
+                .await
+                .unwrap(),
+            Vec::<u64>::new()
+        );
+
+        // Running the migration again resumes from the checkpoint, so there is nothing to do.
+        let report = migrate::<MockTypes, _, _>(&src, &dst, &cfg).await.unwrap();
+        assert_eq!(report.range, end..end);
+        assert_eq!(report.leaves, 0);
+
+        // A destination which is missing data fails verification.
+        let empty = FileSystemStorage::<MockTypes>::create(&dir.path().join("empty"))
+            .await
+            .unwrap();
+        assert_eq!(
+            verify::<MockTypes, _, _>(&src, &empty, 0..end, 2)
+                .await
+                .unwrap(),
+            (0..end).collect::<Vec<_>>()
+        );
+    }
+}
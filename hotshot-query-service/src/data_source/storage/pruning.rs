@@ -10,11 +10,76 @@
 // You should have received a copy of the GNU General Public License along with this program. If not,
 // see <https://www.gnu.org/licenses/>.
 
-use std::{fmt::Debug, time::Duration};
+use std::{collections::BTreeMap, fmt::Debug, time::Duration};
 
 use anyhow::bail;
 use async_trait::async_trait;
 
+/// A kind of data which can be retained for its own period of time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PrunedData {
+    /// Leaves and headers.
+    ///
+    /// Pruning a leaf also prunes all other data in the same block that references it (payloads,
+    /// VID common data and the transactions index).
+    Leaf,
+    /// Block payloads.
+    Payload,
+    /// VID common data and shares.
+    VidCommon,
+    /// The index of transactions by hash.
+    Transactions,
+    /// Historical nodes of merklized state.
+    ///
+    /// The latest version of each node is never pruned.
+    MerklizedState,
+    /// Aggregate statistics.
+    ///
+    /// The latest aggregate is never pruned.
+    Aggregates,
+}
+
+impl PrunedData {
+    /// All kinds of data, in the order in which they are pruned.
+    ///
+    /// Data which is deleted along with the corresponding leaf comes before leaves, so that a
+    /// shorter retention period for this data takes effect before the leaf is pruned.
+    pub const ALL: [Self; 6] = [
+        Self::Payload,
+        Self::VidCommon,
+        Self::Transactions,
+        Self::MerklizedState,
+        Self::Aggregates,
+        Self::Leaf,
+    ];
+
+    /// A stable name for this kind of data, for use in storage.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Leaf => "leaf",
+            Self::Payload => "payload",
+            Self::VidCommon => "vid_common",
+            Self::Transactions => "transactions",
+            Self::MerklizedState => "merklized_state",
+            Self::Aggregates => "aggregates",
+        }
+    }
+
+    /// Whether this data is deleted along with the leaf from the same block.
+    pub fn depends_on_leaf(&self) -> bool {
+        matches!(self, Self::Payload | Self::VidCommon | Self::Transactions)
+    }
+}
+
+/// How long to keep a kind of data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Retention {
+    /// Keep data for at least this long.
+    Duration(Duration),
+    /// Never prune this data.
+    Forever,
+}
+
 #[derive(Clone, Debug)]
 pub struct PrunerCfg {
     pruning_threshold: Option<u64>,
@@ -25,6 +90,7 @@ pub struct PrunerCfg {
     interval: Duration,
     incremental_vacuum_pages: u64,
     state_tables: Vec<String>,
+    retention: BTreeMap<PrunedData, Retention>,
 }
 
 #[async_trait]
@@ -46,16 +112,32 @@ pub trait PruneStorage: PrunerConfig {
 
 #[async_trait]
 pub trait PrunedHeightStorage: Sized {
+    /// The height of the last block whose leaf has been pruned.
+    ///
+    /// All data at or below this height has been pruned.
     async fn load_pruned_height(&mut self) -> anyhow::Result<Option<u64>> {
         Ok(None)
     }
+
+    /// The height of the last block whose `data` has been pruned.
+    async fn load_pruned_height_for(&mut self, _data: PrunedData) -> anyhow::Result<Option<u64>> {
+        self.load_pruned_height().await
+    }
 }
 
 #[async_trait]
 pub trait PrunedHeightDataSource: Sized {
+    /// The height of the last block whose leaf has been pruned.
+    ///
+    /// All data at or below this height has been pruned.
     async fn load_pruned_height(&self) -> anyhow::Result<Option<u64>> {
         Ok(None)
     }
+
+    /// The height of the last block whose `data` has been pruned.
+    async fn load_pruned_height_for(&self, _data: PrunedData) -> anyhow::Result<Option<u64>> {
+        self.load_pruned_height().await
+    }
 }
 
 pub trait PrunerConfig {
@@ -81,6 +163,22 @@ impl PrunerCfg {
             bail!("max_usage must be less than or equal to 10000")
         }
 
+        // Pruning a leaf also deletes the data which references it, so this data cannot be kept
+        // longer than the leaf.
+        if let Some(leaf_retention) = self.retention(PrunedData::Leaf) {
+            for data in PrunedData::ALL {
+                if !data.depends_on_leaf() {
+                    continue;
+                }
+                if self
+                    .retention(data)
+                    .is_none_or(|retention| retention > leaf_retention)
+                {
+                    bail!("{} retention must not exceed leaf retention", data.as_str());
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -89,6 +187,15 @@ impl PrunerCfg {
         self
     }
 
+    /// Set the retention policy for a specific kind of data.
+    ///
+    /// Data without a specific policy is kept for the [target retention](Self::target_retention)
+    /// period, except for aggregate statistics, which are kept forever by default.
+    pub fn with_retention(mut self, data: PrunedData, retention: Retention) -> Self {
+        self.retention.insert(data, retention);
+        self
+    }
+
     pub fn with_pruning_threshold(mut self, pruning_threshold: u64) -> Self {
         self.pruning_threshold = Some(pruning_threshold);
         self
@@ -176,6 +283,17 @@ impl PrunerCfg {
     pub fn state_tables(&self) -> Vec<String> {
         self.state_tables.clone()
     }
+
+    /// Target retention period for a specific kind of data.
+    ///
+    /// Returns [`None`] if this data is never pruned.
+    pub fn retention(&self, data: PrunedData) -> Option<Duration> {
+        match self.retention.get(&data) {
+            Some(Retention::Duration(retention)) => Some(*retention),
+            Some(Retention::Forever) => None,
+            None => Some(self.target_retention),
+        }
+    }
 }
 
 impl Default for PrunerCfg {
@@ -195,6 +313,7 @@ impl Default for PrunerCfg {
             // 8000 pages
             incremental_vacuum_pages: 8000,
             state_tables: Vec::new(),
+            retention: [(PrunedData::Aggregates, Retention::Forever)].into(),
         }
     }
 }
@@ -11,7 +11,7 @@
 // see <https://www.gnu.org/licenses/>.
 
 #![cfg(feature = "sql-data-source")]
-use std::{cmp::min, fmt::Debug, str::FromStr, time::Duration};
+use std::{cmp::min, collections::BTreeMap, fmt::Debug, str::FromStr, sync::Arc, time::Duration};
 
 use anyhow::Context;
 use async_trait::async_trait;
@@ -35,35 +35,47 @@ use sqlx::{
     pool::{Pool, PoolOptions},
     ConnectOptions, Row,
 };
+use tokio::time::sleep;
 
 use crate::{
     availability::{QueryableHeader, QueryablePayload, VidCommonMetadata, VidCommonQueryData},
     data_source::{
-        storage::pruning::{PruneStorage, PrunerCfg, PrunerConfig},
+        storage::pruning::{
+            PruneStorage, PrunedData, PrunedHeightStorage, PrunerCfg, PrunerConfig,
+        },
         update::Transaction as _,
         VersionedDataSource,
     },
     metrics::PrometheusMetrics,
     node::BlockId,
     status::HasMetrics,
+    task::BackgroundTask,
     Header, QueryError, QueryResult, VidCommon,
 };
 pub extern crate sqlx;
 pub use sqlx::{Database, Sqlite};
 
+mod cache;
+mod cold;
 mod db;
 mod migrate;
 mod queries;
+#[cfg(feature = "sqlite-source")]
+mod sqlite_source;
 mod transaction;
 
 pub use anyhow::Error;
+pub use cache::CacheCfg;
+pub use cold::ColdStorageCfg;
 pub use db::*;
 pub use include_dir::include_dir;
 pub use queries::QueryBuilder;
 pub use refinery::Migration;
+#[cfg(feature = "sqlite-source")]
+pub use sqlite_source::SqliteSource;
 pub use transaction::*;
 
-use self::{migrate::Migrator, transaction::PoolMetrics};
+use self::{cache::HotCache, cold::ColdStore, migrate::Migrator, transaction::PoolMetrics};
 use super::{AvailabilityStorage, NodeStorage};
 // This needs to be reexported so that we can reference it by absolute path relative to this crate
 // in the expansion of `include_migrations`, even when `include_migrations` is invoked from another
@@ -225,6 +237,8 @@ pub struct Config {
     migrations: Vec<Migration>,
     no_migrations: bool,
     pruner_cfg: Option<PrunerCfg>,
+    cold_storage_cfg: Option<ColdStorageCfg>,
+    cache_cfg: Option<CacheCfg>,
     archive: bool,
     pool: Option<Pool<Db>>,
 }
@@ -263,6 +277,8 @@ impl From<SqliteConnectOptions> for Config {
             migrations: vec![],
             no_migrations: false,
             pruner_cfg: None,
+            cold_storage_cfg: None,
+            cache_cfg: None,
             archive: false,
             pool: None,
         }
@@ -280,6 +296,8 @@ impl From<PgConnectOptions> for Config {
             migrations: vec![],
             no_migrations: false,
             pruner_cfg: None,
+            cold_storage_cfg: None,
+            cache_cfg: None,
             archive: false,
             pool: None,
         }
@@ -417,6 +435,37 @@ impl Config {
         Ok(self)
     }
 
+    /// Move old payloads and VID common data to a cold storage tier.
+    ///
+    /// Once a block is older than the configured [age](ColdStorageCfg::age), its payload and VID
+    /// common data are moved to an object store, and the database keeps only a pointer to each
+    /// object. The data remains available through all the usual queries; it is just slower to load.
+    ///
+    /// Data is moved by a background task of the
+    /// [`SqlDataSource`](crate::data_source::SqlDataSource), or explicitly using
+    /// [`SqlStorage::offload`].
+    ///
+    /// This can be combined with either [`archive`](Self::archive) or
+    /// [`pruner_cfg`](Self::pruner_cfg). When pruning, objects are deleted from cold storage
+    /// along with the blocks they belong to.
+    pub fn cold_storage(mut self, cfg: ColdStorageCfg) -> Result<Self, Error> {
+        cfg.validate()?;
+        self.cold_storage_cfg = Some(cfg);
+        Ok(self)
+    }
+
+    /// Keep recently inserted objects in an in-memory cache.
+    ///
+    /// Leaves, headers, payload summaries and VID common data are cached as they are inserted, and
+    /// the most recently used ones are kept, up to the limits in `cfg`. Lookups of these objects by
+    /// height or hash are answered from the cache when possible. Pruned objects are removed from
+    /// the cache.
+    pub fn cache(mut self, cfg: CacheCfg) -> Result<Self, Error> {
+        cfg.validate()?;
+        self.cache_cfg = Some(cfg);
+        Ok(self)
+    }
+
     /// Disable pruning and reconstruct previously pruned data.
     ///
     /// While running without pruning is the default behavior, the default will not try to
@@ -488,12 +537,15 @@ pub struct SqlStorage {
     metrics: PrometheusMetrics,
     pool_metrics: PoolMetrics,
     pruner_cfg: Option<PrunerCfg>,
+    cold: Option<Arc<ColdStore>>,
+    cache: Option<Arc<HotCache>>,
+    _offloader: Option<BackgroundTask>,
 }
 
 #[derive(Debug, Default)]
 pub struct Pruner {
-    pruned_height: Option<u64>,
-    target_height: Option<u64>,
+    pruned_height: BTreeMap<PrunedData, u64>,
+    target_height: BTreeMap<PrunedData, Option<u64>>,
     minimum_retention_height: Option<u64>,
 }
 
@@ -507,6 +559,16 @@ impl SqlStorage {
         let pool_metrics = PoolMetrics::new(&*metrics.subgroup("sql".into()));
         let pool = config.pool_opt.clone();
         let pruner_cfg = config.pruner_cfg;
+        let cold = config.cold_storage_cfg.map(ColdStore::open).transpose()?;
+        let cache = config
+            .cache_cfg
+            .map(|cfg| {
+                HotCache::new(
+                    cfg,
+                    &*metrics.subgroup("sql".into()).subgroup("cache".into()),
+                )
+            })
+            .transpose()?;
 
         // re-use the same pool if present and return early
         if let Some(pool) = config.pool {
@@ -515,6 +577,9 @@ impl SqlStorage {
                 pool_metrics,
                 pool,
                 pruner_cfg,
+                cold,
+                cache,
+                _offloader: None,
             });
         }
 
@@ -595,6 +660,9 @@ impl SqlStorage {
             query("DELETE FROM pruned_height WHERE id = 1")
                 .execute(conn.as_mut())
                 .await?;
+            query("DELETE FROM pruned_height_by_type")
+                .execute(conn.as_mut())
+                .await?;
         }
 
         conn.close().await?;
@@ -604,8 +672,238 @@ impl SqlStorage {
             pool_metrics,
             metrics,
             pruner_cfg,
+            cold,
+            cache,
+            _offloader: None,
         })
     }
+
+    /// Start a background task moving old data to cold storage, if cold storage is enabled.
+    ///
+    /// The task runs until this object and all of its clones are dropped.
+    pub(crate) fn spawn_offloader(mut self) -> Self {
+        if let Some(cold) = &self.cold {
+            // The task gets its own handle to the storage, without a reference to the task itself,
+            // so that the task is cancelled when the last external handle is dropped.
+            let storage = self.clone();
+            let interval = cold.cfg().interval();
+            self._offloader = Some(BackgroundTask::spawn(
+                "cold storage offloader",
+                async move {
+                    loop {
+                        storage.offload_all().await;
+                        sleep(interval).await;
+                    }
+                },
+            ));
+        }
+        self
+    }
+
+    /// Move all data which is old enough to cold storage, one batch at a time.
+    async fn offload_all(&self) {
+        loop {
+            match self.offload().await {
+                Ok(Some(height)) => {
+                    tracing::info!(height, "moved data to cold storage");
+                },
+                Ok(None) => break,
+                Err(err) => {
+                    tracing::error!("failed to move data to cold storage: {err:#}");
+                    break;
+                },
+            }
+        }
+    }
+
+    /// Move one batch of old payloads and VID common data to cold storage.
+    ///
+    /// Returns the height of the last block in the batch, or [`None`] if there is no data old
+    /// enough to move, or cold storage is not enabled.
+    pub async fn offload(&self) -> anyhow::Result<Option<u64>> {
+        let Some(cold) = &self.cold else {
+            return Ok(None);
+        };
+        let cfg = cold.cfg();
+
+        let Some(target_height) = self
+            .get_height_by_timestamp(Utc::now().timestamp() - cfg.age().as_secs() as i64)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let cold_height = self.read().await?.load_cold_height().await?;
+        let from = match cold_height {
+            Some(height) => height + 1,
+            None => match self.get_minimum_height().await? {
+                Some(height) => height,
+                None => return Ok(None),
+            },
+        };
+        if from > target_height {
+            return Ok(None);
+        }
+        let to = min(from + cfg.batch_size() - 1, target_height);
+
+        // Upload the objects first, so that we never write a pointer to an object that does not
+        // exist. If we fail partway through, the next attempt will simply overwrite the objects we
+        // already uploaded.
+        let mut tx = self.read().await?;
+        let payloads = query_as::<(i64, Vec<u8>)>(
+            "SELECT height, data FROM payload
+              WHERE height >= $1 AND height <= $2 AND data IS NOT NULL",
+        )
+        .bind(from as i64)
+        .bind(to as i64)
+        .fetch_all(tx.as_mut())
+        .await?;
+        let vid_commons = query_as::<(i64, Vec<u8>)>(
+            "SELECT height, common FROM vid2
+              WHERE height >= $1 AND height <= $2 AND cold_key IS NULL",
+        )
+        .bind(from as i64)
+        .bind(to as i64)
+        .fetch_all(tx.as_mut())
+        .await?;
+        drop(tx);
+
+        let mut moved_payloads = vec![];
+        for (height, data) in payloads {
+            let key = ColdStore::payload_key(height as u64);
+            cold.put(&key, data).await?;
+            moved_payloads.push((height, key));
+        }
+        let mut moved_vid_commons = vec![];
+        for (height, data) in vid_commons {
+            let key = ColdStore::vid_common_key(height as u64);
+            cold.put(&key, data).await?;
+            moved_vid_commons.push((height, key));
+        }
+
+        let mut tx = self.write().await?;
+        for (height, key) in moved_payloads {
+            tx.execute(
+                query("UPDATE payload SET data = NULL, cold_key = $1 WHERE height = $2")
+                    .bind(key)
+                    .bind(height),
+            )
+            .await?;
+        }
+        for (height, key) in moved_vid_commons {
+            // The `common` column is not nullable, so we replace it with an empty blob.
+            tx.execute(
+                query("UPDATE vid2 SET common = $1, cold_key = $2 WHERE height = $3")
+                    .bind(Vec::<u8>::new())
+                    .bind(key)
+                    .bind(height),
+            )
+            .await?;
+        }
+        tx.save_cold_height(to).await?;
+        tx.commit().await?;
+
+        Ok(Some(to))
+    }
+
+    /// Delete objects in cold storage belonging to `data` in blocks up to and including `height`.
+    ///
+    /// This must be called before the corresponding rows are pruned from the database, since the
+    /// database holds the only pointers to these objects.
+    async fn delete_cold_objects(&self, data: PrunedData, height: u64) -> anyhow::Result<()> {
+        let Some(cold) = &self.cold else {
+            return Ok(());
+        };
+        let tables: &[&str] = match data {
+            PrunedData::Leaf => &["payload", "vid2"],
+            PrunedData::Payload => &["payload"],
+            PrunedData::VidCommon => &["vid2"],
+            _ => return Ok(()),
+        };
+        let sql = tables
+            .iter()
+            .map(|table| {
+                format!("SELECT cold_key FROM {table} WHERE height <= $1 AND cold_key IS NOT NULL")
+            })
+            .join(" UNION ALL ");
+        let mut tx = self.read().await?;
+        let keys = query_as::<(String,)>(&sql)
+            .bind(height as i64)
+            .fetch_all(tx.as_mut())
+            .await?;
+        drop(tx);
+        for (key,) in keys {
+            cold.delete(&key).await?;
+        }
+        Ok(())
+    }
+
+    /// Prune the next batch of `data`, up to `target_height`.
+    ///
+    /// Returns the new pruned height for `data`, or [`None`] if there was nothing left to prune
+    /// below `target_height`.
+    async fn prune_batch(
+        &self,
+        pruner: &mut Pruner,
+        cfg: &PrunerCfg,
+        data: PrunedData,
+        target_height: u64,
+    ) -> anyhow::Result<Option<u64>> {
+        let height = match pruner.pruned_height.get(&data) {
+            Some(h) => *h,
+            None => {
+                // Leaves are pruned starting from the oldest header we have. Other data may have
+                // been pruned further than that already.
+                let pruned_height = if data == PrunedData::Leaf {
+                    None
+                } else {
+                    self.read().await?.load_pruned_height_for(data).await?
+                };
+                let height = match pruned_height {
+                    Some(h) => Some(h),
+                    None => self.get_minimum_height().await?,
+                };
+                let Some(height) = height else {
+                    tracing::info!("database is empty, nothing to prune");
+                    return Ok(None);
+                };
+                height
+            },
+        };
+        if height >= target_height {
+            return Ok(None);
+        }
+
+        let height = min(height + cfg.batch_size(), target_height);
+        self.delete_cold_objects(data, height).await?;
+        let mut tx = self.write().await?;
+        tx.delete_batch(data, &cfg.state_tables(), height).await?;
+        tx.commit().await.map_err(|e| QueryError::Error {
+            message: format!("failed to commit {e}"),
+        })?;
+        pruner.pruned_height.insert(data, height);
+        tracing::debug!(data = data.as_str(), height, "pruned batch");
+        Ok(Some(height))
+    }
+}
+
+/// The retention period for `data`, if it needs to be pruned on its own.
+///
+/// Returns [`None`] if `data` is kept forever or if there is nothing to prune. Data which is deleted
+/// along with leaves only needs to be pruned on its own if it has a shorter retention period than
+/// leaves.
+fn own_retention(cfg: &PrunerCfg, data: PrunedData) -> Option<Duration> {
+    let retention = cfg.retention(data)?;
+    if data.depends_on_leaf() {
+        if let Some(leaf_retention) = cfg.retention(PrunedData::Leaf) {
+            if retention >= leaf_retention {
+                return None;
+            }
+        }
+    }
+    if data == PrunedData::MerklizedState && cfg.state_tables().is_empty() {
+        return None;
+    }
+    Some(retention)
 }
 
 impl PrunerConfig for SqlStorage {
@@ -760,49 +1058,36 @@ impl PruneStorage for SqlStorage {
         let cfg = self.get_pruning_config().ok_or(QueryError::Error {
             message: "Pruning config not found".to_string(),
         })?;
-        let batch_size = cfg.batch_size();
         let max_usage = cfg.max_usage();
-        let state_tables = cfg.state_tables();
 
         // If a pruner run was already in progress, some variables may already be set,
         // depending on whether a batch was deleted and which batch it was (target or minimum retention).
         // This enables us to resume the pruner run from the exact heights.
         // If any of these values are not set, they can be loaded from the database if necessary.
         let mut minimum_retention_height = pruner.minimum_retention_height;
-        let mut target_height = pruner.target_height;
-        let mut height = match pruner.pruned_height {
-            Some(h) => h,
-            None => {
-                let Some(height) = self.get_minimum_height().await? else {
-                    tracing::info!("database is empty, nothing to prune");
-                    return Ok(None);
-                };
-
-                height
-            },
-        };
 
-        // Prune data exceeding target retention in batches
-        if pruner.target_height.is_none() {
-            let th = self
-                .get_height_by_timestamp(
-                    Utc::now().timestamp() - (cfg.target_retention().as_secs()) as i64,
-                )
-                .await?;
-            target_height = th;
-            pruner.target_height = target_height;
-        };
+        // Prune each kind of data exceeding its target retention in batches
+        for data in PrunedData::ALL {
+            let Some(retention) = own_retention(&cfg, data) else {
+                continue;
+            };
+            let target_height = match pruner.target_height.get(&data) {
+                Some(th) => *th,
+                None => {
+                    let th = self
+                        .get_height_by_timestamp(
+                            Utc::now().timestamp() - retention.as_secs() as i64,
+                        )
+                        .await?;
+                    pruner.target_height.insert(data, th);
+                    th
+                },
+            };
 
-        if let Some(target_height) = target_height {
-            if height < target_height {
-                height = min(height + batch_size, target_height);
-                let mut tx = self.write().await?;
-                tx.delete_batch(state_tables, height).await?;
-                tx.commit().await.map_err(|e| QueryError::Error {
-                    message: format!("failed to commit {e}"),
-                })?;
-                pruner.pruned_height = Some(height);
-                return Ok(Some(height));
+            if let Some(target_height) = target_height {
+                if let Some(height) = self.prune_batch(pruner, &cfg, data, target_height).await? {
+                    return Ok(Some(height));
+                }
             }
         }
 
@@ -830,21 +1115,21 @@ impl PruneStorage for SqlStorage {
                 }
 
                 if let Some(min_retention_height) = minimum_retention_height {
-                    if (usage as f64 / threshold as f64) > (f64::from(max_usage) / 10000.0)
-                        && height < min_retention_height
-                    {
-                        height = min(height + batch_size, min_retention_height);
-                        let mut tx = self.write().await?;
-                        tx.delete_batch(state_tables, height).await?;
-                        tx.commit().await.map_err(|e| QueryError::Error {
-                            message: format!("failed to commit {e}"),
-                        })?;
-
-                        self.vacuum().await?;
-
-                        pruner.pruned_height = Some(height);
-
-                        return Ok(Some(height));
+                    if (usage as f64 / threshold as f64) > (f64::from(max_usage) / 10000.0) {
+                        // Data which is configured to be kept forever is not pruned, even to free
+                        // up space.
+                        for data in PrunedData::ALL {
+                            if own_retention(&cfg, data).is_none() {
+                                continue;
+                            }
+                            if let Some(height) = self
+                                .prune_batch(pruner, &cfg, data, min_retention_height)
+                                .await?
+                            {
+                                self.vacuum().await?;
+                                return Ok(Some(height));
+                            }
+                        }
                     }
                 }
             }
@@ -865,11 +1150,23 @@ impl VersionedDataSource for SqlStorage {
         Self: 'a;
 
     async fn write(&self) -> anyhow::Result<Transaction<Write>> {
-        Transaction::new(&self.pool, self.pool_metrics.clone()).await
+        Transaction::new(
+            &self.pool,
+            self.pool_metrics.clone(),
+            self.cold.clone(),
+            self.cache.clone(),
+        )
+        .await
     }
 
     async fn read(&self) -> anyhow::Result<Transaction<Read>> {
-        Transaction::new(&self.pool, self.pool_metrics.clone()).await
+        Transaction::new(
+            &self.pool,
+            self.pool_metrics.clone(),
+            self.cold.clone(),
+            self.cache.clone(),
+        )
+        .await
     }
 }
 
@@ -1382,13 +1679,19 @@ mod test {
         prelude::UniversalMerkleTree, MerkleTreeScheme, ToTraversalPath, UniversalMerkleTreeScheme,
     };
     use jf_vid::VidScheme;
+    use tempfile::TempDir;
     use tokio::time::sleep;
+    use url::Url;
     use vbs::version::StaticVersionType;
 
     use super::{testing::TmpDb, *};
     use crate::{
-        availability::LeafQueryData,
-        data_source::storage::{pruning::PrunedHeightStorage, UpdateAvailabilityStorage},
+        availability::{BlockQueryData, LeafId, LeafQueryData, PayloadQueryData},
+        data_source::storage::{
+            integrity::RepairStorage,
+            pruning::{PrunedHeightStorage, Retention},
+            UpdateAvailabilityStorage,
+        },
         merklized_state::{MerklizedState, UpdateStateData},
         testing::{
             mocks::{MockHeader, MockMerkleTree, MockPayload, MockTypes, MockVersions},
@@ -1646,7 +1949,7 @@ mod test {
 
         // This should delete all the nodes having height < 250 and is not the newest node with its position
         let mut tx = storage.write().await.unwrap();
-        tx.delete_batch(vec!["test_tree".to_string()], 250)
+        tx.delete_batch(PrunedData::MerklizedState, &["test_tree".to_string()], 250)
             .await
             .unwrap();
 
@@ -1775,6 +2078,284 @@ mod test {
         }
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_per_type_retention() {
+        setup_test();
+
+        let db = TmpDb::init().await;
+        let mut storage = SqlStorage::connect(db.config()).await.unwrap();
+
+        // Insert some mock data.
+        let mut leaf = LeafQueryData::<MockTypes>::genesis::<TestVersions>(
+            &TestValidatedState::default(),
+            &TestInstanceState::default(),
+        )
+        .await;
+        let common = VidCommon::V0(advz_scheme(2).disperse([]).unwrap().common);
+        for i in 0..5 {
+            leaf.leaf.block_header_mut().block_number = i;
+            leaf.leaf.block_header_mut().timestamp = Utc::now().timestamp() as u64;
+            let block = BlockQueryData::new(leaf.header().clone(), MockPayload::genesis());
+            let vid = VidCommonQueryData::new(leaf.header().clone(), common.clone());
+
+            let mut tx = storage.write().await.unwrap();
+            tx.insert_leaf(leaf.clone()).await.unwrap();
+            tx.insert_block(block).await.unwrap();
+            tx.insert_vid(vid, None).await.unwrap();
+            tx.commit().await.unwrap();
+        }
+
+        // Data cannot be kept longer than the leaves which it depends on.
+        assert!(PrunerCfg::new()
+            .with_retention(PrunedData::Payload, Retention::Forever)
+            .validate()
+            .is_err());
+
+        // Keep leaves forever, but prune payloads after 1s.
+        let cfg = PrunerCfg::new()
+            .with_retention(PrunedData::Leaf, Retention::Forever)
+            .with_retention(PrunedData::VidCommon, Retention::Forever)
+            .with_retention(PrunedData::Transactions, Retention::Forever)
+            .with_retention(
+                PrunedData::Payload,
+                Retention::Duration(Duration::from_secs(1)),
+            );
+        cfg.validate().unwrap();
+        storage.set_pruning_config(cfg);
+        sleep(Duration::from_secs(2)).await;
+        let mut pruner = Default::default();
+        assert_eq!(storage.prune(&mut pruner).await.unwrap(), Some(4));
+        assert_eq!(storage.prune(&mut pruner).await.unwrap(), None);
+
+        let mut tx = storage.read().await.unwrap();
+        assert_eq!(tx.load_pruned_height().await.unwrap(), None);
+        assert_eq!(
+            tx.load_pruned_height_for(PrunedData::Payload)
+                .await
+                .unwrap(),
+            Some(4)
+        );
+        assert_eq!(
+            tx.load_pruned_height_for(PrunedData::VidCommon)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // Leaves, VID and payload metadata are still there, but the payloads are gone.
+        for i in 0..5usize {
+            tx.get_leaf(i.into()).await.unwrap();
+            tx.get_vid_common(i.into()).await.unwrap();
+            tx.get_payload_metadata(i.into()).await.unwrap();
+            tx.get_block(i.into()).await.unwrap_err();
+        }
+
+        // Pruned payloads are not considered missing.
+        assert_eq!(
+            NodeStorage::<MockTypes>::sync_status(&mut tx)
+                .await
+                .unwrap()
+                .missing_blocks,
+            0
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_cold_storage() {
+        setup_test();
+
+        let db = TmpDb::init().await;
+        let dir = TempDir::with_prefix("test_cold_storage").unwrap();
+        let cold_cfg = ColdStorageCfg::new(Url::from_directory_path(dir.path()).unwrap())
+            .with_age(Duration::from_secs(1))
+            .with_batch_size(3);
+        let mut storage = SqlStorage::connect(db.config().cold_storage(cold_cfg).unwrap())
+            .await
+            .unwrap();
+
+        // Insert some mock data.
+        let mut leaf = LeafQueryData::<MockTypes>::genesis::<TestVersions>(
+            &TestValidatedState::default(),
+            &TestInstanceState::default(),
+        )
+        .await;
+        let common = VidCommon::V0(advz_scheme(2).disperse([]).unwrap().common);
+        let mut blocks = vec![];
+        let mut commons = vec![];
+        for i in 0..5 {
+            leaf.leaf.block_header_mut().block_number = i;
+            leaf.leaf.block_header_mut().timestamp = Utc::now().timestamp() as u64;
+            let block = BlockQueryData::new(leaf.header().clone(), MockPayload::genesis());
+            let vid = VidCommonQueryData::new(leaf.header().clone(), common.clone());
+
+            let mut tx = storage.write().await.unwrap();
+            tx.insert_leaf(leaf.clone()).await.unwrap();
+            tx.insert_block(block.clone()).await.unwrap();
+            tx.insert_vid(vid.clone(), None).await.unwrap();
+            tx.commit().await.unwrap();
+
+            blocks.push(block);
+            commons.push(vid);
+        }
+
+        // Nothing is old enough to move yet.
+        assert_eq!(storage.offload().await.unwrap(), None);
+
+        // Once the data is old enough, it is moved in batches.
+        sleep(Duration::from_secs(2)).await;
+        assert_eq!(storage.offload().await.unwrap(), Some(2));
+        assert_eq!(storage.offload().await.unwrap(), Some(4));
+        assert_eq!(storage.offload().await.unwrap(), None);
+
+        // Only pointers are left in the database.
+        let mut tx = storage.read().await.unwrap();
+        let (payloads,) = query_as::<(i64,)>("SELECT count(*) FROM payload WHERE data IS NOT NULL")
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+        assert_eq!(payloads, 0);
+        let (commons_in_db,) =
+            query_as::<(i64,)>("SELECT count(*) FROM vid2 WHERE cold_key IS NULL")
+                .fetch_one(tx.as_mut())
+                .await
+                .unwrap();
+        assert_eq!(commons_in_db, 0);
+
+        // The data can still be read, and is not considered missing.
+        for (block, common) in blocks.iter().zip(&commons) {
+            let height = block.height() as usize;
+            assert_eq!(tx.get_block(height.into()).await.unwrap(), *block);
+            assert_eq!(
+                tx.get_payload(height.into()).await.unwrap(),
+                PayloadQueryData::from(block.clone())
+            );
+            assert_eq!(tx.get_vid_common(height.into()).await.unwrap(), *common);
+        }
+        let block_range = tx.get_block_range(0..5).await.unwrap();
+        assert_eq!(
+            block_range
+                .into_iter()
+                .collect::<QueryResult<Vec<_>>>()
+                .unwrap(),
+            blocks
+        );
+        let common_range = tx.get_vid_common_range(0..5).await.unwrap();
+        assert_eq!(
+            common_range
+                .into_iter()
+                .collect::<QueryResult<Vec<_>>>()
+                .unwrap(),
+            commons
+        );
+        assert_eq!(
+            NodeStorage::<MockTypes>::sync_status(&mut tx)
+                .await
+                .unwrap()
+                .missing_blocks,
+            0
+        );
+        drop(tx);
+
+        // Pruning deletes the data from cold storage as well.
+        storage.set_pruning_config(PrunerCfg::new().with_target_retention(Duration::from_secs(1)));
+        storage.prune(&mut Default::default()).await.unwrap();
+        for kind in ["payload", "vid_common"] {
+            assert_eq!(
+                std::fs::read_dir(dir.path().join(kind)).unwrap().count(),
+                0,
+                "{kind} objects were not deleted"
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_hot_cache() {
+        setup_test();
+
+        // Enough room for the objects from two blocks.
+        let db = TmpDb::init().await;
+        let storage = SqlStorage::connect(
+            db.config()
+                .cache(CacheCfg::default().with_max_objects(8))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+        // Insert some mock data.
+        let mut leaf = LeafQueryData::<MockTypes>::genesis::<TestVersions>(
+            &TestValidatedState::default(),
+            &TestInstanceState::default(),
+        )
+        .await;
+        let common = VidCommon::V0(advz_scheme(2).disperse([]).unwrap().common);
+        let mut leaves = vec![];
+        let mut commons = vec![];
+        for i in 0..6 {
+            leaf.leaf.block_header_mut().block_number = i;
+            let block = BlockQueryData::new(leaf.header().clone(), MockPayload::genesis());
+            let vid = VidCommonQueryData::new(leaf.header().clone(), common.clone());
+
+            let mut tx = storage.write().await.unwrap();
+            tx.insert_leaf(leaf.clone()).await.unwrap();
+            tx.insert_block(block).await.unwrap();
+            tx.insert_vid(vid.clone(), None).await.unwrap();
+            if i < 5 {
+                tx.commit().await.unwrap();
+                leaves.push(leaf.clone());
+                commons.push(vid);
+            } else {
+                // Changes which are reverted never reach the cache.
+                tx.revert().await;
+            }
+        }
+
+        // Delete everything from the database behind the cache's back, so that only cached
+        // objects can still be read.
+        let mut tx = storage.write().await.unwrap();
+        query("DELETE FROM header")
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        // The most recent blocks are served from the cache, by height or by hash.
+        let mut tx = storage.read().await.unwrap();
+        for i in 3..5usize {
+            assert_eq!(tx.get_leaf(i.into()).await.unwrap(), leaves[i]);
+            assert_eq!(
+                tx.get_leaf(LeafId::Hash(leaves[i].hash())).await.unwrap(),
+                leaves[i]
+            );
+            assert_eq!(
+                tx.get_header(BlockId::Hash(leaves[i].block_hash()))
+                    .await
+                    .unwrap(),
+                *leaves[i].header()
+            );
+            assert_eq!(tx.get_vid_common(i.into()).await.unwrap(), commons[i]);
+            tx.get_payload_metadata(i.into()).await.unwrap();
+        }
+
+        // Older blocks have been evicted, and the reverted block was never cached.
+        for i in [0usize, 1, 2, 5] {
+            tx.get_leaf(i.into()).await.unwrap_err();
+        }
+        drop(tx);
+
+        // Deleting a block removes it from the cache.
+        let mut tx = storage.write().await.unwrap();
+        tx.delete_block(4).await.unwrap();
+        tx.commit().await.unwrap();
+        let mut tx = storage.read().await.unwrap();
+        tx.get_leaf(4.into()).await.unwrap_err();
+        tx.get_leaf(LeafId::Hash(leaves[4].hash()))
+            .await
+            .unwrap_err();
+        tx.get_vid_common(4.into()).await.unwrap_err();
+        tx.get_leaf(3.into()).await.unwrap();
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_types_migration() {
         setup_test();
@@ -0,0 +1,393 @@
+// Copyright (c) 2022 Espresso Systems (espressosys.com)
+// This file is part of the HotShot Query Service library.
+//
+// This program is free software: you can redistribute it and/or modify it under the terms of the GNU
+// General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+// This program is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without
+// even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+// You should have received a copy of the GNU General Public License along with this program. If not,
+// see <https://www.gnu.org/licenses/>.
+
+//! In-memory cache of recently inserted objects.
+//!
+//! Most clients are only interested in the latest blocks, so a small number of leaves, headers,
+//! payload summaries and VID common objects account for most reads. When the cache is enabled,
+//! these objects are kept in memory as they are inserted, and point lookups by height or hash are
+//! answered without touching the database. The cache is bounded both by the number of objects and
+//! by their approximate serialized size, and evicts the least recently used objects first.
+//!
+//! The cache is only ever filled by inserts, never by reads, and changes made by a transaction are
+//! only applied to the cache once the transaction commits. Objects are removed from the cache when
+//! they are pruned or deleted.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::ensure;
+use derivative::Derivative;
+use hotshot_types::traits::{metrics::Metrics, node_implementation::NodeType};
+use lru::LruCache;
+use serde::Serialize;
+
+use crate::{
+    availability::{BlockId, LeafId},
+    data_source::{metrics::CacheMetrics, storage::pruning::PrunedData},
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct CacheCfg {
+    max_objects: usize,
+    max_bytes: usize,
+}
+
+impl Default for CacheCfg {
+    fn default() -> Self {
+        Self {
+            max_objects: 4000,
+            max_bytes: 256 * 1024 * 1024,
+        }
+    }
+}
+
+impl CacheCfg {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(self.max_objects > 0, "cache max_objects must be positive");
+        ensure!(self.max_bytes > 0, "cache max_bytes must be positive");
+        Ok(())
+    }
+
+    pub fn with_max_objects(mut self, max_objects: usize) -> Self {
+        self.max_objects = max_objects;
+        self
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Maximum number of objects to keep in the cache.
+    ///
+    /// Each block contributes up to four objects: a leaf, a header, a payload summary and VID
+    /// common data.
+    pub fn max_objects(&self) -> usize {
+        self.max_objects
+    }
+
+    /// Maximum total size of the objects in the cache, in bytes.
+    ///
+    /// Sizes are estimated from the serialized form of each object.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+}
+
+/// The kinds of objects kept in the cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum CachedKind {
+    Leaf,
+    Header,
+    PayloadMetadata,
+    VidCommon,
+}
+
+impl CachedKind {
+    const ALL: [Self; 4] = [
+        Self::Leaf,
+        Self::Header,
+        Self::PayloadMetadata,
+        Self::VidCommon,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Leaf => "leaf",
+            Self::Header => "header",
+            Self::PayloadMetadata => "payload_metadata",
+            Self::VidCommon => "vid_common",
+        }
+    }
+
+    /// The kinds of cached objects which are removed when `data` is pruned.
+    fn pruned_by(data: PrunedData) -> &'static [Self] {
+        match data {
+            // Pruning leaves deletes the whole block.
+            PrunedData::Leaf => &Self::ALL,
+            // The payload summary lists the namespaces in the block, which are loaded from the
+            // transactions index.
+            PrunedData::Payload | PrunedData::Transactions => &[Self::PayloadMetadata],
+            PrunedData::VidCommon => &[Self::VidCommon],
+            PrunedData::MerklizedState | PrunedData::Aggregates => &[],
+        }
+    }
+}
+
+/// Estimate the memory used by a cached object from its serialized size.
+pub(crate) fn serialized_size<T: Serialize>(object: &T) -> usize {
+    bincode::serialized_size(object).unwrap_or_default() as usize
+}
+
+/// How to find an object in the cache.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum CacheKey {
+    Height(u64),
+    LeafHash(String),
+    BlockHash(String),
+}
+
+impl CacheKey {
+    pub(crate) fn leaf<Types: NodeType>(id: LeafId<Types>) -> Self {
+        match id {
+            LeafId::Number(n) => Self::Height(n as u64),
+            LeafId::Hash(h) => Self::LeafHash(h.to_string()),
+        }
+    }
+
+    /// The key for a block, if it can be looked up in the cache.
+    ///
+    /// Blocks cannot be looked up by payload hash, since payloads are not unique, and the cache
+    /// cannot tell which block with a given payload is the first one.
+    pub(crate) fn block<Types: NodeType>(id: BlockId<Types>) -> Option<Self> {
+        match id {
+            BlockId::Number(n) => Some(Self::Height(n as u64)),
+            BlockId::Hash(h) => Some(Self::BlockHash(h.to_string())),
+            BlockId::PayloadHash(_) => None,
+        }
+    }
+}
+
+/// A change to the cache, made by a transaction and applied when it commits.
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub(crate) enum CacheUpdate {
+    /// Cache an object which has been inserted.
+    ///
+    /// `hash` is the leaf hash for leaves and the block hash for headers, under which the object
+    /// can also be looked up.
+    Insert {
+        kind: CachedKind,
+        height: u64,
+        hash: Option<String>,
+        #[derivative(Debug = "ignore")]
+        object: Arc<dyn Any + Send + Sync>,
+        bytes: usize,
+    },
+    /// Remove everything which was pruned along with `data`, up to and including `height`.
+    Prune { data: PrunedData, height: u64 },
+    /// Remove every object belonging to the block at `height`.
+    Delete { height: u64 },
+}
+
+impl CacheUpdate {
+    pub(crate) fn insert<T: Send + Sync + 'static>(
+        kind: CachedKind,
+        height: u64,
+        hash: Option<String>,
+        object: T,
+        bytes: usize,
+    ) -> Self {
+        Self::Insert {
+            kind,
+            height,
+            hash,
+            object: Arc::new(object),
+            bytes,
+        }
+    }
+}
+
+struct Entry {
+    object: Arc<dyn Any + Send + Sync>,
+    bytes: usize,
+    hash: Option<String>,
+}
+
+struct CacheState {
+    objects: LruCache<(CachedKind, u64), Entry>,
+    leaf_hashes: HashMap<String, u64>,
+    block_hashes: HashMap<String, u64>,
+    bytes: usize,
+}
+
+impl CacheState {
+    fn height(&self, key: &CacheKey) -> Option<u64> {
+        match key {
+            CacheKey::Height(height) => Some(*height),
+            CacheKey::LeafHash(hash) => self.leaf_hashes.get(hash).copied(),
+            CacheKey::BlockHash(hash) => self.block_hashes.get(hash).copied(),
+        }
+    }
+
+    fn hashes(&mut self, kind: CachedKind) -> Option<&mut HashMap<String, u64>> {
+        match kind {
+            CachedKind::Leaf => Some(&mut self.leaf_hashes),
+            CachedKind::Header => Some(&mut self.block_hashes),
+            CachedKind::PayloadMetadata | CachedKind::VidCommon => None,
+        }
+    }
+
+    fn insert(&mut self, kind: CachedKind, height: u64, entry: Entry) {
+        if let (Some(hash), Some(hashes)) = (entry.hash.clone(), self.hashes(kind)) {
+            hashes.insert(hash, height);
+        }
+        self.bytes += entry.bytes;
+        if let Some((key, old)) = self.objects.push((kind, height), entry) {
+            self.forget(key, old);
+        }
+    }
+
+    fn remove(&mut self, key: (CachedKind, u64)) {
+        if let Some(entry) = self.objects.pop(&key) {
+            self.forget(key, entry);
+        }
+    }
+
+    /// Account for an entry which has been removed from or replaced in `objects`.
+    fn forget(&mut self, key: (CachedKind, u64), entry: Entry) {
+        self.bytes -= entry.bytes;
+        let Some(hash) = entry.hash else {
+            return;
+        };
+        // Keep the hash if the entry was replaced by another with the same hash.
+        if self
+            .objects
+            .peek(&key)
+            .and_then(|entry| entry.hash.as_ref())
+            == Some(&hash)
+        {
+            return;
+        }
+        let (kind, height) = key;
+        if let Some(hashes) = self.hashes(kind) {
+            if hashes.get(&hash) == Some(&height) {
+                hashes.remove(&hash);
+            }
+        }
+    }
+
+    fn evict(&mut self, cfg: &CacheCfg) {
+        while self.objects.len() > cfg.max_objects || self.bytes > cfg.max_bytes {
+            let Some((key, entry)) = self.objects.pop_lru() else {
+                break;
+            };
+            self.forget(key, entry);
+        }
+    }
+}
+
+/// An in-memory cache of recently inserted objects.
+pub(crate) struct HotCache {
+    cfg: CacheCfg,
+    state: Mutex<CacheState>,
+    metrics: CacheMetrics,
+}
+
+impl Debug for HotCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("HotCache")
+            .field("cfg", &self.cfg)
+            .field("objects", &state.objects.len())
+            .field("bytes", &state.bytes)
+            .finish()
+    }
+}
+
+impl HotCache {
+    pub(crate) fn new(
+        cfg: CacheCfg,
+        metrics: &(impl Metrics + ?Sized),
+    ) -> anyhow::Result<Arc<Self>> {
+        cfg.validate()?;
+        Ok(Arc::new(Self {
+            cfg,
+            state: Mutex::new(CacheState {
+                objects: LruCache::unbounded(),
+                leaf_hashes: Default::default(),
+                block_hashes: Default::default(),
+                bytes: 0,
+            }),
+            metrics: CacheMetrics::new(metrics, &CachedKind::ALL.map(|kind| kind.as_str())),
+        }))
+    }
+
+    /// Look up an object in the cache.
+    pub(crate) fn get<T: Clone + 'static>(&self, kind: CachedKind, key: &CacheKey) -> Option<T> {
+        let object = {
+            let mut state = self.state.lock().unwrap();
+            match state.height(key) {
+                Some(height) => state
+                    .objects
+                    .get(&(kind, height))
+                    .map(|entry| entry.object.clone()),
+                None => None,
+            }
+        };
+        // Clone the object outside of the lock, since some objects are large.
+        let object = object.and_then(|object| object.downcast_ref::<T>().cloned());
+        if object.is_some() {
+            self.metrics.hits[kind as usize].add(1);
+        } else {
+            self.metrics.misses[kind as usize].add(1);
+        }
+        object
+    }
+
+    /// Apply the changes made by a committed transaction.
+    pub(crate) fn apply(&self, updates: impl IntoIterator<Item = CacheUpdate>) {
+        let mut state = self.state.lock().unwrap();
+        for update in updates {
+            match update {
+                CacheUpdate::Insert {
+                    kind,
+                    height,
+                    hash,
+                    object,
+                    bytes,
+                } => {
+                    if bytes > self.cfg.max_bytes {
+                        // Too big to ever fit, but make sure we don't keep an older version.
+                        state.remove((kind, height));
+                        continue;
+                    }
+                    state.insert(
+                        kind,
+                        height,
+                        Entry {
+                            object,
+                            bytes,
+                            hash,
+                        },
+                    );
+                },
+                CacheUpdate::Prune { data, height } => {
+                    let kinds = CachedKind::pruned_by(data);
+                    let pruned = state
+                        .objects
+                        .iter()
+                        .map(|(key, _)| *key)
+                        .filter(|(kind, h)| *h <= height && kinds.contains(kind))
+                        .collect::<Vec<_>>();
+                    for key in pruned {
+                        state.remove(key);
+                    }
+                },
+                CacheUpdate::Delete { height } => {
+                    for kind in CachedKind::ALL {
+                        state.remove((kind, height));
+                    }
+                },
+            }
+        }
+        state.evict(&self.cfg);
+
+        self.metrics.objects.set(state.objects.len());
+        self.metrics.bytes.set(state.bytes);
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright (c) 2022 Espresso Systems (espressosys.com)
+// This file is part of the HotShot Query Service library.
+//
+// This program is free software: you can redistribute it and/or modify it under the terms of the GNU
+// General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+// This program is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without
+// even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+// You should have received a copy of the GNU General Public License along with this program. If not,
+// see <https://www.gnu.org/licenses/>.
+
+//! Cold storage tier for large, rarely accessed objects.
+//!
+//! Block payloads and VID common data make up most of the size of the database, but old ones are
+//! rarely read. When cold storage is enabled, these objects are moved to an object store (such as
+//! S3 or any S3-compatible service, or a local directory) once they reach a configurable age. The
+//! database keeps only a key pointing to each moved object, which is used to load the object from
+//! the cold tier when it is requested.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{ensure, Context};
+use object_store::{path::Path, ObjectStore, PutPayload};
+use url::Url;
+
+#[derive(Clone, Debug)]
+pub struct ColdStorageCfg {
+    url: Url,
+    options: Vec<(String, String)>,
+    age: Duration,
+    batch_size: u64,
+    interval: Duration,
+}
+
+impl ColdStorageCfg {
+    /// Use the object store at `url` for cold storage.
+    ///
+    /// Supported URLs include `s3://bucket/prefix` for S3 and S3-compatible services, and
+    /// `file:///path/to/dir` for a local directory. Credentials and endpoints for S3 can be given
+    /// in the environment (`AWS_ACCESS_KEY_ID`, `AWS_ENDPOINT`, etc.) or via
+    /// [`with_option`](Self::with_option).
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            options: vec![],
+            age: Duration::from_secs(30 * 24 * 3600),
+            batch_size: 1000,
+            interval: Duration::from_secs(3600),
+        }
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            self.batch_size > 0,
+            "cold storage batch_size must be positive"
+        );
+        Ok(())
+    }
+
+    /// Set a configuration option for the object store, such as `aws_endpoint` or
+    /// `aws_allow_http`.
+    pub fn with_option(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.options.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn with_age(mut self, age: Duration) -> Self {
+        self.age = age;
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Location of the object store.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Age at which objects are moved to cold storage.
+    ///
+    /// This is measured using block timestamps, just like the retention periods of the pruner.
+    pub fn age(&self) -> Duration {
+        self.age
+    }
+
+    /// Number of blocks to move in a single operation.
+    pub fn batch_size(&self) -> u64 {
+        self.batch_size
+    }
+
+    /// How often to check for objects to move.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+}
+
+/// A connection to the cold storage tier.
+#[derive(Debug)]
+pub(crate) struct ColdStore {
+    store: Box<dyn ObjectStore>,
+    prefix: Path,
+    cfg: ColdStorageCfg,
+}
+
+impl ColdStore {
+    pub(crate) fn open(cfg: ColdStorageCfg) -> anyhow::Result<Arc<Self>> {
+        cfg.validate()?;
+
+        // Take options from the standard `AWS_*` environment variables first, so that explicit
+        // options override them. Options which don't apply to this kind of store are ignored.
+        let options = std::env::vars()
+            .filter(|(key, _)| key.starts_with("AWS_"))
+            .map(|(key, value)| (key.to_ascii_lowercase(), value))
+            .chain(cfg.options.iter().cloned());
+        let (store, prefix) = object_store::parse_url_opts(&cfg.url, options)
+            .with_context(|| format!("opening cold storage at {}", cfg.url))?;
+        Ok(Arc::new(Self { store, prefix, cfg }))
+    }
+
+    pub(crate) fn cfg(&self) -> &ColdStorageCfg {
+        &self.cfg
+    }
+
+    /// The key under which the payload of block `height` is stored.
+    pub(crate) fn payload_key(height: u64) -> String {
+        format!("payload/{height}")
+    }
+
+    /// The key under which the VID common data of block `height` is stored.
+    pub(crate) fn vid_common_key(height: u64) -> String {
+        format!("vid_common/{height}")
+    }
+
+    pub(crate) async fn put(&self, key: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        self.store
+            .put(&self.path(key), PutPayload::from(data))
+            .await
+            .with_context(|| format!("writing {key} to cold storage"))?;
+        Ok(())
+    }
+
+    pub(crate) async fn get(&self, key: &str) -> anyhow::Result<Vec<u8>> {
+        let data = self
+            .store
+            .get(&self.path(key))
+            .await
+            .with_context(|| format!("reading {key} from cold storage"))?
+            .bytes()
+            .await
+            .with_context(|| format!("reading {key} from cold storage"))?;
+        Ok(data.into())
+    }
+
+    pub(crate) async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match self.store.delete(&self.path(key)).await {
+            // Deleting an object which doesn't exist is not an error; it may have been deleted by
+            // a previous, interrupted operation.
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(err) => Err(err).with_context(|| format!("deleting {key} from cold storage")),
+        }
+    }
+
+    fn path(&self, key: &str) -> Path {
+        key.split('/')
+            .fold(self.prefix.clone(), |path, part| path.child(part))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_file_system_cold_store() {
+        let dir = TempDir::with_prefix("test_file_system_cold_store").unwrap();
+        let url = Url::from_directory_path(dir.path()).unwrap();
+        let store = ColdStore::open(ColdStorageCfg::new(url)).unwrap();
+
+        let key = ColdStore::payload_key(1);
+        store.put(&key, vec![1, 2, 3]).await.unwrap();
+        assert_eq!(store.get(&key).await.unwrap(), vec![1, 2, 3]);
+        assert!(dir.path().join("payload").join("1").exists());
+
+        store.delete(&key).await.unwrap();
+        store.get(&key).await.unwrap_err();
+
+        // Deleting is idempotent.
+        store.delete(&key).await.unwrap();
+    }
+}
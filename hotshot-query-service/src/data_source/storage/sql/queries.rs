@@ -27,9 +27,13 @@ use hotshot_types::{
         node_implementation::NodeType,
     },
 };
-use sqlx::{Arguments, FromRow, Row};
+use sqlx::{types::JsonValue, Arguments, FromRow, Row};
 
-use super::{Database, Db, Query, QueryAs, Transaction};
+use super::{
+    cache::{CacheKey, CachedKind},
+    cold::ColdStore,
+    Database, Db, Query, QueryAs, Transaction,
+};
 use crate::{
     availability::{
         BlockId, BlockQueryData, LeafQueryData, PayloadQueryData, QueryableHeader,
@@ -172,19 +176,25 @@ where
     Types: NodeType,
 {
     fn from_row(row: &'r <Db as Database>::Row) -> sqlx::Result<Self> {
-        let leaf = row.try_get("leaf")?;
-        let leaf: Leaf2<Types> = serde_json::from_value(leaf).decode_error("malformed leaf")?;
-
-        let qc = row.try_get("qc")?;
-        let qc: QuorumCertificate2<Types> =
-            serde_json::from_value(qc).decode_error("malformed QC")?;
-
-        Ok(Self { leaf, qc })
+        leaf_from_parts(row.try_get("leaf")?, row.try_get("qc")?)
     }
 }
 
-const BLOCK_COLUMNS: &str =
-    "h.hash AS hash, h.data AS header_data, p.size AS payload_size, p.data AS payload_data";
+/// Reconstruct a leaf from the columns of the `leaf2` table.
+pub(super) fn leaf_from_parts<Types>(
+    leaf: JsonValue,
+    qc: JsonValue,
+) -> sqlx::Result<LeafQueryData<Types>>
+where
+    Types: NodeType,
+{
+    let leaf: Leaf2<Types> = serde_json::from_value(leaf).decode_error("malformed leaf")?;
+    let qc: QuorumCertificate2<Types> = serde_json::from_value(qc).decode_error("malformed QC")?;
+    Ok(LeafQueryData { leaf, qc })
+}
+
+const BLOCK_COLUMNS: &str = "h.hash AS hash, h.data AS header_data, p.size AS payload_size, \
+                             p.data AS payload_data, p.cold_key AS payload_cold_key";
 
 impl<'r, Types> FromRow<'r, <Db as Database>::Row> for BlockQueryData<Types>
 where
@@ -194,31 +204,81 @@ where
 {
     fn from_row(row: &'r <Db as Database>::Row) -> sqlx::Result<Self> {
         // First, check if we have the payload for this block yet.
-        let size: Option<i32> = row.try_get("payload_size")?;
         let payload_data: Option<Vec<u8>> = row.try_get("payload_data")?;
-        let (size, payload_data) = size.zip(payload_data).ok_or(sqlx::Error::RowNotFound)?;
-        let size = size as u64;
-
-        // Reconstruct the full header.
-        let header_data = row.try_get("header_data")?;
-        let header: Header<Types> =
-            serde_json::from_value(header_data).decode_error("malformed header")?;
+        parse_block(row, payload_data.ok_or(sqlx::Error::RowNotFound)?)
+    }
+}
 
-        // Reconstruct the full block payload.
-        let payload = Payload::<Types>::from_bytes(&payload_data, header.metadata());
+/// Reconstruct a block from a row selected using [`BLOCK_COLUMNS`] and the payload data.
+fn parse_block<Types>(
+    row: &<Db as Database>::Row,
+    payload_data: Vec<u8>,
+) -> sqlx::Result<BlockQueryData<Types>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    let size: Option<i32> = row.try_get("payload_size")?;
+    let size = size.ok_or(sqlx::Error::RowNotFound)?;
+    block_from_parts(
+        row.try_get("header_data")?,
+        payload_data,
+        size,
+        row.try_get("hash")?,
+    )
+}
 
-        // Reconstruct the query data by adding metadata.
-        let hash: String = row.try_get("hash")?;
-        let hash = hash.parse().decode_error("malformed block hash")?;
+/// Reconstruct a block from the columns of the `header` and `payload` tables.
+pub(super) fn block_from_parts<Types>(
+    header_data: JsonValue,
+    payload_data: Vec<u8>,
+    size: i32,
+    hash: String,
+) -> sqlx::Result<BlockQueryData<Types>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    // Reconstruct the full header.
+    let header: Header<Types> =
+        serde_json::from_value(header_data).decode_error("malformed header")?;
+
+    // Reconstruct the full block payload.
+    let payload = Payload::<Types>::from_bytes(&payload_data, header.metadata());
+
+    // Reconstruct the query data by adding metadata.
+    let hash = hash.parse().decode_error("malformed block hash")?;
+    let size = size as u64;
+
+    Ok(BlockQueryData {
+        num_transactions: payload.len(header.metadata()) as u64,
+        header,
+        payload,
+        size,
+        hash,
+    })
+}
 
-        Ok(Self {
-            num_transactions: payload.len(header.metadata()) as u64,
-            header,
-            payload,
-            size,
-            hash,
-        })
-    }
+/// Load a block from a row selected using [`BLOCK_COLUMNS`].
+///
+/// Unlike the [`FromRow`] implementation, this will fetch the payload from cold storage if it has
+/// been moved there.
+pub(super) async fn load_block<Types>(
+    row: &<Db as Database>::Row,
+    cold: Option<&ColdStore>,
+) -> QueryResult<BlockQueryData<Types>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    let payload_data = match row.try_get::<Option<Vec<u8>>, _>("payload_data")? {
+        Some(data) => data,
+        None => load_cold(row, "payload_cold_key", cold).await?,
+    };
+    Ok(parse_block(row, payload_data)?)
 }
 
 const PAYLOAD_COLUMNS: &str = BLOCK_COLUMNS;
@@ -268,7 +328,8 @@ where
 }
 
 const VID_COMMON_COLUMNS: &str = "h.height AS height, h.hash AS block_hash, h.payload_hash AS \
-                                  payload_hash, v.common AS common_data";
+                                  payload_hash, v.common AS common_data, v.cold_key AS \
+                                  common_cold_key";
 
 impl<'r, Types> FromRow<'r, <Db as Database>::Row> for VidCommonQueryData<Types>
 where
@@ -277,25 +338,101 @@ where
     Payload<Types>: QueryablePayload<Types>,
 {
     fn from_row(row: &'r <Db as Database>::Row) -> sqlx::Result<Self> {
-        let height = row.try_get::<i64, _>("height")? as u64;
-        let block_hash: String = row.try_get("block_hash")?;
-        let block_hash = block_hash.parse().decode_error("malformed block hash")?;
-        let payload_hash: String = row.try_get("payload_hash")?;
-        let payload_hash = payload_hash
-            .parse()
-            .decode_error("malformed payload hash")?;
         let common_data: Vec<u8> = row.try_get("common_data")?;
-        let common =
-            bincode::deserialize(&common_data).decode_error("malformed VID common data")?;
-        Ok(Self {
-            height,
-            block_hash,
-            payload_hash,
-            common,
-        })
+        if common_data.is_empty() {
+            // The common data has been moved to cold storage.
+            return Err(sqlx::Error::RowNotFound);
+        }
+        parse_vid_common(row, common_data)
     }
 }
 
+/// Reconstruct VID common data from a row selected using [`VID_COMMON_COLUMNS`] and the
+/// serialized common data.
+fn parse_vid_common<Types>(
+    row: &<Db as Database>::Row,
+    common_data: Vec<u8>,
+) -> sqlx::Result<VidCommonQueryData<Types>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    vid_common_from_parts(
+        row.try_get("height")?,
+        row.try_get("block_hash")?,
+        row.try_get("payload_hash")?,
+        common_data,
+    )
+}
+
+/// Reconstruct VID common data from the columns of the `header` and `vid2` tables.
+pub(super) fn vid_common_from_parts<Types>(
+    height: i64,
+    block_hash: String,
+    payload_hash: String,
+    common_data: Vec<u8>,
+) -> sqlx::Result<VidCommonQueryData<Types>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    let height = height as u64;
+    let block_hash = block_hash.parse().decode_error("malformed block hash")?;
+    let payload_hash = payload_hash
+        .parse()
+        .decode_error("malformed payload hash")?;
+    let common = bincode::deserialize(&common_data).decode_error("malformed VID common data")?;
+    Ok(VidCommonQueryData {
+        height,
+        block_hash,
+        payload_hash,
+        common,
+    })
+}
+
+/// Load VID common data from a row selected using [`VID_COMMON_COLUMNS`].
+///
+/// Unlike the [`FromRow`] implementation, this will fetch the common data from cold storage if it
+/// has been moved there.
+pub(super) async fn load_vid_common<Types>(
+    row: &<Db as Database>::Row,
+    cold: Option<&ColdStore>,
+) -> QueryResult<VidCommonQueryData<Types>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    // The `common` column is not nullable, so when the common data is moved to cold storage, it is
+    // replaced with an empty blob.
+    let mut common_data: Vec<u8> = row.try_get("common_data")?;
+    if common_data.is_empty() {
+        common_data = load_cold(row, "common_cold_key", cold).await?;
+    }
+    Ok(parse_vid_common(row, common_data)?)
+}
+
+/// Fetch an object from cold storage, using the key in `column` of `row`.
+async fn load_cold(
+    row: &<Db as Database>::Row,
+    column: &str,
+    cold: Option<&ColdStore>,
+) -> QueryResult<Vec<u8>> {
+    let Some(key) = row.try_get::<Option<String>, _>(column)? else {
+        return Err(QueryError::NotFound);
+    };
+    let Some(cold) = cold else {
+        return Err(QueryError::Error {
+            message: format!("{key} has been moved to cold storage, but cold storage is disabled"),
+        });
+    };
+    cold.get(&key).await.map_err(|err| QueryError::Error {
+        message: format!("{err:#}"),
+    })
+}
+
 const VID_COMMON_METADATA_COLUMNS: &str =
     "h.height AS height, h.hash AS block_hash, h.payload_hash AS payload_hash";
 
@@ -354,13 +491,20 @@ where
     Types: NodeType,
 {
     fn from_row(row: &'r <Db as Database>::Row) -> sqlx::Result<Self> {
-        let state_cert: LightClientStateUpdateCertificate<Types> =
-            bincode::deserialize(row.try_get("state_cert")?)
-                .decode_error("malformed state cert")?;
-        Ok(state_cert.into())
+        state_cert_from_bytes(row.try_get("state_cert")?)
     }
 }
 
+/// Reconstruct a state certificate from the `state_cert` column.
+pub(super) fn state_cert_from_bytes<Types>(bytes: &[u8]) -> sqlx::Result<StateCertQueryData<Types>>
+where
+    Types: NodeType,
+{
+    let state_cert: LightClientStateUpdateCertificate<Types> =
+        bincode::deserialize(bytes).decode_error("malformed state cert")?;
+    Ok(state_cert.into())
+}
+
 impl<Mode> Transaction<Mode> {
     /// Load a header from storage.
     ///
@@ -376,8 +520,13 @@ impl<Mode> Transaction<Mode> {
         &mut self,
         id: impl Into<BlockId<Types>> + Send,
     ) -> QueryResult<Header<Types>> {
+        let id: BlockId<Types> = id.into();
+        if let Some(header) = self.cached(CachedKind::Header, CacheKey::block(id)) {
+            return Ok(header);
+        }
+
         let mut query = QueryBuilder::default();
-        let where_clause = query.header_where_clause(id.into())?;
+        let where_clause = query.header_where_clause(id)?;
         // ORDER BY h.height ASC ensures that if there are duplicate blocks (this can happen when
         // selecting by payload ID, as payloads are not unique), we return the first one.
         let sql = format!(
@@ -21,9 +21,12 @@ use snafu::OptionExt;
 use sqlx::FromRow;
 
 use super::{
-    super::transaction::{query, Transaction, TransactionMode},
-    QueryBuilder, BLOCK_COLUMNS, LEAF_COLUMNS, PAYLOAD_COLUMNS, PAYLOAD_METADATA_COLUMNS,
-    STATE_CERT_COLUMNS, VID_COMMON_COLUMNS, VID_COMMON_METADATA_COLUMNS,
+    super::{
+        cache::{CacheKey, CachedKind},
+        transaction::{query, Transaction, TransactionMode},
+    },
+    load_block, load_vid_common, QueryBuilder, BLOCK_COLUMNS, LEAF_COLUMNS, PAYLOAD_COLUMNS,
+    PAYLOAD_METADATA_COLUMNS, STATE_CERT_COLUMNS, VID_COMMON_COLUMNS, VID_COMMON_METADATA_COLUMNS,
 };
 use crate::{
     availability::{
@@ -47,6 +50,10 @@ where
     Header<Types>: QueryableHeader<Types>,
 {
     async fn get_leaf(&mut self, id: LeafId<Types>) -> QueryResult<LeafQueryData<Types>> {
+        if let Some(leaf) = self.cached(CachedKind::Leaf, Some(CacheKey::leaf(id))) {
+            return Ok(leaf);
+        }
+
         let mut query = QueryBuilder::default();
         let where_clause = match id {
             LeafId::Number(n) => format!("height = {}", query.bind(n as i64)?),
@@ -76,7 +83,7 @@ where
               LIMIT 1"
         );
         let row = query.query(&sql).fetch_one(self.as_mut()).await?;
-        let block = BlockQueryData::from_row(&row)?;
+        let block = load_block(&row, self.cold().as_deref()).await?;
         Ok(block)
     }
 
@@ -98,14 +105,18 @@ where
               LIMIT 1"
         );
         let row = query.query(&sql).fetch_one(self.as_mut()).await?;
-        let payload = PayloadQueryData::from_row(&row)?;
-        Ok(payload)
+        let block = load_block(&row, self.cold().as_deref()).await?;
+        Ok(block.into())
     }
 
     async fn get_payload_metadata(
         &mut self,
         id: BlockId<Types>,
     ) -> QueryResult<PayloadMetadata<Types>> {
+        if let Some(payload) = self.cached(CachedKind::PayloadMetadata, CacheKey::block(id)) {
+            return Ok(payload);
+        }
+
         let mut query = QueryBuilder::default();
         let where_clause = query.header_where_clause(id)?;
         // ORDER BY h.height ASC ensures that if there are duplicate blocks (this can happen when
@@ -134,6 +145,10 @@ where
         &mut self,
         id: BlockId<Types>,
     ) -> QueryResult<VidCommonQueryData<Types>> {
+        if let Some(common) = self.cached(CachedKind::VidCommon, CacheKey::block(id)) {
+            return Ok(common);
+        }
+
         let mut query = QueryBuilder::default();
         let where_clause = query.header_where_clause(id)?;
         // ORDER BY h.height ASC ensures that if there are duplicate blocks (this can happen when
@@ -147,7 +162,7 @@ where
               LIMIT 1"
         );
         let row = query.query(&sql).fetch_one(self.as_mut()).await?;
-        let common = VidCommonQueryData::from_row(&row)?;
+        let common = load_vid_common(&row, self.cold().as_deref()).await?;
         Ok(common)
     }
 
@@ -207,11 +222,12 @@ where
               {where_clause}
               ORDER BY h.height"
         );
+        let cold = self.cold();
+        let cold = cold.as_deref();
         Ok(query
             .query(&sql)
             .fetch(self.as_mut())
-            .map(|res| BlockQueryData::from_row(&res?))
-            .map_err(QueryError::from)
+            .then(|res| async move { load_block(&res?, cold).await })
             .collect()
             .await)
     }
@@ -257,11 +273,12 @@ where
               {where_clause}
               ORDER BY h.height"
         );
+        let cold = self.cold();
+        let cold = cold.as_deref();
         Ok(query
             .query(&sql)
             .fetch(self.as_mut())
-            .map(|res| PayloadQueryData::from_row(&res?))
-            .map_err(QueryError::from)
+            .then(|res| async move { load_block(&res?, cold).await.map(PayloadQueryData::from) })
             .collect()
             .await)
     }
@@ -318,11 +335,12 @@ where
               {where_clause}
               ORDER BY h.height"
         );
+        let cold = self.cold();
+        let cold = cold.as_deref();
         Ok(query
             .query(&sql)
             .fetch(self.as_mut())
-            .map(|res| VidCommonQueryData::from_row(&res?))
-            .map_err(QueryError::from)
+            .then(|res| async move { load_vid_common(&res?, cold).await })
             .collect()
             .await)
     }
@@ -373,7 +391,7 @@ where
         let row = query.query(&sql).fetch_one(self.as_mut()).await?;
 
         // Extract the block.
-        let block = BlockQueryData::from_row(&row)?;
+        let block = load_block(&row, self.cold().as_deref()).await?;
 
         TransactionQueryData::with_hash(&block, hash).context(ErrorSnafu {
             message: format!(
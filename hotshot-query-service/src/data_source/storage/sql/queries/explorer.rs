@@ -12,7 +12,10 @@
 
 //! Explorer storage implementation for a database query engine.
 
-use std::{collections::VecDeque, num::NonZeroUsize};
+use std::{
+    collections::{HashMap, VecDeque},
+    num::NonZeroUsize,
+};
 
 use async_trait::async_trait;
 use committable::{Commitment, Committable};
@@ -23,11 +26,11 @@ use sqlx::{FromRow, Row};
 use tagged_base64::{Tagged, TaggedBase64};
 
 use super::{
-    super::transaction::{query, Transaction, TransactionMode},
-    Database, Db, DecodeError, BLOCK_COLUMNS,
+    super::transaction::{query, query_as, Transaction, TransactionMode},
+    load_block, Database, Db, DecodeError, BLOCK_COLUMNS,
 };
 use crate::{
-    availability::{BlockQueryData, QueryableHeader, QueryablePayload},
+    availability::{BlockQueryData, NamespaceId, QueryableHeader, QueryablePayload},
     data_source::storage::{ExplorerStorage, NodeStorage},
     explorer::{
         self,
@@ -36,10 +39,12 @@ use crate::{
         traits::ExplorerHeader,
         BalanceAmount, BlockDetail, BlockIdentifier, BlockRange, BlockSummary, ExplorerHistograms,
         ExplorerSummary, GenesisOverview, GetBlockDetailError, GetBlockSummariesError,
-        GetBlockSummariesRequest, GetExplorerSummaryError, GetSearchResultsError,
-        GetTransactionDetailError, GetTransactionSummariesError, GetTransactionSummariesRequest,
-        MonetaryValue, SearchResult, TransactionIdentifier, TransactionRange, TransactionSummary,
-        TransactionSummaryFilter,
+        GetBlockSummariesRequest, GetExplorerSummaryError, GetNamespaceDetailError,
+        GetNamespaceDetailRequest, GetNamespaceSummariesError, GetNamespaceSummariesRequest,
+        GetSearchResultsError, GetTransactionDetailError, GetTransactionSummariesError,
+        GetTransactionSummariesRequest, MonetaryValue, NamespaceDetail, NamespaceHistogram,
+        NamespaceSummary, SearchResult, TransactionIdentifier, TransactionRange,
+        TransactionSummary, TransactionSummaryFilter,
     },
     types::HeightIndexed,
     Header, Payload, QueryError, QueryResult, Transaction as HotshotTransaction,
@@ -81,6 +86,18 @@ impl From<sqlx::Error> for GetSearchResultsError {
     }
 }
 
+impl From<sqlx::Error> for GetNamespaceSummariesError {
+    fn from(err: sqlx::Error) -> Self {
+        Self::from(QueryError::from(err))
+    }
+}
+
+impl From<sqlx::Error> for GetNamespaceDetailError {
+    fn from(err: sqlx::Error) -> Self {
+        Self::from(QueryError::from(err))
+    }
+}
+
 impl<'r, Types> FromRow<'r, <Db as Database>::Row> for BlockSummary<Types>
 where
     Types: NodeType,
@@ -267,6 +284,47 @@ lazy_static::lazy_static! {
                 ORDER BY h.height DESC"
         )
     };
+
+    // Namespace summaries are computed from the cumulative per-namespace statistics in the
+    // `aggregate` table, as of the latest aggregated block ($1) and as of the last block more than
+    // a day older than that ($2), plus the first and last blocks containing each namespace.
+    static ref GET_NAMESPACE_SUMMARIES_QUERY: String =
+        namespace_summaries_query("", "", "LIMIT $3");
+
+    // Summaries are ordered by `last_seen` descending, then by namespace, so
+    // the page starting at `($4, $5)` holds the summaries at or after that
+    // position in the same order.
+    static ref GET_NAMESPACE_SUMMARIES_QUERY_FROM: String = namespace_summaries_query(
+        "",
+        "AND (s.last_seen < $4 OR (s.last_seen = $4 AND a.namespace >= $5))",
+        "LIMIT $3",
+    );
+
+    static ref GET_NAMESPACE_SUMMARY_QUERY_FOR_NAMESPACE: String =
+        namespace_summaries_query("AND ns_id = $3", "", "");
+}
+
+/// Build a query for namespace summaries, with `ns_filter` applied to the
+/// transactions of each namespace, `page_filter` applied to the summaries,
+/// and `limit` following the ordering.
+fn namespace_summaries_query(ns_filter: &str, page_filter: &str, limit: &str) -> String {
+    format!(
+        "SELECT a.namespace AS namespace, s.first_seen AS first_seen, s.last_seen AS last_seen,
+                a.num_transactions AS num_transactions, a.payload_size AS payload_size,
+                coalesce(b.num_transactions, 0) AS prev_num_transactions,
+                coalesce(b.payload_size, 0) AS prev_payload_size
+           FROM aggregate AS a
+           JOIN (
+               SELECT ns_id, min(block_height) AS first_seen, max(block_height) AS last_seen
+                 FROM transactions
+                WHERE block_height <= $1 {ns_filter}
+                GROUP BY ns_id
+           ) AS s ON s.ns_id = a.namespace
+           LEFT JOIN aggregate AS b ON b.namespace = a.namespace AND b.height = $2
+          WHERE a.height = $1 {page_filter}
+          ORDER BY s.last_seen DESC, a.namespace
+          {limit}"
+    )
 }
 
 /// [EXPLORER_SUMMARY_HISTOGRAM_NUM_ENTRIES] is the number of entries we want
@@ -281,6 +339,173 @@ const EXPLORER_SUMMARY_NUM_BLOCKS: usize = 10;
 /// to return in our explorer summary.
 const EXPLORER_SUMMARY_NUM_TRANSACTIONS: usize = 10;
 
+/// [NAMESPACE_RECENT_ACTIVITY_WINDOW] is the length, in seconds, of the window
+/// of recent activity reported in a [NamespaceSummary].
+const NAMESPACE_RECENT_ACTIVITY_WINDOW: i64 = 24 * 60 * 60;
+
+impl<Mode: TransactionMode> Transaction<Mode> {
+    /// The height and timestamp of the latest block included in the aggregate
+    /// statistics, if any.
+    async fn latest_aggregated_block(&mut self) -> QueryResult<Option<(i64, i64)>> {
+        let Some(row) = query(
+            "SELECT h.height AS height, h.timestamp AS timestamp
+               FROM header AS h
+              WHERE h.height = (SELECT max(height) FROM aggregate)",
+        )
+        .fetch_optional(self.as_mut())
+        .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some((row.try_get("height")?, row.try_get("timestamp")?)))
+    }
+
+    /// Summaries of the namespaces selected by `request`, or of just
+    /// `namespace`, as of the block `(height, timestamp)`.
+    async fn namespace_summaries<Types>(
+        &mut self,
+        (height, timestamp): (i64, i64),
+        namespace: Option<NamespaceId<Types>>,
+        request: GetNamespaceSummariesRequest,
+    ) -> QueryResult<Vec<NamespaceSummary<Types>>>
+    where
+        Types: NodeType,
+        Header<Types>: QueryableHeader<Types>,
+    {
+        // Find the last block outside of the recent activity window. If there is none, the whole
+        // history of each namespace falls within the window.
+        let (cutoff,): (Option<i64>,) =
+            query_as("SELECT max(height) FROM header WHERE timestamp <= $1")
+                .bind(timestamp - NAMESPACE_RECENT_ACTIVITY_WINDOW)
+                .fetch_one(self.as_mut())
+                .await?;
+        let cutoff = cutoff.unwrap_or(-1);
+
+        let query_stmt = match namespace {
+            Some(ns) => query(&GET_NAMESPACE_SUMMARY_QUERY_FOR_NAMESPACE)
+                .bind(height)
+                .bind(cutoff)
+                .bind(Into::<i64>::into(ns)),
+            None => {
+                // Without a limit, select every summary.
+                let limit = request
+                    .limit
+                    .map_or(i64::MAX, |limit| limit.get().try_into().unwrap_or(i64::MAX));
+                match request.start {
+                    Some(start) => query(&GET_NAMESPACE_SUMMARIES_QUERY_FROM)
+                        .bind(height)
+                        .bind(cutoff)
+                        .bind(limit)
+                        .bind(start.last_seen() as i64)
+                        .bind(start.namespace()),
+                    None => query(&GET_NAMESPACE_SUMMARIES_QUERY)
+                        .bind(height)
+                        .bind(cutoff)
+                        .bind(limit),
+                }
+            },
+        };
+        let summaries = query_stmt
+            .fetch(self.as_mut())
+            .map(|row| -> sqlx::Result<NamespaceSummary<Types>> {
+                let row = row?;
+                let num_transactions: i64 = row.try_get("num_transactions")?;
+                let payload_size: i64 = row.try_get("payload_size")?;
+                let prev_num_transactions: i64 = row.try_get("prev_num_transactions")?;
+                let prev_payload_size: i64 = row.try_get("prev_payload_size")?;
+                Ok(NamespaceSummary {
+                    namespace: row.try_get::<i64, _>("namespace")?.into(),
+                    first_seen: row.try_get::<i64, _>("first_seen")? as u64,
+                    last_seen: row.try_get::<i64, _>("last_seen")? as u64,
+                    num_transactions: num_transactions as u64,
+                    size: payload_size as u64,
+                    num_transactions_last_24h: (num_transactions - prev_num_transactions) as u64,
+                    size_last_24h: (payload_size - prev_payload_size) as u64,
+                })
+            })
+            .try_collect()
+            .await?;
+        Ok(summaries)
+    }
+
+    /// A histogram of the activity of `namespace` in the `num_buckets` buckets
+    /// of `bucket_width` seconds ending at the block `(height, timestamp)`.
+    async fn namespace_histogram<Types>(
+        &mut self,
+        (height, timestamp): (i64, i64),
+        namespace: NamespaceId<Types>,
+        bucket_width: i64,
+        num_buckets: i64,
+    ) -> QueryResult<NamespaceHistogram>
+    where
+        Types: NodeType,
+        Header<Types>: QueryableHeader<Types>,
+    {
+        let namespace: i64 = namespace.into();
+        let start = timestamp + 1 - bucket_width * num_buckets;
+
+        // The aggregate statistics are cumulative, so the activity in each bucket is the
+        // difference between the totals at the end of that bucket and at the end of the previous
+        // one. Start with the totals just before the first bucket.
+        let baseline: Option<(i64, i64)> = query_as(
+            "SELECT a.num_transactions, a.payload_size
+               FROM aggregate AS a
+               JOIN header AS h ON h.height = a.height
+              WHERE a.namespace = $1 AND h.timestamp < $2
+              ORDER BY a.height DESC
+              LIMIT 1",
+        )
+        .bind(namespace)
+        .bind(start)
+        .fetch_optional(self.as_mut())
+        .await?;
+        let (mut prev_num_transactions, mut prev_payload_size) = baseline.unwrap_or((0, 0));
+
+        let totals: HashMap<i64, (i64, i64)> = query_as::<(i64, i64, i64)>(
+            "SELECT (h.timestamp - $2) / $3 AS bucket,
+                    max(a.num_transactions) AS num_transactions,
+                    max(a.payload_size) AS payload_size
+               FROM aggregate AS a
+               JOIN header AS h ON h.height = a.height
+              WHERE a.namespace = $1 AND h.timestamp >= $2 AND a.height <= $4
+              GROUP BY bucket",
+        )
+        .bind(namespace)
+        .bind(start)
+        .bind(bucket_width)
+        .bind(height)
+        .fetch(self.as_mut())
+        .map_ok(|(bucket, num_transactions, payload_size)| {
+            (bucket, (num_transactions, payload_size))
+        })
+        .try_collect()
+        .await?;
+
+        let mut histogram = NamespaceHistogram {
+            bucket_width: bucket_width as u64,
+            bucket_start: Vec::with_capacity(num_buckets as usize),
+            transactions: Vec::with_capacity(num_buckets as usize),
+            size: Vec::with_capacity(num_buckets as usize),
+        };
+        for bucket in 0..num_buckets {
+            let (num_transactions, payload_size) = totals
+                .get(&bucket)
+                .copied()
+                .unwrap_or((prev_num_transactions, prev_payload_size));
+            histogram.bucket_start.push(start + bucket * bucket_width);
+            histogram
+                .transactions
+                .push((num_transactions - prev_num_transactions) as u64);
+            histogram
+                .size
+                .push((payload_size - prev_payload_size) as u64);
+            prev_num_transactions = num_transactions;
+            prev_payload_size = payload_size;
+        }
+        Ok(histogram)
+    }
+}
+
 #[async_trait]
 impl<Mode, Types> ExplorerStorage<Types> for Transaction<Mode>
 where
@@ -309,8 +534,15 @@ where
                 .bind(request.num_blocks.get() as i64),
         };
 
+        let cold = self.cold();
+        let cold = cold.as_deref();
         let row_stream = query_stmt.fetch(self.as_mut());
-        let result = row_stream.map(|row| BlockSummary::from_row(&row?));
+        let result = row_stream.then(|row| async move {
+            let block = load_block::<Types>(&row?, cold).await?;
+            Ok::<_, QueryError>(
+                BlockSummary::try_from(block).decode_error("malformed block summary")?,
+            )
+        });
 
         Ok(result.try_collect().await?)
     }
@@ -330,7 +562,8 @@ where
         };
 
         let query_result = query_stmt.fetch_one(self.as_mut()).await?;
-        let block = BlockDetail::from_row(&query_result)?;
+        let block = load_block::<Types>(&query_result, self.cold().as_deref()).await?;
+        let block = BlockDetail::try_from(block).decode_error("malformed block detail")?;
 
         Ok(block)
     }
@@ -408,9 +641,11 @@ where
             },
         };
 
+        let cold = self.cold();
+        let cold = cold.as_deref();
         let block_stream = query_stmt
             .fetch(self.as_mut())
-            .map(|row| BlockQueryData::from_row(&row?));
+            .then(|row| async move { load_block::<Types>(&row?, cold).await });
 
         let transaction_summary_stream = block_stream.flat_map(|row| match row {
             Ok(block) => {
@@ -443,7 +678,7 @@ where
                         .collect::<Vec<QueryResult<TransactionSummary<Types>>>>(),
                 )
             },
-            Err(err) => stream::iter(vec![Err(err.into())]),
+            Err(err) => stream::iter(vec![Err(err)]),
         });
 
         let transaction_summary_vec = transaction_summary_stream
@@ -483,7 +718,7 @@ where
         };
 
         let query_row = query_stmt.fetch_one(self.as_mut()).await?;
-        let block = BlockQueryData::<Types>::from_row(&query_row)?;
+        let block = load_block::<Types>(&query_row, self.cold().as_deref()).await?;
 
         let txns = block.enumerate().map(|(_, txn)| txn).collect::<Vec<_>>();
 
@@ -643,11 +878,13 @@ where
                 .fetch_one(self.as_mut())
                 .await?;
 
-            let block = BlockSummary::from_row(&row)?;
+            let block = load_block::<Types>(&row, self.cold().as_deref()).await?;
+            let block = BlockSummary::try_from(block).decode_error("malformed block summary")?;
 
             Ok(SearchResult {
                 blocks: vec![block],
                 transactions: Vec::new(),
+                accounts: Vec::new(),
             })
         } else {
             let transactions_query = format!(
@@ -659,12 +896,15 @@ where
                     ORDER BY h.height DESC
                     LIMIT 5"
             );
+            let cold = self.cold();
+            let cold = cold.as_deref();
             let transactions_query_rows = query(transactions_query.as_str())
                 .bind(&search_query_string)
                 .fetch(self.as_mut());
             let transactions_query_result: Vec<TransactionSummary<Types>> = transactions_query_rows
-                .map(|row| -> Result<Vec<TransactionSummary<Types>>, QueryError>{
-                    let block = BlockQueryData::<Types>::from_row(&row?)?;
+                .then(|row| async move { load_block::<Types>(&row?, cold).await })
+                .map(|block| -> Result<Vec<TransactionSummary<Types>>, QueryError>{
+                    let block = block?;
                     let transactions = block
                         .enumerate()
                         .enumerate()
@@ -686,7 +926,51 @@ where
             Ok(SearchResult {
                 blocks: Vec::new(),
                 transactions: transactions_query_result,
+                accounts: Vec::new(),
             })
         }
     }
+
+    async fn get_namespace_summaries(
+        &mut self,
+        request: GetNamespaceSummariesRequest,
+    ) -> Result<Vec<NamespaceSummary<Types>>, GetNamespaceSummariesError> {
+        let Some(latest) = self.latest_aggregated_block().await? else {
+            return Ok(vec![]);
+        };
+        Ok(self.namespace_summaries(latest, None, request).await?)
+    }
+
+    async fn get_namespace_detail(
+        &mut self,
+        request: GetNamespaceDetailRequest<Types>,
+    ) -> Result<NamespaceDetail<Types>, GetNamespaceDetailError> {
+        let ns: i64 = request.namespace.into();
+        let not_found = || {
+            GetNamespaceDetailError::NamespaceNotFound(NotFound {
+                key: format!("namespace {ns}"),
+            })
+        };
+
+        let latest = self
+            .latest_aggregated_block()
+            .await?
+            .ok_or_else(not_found)?;
+        let summary = self
+            .namespace_summaries(latest, Some(request.namespace), Default::default())
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(not_found)?;
+        let histogram = self
+            .namespace_histogram(
+                latest,
+                request.namespace,
+                request.bucket_width.get() as i64,
+                request.num_buckets.get() as i64,
+            )
+            .await?;
+
+        Ok(NamespaceDetail { summary, histogram })
+    }
 }
@@ -14,7 +14,7 @@
 
 use std::{
     collections::HashMap,
-    ops::{Bound, RangeBounds},
+    ops::{Bound, Range, RangeBounds},
 };
 
 use anyhow::anyhow;
@@ -153,6 +153,25 @@ where
         Ok(share)
     }
 
+    async fn vid_share_range(&mut self, range: Range<usize>) -> QueryResult<Vec<(u64, VidShare)>> {
+        let rows = query_as::<(i64, Vec<u8>)>(
+            "SELECT height, share FROM vid2
+              WHERE height >= $1 AND height < $2 AND share IS NOT NULL
+              ORDER BY height",
+        )
+        .bind(range.start as i64)
+        .bind(range.end as i64)
+        .fetch_all(self.as_mut())
+        .await?;
+        rows.into_iter()
+            .map(|(height, share_data)| {
+                let share =
+                    bincode::deserialize(&share_data).decode_error("malformed VID share")?;
+                Ok((height as u64, share))
+            })
+            .collect()
+    }
+
     async fn sync_status(&mut self) -> QueryResult<SyncStatus> {
         // A leaf can only be missing if there is no row for it in the database (all its columns are
         // non-nullable). A block can be missing if its corresponding leaf is missing or if the
@@ -171,20 +190,27 @@ where
         // We can get the number of missing leaf rows very efficiently, by subtracting the total
         // number of leaf rows from the block height (since the block height by definition is the
         // height of the highest leaf we do have). We can also get the number of null payloads
-        // directly using an `IS NULL` filter.
+        // directly using an `IS NULL` filter, excluding payloads which are null because they have
+        // been moved to cold storage or pruned.
         //
         // For VID, common data can only be missing if the entire row is missing. Shares can be
         // missing in that case _or_ if the row is present but share data is NULL. Thus, we also
         // need to select the total number of VID rows and the number of present VID rows with a
-        // NULL share.
+        // NULL share. VID rows may also be missing because they were pruned separately from the
+        // corresponding leaves, so we need the VID pruned height to account for these.
         let sql = "SELECT l.max_height, l.total_leaves, p.null_payloads, v.total_vid, \
-                   vn.null_vid, pruned_height FROM
+                   vn.null_vid, pruned_height, vid_pruned_height FROM
                 (SELECT max(leaf2.height) AS max_height, count(*) AS total_leaves FROM leaf2) AS l,
-                (SELECT count(*) AS null_payloads FROM payload WHERE data IS NULL) AS p,
+                (SELECT count(*) AS null_payloads FROM payload
+                    WHERE data IS NULL AND cold_key IS NULL AND height > COALESCE(
+                        (SELECT last_height FROM pruned_height_by_type
+                            WHERE data_type = 'payload'), -1)) AS p,
                 (SELECT count(*) AS total_vid FROM vid2) AS v,
                 (SELECT count(*) AS null_vid FROM vid2 WHERE share IS NULL) AS vn,
                 (SELECT(SELECT last_height FROM pruned_height ORDER BY id DESC LIMIT 1) as \
-                   pruned_height)
+                   pruned_height),
+                (SELECT(SELECT last_height FROM pruned_height_by_type
+                    WHERE data_type = 'vid_common') as vid_pruned_height)
             ";
         let row = query(sql)
             .fetch_optional(self.as_mut())
@@ -209,10 +235,20 @@ where
         let pruned_height = row
             .get::<Option<i64>, _>("pruned_height")
             .map(|h| h as usize);
+        let vid_pruned_height = row
+            .get::<Option<i64>, _>("vid_pruned_height")
+            .map(|h| h as usize);
+
+        // Count the blocks which still have leaves but whose VID was pruned.
+        let pruned_vid = match (vid_pruned_height, pruned_height) {
+            (Some(vid), Some(leaf)) => vid.saturating_sub(leaf),
+            (Some(vid), None) => vid + 1,
+            (None, _) => 0,
+        };
 
         let missing_leaves = block_height.saturating_sub(total_leaves);
         let missing_blocks = missing_leaves + null_payloads;
-        let missing_vid_common = block_height.saturating_sub(total_vid);
+        let missing_vid_common = block_height.saturating_sub(total_vid + pruned_vid);
         let missing_vid_shares = missing_vid_common + null_vid;
 
         Ok(SyncStatus {
@@ -221,6 +257,7 @@ where
             missing_vid_common,
             missing_vid_shares,
             pruned_height,
+            backfill: None,
         })
     }
 
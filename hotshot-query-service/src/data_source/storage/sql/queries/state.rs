@@ -14,6 +14,8 @@
 
 use std::{
     collections::{HashMap, HashSet, VecDeque},
+    iter,
+    ops::Range,
     sync::Arc,
 };
 
@@ -28,19 +30,32 @@ use jf_merkle_tree::{
 use sqlx::types::{BitVec, JsonValue};
 
 use super::{
-    super::transaction::{query_as, Transaction, TransactionMode, Write},
+    super::transaction::{query, query_as, Transaction, TransactionMode, Write},
     DecodeError, QueryBuilder,
 };
 use crate::{
-    data_source::storage::{
-        pruning::PrunedHeightStorage,
-        sql::{build_where_in, sqlx::Row},
-        MerklizedStateHeightStorage, MerklizedStateStorage,
+    data_source::{
+        storage::{
+            migration::{MerkleNodeRow, MerklizedStateSource},
+            pruning::{PrunedData, PrunedHeightStorage},
+            sql::{build_where_in, sqlx::Row, SqlStorage},
+            MerklizedStateHeightStorage, MerklizedStateStorage,
+        },
+        VersionedDataSource,
     },
-    merklized_state::{MerklizedState, Snapshot},
+    merklized_state::{EntryUpdate, MerklizedState, Snapshot},
     QueryError, QueryResult,
 };
 
+/// The maximum number of nodes written in one statement when migrating merklized state.
+///
+/// Each node may bring up to `ARITY + 1` hashes with it, which all need to be bound as parameters,
+/// so this is kept well below the limit on the number of parameters in a statement.
+const MERKLE_NODE_CHUNK_SIZE: usize = 100;
+
+/// The maximum number of hash IDs looked up in one statement when migrating merklized state.
+pub(crate) const HASH_ID_CHUNK_SIZE: usize = 10_000;
+
 #[async_trait]
 impl<Mode, Types, State, const ARITY: usize> MerklizedStateStorage<Types, State, ARITY>
     for Transaction<Mode>
@@ -251,6 +266,122 @@ where
             proof: proof_path.into(),
         })
     }
+
+    /// Retrieves the history of a single entry from the database
+    async fn get_entry_history(
+        &mut self,
+        key: State::Key,
+        from: u64,
+        to: u64,
+    ) -> QueryResult<Vec<EntryUpdate<State::Entry>>> {
+        let state_type = State::state_type();
+
+        // Every time an entry changes, a new version of its leaf node is stored, so the history of
+        // the entry is just the history of the node at the full traversal path of its index.
+        let path = State::Key::to_traversal_path(&key, State::tree_height())
+            .into_iter()
+            .rev()
+            .map(|x| x as i32)
+            .collect::<Vec<_>>();
+        let path: JsonValue = path.into();
+
+        // Find the value of the entry as of the start of the range.
+        let prev = query_as::<(Option<JsonValue>,)>(&format!(
+            "SELECT entry FROM {state_type} WHERE path = $1 AND created < $2 ORDER BY created \
+             DESC LIMIT 1"
+        ))
+        .bind(&path)
+        .bind(from as i64)
+        .fetch_optional(self.as_mut())
+        .await?;
+        let mut old = match prev {
+            Some((Some(entry),)) => {
+                Some(serde_json::from_value(entry).decode_error("malformed merkle element")?)
+            },
+            _ => None,
+        };
+
+        let rows = query_as::<(i64, Option<JsonValue>)>(&format!(
+            "SELECT created, entry FROM {state_type} WHERE path = $1 AND created >= $2 AND \
+             created <= $3 ORDER BY created"
+        ))
+        .bind(&path)
+        .bind(from as i64)
+        .bind(to as i64)
+        .fetch_all(self.as_mut())
+        .await?;
+
+        let mut updates = vec![];
+        for (created, entry) in rows {
+            let new: Option<State::Entry> = entry
+                .map(|entry| serde_json::from_value(entry).decode_error("malformed merkle element"))
+                .transpose()?;
+            // The leaf is rewritten whenever the path to it is, even if the entry itself did not
+            // change.
+            if new == old {
+                continue;
+            }
+            updates.push(EntryUpdate {
+                height: created as u64,
+                old: old.clone(),
+                new: new.clone(),
+            });
+            old = new;
+        }
+        Ok(updates)
+    }
+
+    /// Retrieves the root of the tree directly from the database
+    async fn get_root(&mut self, height: u64) -> QueryResult<(State::T, State::Commit)> {
+        let state_type = State::state_type();
+        let header_state_commitment_field = State::header_state_commitment_field();
+
+        // The state at `height` is only available if it is up to date and not yet pruned.
+        if (self.get_last_state_height().await? as u64) < height {
+            return Err(QueryError::NotFound);
+        }
+        let pruned_height = self
+            .load_pruned_height_for(PrunedData::MerklizedState)
+            .await
+            .map_err(|e| QueryError::Error {
+                message: format!("failed to load pruned height: {e}"),
+            })?;
+        if pruned_height.is_some_and(|h| height <= h) {
+            return Err(QueryError::NotFound);
+        }
+
+        // Headers from before this state was introduced have no commitment to it.
+        let Some((Some(commit),)) = query_as::<(Option<String>,)>(&format!(
+            "SELECT {header_state_commitment_field} FROM header WHERE height = $1 LIMIT 1"
+        ))
+        .bind(height as i64)
+        .fetch_optional(self.as_mut())
+        .await?
+        else {
+            return Err(QueryError::NotFound);
+        };
+        let commit =
+            serde_json::from_value(commit.into()).decode_error("malformed state commitment")?;
+
+        // The root is the latest version of the node with an empty path, as of `height`. An empty
+        // tree has no nodes at all.
+        let root_path: JsonValue = Vec::<i32>::new().into();
+        let root = query_as::<(Vec<u8>,)>(&format!(
+            "SELECT h.value FROM {state_type} AS t JOIN hash AS h ON t.hash_id = h.id
+              WHERE t.path = $1 AND t.created <= $2
+              ORDER BY t.created DESC LIMIT 1"
+        ))
+        .bind(&root_path)
+        .bind(height as i64)
+        .fetch_optional(self.as_mut())
+        .await?;
+        let root = match root {
+            Some((value,)) => State::T::deserialize_compressed(value.as_slice())
+                .decode_error("malformed merkle node value")?,
+            None => State::T::default(),
+        };
+        Ok((root, commit))
+    }
 }
 
 #[async_trait]
@@ -340,6 +471,174 @@ impl<Mode: TransactionMode> Transaction<Mode> {
     }
 }
 
+impl<Mode: TransactionMode> Transaction<Mode> {
+    /// Load the nodes of the tree stored in `table` which were created in blocks in `created`.
+    async fn load_merkle_node_rows(
+        &mut self,
+        table: &str,
+        created: Range<u64>,
+    ) -> QueryResult<Vec<MerkleNodeRow>> {
+        let sql = format!(
+            "SELECT t.path, t.created, t.hash_id, t.children, t.children_bitvec, t.idx, t.entry, \
+             h.value AS hash
+               FROM {table} AS t
+               JOIN hash AS h ON t.hash_id = h.id
+              WHERE t.created >= $1 AND t.created < $2"
+        );
+        let rows = query(&sql)
+            .bind(created.start as i64)
+            .bind(created.end as i64)
+            .fetch_all(self.as_mut())
+            .await?;
+        let nodes = rows
+            .into_iter()
+            .map(|row| {
+                let hash: Vec<u8> = row.try_get("hash")?;
+                Ok((Node::from(row), hash))
+            })
+            .collect::<QueryResult<Vec<_>>>()?;
+
+        // Look up the hashes of all the children, which are stored by ID.
+        let mut child_ids = HashSet::new();
+        for (node, _) in &nodes {
+            child_ids.extend(node.children_ids()?);
+        }
+        let child_ids = child_ids.into_iter().collect::<Vec<_>>();
+        let mut hashes = HashMap::new();
+        for ids in child_ids.chunks(HASH_ID_CHUNK_SIZE) {
+            let (query, sql) =
+                build_where_in("SELECT id, value FROM hash", "id", ids.iter().copied())?;
+            let chunk: HashMap<i32, Vec<u8>> = query
+                .query_as(&sql)
+                .fetch(self.as_mut())
+                .try_collect()
+                .await?;
+            hashes.extend(chunk);
+        }
+
+        resolve_merkle_node_rows(nodes, &hashes)
+    }
+}
+
+/// Convert nodes, with the values of their own hashes, into portable rows.
+///
+/// `hashes` must contain the values of the hashes of all the nodes' children, by ID.
+pub(crate) fn resolve_merkle_node_rows(
+    nodes: Vec<(Node, Vec<u8>)>,
+    hashes: &HashMap<i32, Vec<u8>>,
+) -> QueryResult<Vec<MerkleNodeRow>> {
+    nodes
+        .into_iter()
+        .map(|(node, hash)| {
+            let children = match &node.children {
+                Some(_) => Some(
+                    node.children_ids()?
+                        .into_iter()
+                        .map(|id| {
+                            hashes.get(&id).cloned().ok_or_else(|| QueryError::Error {
+                                message: format!("node references non-existent hash {id}"),
+                            })
+                        })
+                        .collect::<QueryResult<Vec<_>>>()?,
+                ),
+                None => None,
+            };
+            Ok(MerkleNodeRow {
+                path: node.path,
+                created: node.created,
+                hash,
+                children,
+                children_bitvec: node.children_bitvec,
+                idx: node.idx,
+                entry: node.entry,
+            })
+        })
+        .collect()
+}
+
+impl Transaction<Write> {
+    /// Insert nodes copied from another database into the tree stored in `table`.
+    pub(crate) async fn insert_merkle_node_rows(
+        &mut self,
+        table: &str,
+        rows: Vec<MerkleNodeRow>,
+    ) -> anyhow::Result<()> {
+        for rows in rows.chunks(MERKLE_NODE_CHUNK_SIZE) {
+            // Insert the hashes first, to find out their IDs in this database.
+            let hashes = rows
+                .iter()
+                .flat_map(|row| iter::once(&row.hash).chain(row.children.iter().flatten()))
+                .cloned()
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect::<Vec<_>>();
+            let (query, sql) = build_hash_batch_insert(&hashes)?;
+            let ids: HashMap<Vec<u8>, i32> = query
+                .query_as(&sql)
+                .fetch(self.as_mut())
+                .try_collect()
+                .await?;
+            let id = |hash: &Vec<u8>| {
+                ids.get(hash).copied().ok_or(QueryError::Error {
+                    message: "Missing node hash".to_string(),
+                })
+            };
+
+            let nodes = rows
+                .iter()
+                .map(|row| {
+                    let children = row
+                        .children
+                        .as_ref()
+                        .map(|children| children.iter().map(id).collect::<QueryResult<Vec<_>>>())
+                        .transpose()?;
+                    Ok(Node {
+                        path: row.path.clone(),
+                        created: row.created,
+                        hash_id: id(&row.hash)?,
+                        children: children.map(Into::into),
+                        children_bitvec: row.children_bitvec.clone(),
+                        idx: row.idx.clone(),
+                        entry: row.entry.clone(),
+                    })
+                })
+                .collect::<QueryResult<Vec<_>>>()?;
+            Node::upsert(table, nodes, self).await?;
+        }
+        Ok(())
+    }
+
+    /// Record the height of the last block for which merklized state is stored.
+    pub(crate) async fn set_merklized_state_height(&mut self, height: u64) -> anyhow::Result<()> {
+        self.upsert(
+            "last_merklized_state_height",
+            ["id", "height"],
+            ["id"],
+            [(1i32, height as i64)],
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl MerklizedStateSource for SqlStorage {
+    async fn last_state_height(&self) -> anyhow::Result<u64> {
+        Ok(self.read().await?.get_last_state_height().await? as u64)
+    }
+
+    async fn load_merkle_nodes(
+        &self,
+        table: &str,
+        created: Range<u64>,
+    ) -> anyhow::Result<Vec<MerkleNodeRow>> {
+        Ok(self
+            .read()
+            .await?
+            .load_merkle_node_rows(table, created)
+            .await?)
+    }
+}
+
 // TODO: create a generic upsert function with retries that returns the column
 pub(crate) fn build_hash_batch_insert(
     hashes: &[Vec<u8>],
@@ -369,7 +668,7 @@ pub(crate) struct Node {
     pub(crate) entry: Option<JsonValue>,
 }
 
-#[cfg(feature = "embedded-db")]
+#[cfg(any(feature = "embedded-db", feature = "sqlite-source"))]
 impl From<sqlx::sqlite::SqliteRow> for Node {
     fn from(row: sqlx::sqlite::SqliteRow) -> Self {
         let bit_string: Option<String> = row.get_unchecked("children_bitvec");
@@ -404,6 +703,16 @@ impl From<sqlx::postgres::PgRow> for Node {
 }
 
 impl Node {
+    /// The IDs of the hashes of this node's children, if it is a branch.
+    pub(crate) fn children_ids(&self) -> QueryResult<Vec<i32>> {
+        let Some(children) = &self.children else {
+            return Ok(vec![]);
+        };
+        serde_json::from_value(children.clone()).map_err(|e| QueryError::Error {
+            message: format!("Error deserializing 'children' into Vec<i32>: {e}"),
+        })
+    }
+
     pub(crate) async fn upsert(
         name: &str,
         nodes: impl IntoIterator<Item = Self>,
@@ -501,7 +810,10 @@ mod test {
     use super::*;
     use crate::{
         data_source::{
-            storage::sql::{testing::TmpDb, *},
+            storage::{
+                migration::migrate_merklized_state,
+                sql::{testing::TmpDb, *},
+            },
             VersionedDataSource,
         },
         merklized_state::UpdateStateData,
@@ -672,6 +984,71 @@ mod test {
             .await
             .unwrap();
         assert_eq!(path_with_bh_1, proof_bh_1);
+
+        // The history of index 0 shows both versions.
+        let mut tx = storage.read().await.unwrap();
+        let history =
+            MerklizedStateStorage::<_, MockMerkleTree, 8>::get_entry_history(&mut tx, 0, 0, 2)
+                .await
+                .unwrap();
+        assert_eq!(
+            history,
+            [
+                EntryUpdate {
+                    height: 1,
+                    old: None,
+                    new: Some(0),
+                },
+                EntryUpdate {
+                    height: 2,
+                    old: Some(0),
+                    new: Some(99),
+                },
+            ]
+        );
+
+        // A range starting after the first version still knows the old value.
+        let history =
+            MerklizedStateStorage::<_, MockMerkleTree, 8>::get_entry_history(&mut tx, 0, 2, 2)
+                .await
+                .unwrap();
+        assert_eq!(
+            history,
+            [EntryUpdate {
+                height: 2,
+                old: Some(0),
+                new: Some(99),
+            }]
+        );
+
+        // Index 1 only changed once.
+        let history =
+            MerklizedStateStorage::<_, MockMerkleTree, 8>::get_entry_history(&mut tx, 1, 0, 2)
+                .await
+                .unwrap();
+        assert_eq!(
+            history,
+            [EntryUpdate {
+                height: 1,
+                old: None,
+                new: Some(1),
+            }]
+        );
+
+        // The root of the tree at each height matches the commitment in the header.
+        for height in [1, 2] {
+            let (root, commit) =
+                MerklizedStateStorage::<_, MockMerkleTree, 8>::get_root(&mut tx, height)
+                    .await
+                    .unwrap();
+            assert_eq!(root, commit.digest(), "wrong root at height {height}");
+        }
+
+        // State beyond the last state height is not available.
+        let err = MerklizedStateStorage::<_, MockMerkleTree, 8>::get_root(&mut tx, 3)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, QueryError::NotFound), "{err:#}");
     }
 
     #[tokio::test(flavor = "multi_thread")]
@@ -1410,4 +1787,99 @@ mod test {
             assert!(matches!(err, QueryError::Missing));
         }
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_migrate_merklized_state() {
+        setup_test();
+
+        let src_db = TmpDb::init().await;
+        let src = SqlStorage::connect(src_db.config()).await.unwrap();
+        let dst_db = TmpDb::init().await;
+        let dst = SqlStorage::connect(dst_db.config()).await.unwrap();
+
+        // Build up a tree in the source over several blocks, overwriting some entries so that
+        // there are multiple versions of some nodes.
+        let mut test_tree: UniversalMerkleTree<_, _, _, 8, _> =
+            MockMerkleTree::new(MockMerkleTree::tree_height());
+        let mut proofs = vec![];
+        for block_height in 0..5usize {
+            let mut tx = src.write().await.unwrap();
+            for i in 0..10 {
+                test_tree.update(i, block_height * 10 + i).unwrap();
+                let (_, proof) = test_tree.lookup(i).expect_ok().unwrap();
+                let traversal_path =
+                    <usize as ToTraversalPath<8>>::to_traversal_path(&i, test_tree.height());
+                UpdateStateData::<_, MockMerkleTree, 8>::insert_merkle_nodes(
+                    &mut tx,
+                    proof,
+                    traversal_path,
+                    block_height as u64,
+                )
+                .await
+                .unwrap();
+            }
+            UpdateStateData::<_, MockMerkleTree, 8>::set_last_state_height(&mut tx, block_height)
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+
+            // `get_path` checks for the header at the requested height, so insert it into both
+            // databases.
+            let test_data = serde_json::json!({ MockMerkleTree::header_state_commitment_field() : serde_json::to_value(test_tree.commitment()).unwrap()});
+            for storage in [&src, &dst] {
+                let mut tx = storage.write().await.unwrap();
+                tx.upsert(
+                    "header",
+                    ["height", "hash", "payload_hash", "timestamp", "data"],
+                    ["height"],
+                    [(
+                        block_height as i64,
+                        format!("randomHash{block_height}"),
+                        "t".to_string(),
+                        0,
+                        test_data.clone(),
+                    )],
+                )
+                .await
+                .unwrap();
+                tx.commit().await.unwrap();
+            }
+
+            proofs.push(
+                (0..10)
+                    .map(|i| test_tree.lookup(i).expect_ok().unwrap().1)
+                    .collect::<Vec<_>>(),
+            );
+        }
+
+        // Migrate in batches which don't evenly divide the number of blocks.
+        let nodes = migrate_merklized_state(&src, &dst, &["test_tree"], 2)
+            .await
+            .unwrap();
+        assert!(nodes > 0);
+
+        // Every historical snapshot is available in the destination.
+        let mut tx = dst.read().await.unwrap();
+        assert_eq!(tx.get_last_state_height().await.unwrap(), 4);
+        for (block_height, proofs) in proofs.into_iter().enumerate() {
+            for (i, proof) in proofs.into_iter().enumerate() {
+                let path = tx
+                    .get_path(
+                        Snapshot::<_, MockMerkleTree, 8>::Index(block_height as u64),
+                        i,
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(path, proof, "block {block_height}, index {i}");
+            }
+        }
+
+        // Migrating again is idempotent.
+        assert_eq!(
+            migrate_merklized_state(&src, &dst, &["test_tree"], 2)
+                .await
+                .unwrap(),
+            nodes
+        );
+    }
 }
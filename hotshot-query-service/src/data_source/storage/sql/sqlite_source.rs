@@ -0,0 +1,299 @@
+// Copyright (c) 2022 Espresso Systems (espressosys.com)
+// This file is part of the HotShot Query Service library.
+//
+// This program is free software: you can redistribute it and/or modify it under the terms of the GNU
+// General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+// This program is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without
+// even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+// You should have received a copy of the GNU General Public License along with this program. If not,
+// see <https://www.gnu.org/licenses/>.
+
+//! Direct, read-only access to a SQLite database, regardless of the SQL backend of this build.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    ops::Range,
+    path::Path,
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use futures::stream::TryStreamExt;
+use hotshot_types::{
+    data::VidShare,
+    traits::node_implementation::{ConsensusTime, NodeType},
+};
+use itertools::Itertools;
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions},
+    Row,
+};
+
+use super::queries::{
+    block_from_parts, leaf_from_parts,
+    state::{resolve_merkle_node_rows, Node, HASH_ID_CHUNK_SIZE},
+    state_cert_from_bytes, vid_common_from_parts, DecodeError,
+};
+use crate::{
+    availability::{QueryableHeader, QueryablePayload},
+    data_source::storage::migration::{
+        Batch, MerkleNodeRow, MerklizedStateSource, MigrationSource, Summary,
+    },
+    types::HeightIndexed,
+    Header, Payload,
+};
+
+/// A SQLite database created by [`SqlStorage`](super::SqlStorage), opened read-only.
+///
+/// Whether [`SqlStorage`](super::SqlStorage) uses Postgres or SQLite is fixed at compile time by
+/// the `embedded-db` feature. [`SqliteSource`] can read a SQLite database in either kind of build,
+/// which makes it possible to [migrate](crate::data_source::storage::migration) a node from SQLite
+/// to Postgres. Only the database itself is read: objects which were moved to cold storage are
+/// treated as missing, and can be fetched from peers by the destination node.
+#[derive(Clone, Debug)]
+pub struct SqliteSource {
+    pool: SqlitePool,
+}
+
+impl SqliteSource {
+    /// Open the existing SQLite database at `path`.
+    pub async fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let options = SqliteConnectOptions::new().filename(path).read_only(true);
+        let pool = SqlitePoolOptions::new()
+            .connect_with(options)
+            .await
+            .with_context(|| format!("opening SQLite database {path:?}"))?;
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl<Types> MigrationSource<Types> for SqliteSource
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+{
+    async fn block_height(&self) -> anyhow::Result<u64> {
+        let (height,): (Option<i64>,) = sqlx::query_as("SELECT max(height) FROM header")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(height.map(|h| h as u64 + 1).unwrap_or(0))
+    }
+
+    async fn load_batch(
+        &self,
+        range: Range<u64>,
+        epochs: &mut BTreeSet<u64>,
+    ) -> anyhow::Result<Batch<Types>> {
+        // Read the whole batch in one transaction, so that it is consistent even if the database
+        // is being written to by a running node.
+        let mut tx = self.pool.begin().await?;
+        let (start, end) = (range.start as i64, range.end as i64);
+
+        let leaves = sqlx::query(
+            "SELECT leaf, qc FROM leaf2 WHERE height >= $1 AND height < $2 ORDER BY height",
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| leaf_from_parts(row.try_get("leaf")?, row.try_get("qc")?))
+        .collect::<sqlx::Result<Vec<_>>>()?;
+
+        let blocks = sqlx::query(
+            "SELECT h.hash AS hash, h.data AS header_data, p.size AS payload_size,
+                    p.data AS payload_data
+               FROM header AS h
+               JOIN payload AS p ON h.height = p.height
+              WHERE h.height >= $1 AND h.height < $2
+                AND p.data IS NOT NULL AND p.size IS NOT NULL
+              ORDER BY h.height",
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| {
+            block_from_parts(
+                row.try_get("header_data")?,
+                row.try_get("payload_data")?,
+                row.try_get("payload_size")?,
+                row.try_get("hash")?,
+            )
+        })
+        .collect::<sqlx::Result<Vec<_>>>()?;
+
+        let vid = sqlx::query(
+            "SELECT h.height AS height, h.hash AS block_hash, h.payload_hash AS payload_hash,
+                    v.common AS common_data, v.share AS share_data
+               FROM header AS h
+               JOIN vid2 AS v ON h.height = v.height
+              WHERE h.height >= $1 AND h.height < $2 AND length(v.common) > 0
+              ORDER BY h.height",
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| {
+            let common = vid_common_from_parts(
+                row.try_get("height")?,
+                row.try_get("block_hash")?,
+                row.try_get("payload_hash")?,
+                row.try_get("common_data")?,
+            )?;
+            let share = row
+                .try_get::<Option<Vec<u8>>, _>("share_data")?
+                .map(|bytes| bincode::deserialize::<VidShare>(&bytes))
+                .transpose()
+                .decode_error("malformed VID share")?;
+            Ok((common, share))
+        })
+        .collect::<sqlx::Result<Vec<_>>>()?;
+
+        let mut state_certs = vec![];
+        for epoch in leaves.iter().filter_map(|leaf| leaf.qc().data.epoch) {
+            let epoch = epoch.u64();
+            if !epochs.insert(epoch) {
+                continue;
+            }
+            // Not every epoch has a state certificate in storage.
+            let Some((bytes,)) = sqlx::query_as::<_, (Vec<u8>,)>(
+                "SELECT state_cert FROM finalized_state_cert WHERE epoch = $1 LIMIT 1",
+            )
+            .bind(epoch as i64)
+            .fetch_optional(&mut *tx)
+            .await?
+            else {
+                continue;
+            };
+            state_certs.push(
+                state_cert_from_bytes(&bytes)
+                    .with_context(|| format!("loading state cert {epoch}"))?,
+            );
+        }
+
+        Ok(Batch {
+            leaves,
+            blocks,
+            vid,
+            state_certs,
+        })
+    }
+
+    async fn load_summary(
+        &self,
+        range: Range<u64>,
+    ) -> anyhow::Result<BTreeMap<u64, Summary<Types>>> {
+        let mut tx = self.pool.begin().await?;
+        let (start, end) = (range.start as i64, range.end as i64);
+        let mut summaries = BTreeMap::<u64, Summary<Types>>::new();
+
+        let leaves = sqlx::query("SELECT leaf, qc FROM leaf2 WHERE height >= $1 AND height < $2")
+            .bind(start)
+            .bind(end)
+            .fetch_all(&mut *tx)
+            .await?;
+        for row in leaves {
+            let leaf = leaf_from_parts::<Types>(row.try_get("leaf")?, row.try_get("qc")?)?;
+            summaries.entry(leaf.height()).or_default().leaf = Some(leaf.hash());
+        }
+
+        let blocks: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT h.height, h.hash
+               FROM header AS h
+               JOIN payload AS p ON h.height = p.height
+              WHERE h.height >= $1 AND h.height < $2 AND p.data IS NOT NULL",
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&mut *tx)
+        .await?;
+        for (height, hash) in blocks {
+            summaries.entry(height as u64).or_default().block =
+                Some(hash.parse().decode_error("malformed block hash")?);
+        }
+
+        let vid: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT h.height, h.hash
+               FROM header AS h
+               JOIN vid2 AS v ON h.height = v.height
+              WHERE h.height >= $1 AND h.height < $2 AND length(v.common) > 0",
+        )
+        .bind(start)
+        .bind(end)
+        .fetch_all(&mut *tx)
+        .await?;
+        for (height, hash) in vid {
+            summaries.entry(height as u64).or_default().vid_common =
+                Some(hash.parse().decode_error("malformed block hash")?);
+        }
+
+        Ok(summaries)
+    }
+}
+
+#[async_trait]
+impl MerklizedStateSource for SqliteSource {
+    async fn last_state_height(&self) -> anyhow::Result<u64> {
+        let height: Option<(i64,)> =
+            sqlx::query_as("SELECT height FROM last_merklized_state_height")
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(height.map(|(h,)| h as u64).unwrap_or(0))
+    }
+
+    async fn load_merkle_nodes(
+        &self,
+        table: &str,
+        created: Range<u64>,
+    ) -> anyhow::Result<Vec<MerkleNodeRow>> {
+        let mut tx = self.pool.begin().await?;
+
+        let sql = format!(
+            "SELECT t.path, t.created, t.hash_id, t.children, t.children_bitvec, t.idx, t.entry,
+                    h.value AS hash
+               FROM {table} AS t
+               JOIN hash AS h ON t.hash_id = h.id
+              WHERE t.created >= $1 AND t.created < $2"
+        );
+        let nodes = sqlx::query(&sql)
+            .bind(created.start as i64)
+            .bind(created.end as i64)
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .map(|row| {
+                let hash: Vec<u8> = row.try_get("hash")?;
+                Ok((Node::from(row), hash))
+            })
+            .collect::<sqlx::Result<Vec<_>>>()?;
+
+        // Look up the hashes of all the children, which are stored by ID.
+        let mut child_ids = HashSet::new();
+        for (node, _) in &nodes {
+            child_ids.extend(node.children_ids()?);
+        }
+        let child_ids = child_ids.into_iter().collect::<Vec<_>>();
+        let mut hashes = HashMap::new();
+        for ids in child_ids.chunks(HASH_ID_CHUNK_SIZE) {
+            // The IDs are integers, so they can be formatted directly into the query.
+            let sql = format!(
+                "SELECT id, value FROM hash WHERE id IN ({})",
+                ids.iter().join(",")
+            );
+            let chunk: HashMap<i32, Vec<u8>> =
+                sqlx::query_as(&sql).fetch(&mut *tx).try_collect().await?;
+            hashes.extend(chunk);
+        }
+
+        Ok(resolve_merkle_node_rows(nodes, &hashes)?)
+    }
+}
@@ -19,8 +19,11 @@
 //! transaction.
 
 use std::{
+    cmp::max,
     collections::{HashMap, HashSet},
     marker::PhantomData,
+    mem,
+    sync::Arc,
     time::Instant,
 };
 
@@ -47,6 +50,8 @@ use sqlx::{
 };
 
 use super::{
+    cache::{serialized_size, CacheKey, CacheUpdate, CachedKind, HotCache},
+    cold::ColdStore,
     queries::{
         self,
         state::{build_hash_batch_insert, Node},
@@ -56,11 +61,15 @@ use super::{
 };
 use crate::{
     availability::{
-        BlockQueryData, LeafQueryData, QueryableHeader, QueryablePayload, StateCertQueryData,
-        VidCommonQueryData,
+        BlockQueryData, LeafQueryData, NamespaceId, NamespaceInfo, PayloadMetadata,
+        QueryableHeader, QueryablePayload, StateCertQueryData, VidCommonQueryData,
     },
     data_source::{
-        storage::{pruning::PrunedHeightStorage, UpdateAvailabilityStorage},
+        storage::{
+            integrity::RepairStorage,
+            pruning::{PrunedData, PrunedHeightStorage},
+            UpdateAvailabilityStorage,
+        },
         update,
     },
     merklized_state::{MerklizedState, UpdateStateData},
@@ -227,14 +236,63 @@ pub struct Transaction<Mode> {
     #[deref_mut]
     inner: sqlx::Transaction<'static, Db>,
     metrics: TransactionMetricsGuard<Mode>,
+    cold: Option<Arc<ColdStore>>,
+    cache: Option<Arc<HotCache>>,
+    cache_updates: Vec<CacheUpdate>,
 }
 
 impl<Mode: TransactionMode> Transaction<Mode> {
-    pub(super) async fn new(pool: &Pool<Db>, metrics: PoolMetrics) -> anyhow::Result<Self> {
+    pub(super) async fn new(
+        pool: &Pool<Db>,
+        metrics: PoolMetrics,
+        cold: Option<Arc<ColdStore>>,
+        cache: Option<Arc<HotCache>>,
+    ) -> anyhow::Result<Self> {
         let mut inner = pool.begin().await?;
         let metrics = TransactionMetricsGuard::begin(metrics);
         Mode::begin(inner.as_mut()).await?;
-        Ok(Self { inner, metrics })
+        Ok(Self {
+            inner,
+            metrics,
+            cold,
+            cache,
+            cache_updates: vec![],
+        })
+    }
+
+    /// The cold storage tier, if enabled.
+    pub(super) fn cold(&self) -> Option<Arc<ColdStore>> {
+        self.cold.clone()
+    }
+
+    /// Load the height of the last block whose data has been moved to cold storage.
+    pub(super) async fn load_cold_height(&mut self) -> anyhow::Result<Option<u64>> {
+        let Some((height,)) =
+            query_as::<(i64,)>("SELECT last_height FROM cold_height ORDER BY id DESC LIMIT 1")
+                .fetch_optional(self.as_mut())
+                .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(height as u64))
+    }
+}
+
+impl<Mode> Transaction<Mode> {
+    /// Look up an object in the in-memory cache, if enabled.
+    pub(super) fn cached<T: Clone + 'static>(
+        &self,
+        kind: CachedKind,
+        key: Option<CacheKey>,
+    ) -> Option<T> {
+        self.cache.as_ref()?.get(kind, &key?)
+    }
+
+    /// Update the in-memory cache, if enabled, once this transaction commits.
+    pub(super) fn update_cache(&mut self, update: impl FnOnce() -> CacheUpdate) {
+        if self.cache.is_some() {
+            self.cache_updates.push(update());
+        }
     }
 }
 
@@ -242,6 +300,9 @@ impl<Mode: TransactionMode> update::Transaction for Transaction<Mode> {
     async fn commit(mut self) -> anyhow::Result<()> {
         self.inner.commit().await?;
         self.metrics.set_closed(CloseType::Commit);
+        if let Some(cache) = &self.cache {
+            cache.apply(mem::take(&mut self.cache_updates));
+        }
         Ok(())
     }
     fn revert(mut self) -> impl Future + Send {
@@ -416,34 +477,77 @@ impl Transaction<Write> {
 /// Query service specific mutations.
 impl Transaction<Write> {
     /// Delete a batch of data for pruning.
+    ///
+    /// Deletes `data` for all blocks up to and including `height`.
     pub(super) async fn delete_batch(
         &mut self,
-        state_tables: Vec<String>,
+        data: PrunedData,
+        state_tables: &[String],
         height: u64,
     ) -> anyhow::Result<()> {
-        self.execute(query("DELETE FROM header WHERE height <= $1").bind(height as i64))
-            .await?;
-
-        // prune merklized state tables
-        // only delete nodes having created < h AND
-        // is not the newest node with its position
-        for state_table in state_tables {
-            self.execute(
-                query(&format!(
-                    "
+        match data {
+            PrunedData::Leaf => {
+                // Payloads, VID and transactions are deleted by cascading.
+                self.execute(query("DELETE FROM header WHERE height <= $1").bind(height as i64))
+                    .await?;
+                self.update_cache(|| CacheUpdate::Prune { data, height });
+                return self.save_pruned_height(height).await;
+            },
+            PrunedData::Payload => {
+                // Keep the payload rows, which hold metadata about each block (such as its size)
+                // which is still useful after the payload itself is gone.
+                self.execute(
+                    query(
+                        "UPDATE payload SET data = NULL, cold_key = NULL
+                          WHERE height <= $1 AND (data IS NOT NULL OR cold_key IS NOT NULL)",
+                    )
+                    .bind(height as i64),
+                )
+                .await?;
+            },
+            PrunedData::VidCommon => {
+                self.execute(query("DELETE FROM vid2 WHERE height <= $1").bind(height as i64))
+                    .await?;
+            },
+            PrunedData::Transactions => {
+                self.execute(
+                    query("DELETE FROM transactions WHERE block_height <= $1").bind(height as i64),
+                )
+                .await?;
+            },
+            PrunedData::MerklizedState => {
+                // only delete nodes having created < h AND
+                // is not the newest node with its position
+                for state_table in state_tables {
+                    self.execute(
+                        query(&format!(
+                            "
                 DELETE FROM {state_table} WHERE (path, created) IN
                 (SELECT path, created FROM 
                 (SELECT path, created, 
                 ROW_NUMBER() OVER (PARTITION BY path ORDER BY created DESC) as rank 
                 FROM {state_table} WHERE created <= $1) ranked_nodes WHERE rank != 1)"
-                ))
-                .bind(height as i64),
-            )
-            .await?;
+                        ))
+                        .bind(height as i64),
+                    )
+                    .await?;
+                }
+            },
+            PrunedData::Aggregates => {
+                // Always keep the latest aggregate, which new aggregates are computed from.
+                self.execute(
+                    query(
+                        "DELETE FROM aggregate WHERE height <= $1
+                          AND height < (SELECT max(height) FROM aggregate)",
+                    )
+                    .bind(height as i64),
+                )
+                .await?;
+            },
         }
 
-        self.save_pruned_height(height).await?;
-        Ok(())
+        self.update_cache(|| CacheUpdate::Prune { data, height });
+        self.save_pruned_height_for(data, height).await
     }
 
     /// Record the height of the latest pruned header.
@@ -458,6 +562,59 @@ impl Transaction<Write> {
         )
         .await
     }
+
+    /// Record the height of the last block whose `data` has been pruned.
+    pub(super) async fn save_pruned_height_for(
+        &mut self,
+        data: PrunedData,
+        height: u64,
+    ) -> anyhow::Result<()> {
+        self.upsert(
+            "pruned_height_by_type",
+            ["data_type", "last_height"],
+            ["data_type"],
+            [(data.as_str().to_string(), height as i64)],
+        )
+        .await
+    }
+
+    /// Record the height of the last block whose data has been moved to cold storage.
+    pub(super) async fn save_cold_height(&mut self, height: u64) -> anyhow::Result<()> {
+        self.upsert(
+            "cold_height",
+            ["id", "last_height"],
+            ["id"],
+            [(1i32, height as i64)],
+        )
+        .await
+    }
+}
+
+impl RepairStorage for Transaction<Write> {
+    async fn delete_block(&mut self, height: u64) -> anyhow::Result<()> {
+        // The database holds the only pointers to objects in cold storage, so delete those first.
+        // If this transaction then fails to commit, the remaining rows point to missing objects,
+        // which makes them unreadable, and they will be found and repaired by the next scan.
+        if let Some(cold) = self.cold() {
+            let keys = query_as::<(String,)>(
+                "SELECT cold_key FROM payload WHERE height = $1 AND cold_key IS NOT NULL
+                 UNION ALL
+                 SELECT cold_key FROM vid2 WHERE height = $1 AND cold_key IS NOT NULL",
+            )
+            .bind(height as i64)
+            .fetch_all(self.as_mut())
+            .await?;
+            for (key,) in keys {
+                cold.delete(&key).await?;
+            }
+        }
+
+        // The leaf, payload, VID and transactions are deleted by cascading.
+        self.execute(query("DELETE FROM header WHERE height = $1").bind(height as i64))
+            .await?;
+        self.update_cache(|| CacheUpdate::Delete { height });
+        Ok(())
+    }
 }
 
 impl<Types> UpdateAvailabilityStorage<Types> for Transaction<Write>
@@ -529,6 +686,28 @@ where
         )
         .await?;
 
+        self.update_cache(|| {
+            let header = leaf.leaf().block_header().clone();
+            let bytes = serialized_size(&header);
+            CacheUpdate::insert(
+                CachedKind::Header,
+                height,
+                Some(leaf.block_hash().to_string()),
+                header,
+                bytes,
+            )
+        });
+        self.update_cache(|| {
+            let bytes = serialized_size(&leaf);
+            CacheUpdate::insert(
+                CachedKind::Leaf,
+                height,
+                Some(leaf.hash().to_string()),
+                leaf,
+                bytes,
+            )
+        });
+
         Ok(())
     }
 
@@ -537,7 +716,7 @@ where
 
         // Ignore the block if it is below the pruned height. This can happen if, for instance, the
         // fetcher is racing with the pruner.
-        if let Some(pruned_height) = self.load_pruned_height().await? {
+        if let Some(pruned_height) = self.load_pruned_height_for(PrunedData::Payload).await? {
             if height <= pruned_height {
                 tracing::info!(
                     height,
@@ -565,7 +744,16 @@ where
         )
         .await?;
 
-        // Index the transactions and namespaces in the block.
+        // Index the transactions and namespaces in the block, unless the index for this block has
+        // already been pruned. In that case, the namespaces are missing from the stored payload
+        // metadata, so we must not cache the complete metadata either.
+        if self
+            .load_pruned_height_for(PrunedData::Transactions)
+            .await?
+            .is_some_and(|pruned_height| height <= pruned_height)
+        {
+            return Ok(());
+        }
         let mut rows = vec![];
         for (txn_ix, txn) in block.enumerate() {
             let ns_id = block.header().namespace_id(&txn_ix.ns_index).unwrap();
@@ -587,6 +775,13 @@ where
             .await?;
         }
 
+        self.update_cache(|| {
+            let metadata = PayloadMetadata::from(block);
+            let bytes = mem::size_of_val(&metadata)
+                + metadata.namespaces.len() * mem::size_of::<(NamespaceId<Types>, NamespaceInfo)>();
+            CacheUpdate::insert(CachedKind::PayloadMetadata, height, None, metadata, bytes)
+        });
+
         Ok(())
     }
 
@@ -599,7 +794,7 @@ where
 
         // Ignore the object if it is below the pruned height. This can happen if, for instance, the
         // fetcher is racing with the pruner.
-        if let Some(pruned_height) = self.load_pruned_height().await? {
+        if let Some(pruned_height) = self.load_pruned_height_for(PrunedData::VidCommon).await? {
             if height <= pruned_height {
                 tracing::info!(
                     height,
@@ -612,6 +807,7 @@ where
 
         let common_data =
             bincode::serialize(common.common()).context("failed to serialize VID common data")?;
+        let bytes = common_data.len();
         if let Some(share) = share {
             let share_data = bincode::serialize(&share).context("failed to serialize VID share")?;
             self.upsert(
@@ -620,7 +816,7 @@ where
                 ["height"],
                 [(height as i64, common_data, share_data)],
             )
-            .await
+            .await?;
         } else {
             // Don't touch the `share` column at all if we don't have a share to insert. It's
             // possible that this column already exists, and we are just upserting the common data,
@@ -631,8 +827,13 @@ where
                 ["height"],
                 [(height as i64, common_data)],
             )
-            .await
+            .await?;
         }
+
+        self.update_cache(|| {
+            CacheUpdate::insert(CachedKind::VidCommon, height, None, common, bytes)
+        });
+        Ok(())
     }
 
     async fn insert_state_cert(
@@ -857,6 +1058,25 @@ impl<Mode: TransactionMode> PrunedHeightStorage for Transaction<Mode> {
         };
         Ok(Some(height as u64))
     }
+
+    async fn load_pruned_height_for(&mut self, data: PrunedData) -> anyhow::Result<Option<u64>> {
+        if data == PrunedData::Leaf {
+            return self.load_pruned_height().await;
+        }
+        let height = query_as::<(i64,)>(
+            "SELECT last_height FROM pruned_height_by_type WHERE data_type = $1 LIMIT 1",
+        )
+        .bind(data.as_str())
+        .fetch_optional(self.as_mut())
+        .await?
+        .map(|(height,)| height as u64);
+        if data.depends_on_leaf() {
+            // This data is also pruned whenever the corresponding leaf is pruned.
+            Ok(max(height, self.load_pruned_height().await?))
+        } else {
+            Ok(height)
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
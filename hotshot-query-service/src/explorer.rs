@@ -17,7 +17,11 @@ pub(crate) mod monetary_value;
 pub(crate) mod query_data;
 pub(crate) mod traits;
 
-use std::{fmt::Display, num::NonZeroUsize, path::Path};
+use std::{
+    fmt::Display,
+    num::{NonZeroU64, NonZeroUsize},
+    path::PathBuf,
+};
 
 pub use currency::*;
 pub use data_source::*;
@@ -31,12 +35,25 @@ pub use traits::*;
 use vbs::version::StaticVersionType;
 
 use self::errors::InvalidLimit;
+pub use self::errors::{BadQuery, NotFound, Pruned};
 use crate::{
     api::load_api,
-    availability::{QueryableHeader, QueryablePayload},
-    Header, Payload, Transaction,
+    availability::{AvailabilityDataSource, Cursor, Page, QueryableHeader, QueryablePayload},
+    data_source::storage::pruning::PrunedData,
+    Header, Payload, QueryResult, Transaction,
 };
 
+#[derive(Debug, Default)]
+pub struct Options {
+    pub api_path: Option<PathBuf>,
+
+    /// Additional API specification files to merge with `explorer-api-path`.
+    ///
+    /// These optional files may contain route definitions for application-specific routes that have
+    /// been added as extensions to the basic explorer API.
+    pub extensions: Vec<toml::Value>,
+}
+
 /// [Error] is an enum that represents the various errors that can be returned
 /// from the Explorer API.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +65,10 @@ pub enum Error {
     GetTransactionSummaries(GetTransactionSummariesError),
     GetExplorerSummary(GetExplorerSummaryError),
     GetSearchResults(GetSearchResultsError),
+    GetNamespaceSummaries(GetNamespaceSummariesError),
+    GetNamespaceDetail(GetNamespaceDetailError),
+    GetAccountDetail(GetAccountDetailError),
+    GetValidator(GetValidatorError),
 }
 
 impl Error {
@@ -59,6 +80,10 @@ impl Error {
             Error::GetTransactionSummaries(e) => e.status(),
             Error::GetExplorerSummary(e) => e.status(),
             Error::GetSearchResults(e) => e.status(),
+            Error::GetNamespaceSummaries(e) => e.status(),
+            Error::GetNamespaceDetail(e) => e.status(),
+            Error::GetAccountDetail(e) => e.status(),
+            Error::GetValidator(e) => e.status(),
         }
     }
 }
@@ -72,6 +97,10 @@ impl Display for Error {
             Error::GetTransactionSummaries(e) => e.fmt(f),
             Error::GetExplorerSummary(e) => e.fmt(f),
             Error::GetSearchResults(e) => e.fmt(f),
+            Error::GetNamespaceSummaries(e) => e.fmt(f),
+            Error::GetNamespaceDetail(e) => e.fmt(f),
+            Error::GetAccountDetail(e) => e.fmt(f),
+            Error::GetValidator(e) => e.fmt(f),
         }
     }
 }
@@ -85,6 +114,10 @@ impl std::error::Error for Error {
             Error::GetTransactionSummaries(e) => Some(e),
             Error::GetExplorerSummary(e) => Some(e),
             Error::GetSearchResults(e) => Some(e),
+            Error::GetNamespaceSummaries(e) => Some(e),
+            Error::GetNamespaceDetail(e) => Some(e),
+            Error::GetAccountDetail(e) => Some(e),
+            Error::GetValidator(e) => Some(e),
         }
     }
 }
@@ -213,6 +246,85 @@ where
     }
 }
 
+/// [NamespaceSummariesResponse] is a struct that represents the response from
+/// the `get_namespace_summaries` endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct NamespaceSummariesResponse<Types: NodeType>
+where
+    Header<Types>: QueryableHeader<Types>,
+{
+    pub namespace_summaries: Vec<NamespaceSummary<Types>>,
+}
+
+impl<Types: NodeType> From<Vec<NamespaceSummary<Types>>> for NamespaceSummariesResponse<Types>
+where
+    Header<Types>: QueryableHeader<Types>,
+{
+    fn from(namespace_summaries: Vec<NamespaceSummary<Types>>) -> Self {
+        Self {
+            namespace_summaries,
+        }
+    }
+}
+
+/// [NamespaceDetailResponse] is a struct that represents the response from the
+/// `get_namespace_detail` endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct NamespaceDetailResponse<Types: NodeType>
+where
+    Header<Types>: QueryableHeader<Types>,
+{
+    pub namespace_detail: NamespaceDetail<Types>,
+}
+
+impl<Types: NodeType> From<NamespaceDetail<Types>> for NamespaceDetailResponse<Types>
+where
+    Header<Types>: QueryableHeader<Types>,
+{
+    fn from(namespace_detail: NamespaceDetail<Types>) -> Self {
+        Self { namespace_detail }
+    }
+}
+
+/// The default width of a bucket in the namespace activity histogram, in
+/// seconds.
+const DEFAULT_NAMESPACE_HISTOGRAM_BUCKET_WIDTH: u64 = 60 * 60;
+
+/// The default number of buckets in the namespace activity histogram.
+const DEFAULT_NAMESPACE_HISTOGRAM_NUM_BUCKETS: usize = 24;
+
+/// The number of block summaries in each page returned by `get_block_summary_page`.
+const BLOCK_SUMMARY_PAGE_SIZE: usize = 50;
+
+/// The number of transaction summaries in each page returned by
+/// `get_transaction_summary_page`.
+const TRANSACTION_SUMMARY_PAGE_SIZE: usize = 50;
+
+/// The number of namespace summaries in each page returned by
+/// `get_namespace_summary_page`.
+const NAMESPACE_SUMMARY_PAGE_SIZE: usize = 50;
+
+/// `check_pruned` returns a [Pruned] error if the payload of the block at
+/// `height` has been pruned, as the availability API does for the same block.
+///
+/// A cursor always points to a block which existed when the cursor was
+/// created, so this tells clients that a page is gone rather than silently
+/// skipping ahead.
+async fn check_pruned<Types, D>(state: &D, height: u64) -> QueryResult<Option<Pruned>>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    D: AvailabilityDataSource<Types> + Sync,
+{
+    let pruned_height = state.pruned_height(PrunedData::Payload).await?;
+    Ok(pruned_height
+        .filter(|pruned_height| height <= *pruned_height)
+        .map(|_| Pruned { height }))
+}
+
 fn validate_limit(
     limit: Result<usize, tide_disco::RequestError>,
 ) -> Result<NonZeroUsize, InvalidLimit> {
@@ -237,6 +349,26 @@ fn validate_limit(
 /// module of the HotShot Query Service. It implements the specification
 /// defined in the `explorer.toml` file.
 pub fn define_api<State, Types: NodeType, Ver: StaticVersionType + 'static>(
+    ver: Ver,
+    api_ver: semver::Version,
+) -> Result<Api<State, Error, Ver>, ApiError>
+where
+    State: 'static + Send + Sync + ReadState,
+    Header<Types>: ExplorerHeader<Types> + QueryableHeader<Types>,
+    Transaction<Types>: ExplorerTransaction<Types>,
+    Payload<Types>: QueryablePayload<Types>,
+    <State as ReadState>::State:
+        ExplorerDataSource<Types> + AvailabilityDataSource<Types> + Send + Sync,
+{
+    define_api_with_options(&Default::default(), ver, api_ver)
+}
+
+/// Define the Explorer API with a custom specification file or application-specific extensions.
+///
+/// This is the same as [`define_api`], except that the specification is loaded according to
+/// `options`, so that applications can add their own routes to the resulting [`Api`].
+pub fn define_api_with_options<State, Types: NodeType, Ver: StaticVersionType + 'static>(
+    options: &Options,
     _: Ver,
     api_ver: semver::Version,
 ) -> Result<Api<State, Error, Ver>, ApiError>
@@ -245,12 +377,13 @@ where
     Header<Types>: ExplorerHeader<Types> + QueryableHeader<Types>,
     Transaction<Types>: ExplorerTransaction<Types>,
     Payload<Types>: QueryablePayload<Types>,
-    <State as ReadState>::State: ExplorerDataSource<Types> + Send + Sync,
+    <State as ReadState>::State:
+        ExplorerDataSource<Types> + AvailabilityDataSource<Types> + Send + Sync,
 {
     let mut api = load_api::<State, Error, Ver>(
-        Option::<Box<Path>>::None,
+        options.api_path.as_ref(),
         include_str!("../api/explorer.toml"),
-        None,
+        options.extensions.clone(),
     )?;
 
     api.with_version(api_ver)
@@ -299,6 +432,52 @@ where
             }
             .boxed()
         })?
+        .get("get_block_summary_page", move |req, state| {
+            async move {
+                let cursor = req
+                    .opt_blob_param::<str, Cursor>("cursor")
+                    .map_err(|_| GetBlockSummariesError::InvalidCursor(BadQuery {}))
+                    .map_err(Error::GetBlockSummaries)?;
+                let target = match cursor {
+                    Some(cursor) => {
+                        if let Some(pruned) = check_pruned::<Types, _>(state, cursor.height())
+                            .await
+                            .map_err(GetBlockSummariesError::from)
+                            .map_err(Error::GetBlockSummaries)?
+                        {
+                            return Err(Error::GetBlockSummaries(GetBlockSummariesError::Pruned(
+                                pruned,
+                            )));
+                        }
+                        BlockIdentifier::Height(cursor.height() as usize)
+                    },
+                    None => BlockIdentifier::Latest,
+                };
+
+                // Load one extra block, which is not returned but tells us where the next page
+                // starts.
+                let mut block_summaries = state
+                    .get_block_summaries(GetBlockSummariesRequest(BlockRange {
+                        target,
+                        num_blocks: NonZeroUsize::new(BLOCK_SUMMARY_PAGE_SIZE + 1).unwrap(),
+                    }))
+                    .await
+                    .map_err(Error::GetBlockSummaries)?;
+
+                let next = if block_summaries.len() > BLOCK_SUMMARY_PAGE_SIZE {
+                    block_summaries
+                        .pop()
+                        .map(|summary| Cursor::new(summary.height))
+                } else {
+                    None
+                };
+                Ok(Page {
+                    items: block_summaries,
+                    next,
+                })
+            }
+            .boxed()
+        })?
         .get("get_transaction_detail", move |req, state| {
             async move {
                 state
@@ -362,6 +541,81 @@ where
             }
             .boxed()
         })?
+        .get("get_transaction_summary_page", move |req, state| {
+            async move {
+                let cursor = req
+                    .opt_blob_param::<str, Cursor>("cursor")
+                    .map_err(|_| GetTransactionSummariesError::InvalidCursor(BadQuery {}))
+                    .map_err(Error::GetTransactionSummaries)?;
+
+                let filter = match (
+                    req.opt_integer_param("block"),
+                    req.opt_integer_param::<_, i64>("namespace"),
+                ) {
+                    (Ok(Some(block)), _) => TransactionSummaryFilter::Block(block),
+                    (_, Ok(Some(namespace))) => TransactionSummaryFilter::RollUp(namespace.into()),
+                    _ => TransactionSummaryFilter::None,
+                };
+
+                // The offset of a cursor counts the matching transactions which come before the
+                // page in its block, in descending order, which is exactly what the offset of a
+                // transaction target skips.
+                let target = match cursor {
+                    Some(cursor) => {
+                        if let Some(pruned) = check_pruned::<Types, _>(state, cursor.height())
+                            .await
+                            .map_err(GetTransactionSummariesError::from)
+                            .map_err(Error::GetTransactionSummaries)?
+                        {
+                            return Err(Error::GetTransactionSummaries(
+                                GetTransactionSummariesError::Pruned(pruned),
+                            ));
+                        }
+                        TransactionIdentifier::HeightAndOffset(
+                            cursor.height() as usize,
+                            cursor.offset() as usize,
+                        )
+                    },
+                    None => TransactionIdentifier::Latest,
+                };
+
+                // Load one extra transaction, which is not returned but tells us where the next
+                // page starts.
+                let mut transaction_summaries = state
+                    .get_transaction_summaries(GetTransactionSummariesRequest {
+                        range: TransactionRange {
+                            target,
+                            num_transactions: NonZeroUsize::new(TRANSACTION_SUMMARY_PAGE_SIZE + 1)
+                                .unwrap(),
+                        },
+                        filter,
+                    })
+                    .await
+                    .map_err(Error::GetTransactionSummaries)?;
+
+                let next = if transaction_summaries.len() > TRANSACTION_SUMMARY_PAGE_SIZE {
+                    transaction_summaries.pop().map(|summary| {
+                        let mut offset = transaction_summaries
+                            .iter()
+                            .filter(|prev| prev.height == summary.height)
+                            .count() as u64;
+                        if let Some(cursor) = cursor {
+                            if cursor.height() == summary.height {
+                                offset += cursor.offset();
+                            }
+                        }
+                        Cursor::with_offset(summary.height, offset)
+                    })
+                } else {
+                    None
+                };
+                Ok(Page {
+                    items: transaction_summaries,
+                    next,
+                })
+            }
+            .boxed()
+        })?
         .get("get_explorer_summary", move |_req, state| {
             async move {
                 state
@@ -374,6 +628,25 @@ where
         })?
         .get("get_search_result", move |req, state| {
             async move {
+                // Account addresses are not tagged, so they are searched for under their own path.
+                if let Some(address) = req
+                    .opt_string_param("address")
+                    .map_err(|err| {
+                        tracing::error!("address param error: {}", err);
+                        GetSearchResultsError::InvalidQuery(errors::BadQuery {})
+                    })
+                    .map_err(Error::GetSearchResults)?
+                {
+                    let account = Header::<Types>::parse_wallet_address(address)
+                        .ok_or(GetSearchResultsError::InvalidQuery(errors::BadQuery {}))
+                        .map_err(Error::GetSearchResults)?;
+                    return Ok(SearchResultResponse::from(SearchResult {
+                        blocks: vec![],
+                        transactions: vec![],
+                        accounts: vec![account],
+                    }));
+                }
+
                 let query = req
                     .tagged_base64_param("query")
                     .map_err(|err| {
@@ -389,6 +662,82 @@ where
                     .map_err(Error::GetSearchResults)
             }
             .boxed()
+        })?
+        .get("get_namespace_summaries", move |_req, state| {
+            async move {
+                state
+                    .get_namespace_summaries(Default::default())
+                    .await
+                    .map(NamespaceSummariesResponse::from)
+                    .map_err(Error::GetNamespaceSummaries)
+            }
+            .boxed()
+        })?
+        .get("get_namespace_summary_page", move |req, state| {
+            async move {
+                let start = req
+                    .opt_blob_param::<str, NamespaceCursor>("cursor")
+                    .map_err(|_| GetNamespaceSummariesError::InvalidCursor(BadQuery {}))
+                    .map_err(Error::GetNamespaceSummaries)?;
+
+                // Load one extra summary, which is not returned but tells us where the next page
+                // starts.
+                let mut namespace_summaries = state
+                    .get_namespace_summaries(GetNamespaceSummariesRequest {
+                        start,
+                        limit: NonZeroUsize::new(NAMESPACE_SUMMARY_PAGE_SIZE + 1),
+                    })
+                    .await
+                    .map_err(Error::GetNamespaceSummaries)?;
+
+                let next = if namespace_summaries.len() > NAMESPACE_SUMMARY_PAGE_SIZE {
+                    namespace_summaries
+                        .pop()
+                        .map(|summary| NamespaceCursor::from(&summary))
+                } else {
+                    None
+                };
+                Ok(Page {
+                    items: namespace_summaries,
+                    next,
+                })
+            }
+            .boxed()
+        })?
+        .get("get_namespace_detail", move |req, state| {
+            async move {
+                let namespace = req
+                    .integer_param::<_, i64>("namespace")
+                    .map_err(|_| GetNamespaceDetailError::InvalidQuery(errors::BadQuery {}))
+                    .map_err(Error::GetNamespaceDetail)?
+                    .into();
+
+                let (bucket_width, num_buckets) = match req.opt_integer_param("bucket_width") {
+                    Ok(Some(bucket_width)) => (
+                        NonZeroU64::new(bucket_width)
+                            .ok_or(GetNamespaceDetailError::InvalidQuery(errors::BadQuery {}))
+                            .map_err(Error::GetNamespaceDetail)?,
+                        validate_limit(req.integer_param("limit"))
+                            .map_err(GetNamespaceDetailError::InvalidLimit)
+                            .map_err(Error::GetNamespaceDetail)?,
+                    ),
+                    _ => (
+                        NonZeroU64::new(DEFAULT_NAMESPACE_HISTOGRAM_BUCKET_WIDTH).unwrap(),
+                        NonZeroUsize::new(DEFAULT_NAMESPACE_HISTOGRAM_NUM_BUCKETS).unwrap(),
+                    ),
+                };
+
+                state
+                    .get_namespace_detail(GetNamespaceDetailRequest {
+                        namespace,
+                        bucket_width,
+                        num_buckets,
+                    })
+                    .await
+                    .map(NamespaceDetailResponse::from)
+                    .map_err(Error::GetNamespaceDetail)
+            }
+            .boxed()
         })?;
     Ok(api)
 }
@@ -399,17 +748,27 @@ mod test {
 
     use futures::StreamExt;
     use portpicker::pick_unused_port;
-    use surf_disco::Client;
+    use serde::de::DeserializeOwned;
+    use surf_disco::{Client, Error as _};
     use tide_disco::App;
 
     use super::*;
     use crate::{
-        availability,
+        availability::{self, UpdateAvailabilityData},
+        data_source::{
+            storage::{
+                pruning::{PrunedHeightStorage, PrunerCfg},
+                sql::testing::TmpDb,
+            },
+            VersionedDataSource,
+        },
+        fetching::provider::NoFetching,
         testing::{
-            consensus::{MockNetwork, MockSqlDataSource},
+            consensus::{MockDataSource, MockNetwork, MockSqlDataSource},
             mocks::{mock_transaction, MockBase, MockTypes, MockVersions},
-            setup_test,
+            setup_test, sleep,
         },
+        types::HeightIndexed,
         ApiState, Error,
     };
 
@@ -847,6 +1206,138 @@ mod test {
                 }
             }
         }
+
+        {
+            // Every namespace in the directory has a detail page consistent with its summary.
+            let namespace_summaries_response: NamespaceSummariesResponse<MockTypes> =
+                client.get("namespaces").send().await.unwrap();
+            for summary in namespace_summaries_response.namespace_summaries {
+                assert!(summary.first_seen <= summary.last_seen);
+                assert!(summary.num_transactions_last_24h <= summary.num_transactions);
+                assert!(summary.size_last_24h <= summary.size);
+
+                let namespace_detail_response: NamespaceDetailResponse<MockTypes> = client
+                    .get(
+                        format!("namespace/{}/histogram/{}/{}", summary.namespace, 1, 100).as_str(),
+                    )
+                    .send()
+                    .await
+                    .unwrap();
+                let NamespaceDetail {
+                    summary: detail_summary,
+                    histogram,
+                } = namespace_detail_response.namespace_detail;
+                assert_eq!(detail_summary, summary);
+                assert_eq!(histogram.bucket_width, 1);
+                assert_eq!(histogram.bucket_start.len(), 100);
+                assert_eq!(histogram.transactions.len(), 100);
+                assert_eq!(histogram.size.len(), 100);
+                assert!(histogram.transactions.iter().sum::<u64>() <= summary.num_transactions);
+                assert!(histogram.size.iter().sum::<u64>() <= summary.size);
+            }
+
+            // An unknown namespace is not found.
+            client
+                .get::<NamespaceDetailResponse<MockTypes>>("namespace/1")
+                .send()
+                .await
+                .unwrap_err();
+        }
+
+        {
+            // Following block summary cursors from the latest block visits every block, in
+            // descending order, down to genesis.
+            let block_summaries: Vec<BlockSummary<MockTypes>> =
+                collect_pages::<_, Cursor>(client, "page/blocks/latest", "page/blocks", "").await;
+            assert!(block_summaries.first().unwrap().height >= latest_block.height);
+            for (i, summary) in block_summaries.iter().rev().enumerate() {
+                assert_eq!(summary.height, i as u64);
+            }
+        }
+
+        {
+            // Following transaction summary cursors from the latest transaction visits every
+            // transaction, in descending order.
+            let transaction_summaries: Vec<TransactionSummary<MockTypes>> =
+                collect_pages::<_, Cursor>(
+                    client,
+                    "page/transactions/latest",
+                    "page/transactions",
+                    "",
+                )
+                .await;
+            assert!(transaction_summaries.len() as u64 >= num_transactions);
+            assert_eq!(
+                &transaction_summaries[..latest_transactions.len()],
+                &latest_transactions[..]
+            );
+            for (a, b) in transaction_summaries
+                .iter()
+                .zip(transaction_summaries.iter().skip(1))
+            {
+                assert!((a.height, a.offset) > (b.height, b.offset));
+            }
+
+            // The same goes for a filtered list, with the filter repeated along with each
+            // cursor.
+            if let Some(last_transaction) = latest_transactions.first() {
+                let block = last_transaction.height;
+                let block_transactions: Vec<TransactionSummary<MockTypes>> =
+                    collect_pages::<_, Cursor>(
+                        client,
+                        &format!("page/transactions/latest/block/{block}"),
+                        "page/transactions",
+                        &format!("/block/{block}"),
+                    )
+                    .await;
+                assert_eq!(
+                    block_transactions,
+                    transaction_summaries
+                        .iter()
+                        .filter(|t| t.height == block)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                );
+            }
+        }
+
+        {
+            // Following namespace summary cursors visits every namespace in the directory.
+            let namespace_summaries: Vec<NamespaceSummary<MockTypes>> =
+                collect_pages::<_, NamespaceCursor>(
+                    client,
+                    "page/namespaces/latest",
+                    "page/namespaces",
+                    "",
+                )
+                .await;
+            let namespace_summaries_response: NamespaceSummariesResponse<MockTypes> =
+                client.get("namespaces").send().await.unwrap();
+            assert_eq!(
+                namespace_summaries,
+                namespace_summaries_response.namespace_summaries
+            );
+        }
+    }
+
+    /// Collect every item of a paginated route, starting from `first` and following cursors
+    /// (as `{prefix}/{cursor}{suffix}`) until there are no more pages.
+    async fn collect_pages<T: DeserializeOwned, C: DeserializeOwned + Display>(
+        client: &Client<Error, MockBase>,
+        first: &str,
+        prefix: &str,
+        suffix: &str,
+    ) -> Vec<T> {
+        let mut items = vec![];
+        let mut route = first.to_string();
+        loop {
+            let page: Page<T, C> = client.get(&route).send().await.unwrap();
+            items.extend(page.items);
+            match page.next {
+                Some(cursor) => route = format!("{prefix}/{cursor}{suffix}"),
+                None => break items,
+            }
+        }
     }
 
     #[tokio::test(flavor = "multi_thread")]
@@ -945,4 +1436,93 @@ mod test {
         validate(&explorer_client).await;
         network.shut_down().await;
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_pruned_pages() {
+        setup_test();
+
+        // Create the consensus network and wait for a few blocks.
+        let mut network = MockNetwork::<MockDataSource, MockVersions>::init().await;
+        network.start().await;
+        let leaves = network
+            .data_source()
+            .subscribe_leaves(1)
+            .await
+            .take(3)
+            .collect::<Vec<_>>()
+            .await;
+        let last_leaf = leaves.last().unwrap();
+
+        // Start a data source which prunes everything it can, and give it the leaves.
+        let db = TmpDb::init().await;
+        let data_source: MockSqlDataSource = db
+            .config()
+            .pruner_cfg(
+                PrunerCfg::new()
+                    .with_target_retention(Duration::from_secs(0))
+                    .with_interval(Duration::from_secs(1)),
+            )
+            .unwrap()
+            .connect(NoFetching)
+            .await
+            .unwrap();
+        for leaf in &leaves {
+            data_source.append(leaf.clone().into()).await.unwrap();
+        }
+
+        // Wait for the pruner to run.
+        loop {
+            let pruned_height = data_source
+                .read()
+                .await
+                .unwrap()
+                .load_pruned_height()
+                .await
+                .unwrap();
+            if pruned_height == Some(last_leaf.height()) {
+                break;
+            }
+            tracing::info!(?pruned_height, "waiting for pruner to run");
+            sleep(Duration::from_secs(1)).await;
+        }
+
+        // Start the web server.
+        let port = pick_unused_port().unwrap();
+        let mut app = App::<_, Error>::with_state(ApiState::from(data_source.clone()));
+        app.register_module(
+            "explorer",
+            define_api(MockBase::instance(), "0.0.1".parse().unwrap()).unwrap(),
+        )
+        .unwrap();
+        network.spawn(
+            "server",
+            app.serve(format!("0.0.0.0:{port}"), MockBase::instance()),
+        );
+
+        // Start a client.
+        let client = Client::<Error, MockBase>::new(
+            format!("http://localhost:{port}/explorer").parse().unwrap(),
+        );
+        assert!(client.connect(Some(Duration::from_secs(60))).await);
+
+        // Requests for pages starting at a pruned block fail, rather than silently skipping the
+        // pruned data.
+        let height = last_leaf.height();
+        let cursor = Cursor::new(height);
+        for route in [
+            format!("page/blocks/{cursor}"),
+            format!("page/transactions/{cursor}"),
+            format!("page/transactions/{cursor}/block/{height}"),
+        ] {
+            tracing::info!(route, "checking pruned page");
+            let err = client
+                .get::<serde_json::Value>(&route)
+                .send()
+                .await
+                .unwrap_err();
+            assert_eq!(err.status(), StatusCode::GONE, "{route}: {err}");
+        }
+
+        network.shut_down().await;
+    }
 }
@@ -18,9 +18,11 @@ use super::{
     query_data::{
         BlockDetail, BlockIdentifier, BlockSummary, ExplorerSummary, GetBlockDetailError,
         GetBlockSummariesError, GetBlockSummariesRequest, GetExplorerSummaryError,
-        GetSearchResultsError, GetTransactionDetailError, GetTransactionSummariesError,
-        GetTransactionSummariesRequest, SearchResult, TransactionDetailResponse,
-        TransactionIdentifier, TransactionSummary,
+        GetNamespaceDetailError, GetNamespaceDetailRequest, GetNamespaceSummariesError,
+        GetNamespaceSummariesRequest, GetSearchResultsError, GetTransactionDetailError,
+        GetTransactionSummariesError, GetTransactionSummariesRequest, NamespaceDetail,
+        NamespaceSummary, SearchResult, TransactionDetailResponse, TransactionIdentifier,
+        TransactionSummary,
     },
     traits::{ExplorerHeader, ExplorerTransaction},
 };
@@ -91,4 +93,21 @@ where
         &self,
         query: TaggedBase64,
     ) -> Result<SearchResult<Types>, GetSearchResultsError>;
+
+    /// `get_namespace_summaries` is a method that retrieves a
+    /// [NamespaceSummary] for each namespace which has appeared in the
+    /// blockchain, ordered from most to least recently seen.  The range of
+    /// summaries is given by the [GetNamespaceSummariesRequest].
+    async fn get_namespace_summaries(
+        &self,
+        request: GetNamespaceSummariesRequest,
+    ) -> Result<Vec<NamespaceSummary<Types>>, GetNamespaceSummariesError>;
+
+    /// `get_namespace_detail` is a method that retrieves the summary and a
+    /// histogram of recent activity for a single namespace.  The namespace
+    /// and histogram dimensions are given by the [GetNamespaceDetailRequest].
+    async fn get_namespace_detail(
+        &self,
+        request: GetNamespaceDetailRequest<Types>,
+    ) -> Result<NamespaceDetail<Types>, GetNamespaceDetailError>;
 }
@@ -167,6 +167,51 @@ impl Serialize for NotFound {
     }
 }
 
+/// [Pruned] is an error that indicates the requested data has been pruned, and
+/// is no longer available from this node.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "code", rename = "PRUNED")]
+pub struct Pruned {
+    pub height: u64,
+}
+
+impl Pruned {
+    pub fn status(&self) -> StatusCode {
+        StatusCode::GONE
+    }
+
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+}
+
+impl ExplorerAPIError for Pruned {
+    fn code(&self) -> &str {
+        "PRUNED"
+    }
+}
+
+impl Display for Pruned {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "data at height {} has been pruned", self.height)
+    }
+}
+
+impl std::error::Error for Pruned {}
+
+impl Serialize for Pruned {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut st = serializer.serialize_struct("Pruned", 3)?;
+        st.serialize_field("code", &self.code())?;
+        st.serialize_field("height", &self.height())?;
+        st.serialize_field("message", &format!("{self}"))?;
+        st.end()
+    }
+}
+
 /// QueryError is an error that indicates that a specific error occurred while
 /// evaluating a query, or decoding the results of a query.
 #[derive(Debug, Clone, Deserialize)]
@@ -266,7 +311,7 @@ impl Serialize for BadQuery {
 
 #[cfg(test)]
 mod test {
-    use super::{BadQuery, InvalidLimit, NotFound, QueryError, Unimplemented};
+    use super::{BadQuery, InvalidLimit, NotFound, Pruned, QueryError, Unimplemented};
 
     #[test]
     fn test_serialize_deserialize_unimplemented() {
@@ -383,6 +428,44 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_serialize_deserialize_pruned() {
+        let pruned = Pruned { height: 10 };
+        let serialize_result = serde_json::to_string(&pruned);
+        assert!(
+            serialize_result.is_ok(),
+            "failed to serialize Pruned: {}",
+            serialize_result.err().unwrap(),
+        );
+        let serialized = serialize_result.unwrap();
+
+        {
+            let have = &serialized;
+            let want =
+                r#"{"code":"PRUNED","height":10,"message":"data at height 10 has been pruned"}"#;
+            assert_eq!(
+                have, want,
+                "serialized Pruned mismatch: have: {have}, want: {want}"
+            );
+        }
+
+        let deserialize_result: Result<Pruned, _> = serde_json::from_str(&serialized);
+        assert!(
+            deserialize_result.is_ok(),
+            "failed to deserialize Pruned: {}",
+            deserialize_result.err().unwrap(),
+        );
+        let deserialized = deserialize_result.unwrap();
+        {
+            let have = deserialized;
+            let want = pruned;
+            assert_eq!(
+                have, want,
+                "deserialized Pruned mismatch: have: {have}, want: {want}"
+            );
+        }
+    }
+
     #[test]
     fn test_serialize_deserialize_query_error() {
         let query_error = QueryError {
@@ -13,22 +13,26 @@
 use std::{
     collections::VecDeque,
     fmt::{Debug, Display},
-    num::{NonZeroUsize, TryFromIntError},
+    num::{NonZeroU64, NonZeroUsize, TryFromIntError},
 };
 
 use hotshot_types::traits::{block_contents::BlockHeader, node_implementation::NodeType};
 use serde::{Deserialize, Serialize};
+use tagged_base64::TaggedBase64;
 use tide_disco::StatusCode;
 use time::format_description::well_known::Rfc3339;
 
 use super::{
-    errors::{BadQuery, ExplorerAPIError, InvalidLimit, NotFound, QueryError, Unimplemented},
+    errors::{
+        BadQuery, ExplorerAPIError, InvalidLimit, NotFound, Pruned, QueryError, Unimplemented,
+    },
     monetary_value::MonetaryValue,
     traits::{ExplorerHeader, ExplorerTransaction},
 };
 use crate::{
     availability::{
-        BlockQueryData, NamespaceId, QueryableHeader, QueryablePayload, TransactionHash,
+        BlockQueryData, InvalidCursor, NamespaceId, QueryableHeader, QueryablePayload,
+        TransactionHash,
     },
     node::BlockHash,
     types::HeightIndexed,
@@ -515,8 +519,8 @@ where
 }
 
 /// [SearchResult] is a struct that represents the results of executing a
-/// search query against the chain.  It contains a list of blocks and
-/// transactions that match the search query.
+/// search query against the chain.  It contains a list of blocks,
+/// transactions, and accounts that match the search query.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct SearchResult<Types: NodeType>
@@ -526,6 +530,168 @@ where
 {
     pub blocks: Vec<BlockSummary<Types>>,
     pub transactions: Vec<TransactionSummary<Types>>,
+    #[serde(default)]
+    pub accounts: Vec<WalletAddress<Types>>,
+}
+
+/// [NamespaceSummary] is an overview of the activity of a single namespace
+/// (rollup) over the life of the chain.
+///
+/// `first_seen` and `last_seen` are the heights of the first and last blocks
+/// containing a transaction in this namespace.  The `_last_24h` fields count
+/// only the blocks whose timestamps fall within 24 hours of the most recent
+/// block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct NamespaceSummary<Types>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+{
+    pub namespace: NamespaceId<Types>,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    pub num_transactions: u64,
+    pub size: u64,
+    pub num_transactions_last_24h: u64,
+    pub size_last_24h: u64,
+}
+
+const NAMESPACE_CURSOR_TAG: &str = "NS_CURSOR";
+
+/// [NamespaceCursor] is an opaque position in the list of [NamespaceSummary]s
+/// returned by `get_namespace_summary_page`.
+///
+/// Namespace summaries are ordered from most to least recently seen, and then
+/// by namespace, so a cursor records both for the first summary in its page.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "TaggedBase64", try_from = "TaggedBase64")]
+pub struct NamespaceCursor {
+    last_seen: u64,
+    namespace: i64,
+}
+
+impl NamespaceCursor {
+    pub(crate) fn new(last_seen: u64, namespace: i64) -> Self {
+        Self {
+            last_seen,
+            namespace,
+        }
+    }
+
+    /// The height of the last block containing the first namespace in the
+    /// page.
+    pub(crate) fn last_seen(&self) -> u64 {
+        self.last_seen
+    }
+
+    /// The first namespace in the page.
+    pub(crate) fn namespace(&self) -> i64 {
+        self.namespace
+    }
+}
+
+impl<Types> From<&NamespaceSummary<Types>> for NamespaceCursor
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+{
+    fn from(summary: &NamespaceSummary<Types>) -> Self {
+        Self::new(summary.last_seen, summary.namespace.into())
+    }
+}
+
+impl Display for NamespaceCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", TaggedBase64::from(*self))
+    }
+}
+
+impl From<NamespaceCursor> for TaggedBase64 {
+    fn from(cursor: NamespaceCursor) -> Self {
+        let mut bytes = cursor.last_seen.to_le_bytes().to_vec();
+        bytes.extend(cursor.namespace.to_le_bytes());
+        TaggedBase64::new(NAMESPACE_CURSOR_TAG, &bytes).unwrap()
+    }
+}
+
+impl TryFrom<&TaggedBase64> for NamespaceCursor {
+    type Error = InvalidCursor;
+
+    fn try_from(tb64: &TaggedBase64) -> Result<Self, Self::Error> {
+        if tb64.tag() != NAMESPACE_CURSOR_TAG {
+            return Err(InvalidCursor);
+        }
+        let bytes: [u8; 16] = tb64.value().try_into().map_err(|_| InvalidCursor)?;
+        let (last_seen, namespace) = bytes.split_at(8);
+        Ok(Self::new(
+            u64::from_le_bytes(last_seen.try_into().unwrap()),
+            i64::from_le_bytes(namespace.try_into().unwrap()),
+        ))
+    }
+}
+
+impl TryFrom<TaggedBase64> for NamespaceCursor {
+    type Error = InvalidCursor;
+
+    fn try_from(tb64: TaggedBase64) -> Result<Self, Self::Error> {
+        (&tb64).try_into()
+    }
+}
+
+/// [GetNamespaceSummariesRequest] is a struct that represents an incoming
+/// request for a range of the [NamespaceSummary]s, in the order they are
+/// listed by the explorer.  The default request selects every summary.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GetNamespaceSummariesRequest {
+    /// The position of the first summary to return, or [None] to start with
+    /// the most recently seen namespace.
+    pub start: Option<NamespaceCursor>,
+    /// The maximum number of summaries to return, or [None] for no limit.
+    pub limit: Option<NonZeroUsize>,
+}
+
+/// [NamespaceHistogram] provides the activity of a namespace over a series of
+/// consecutive, equally sized time buckets, ending with the bucket containing
+/// the most recent block.
+///
+/// All of the vectors **MUST** have the same length.  The labels of the graph
+/// points are the `bucket_start` timestamps, in seconds since the Unix epoch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceHistogram {
+    pub bucket_width: u64,
+    pub bucket_start: Vec<i64>,
+    pub transactions: Vec<u64>,
+    pub size: Vec<u64>,
+}
+
+/// [NamespaceDetail] is the detailed view of a single namespace, combining its
+/// [NamespaceSummary] with a [NamespaceHistogram] of its recent activity.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct NamespaceDetail<Types>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+{
+    pub summary: NamespaceSummary<Types>,
+    pub histogram: NamespaceHistogram,
+}
+
+/// [GetNamespaceDetailRequest] is a struct that represents an incoming request
+/// for the detail of a namespace.  This isn't sent on the line, but an
+/// endpoint will be mapped to this struct in order for the request to be
+/// processed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GetNamespaceDetailRequest<Types>
+where
+    Types: NodeType,
+    Header<Types>: QueryableHeader<Types>,
+{
+    pub namespace: NamespaceId<Types>,
+    /// The width of each histogram bucket, in seconds.
+    pub bucket_width: NonZeroU64,
+    pub num_buckets: NonZeroUsize,
 }
 
 /// [GetBlockDetailError] represents an error that has occurred in response to
@@ -591,7 +757,9 @@ impl From<crate::QueryError> for GetBlockDetailError {
 pub enum GetBlockSummariesError {
     Unimplemented(Unimplemented),
     InvalidLimit(InvalidLimit),
+    InvalidCursor(BadQuery),
     TargetNotFound(NotFound),
+    Pruned(Pruned),
     QueryError(QueryError),
 }
 
@@ -600,8 +768,10 @@ impl GetBlockSummariesError {
         match self {
             GetBlockSummariesError::Unimplemented(err) => err.status(),
             GetBlockSummariesError::InvalidLimit(err) => err.status(),
+            GetBlockSummariesError::InvalidCursor(err) => err.status(),
             GetBlockSummariesError::QueryError(err) => err.status(),
             GetBlockSummariesError::TargetNotFound(err) => err.status(),
+            GetBlockSummariesError::Pruned(err) => err.status(),
         }
     }
 }
@@ -611,8 +781,10 @@ impl Display for GetBlockSummariesError {
         match self {
             GetBlockSummariesError::Unimplemented(err) => write!(f, "{err}"),
             GetBlockSummariesError::InvalidLimit(err) => write!(f, "{err}"),
+            GetBlockSummariesError::InvalidCursor(err) => write!(f, "{err}"),
             GetBlockSummariesError::QueryError(err) => write!(f, "{err}"),
             GetBlockSummariesError::TargetNotFound(err) => write!(f, "{err}"),
+            GetBlockSummariesError::Pruned(err) => write!(f, "{err}"),
         }
     }
 }
@@ -622,8 +794,10 @@ impl ExplorerAPIError for GetBlockSummariesError {
         match self {
             GetBlockSummariesError::Unimplemented(err) => err.code(),
             GetBlockSummariesError::InvalidLimit(err) => err.code(),
+            GetBlockSummariesError::InvalidCursor(err) => err.code(),
             GetBlockSummariesError::QueryError(err) => err.code(),
             GetBlockSummariesError::TargetNotFound(err) => err.code(),
+            GetBlockSummariesError::Pruned(err) => err.code(),
         }
     }
 }
@@ -633,7 +807,9 @@ impl std::error::Error for GetBlockSummariesError {
         match self {
             GetBlockSummariesError::Unimplemented(err) => Some(err),
             GetBlockSummariesError::InvalidLimit(err) => Some(err),
+            GetBlockSummariesError::InvalidCursor(err) => Some(err),
             GetBlockSummariesError::QueryError(err) => Some(err),
+            GetBlockSummariesError::Pruned(err) => Some(err),
             _ => None,
         }
     }
@@ -718,7 +894,9 @@ impl From<TimestampConversionError> for GetTransactionDetailError {
 pub enum GetTransactionSummariesError {
     Unimplemented(Unimplemented),
     InvalidLimit(InvalidLimit),
+    InvalidCursor(BadQuery),
     TargetNotFound(NotFound),
+    Pruned(Pruned),
     QueryError(QueryError),
 }
 
@@ -727,8 +905,10 @@ impl GetTransactionSummariesError {
         match self {
             GetTransactionSummariesError::Unimplemented(err) => err.status(),
             GetTransactionSummariesError::InvalidLimit(err) => err.status(),
+            GetTransactionSummariesError::InvalidCursor(err) => err.status(),
             GetTransactionSummariesError::QueryError(err) => err.status(),
             GetTransactionSummariesError::TargetNotFound(err) => err.status(),
+            GetTransactionSummariesError::Pruned(err) => err.status(),
         }
     }
 }
@@ -738,8 +918,10 @@ impl Display for GetTransactionSummariesError {
         match self {
             GetTransactionSummariesError::Unimplemented(err) => write!(f, "{err}"),
             GetTransactionSummariesError::InvalidLimit(err) => write!(f, "{err}"),
+            GetTransactionSummariesError::InvalidCursor(err) => write!(f, "{err}"),
             GetTransactionSummariesError::QueryError(err) => write!(f, "{err}"),
             GetTransactionSummariesError::TargetNotFound(err) => write!(f, "{err}"),
+            GetTransactionSummariesError::Pruned(err) => write!(f, "{err}"),
         }
     }
 }
@@ -749,8 +931,10 @@ impl ExplorerAPIError for GetTransactionSummariesError {
         match self {
             GetTransactionSummariesError::Unimplemented(err) => err.code(),
             GetTransactionSummariesError::InvalidLimit(err) => err.code(),
+            GetTransactionSummariesError::InvalidCursor(err) => err.code(),
             GetTransactionSummariesError::QueryError(err) => err.code(),
             GetTransactionSummariesError::TargetNotFound(err) => err.code(),
+            GetTransactionSummariesError::Pruned(err) => err.code(),
         }
     }
 }
@@ -760,7 +944,9 @@ impl std::error::Error for GetTransactionSummariesError {
         match self {
             GetTransactionSummariesError::Unimplemented(err) => Some(err),
             GetTransactionSummariesError::InvalidLimit(err) => Some(err),
+            GetTransactionSummariesError::InvalidCursor(err) => Some(err),
             GetTransactionSummariesError::QueryError(err) => Some(err),
+            GetTransactionSummariesError::Pruned(err) => Some(err),
             _ => None,
         }
     }
@@ -913,3 +1099,249 @@ impl From<crate::QueryError> for GetSearchResultsError {
         GetSearchResultsError::QueryError(QueryError { error: value })
     }
 }
+
+/// [GetNamespaceSummariesError] represents an error that has occurred in
+/// response to the `get_namespace_summaries` request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GetNamespaceSummariesError {
+    Unimplemented(Unimplemented),
+    InvalidCursor(BadQuery),
+    QueryError(QueryError),
+}
+
+impl GetNamespaceSummariesError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            GetNamespaceSummariesError::Unimplemented(err) => err.status(),
+            GetNamespaceSummariesError::InvalidCursor(err) => err.status(),
+            GetNamespaceSummariesError::QueryError(err) => err.status(),
+        }
+    }
+}
+
+impl Display for GetNamespaceSummariesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GetNamespaceSummariesError::Unimplemented(err) => write!(f, "{err}"),
+            GetNamespaceSummariesError::InvalidCursor(err) => write!(f, "{err}"),
+            GetNamespaceSummariesError::QueryError(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl ExplorerAPIError for GetNamespaceSummariesError {
+    fn code(&self) -> &str {
+        match self {
+            GetNamespaceSummariesError::Unimplemented(err) => err.code(),
+            GetNamespaceSummariesError::InvalidCursor(err) => err.code(),
+            GetNamespaceSummariesError::QueryError(err) => err.code(),
+        }
+    }
+}
+
+impl std::error::Error for GetNamespaceSummariesError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GetNamespaceSummariesError::Unimplemented(err) => Some(err),
+            GetNamespaceSummariesError::InvalidCursor(err) => Some(err),
+            GetNamespaceSummariesError::QueryError(err) => Some(err),
+        }
+    }
+}
+
+impl From<crate::QueryError> for GetNamespaceSummariesError {
+    fn from(value: crate::QueryError) -> Self {
+        GetNamespaceSummariesError::QueryError(QueryError { error: value })
+    }
+}
+
+/// [GetNamespaceDetailError] represents an error that has occurred in response
+/// to the [GetNamespaceDetailRequest] request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GetNamespaceDetailError {
+    Unimplemented(Unimplemented),
+    InvalidLimit(InvalidLimit),
+    InvalidQuery(BadQuery),
+    NamespaceNotFound(NotFound),
+    QueryError(QueryError),
+}
+
+impl GetNamespaceDetailError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            GetNamespaceDetailError::Unimplemented(err) => err.status(),
+            GetNamespaceDetailError::InvalidLimit(err) => err.status(),
+            GetNamespaceDetailError::InvalidQuery(err) => err.status(),
+            GetNamespaceDetailError::NamespaceNotFound(err) => err.status(),
+            GetNamespaceDetailError::QueryError(err) => err.status(),
+        }
+    }
+}
+
+impl Display for GetNamespaceDetailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GetNamespaceDetailError::Unimplemented(err) => write!(f, "{err}"),
+            GetNamespaceDetailError::InvalidLimit(err) => write!(f, "{err}"),
+            GetNamespaceDetailError::InvalidQuery(err) => write!(f, "{err}"),
+            GetNamespaceDetailError::NamespaceNotFound(err) => write!(f, "{err}"),
+            GetNamespaceDetailError::QueryError(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl ExplorerAPIError for GetNamespaceDetailError {
+    fn code(&self) -> &str {
+        match self {
+            GetNamespaceDetailError::Unimplemented(err) => err.code(),
+            GetNamespaceDetailError::InvalidLimit(err) => err.code(),
+            GetNamespaceDetailError::InvalidQuery(err) => err.code(),
+            GetNamespaceDetailError::NamespaceNotFound(err) => err.code(),
+            GetNamespaceDetailError::QueryError(err) => err.code(),
+        }
+    }
+}
+
+impl std::error::Error for GetNamespaceDetailError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GetNamespaceDetailError::Unimplemented(err) => Some(err),
+            GetNamespaceDetailError::InvalidLimit(err) => Some(err),
+            GetNamespaceDetailError::InvalidQuery(err) => Some(err),
+            GetNamespaceDetailError::QueryError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<crate::QueryError> for GetNamespaceDetailError {
+    fn from(value: crate::QueryError) -> Self {
+        GetNamespaceDetailError::QueryError(QueryError { error: value })
+    }
+}
+
+/// [GetAccountDetailError] represents an error that has occurred in response
+/// to a request for the details of an account.
+///
+/// Accounts are application specific, so there is no account endpoint in the
+/// base explorer API; this error is provided for applications which add one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GetAccountDetailError {
+    Unimplemented(Unimplemented),
+    InvalidAddress(BadQuery),
+    QueryError(QueryError),
+}
+
+impl GetAccountDetailError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            GetAccountDetailError::Unimplemented(err) => err.status(),
+            GetAccountDetailError::InvalidAddress(err) => err.status(),
+            GetAccountDetailError::QueryError(err) => err.status(),
+        }
+    }
+}
+
+impl Display for GetAccountDetailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GetAccountDetailError::Unimplemented(err) => write!(f, "{err}"),
+            GetAccountDetailError::InvalidAddress(err) => write!(f, "{err}"),
+            GetAccountDetailError::QueryError(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl ExplorerAPIError for GetAccountDetailError {
+    fn code(&self) -> &str {
+        match self {
+            GetAccountDetailError::Unimplemented(err) => err.code(),
+            GetAccountDetailError::InvalidAddress(err) => err.code(),
+            GetAccountDetailError::QueryError(err) => err.code(),
+        }
+    }
+}
+
+impl std::error::Error for GetAccountDetailError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GetAccountDetailError::Unimplemented(err) => Some(err),
+            GetAccountDetailError::InvalidAddress(err) => Some(err),
+            GetAccountDetailError::QueryError(err) => Some(err),
+        }
+    }
+}
+
+impl From<crate::QueryError> for GetAccountDetailError {
+    fn from(value: crate::QueryError) -> Self {
+        GetAccountDetailError::QueryError(QueryError { error: value })
+    }
+}
+
+/// [GetValidatorError] represents an error that has occurred in response to a
+/// request for the validator directory or the details of a single validator.
+///
+/// Like accounts, validators are application specific, so there are no
+/// validator endpoints in the base explorer API; this error is provided for
+/// applications which add them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum GetValidatorError {
+    Unimplemented(Unimplemented),
+    InvalidQuery(BadQuery),
+    NotFound(NotFound),
+    QueryError(QueryError),
+}
+
+impl GetValidatorError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            GetValidatorError::Unimplemented(err) => err.status(),
+            GetValidatorError::InvalidQuery(err) => err.status(),
+            GetValidatorError::NotFound(err) => err.status(),
+            GetValidatorError::QueryError(err) => err.status(),
+        }
+    }
+}
+
+impl Display for GetValidatorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GetValidatorError::Unimplemented(err) => write!(f, "{err}"),
+            GetValidatorError::InvalidQuery(err) => write!(f, "{err}"),
+            GetValidatorError::NotFound(err) => write!(f, "{err}"),
+            GetValidatorError::QueryError(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl ExplorerAPIError for GetValidatorError {
+    fn code(&self) -> &str {
+        match self {
+            GetValidatorError::Unimplemented(err) => err.code(),
+            GetValidatorError::InvalidQuery(err) => err.code(),
+            GetValidatorError::NotFound(err) => err.code(),
+            GetValidatorError::QueryError(err) => err.code(),
+        }
+    }
+}
+
+impl std::error::Error for GetValidatorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GetValidatorError::Unimplemented(err) => Some(err),
+            GetValidatorError::InvalidQuery(err) => Some(err),
+            GetValidatorError::NotFound(err) => Some(err),
+            GetValidatorError::QueryError(err) => Some(err),
+        }
+    }
+}
+
+impl From<crate::QueryError> for GetValidatorError {
+    fn from(value: crate::QueryError) -> Self {
+        GetValidatorError::QueryError(QueryError { error: value })
+    }
+}
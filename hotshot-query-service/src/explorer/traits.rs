@@ -58,6 +58,13 @@ where
 
     /// A collection of namespace ids that are contained within the block header.
     fn namespace_ids(&self) -> Vec<NamespaceId<Types>>;
+
+    /// Recognize a search query as the address of a Wallet, if it is one.
+    ///
+    /// The default implementation does not recognize any addresses.
+    fn parse_wallet_address(_query: &str) -> Option<Self::WalletAddress> {
+        None
+    }
 }
 
 /// ExplorerTransaction is a trait that allows the Explorer API to be able to
@@ -38,8 +38,19 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use hotshot_types::traits::node_implementation::NodeType;
 
-use super::Request;
+use super::{
+    request::{BlockRangeRequest, LeafRangeRequest, LeafRequest, VidCommonRangeRequest},
+    Request,
+};
+use crate::{
+    availability::{
+        BlockQueryData, LeafQueryData, QueryableHeader, QueryablePayload, VidCommonQueryData,
+    },
+    data_source::AvailabilityProvider,
+    Header, Payload,
+};
 
 mod any;
 mod query_service;
@@ -73,6 +84,8 @@ impl<Types, T: Send + Request<Types> + 'static> Provider<Types, T> for NoFetchin
     }
 }
 
+impl<Types: NodeType> AvailabilityProvider<Types> for NoFetching {}
+
 #[async_trait]
 impl<Types, T, P> Provider<Types, T> for Arc<P>
 where
@@ -83,3 +96,38 @@ where
         (**self).fetch(req).await
     }
 }
+
+#[async_trait]
+impl<Types, P> AvailabilityProvider<Types> for Arc<P>
+where
+    Types: NodeType,
+    P: AvailabilityProvider<Types>,
+{
+    async fn fetch_leaf_range(
+        &self,
+        req: LeafRangeRequest,
+        last: Option<LeafRequest<Types>>,
+    ) -> Option<Vec<LeafQueryData<Types>>> {
+        (**self).fetch_leaf_range(req, last).await
+    }
+
+    async fn fetch_block_range(
+        &self,
+        req: BlockRangeRequest,
+        headers: &[Header<Types>],
+    ) -> Option<Vec<BlockQueryData<Types>>>
+    where
+        Header<Types>: QueryableHeader<Types>,
+        Payload<Types>: QueryablePayload<Types>,
+    {
+        (**self).fetch_block_range(req, headers).await
+    }
+
+    async fn fetch_vid_common_range(
+        &self,
+        req: VidCommonRangeRequest,
+        headers: &[Header<Types>],
+    ) -> Option<Vec<VidCommonQueryData<Types>>> {
+        (**self).fetch_vid_common_range(req, headers).await
+    }
+}
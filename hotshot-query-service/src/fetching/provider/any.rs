@@ -10,18 +10,39 @@
 // You should have received a copy of the GNU General Public License along with this program. If not,
 // see <https://www.gnu.org/licenses/>.
 
-use std::{fmt::Debug, sync::Arc};
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use async_trait::async_trait;
 use derivative::Derivative;
-use hotshot_types::traits::node_implementation::NodeType;
+use futures::{
+    future::{BoxFuture, Future},
+    stream::{FuturesUnordered, StreamExt},
+};
+use hotshot_types::traits::{
+    metrics::{Counter, Gauge, Metrics, MetricsFamily},
+    node_implementation::NodeType,
+};
+use parking_lot::Mutex;
+use tokio::time::timeout;
 
 use super::{Provider, Request};
 use crate::{
-    availability::LeafQueryData,
+    availability::{
+        BlockQueryData, LeafQueryData, QueryableHeader, QueryablePayload, VidCommonQueryData,
+    },
     data_source::AvailabilityProvider,
-    fetching::request::{LeafRequest, PayloadRequest, VidCommonRequest},
-    Payload, VidCommon,
+    fetching::request::{
+        BlockRangeRequest, LeafRangeRequest, LeafRequest, PayloadRequest, VidCommonRangeRequest,
+        VidCommonRequest,
+    },
+    Header, Payload, VidCommon,
 };
 
 /// Blanket trait combining [`Debug`] and [`Provider`].
@@ -44,9 +65,28 @@ where
 {
 }
 
-type PayloadProvider<Types> = Arc<dyn DebugProvider<Types, PayloadRequest>>;
-type LeafProvider<Types> = Arc<dyn DebugProvider<Types, LeafRequest<Types>>>;
-type VidCommonProvider<Types> = Arc<dyn DebugProvider<Types, VidCommonRequest>>;
+type PayloadProvider<Types> = Scored<dyn DebugProvider<Types, PayloadRequest>>;
+type LeafProvider<Types> = Scored<dyn DebugProvider<Types, LeafRequest<Types>>>;
+type VidCommonProvider<Types> = Scored<dyn DebugProvider<Types, VidCommonRequest>>;
+type RangeProvider<Types> = Scored<dyn DebugAvailabilityProvider<Types>>;
+
+/// Blanket trait combining [`Debug`] and [`AvailabilityProvider`], for the same reason as
+/// [`DebugProvider`].
+trait DebugAvailabilityProvider<Types: NodeType>: AvailabilityProvider<Types> + Debug {}
+
+impl<Types, P> DebugAvailabilityProvider<Types> for P
+where
+    Types: NodeType,
+    P: AvailabilityProvider<Types> + Debug,
+{
+}
+
+/// Weight of the latest sample in the moving averages of provider latency and success rate.
+const SMOOTHING: f64 = 0.2;
+
+/// Lower bound on the success rate used to rank providers, so that a provider which has failed many
+/// times in a row can still be ranked by its latency.
+const MIN_SUCCESS_RATE: f64 = 0.01;
 
 /// Adaptor combining multiple data availability providers.
 ///
@@ -62,6 +102,22 @@ type VidCommonProvider<Types> = Arc<dyn DebugProvider<Types, VidCommonRequest>>;
 /// provides blocks and one which only provides leaves into a provider which provides both, and thus
 /// can be used as a provider for the availability API module.
 ///
+/// # Routing
+///
+/// [`AnyProvider`] keeps track of how each underlying provider performs: how often it has the
+/// requested object, how long it takes to respond, and how many of its responses were invalid for
+/// the request. Requests go first to the provider with the lowest expected time to a successful
+/// response. If that provider takes longer than the [hedge delay](Self::with_hedge_delay) to
+/// respond, the request is also sent to the next best provider, and whichever valid response
+/// arrives first is used. A provider which returns an invalid response is quarantined for a
+/// [while](Self::with_quarantine_period), during which it is only used as a last resort. These
+/// statistics can be exported using [`register_metrics`](Self::register_metrics).
+///
+/// Requests for ranges of objects are spread over the underlying providers: each range request
+/// starts with a different provider, in round-robin order, so that many range requests made at
+/// once are served by several providers in parallel. Range requests are never hedged, since they
+/// are expensive to serve, but quarantined providers are still tried last.
+///
 /// # Examples
 ///
 /// Fetching from multiple query services, for resiliency.
@@ -95,6 +151,13 @@ where
     payload_providers: Vec<PayloadProvider<Types>>,
     leaf_providers: Vec<LeafProvider<Types>>,
     vid_common_providers: Vec<VidCommonProvider<Types>>,
+    range_providers: Vec<RangeProvider<Types>>,
+    // The statistics of each underlying provider, in the order they were added. A provider which
+    // serves several kinds of request shares one set of statistics between all of them.
+    stats: Vec<Arc<ProviderStats>>,
+    cfg: RoutingCfg,
+    // The index of the provider to try first for the next range request.
+    next_range_provider: Arc<AtomicUsize>,
 }
 
 #[async_trait]
@@ -103,7 +166,7 @@ where
     Types: NodeType,
 {
     async fn fetch(&self, req: PayloadRequest) -> Option<Payload<Types>> {
-        any_fetch(&self.payload_providers, req).await
+        any_fetch(&self.payload_providers, req, &self.cfg).await
     }
 }
 
@@ -113,7 +176,7 @@ where
     Types: NodeType,
 {
     async fn fetch(&self, req: LeafRequest<Types>) -> Option<LeafQueryData<Types>> {
-        any_fetch(&self.leaf_providers, req).await
+        any_fetch(&self.leaf_providers, req, &self.cfg).await
     }
 }
 
@@ -123,7 +186,62 @@ where
     Types: NodeType,
 {
     async fn fetch(&self, req: VidCommonRequest) -> Option<VidCommon> {
-        any_fetch(&self.vid_common_providers, req).await
+        any_fetch(&self.vid_common_providers, req, &self.cfg).await
+    }
+}
+
+#[async_trait]
+impl<Types> AvailabilityProvider<Types> for AnyProvider<Types>
+where
+    Types: NodeType,
+{
+    async fn fetch_leaf_range(
+        &self,
+        req: LeafRangeRequest,
+        last: Option<LeafRequest<Types>>,
+    ) -> Option<Vec<LeafQueryData<Types>>> {
+        any_fetch_range(
+            &self.range_providers,
+            req,
+            &self.cfg,
+            &self.next_range_provider,
+            |p| p.fetch_leaf_range(req, last),
+        )
+        .await
+    }
+
+    async fn fetch_block_range(
+        &self,
+        req: BlockRangeRequest,
+        headers: &[Header<Types>],
+    ) -> Option<Vec<BlockQueryData<Types>>>
+    where
+        Header<Types>: QueryableHeader<Types>,
+        Payload<Types>: QueryablePayload<Types>,
+    {
+        any_fetch_range(
+            &self.range_providers,
+            req,
+            &self.cfg,
+            &self.next_range_provider,
+            |p| p.fetch_block_range(req, headers),
+        )
+        .await
+    }
+
+    async fn fetch_vid_common_range(
+        &self,
+        req: VidCommonRangeRequest,
+        headers: &[Header<Types>],
+    ) -> Option<Vec<VidCommonQueryData<Types>>> {
+        any_fetch_range(
+            &self.range_providers,
+            req,
+            &self.cfg,
+            &self.next_range_provider,
+            |p| p.fetch_vid_common_range(req, headers),
+        )
+        .await
     }
 }
 
@@ -137,9 +255,14 @@ where
         P: AvailabilityProvider<Types> + Debug + 'static,
     {
         let provider = Arc::new(provider);
-        self.payload_providers.push(provider.clone());
-        self.leaf_providers.push(provider.clone());
-        self.vid_common_providers.push(provider);
+        let stats = self.new_stats();
+        self.payload_providers
+            .push(Scored::new(provider.clone(), stats.clone()));
+        self.leaf_providers
+            .push(Scored::new(provider.clone(), stats.clone()));
+        self.vid_common_providers
+            .push(Scored::new(provider.clone(), stats.clone()));
+        self.range_providers.push(Scored::new(provider, stats));
         self
     }
 
@@ -148,7 +271,9 @@ where
     where
         P: Provider<Types, PayloadRequest> + Debug + 'static,
     {
-        self.payload_providers.push(Arc::new(provider));
+        let stats = self.new_stats();
+        self.payload_providers
+            .push(Scored::new(Arc::new(provider), stats));
         self
     }
 
@@ -157,7 +282,9 @@ where
     where
         P: Provider<Types, LeafRequest<Types>> + Debug + 'static,
     {
-        self.leaf_providers.push(Arc::new(provider));
+        let stats = self.new_stats();
+        self.leaf_providers
+            .push(Scored::new(Arc::new(provider), stats));
         self
     }
 
@@ -166,33 +293,423 @@ where
     where
         P: Provider<Types, VidCommonRequest> + Debug + 'static,
     {
-        self.vid_common_providers.push(Arc::new(provider));
+        let stats = self.new_stats();
+        self.vid_common_providers
+            .push(Scored::new(Arc::new(provider), stats));
+        self
+    }
+
+    /// Set how long to wait for a provider before also trying the next one.
+    ///
+    /// The default is 2 seconds.
+    pub fn with_hedge_delay(mut self, delay: Duration) -> Self {
+        self.cfg.hedge_delay = Some(delay);
+        self
+    }
+
+    /// Only send each request to one provider at a time.
+    ///
+    /// The next provider is only tried once the previous one has failed.
+    pub fn without_hedging(mut self) -> Self {
+        self.cfg.hedge_delay = None;
+        self
+    }
+
+    /// Set how long to avoid a provider after it returns an invalid response.
+    ///
+    /// The default is 10 minutes.
+    pub fn with_quarantine_period(mut self, period: Duration) -> Self {
+        self.cfg.quarantine_period = period;
         self
     }
+
+    /// Report the statistics of each provider in `metrics`.
+    ///
+    /// Providers are labeled with their index, in the order they were added to this
+    /// [`AnyProvider`]. Since the statistics are shared between clones of an [`AnyProvider`], this
+    /// can be called on a clone of a provider which is already in use by a data source.
+    pub fn register_metrics(&self, metrics: &dyn Metrics) {
+        for (stats, metrics) in self
+            .stats
+            .iter()
+            .zip(ProviderMetrics::new(metrics, self.stats.len()))
+        {
+            let mut state = stats.state.lock();
+            // Count everything which happened before the metrics were registered.
+            metrics.successes.add(state.successes);
+            metrics.failures.add(state.failures);
+            metrics.invalid.add(state.invalid);
+            state.metrics = Some(metrics);
+            state.update_metrics();
+        }
+    }
+
+    fn new_stats(&mut self) -> Arc<ProviderStats> {
+        let stats = Arc::<ProviderStats>::default();
+        self.stats.push(stats.clone());
+        stats
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct RoutingCfg {
+    hedge_delay: Option<Duration>,
+    quarantine_period: Duration,
+}
+
+impl Default for RoutingCfg {
+    fn default() -> Self {
+        Self {
+            hedge_delay: Some(Duration::from_secs(2)),
+            quarantine_period: Duration::from_secs(600),
+        }
+    }
+}
+
+/// A provider together with the record of how it has performed.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = "P: Debug"))]
+struct Scored<P: ?Sized> {
+    provider: Arc<P>,
+    stats: Arc<ProviderStats>,
+}
+
+impl<P: ?Sized> Scored<P> {
+    fn new(provider: Arc<P>, stats: Arc<ProviderStats>) -> Self {
+        Self { provider, stats }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ProviderStats {
+    state: Mutex<ProviderState>,
+}
+
+impl ProviderStats {
+    fn record(&self, outcome: Outcome, latency: Duration, cfg: &RoutingCfg) {
+        let mut state = self.state.lock();
+        state.latency = Some(match state.latency {
+            Some(avg) => avg.mul_f64(1. - SMOOTHING) + latency.mul_f64(SMOOTHING),
+            None => latency,
+        });
+        match outcome {
+            Outcome::Success => {
+                state.successes += 1;
+                state.update_success_rate(1.);
+                if let Some(metrics) = &state.metrics {
+                    metrics.successes.add(1);
+                }
+            },
+            Outcome::Failure => {
+                state.failures += 1;
+                state.update_success_rate(0.);
+                if let Some(metrics) = &state.metrics {
+                    metrics.failures.add(1);
+                }
+            },
+            Outcome::Invalid => {
+                state.invalid += 1;
+                state.update_success_rate(0.);
+                state.quarantined_until = Some(Instant::now() + cfg.quarantine_period);
+                if let Some(metrics) = &state.metrics {
+                    metrics.invalid.add(1);
+                }
+            },
+            // An abandoned request tells us the provider is at least this slow, but not whether it
+            // would have succeeded.
+            Outcome::Abandoned => {},
+        }
+        state.update_metrics();
+    }
+
+    /// Whether the provider is quarantined, and its expected time to a successful response.
+    fn score(&self, now: Instant) -> (bool, f64) {
+        let mut state = self.state.lock();
+        if state.quarantined_until.is_some_and(|until| until <= now) {
+            state.quarantined_until = None;
+            state.update_metrics();
+        }
+        // A provider we have not heard from yet is assumed to be fast, so that it gets tried.
+        let latency = state.latency.unwrap_or_default().as_secs_f64();
+        (
+            state.quarantined_until.is_some(),
+            latency / state.success_rate.max(MIN_SUCCESS_RATE),
+        )
+    }
+
+    fn is_quarantined(&self, now: Instant) -> bool {
+        self.score(now).0
+    }
+}
+
+#[derive(Debug)]
+struct ProviderState {
+    /// Moving average of the fraction of requests which succeeded.
+    success_rate: f64,
+    /// Moving average of the time taken to respond to a request.
+    latency: Option<Duration>,
+    successes: usize,
+    failures: usize,
+    invalid: usize,
+    quarantined_until: Option<Instant>,
+    metrics: Option<ProviderMetrics>,
+}
+
+impl Default for ProviderState {
+    fn default() -> Self {
+        Self {
+            success_rate: 1.,
+            latency: None,
+            successes: 0,
+            failures: 0,
+            invalid: 0,
+            quarantined_until: None,
+            metrics: None,
+        }
+    }
 }
 
-async fn any_fetch<Types, P, T>(providers: &[Arc<P>], req: T) -> Option<T::Response>
+impl ProviderState {
+    fn update_success_rate(&mut self, sample: f64) {
+        self.success_rate = self.success_rate * (1. - SMOOTHING) + sample * SMOOTHING;
+    }
+
+    fn update_metrics(&self) {
+        let Some(metrics) = &self.metrics else {
+            return;
+        };
+        metrics
+            .latency
+            .set(self.latency.unwrap_or_default().as_millis() as usize);
+        metrics
+            .success_rate
+            .set((self.success_rate * 100.).round() as usize);
+        metrics
+            .quarantined
+            .set(self.quarantined_until.is_some() as usize);
+    }
+}
+
+#[derive(Debug)]
+struct ProviderMetrics {
+    /// Number of valid responses.
+    successes: Box<dyn Counter>,
+    /// Number of requests which the provider could not answer.
+    failures: Box<dyn Counter>,
+    /// Number of responses which were invalid for their request.
+    invalid: Box<dyn Counter>,
+    /// Average response time (ms).
+    latency: Box<dyn Gauge>,
+    /// Recent success rate (%).
+    success_rate: Box<dyn Gauge>,
+    /// Whether the provider is quarantined (1) or not (0).
+    quarantined: Box<dyn Gauge>,
+}
+
+impl ProviderMetrics {
+    fn new(metrics: &dyn Metrics, count: usize) -> Vec<Self> {
+        let group = metrics.subgroup("provider".into());
+        let labels = vec!["provider".to_string()];
+        let successes = group.counter_family("successes".into(), labels.clone());
+        let failures = group.counter_family("failures".into(), labels.clone());
+        let invalid = group.counter_family("invalid".into(), labels.clone());
+        let latency = group.gauge_family("latency_ms".into(), labels.clone());
+        let success_rate = group.gauge_family("success_rate_percent".into(), labels.clone());
+        let quarantined = group.gauge_family("quarantined".into(), labels);
+        (0..count)
+            .map(|i| {
+                let label = vec![i.to_string()];
+                Self {
+                    successes: successes.create(label.clone()),
+                    failures: failures.create(label.clone()),
+                    invalid: invalid.create(label.clone()),
+                    latency: latency.create(label.clone()),
+                    success_rate: success_rate.create(label.clone()),
+                    quarantined: quarantined.create(label),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Outcome {
+    Success,
+    Failure,
+    Invalid,
+    Abandoned,
+}
+
+/// A request to a single provider.
+///
+/// The outcome is recorded in the provider's statistics when this is dropped, which also covers
+/// requests that are abandoned before they complete, e.g. because another provider answered first.
+struct Attempt<'a> {
+    stats: &'a ProviderStats,
+    cfg: &'a RoutingCfg,
+    start: Instant,
+    outcome: Outcome,
+}
+
+impl Drop for Attempt<'_> {
+    fn drop(&mut self) {
+        self.stats
+            .record(self.outcome, self.start.elapsed(), self.cfg);
+    }
+}
+
+/// Order providers from best to worst.
+fn rank<P: ?Sized>(providers: &[Scored<P>]) -> Vec<usize> {
+    let now = Instant::now();
+    let scores = providers
+        .iter()
+        .map(|p| p.stats.score(now))
+        .collect::<Vec<_>>();
+    let mut order = (0..providers.len()).collect::<Vec<_>>();
+    // The sort is stable, so providers with equal scores are tried in the order they were added.
+    order.sort_by(|&i, &j| {
+        let (quarantined_i, cost_i) = scores[i];
+        let (quarantined_j, cost_j) = scores[j];
+        quarantined_i
+            .cmp(&quarantined_j)
+            .then(cost_i.total_cmp(&cost_j))
+    });
+    order
+}
+
+async fn fetch_one<Types, P, T>(
+    providers: &[Scored<P>],
+    i: usize,
+    req: T,
+    cfg: &RoutingCfg,
+) -> Option<T::Response>
 where
     Types: NodeType,
     P: Provider<Types, T> + Debug + ?Sized,
     T: Request<Types>,
 {
-    // There's a policy question of how to decide when to try each fetcher: all in parallel, in
-    // serial, or a combination. For now, we do the simplest thing of trying each in order, in
-    // serial. This has the best performance in the common case when we succeed on the first
-    // fetcher: low latency, and no undue burden on the other providers. However, a more complicated
-    // strategy where we slowly ramp up the parallelism as more and more requests fail may provide
-    // better worst-case latency.
-    for (i, p) in providers.iter().enumerate() {
-        match p.fetch(req).await {
-            Some(obj) => return Some(obj),
-            None => {
-                tracing::warn!(
-                    "failed to fetch {req:?} from provider {i}/{}: {p:?}",
-                    providers.len()
-                );
-                continue;
+    record_fetch(providers, i, req, cfg, providers[i].provider.fetch(req)).await
+}
+
+/// Wait for a `fetch` of `req` from provider `i`, and record the outcome.
+async fn record_fetch<Types, P, T>(
+    providers: &[Scored<P>],
+    i: usize,
+    req: T,
+    cfg: &RoutingCfg,
+    fetch: impl Future<Output = Option<T::Response>>,
+) -> Option<T::Response>
+where
+    Types: NodeType,
+    P: Debug + ?Sized,
+    T: Request<Types>,
+{
+    let p = &providers[i];
+    let mut attempt = Attempt {
+        stats: &p.stats,
+        cfg,
+        start: Instant::now(),
+        outcome: Outcome::Abandoned,
+    };
+    match fetch.await {
+        Some(obj) if req.is_valid_response(&obj) => {
+            attempt.outcome = Outcome::Success;
+            Some(obj)
+        },
+        Some(_) => {
+            tracing::error!(
+                "provider {i}/{} returned an invalid response to {req:?}, quarantining it: {:?}",
+                providers.len(),
+                p.provider
+            );
+            attempt.outcome = Outcome::Invalid;
+            None
+        },
+        None => {
+            tracing::warn!(
+                "failed to fetch {req:?} from provider {i}/{}: {:?}",
+                providers.len(),
+                p.provider
+            );
+            attempt.outcome = Outcome::Failure;
+            None
+        },
+    }
+}
+
+async fn any_fetch<Types, P, T>(
+    providers: &[Scored<P>],
+    req: T,
+    cfg: &RoutingCfg,
+) -> Option<T::Response>
+where
+    Types: NodeType,
+    P: Provider<Types, T> + Debug + ?Sized,
+    T: Request<Types>,
+{
+    // Try the providers in order, best first. Usually the first provider succeeds, which gives low
+    // latency without placing any undue burden on the other providers. If a provider is slow to
+    // respond, we hedge by also trying the next one, and take whichever valid response arrives
+    // first.
+    let mut order = rank(providers).into_iter();
+    let mut pending = FuturesUnordered::new();
+    loop {
+        // If nothing is in flight, because all the providers we have tried so far have failed, move
+        // on to the next one, giving up if there are none left.
+        if pending.is_empty() {
+            let i = order.next()?;
+            pending.push(fetch_one(providers, i, req, cfg));
+        }
+
+        let res = match cfg.hedge_delay.filter(|_| !order.as_slice().is_empty()) {
+            Some(delay) => match timeout(delay, pending.next()).await {
+                Ok(res) => res,
+                Err(_) => {
+                    if let Some(i) = order.next() {
+                        tracing::info!(
+                            "request for {req:?} is slow, also trying provider {i}/{}",
+                            providers.len()
+                        );
+                        pending.push(fetch_one(providers, i, req, cfg));
+                    }
+                    continue;
+                },
             },
+            None => pending.next().await,
+        };
+        if let Some(Some(obj)) = res {
+            return Some(obj);
+        }
+    }
+}
+
+async fn any_fetch_range<'a, Types, T>(
+    providers: &'a [RangeProvider<Types>],
+    req: T,
+    cfg: &RoutingCfg,
+    next: &AtomicUsize,
+    fetch: impl Fn(&'a dyn DebugAvailabilityProvider<Types>) -> BoxFuture<'a, Option<T::Response>>,
+) -> Option<T::Response>
+where
+    Types: NodeType,
+    T: Request<Types>,
+{
+    if providers.is_empty() {
+        return None;
+    }
+
+    // Range requests are typically made in large batches, by a caller who wants to spread the load
+    // over all available providers. Start each request at a different provider, falling back to
+    // the others in order, and leaving quarantined providers until last.
+    let first = next.fetch_add(1, Ordering::Relaxed) % providers.len();
+    let now = Instant::now();
+    let (healthy, quarantined): (Vec<_>, Vec<_>) = (first..providers.len())
+        .chain(0..first)
+        .partition(|&i| !providers[i].stats.is_quarantined(now));
+    for i in healthy.into_iter().chain(quarantined) {
+        let res = record_fetch(providers, i, req, cfg, fetch(&*providers[i].provider)).await;
+        if let Some(obj) = res {
+            return Some(obj);
         }
     }
 
@@ -205,6 +722,7 @@ mod test {
     use futures::stream::StreamExt;
     use portpicker::pick_unused_port;
     use tide_disco::App;
+    use tokio::time::sleep;
     use vbs::version::StaticVersionType;
 
     use super::*;
@@ -293,4 +811,87 @@ mod test {
         assert_eq!(payload.block_hash(), test_payload.block_hash());
         assert_eq!(payload.hash(), test_payload.payload_hash());
     }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    struct TestRequest(u64);
+
+    impl Request<MockTypes> for TestRequest {
+        type Response = u64;
+
+        fn is_valid_response(&self, response: &u64) -> bool {
+            *response == self.0
+        }
+    }
+
+    /// A provider which answers every request after a fixed delay, correctly or not.
+    #[derive(Debug)]
+    struct FakeProvider {
+        honest: bool,
+        delay: Duration,
+        requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl super::Provider<MockTypes, TestRequest> for FakeProvider {
+        async fn fetch(&self, req: TestRequest) -> Option<u64> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            sleep(self.delay).await;
+            Some(if self.honest { req.0 } else { req.0 + 1 })
+        }
+    }
+
+    fn fake_provider(honest: bool, delay: Duration) -> Scored<FakeProvider> {
+        Scored::new(
+            Arc::new(FakeProvider {
+                honest,
+                delay,
+                requests: Default::default(),
+            }),
+            Default::default(),
+        )
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quarantine_invalid_provider() {
+        setup_test();
+
+        let providers = [
+            fake_provider(false, Duration::ZERO),
+            fake_provider(true, Duration::ZERO),
+        ];
+        let cfg = RoutingCfg::default();
+
+        // The first provider is tried first, but its response is rejected.
+        assert_eq!(any_fetch(&providers, TestRequest(1), &cfg).await, Some(1));
+        assert_eq!(providers[0].stats.state.lock().invalid, 1);
+        assert!(providers[0].stats.is_quarantined(Instant::now()));
+
+        // While it is quarantined, the honest provider is tried first.
+        assert_eq!(rank(&providers), [1, 0]);
+        assert_eq!(any_fetch(&providers, TestRequest(2), &cfg).await, Some(2));
+        assert_eq!(providers[0].provider.requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_hedge_slow_provider() {
+        setup_test();
+
+        let providers = [
+            fake_provider(true, Duration::from_secs(60)),
+            fake_provider(true, Duration::ZERO),
+        ];
+        let cfg = RoutingCfg {
+            hedge_delay: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+
+        // The slow provider is tried first, but the fast one answers for it.
+        let start = Instant::now();
+        assert_eq!(any_fetch(&providers, TestRequest(1), &cfg).await, Some(1));
+        assert!(start.elapsed() < Duration::from_secs(30));
+        assert_eq!(providers[1].provider.requests.load(Ordering::SeqCst), 1);
+
+        // The abandoned request counts against the slow provider, so now the fast one is preferred.
+        assert_eq!(rank(&providers), [1, 0]);
+    }
 }
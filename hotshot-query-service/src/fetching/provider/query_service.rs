@@ -21,16 +21,22 @@ use hotshot_types::{
     },
 };
 use jf_vid::VidScheme;
+use serde::de::DeserializeOwned;
 use surf_disco::{Client, Url};
 use vbs::{version::StaticVersionType, BinarySerializer};
 
 use super::Provider;
 use crate::{
     availability::{
-        ADVZCommonQueryData, ADVZPayloadQueryData, LeafQueryData, LeafQueryDataLegacy,
-        PayloadQueryData, VidCommonQueryData,
+        ADVZCommonQueryData, ADVZPayloadQueryData, BlockQueryData, LeafQueryData,
+        LeafQueryDataLegacy, PayloadQueryData, QueryableHeader, QueryablePayload,
+        VidCommonQueryData,
+    },
+    data_source::AvailabilityProvider,
+    fetching::request::{
+        BlockRangeRequest, LeafRangeRequest, LeafRequest, PayloadRequest, VidCommonRangeRequest,
+        VidCommonRequest,
     },
-    fetching::request::{LeafRequest, PayloadRequest, VidCommonRequest},
     types::HeightIndexed,
     Error, Header, Payload, VidCommon,
 };
@@ -501,6 +507,94 @@ where
     }
 }
 
+#[async_trait]
+impl<Types, Ver: StaticVersionType + 'static> AvailabilityProvider<Types>
+    for QueryServiceProvider<Ver>
+where
+    Types: NodeType,
+{
+    /// Fetches a range of leaves in a single request.
+    ///
+    /// The leaves are only checked for having the requested heights. The caller is responsible for
+    /// checking that they form a valid chain.
+    async fn fetch_leaf_range(
+        &self,
+        req: LeafRangeRequest,
+        _last: Option<LeafRequest<Types>>,
+    ) -> Option<Vec<LeafQueryData<Types>>> {
+        let mut leaves = self
+            .fetch_range::<LeafQueryData<Types>>("leaf", req.start, req.end)
+            .await?;
+
+        // As when fetching a single leaf, drop any payloads the peer may have included in the
+        // leaves, since we fetch and store payloads separately.
+        for leaf in &mut leaves {
+            leaf.leaf.unfill_block_payload();
+        }
+        Some(leaves)
+    }
+
+    /// Fetches a range of blocks in a single request.
+    ///
+    /// The blocks are only checked for having the requested heights. The caller is responsible for
+    /// checking them against known headers.
+    async fn fetch_block_range(
+        &self,
+        req: BlockRangeRequest,
+        _headers: &[Header<Types>],
+    ) -> Option<Vec<BlockQueryData<Types>>>
+    where
+        Header<Types>: QueryableHeader<Types>,
+        Payload<Types>: QueryablePayload<Types>,
+    {
+        self.fetch_range("block", req.start, req.end).await
+    }
+
+    /// Fetches VID common data for a range of blocks in a single request.
+    ///
+    /// The data is only checked for having the requested heights. The caller is responsible for
+    /// checking it against known headers.
+    async fn fetch_vid_common_range(
+        &self,
+        req: VidCommonRangeRequest,
+        _headers: &[Header<Types>],
+    ) -> Option<Vec<VidCommonQueryData<Types>>> {
+        self.fetch_range("vid/common", req.start, req.end).await
+    }
+}
+
+impl<Ver: StaticVersionType> QueryServiceProvider<Ver> {
+    async fn fetch_range<T>(&self, resource: &str, start: u64, end: u64) -> Option<Vec<T>>
+    where
+        T: HeightIndexed + DeserializeOwned + Send,
+    {
+        let client_url = self.client.base_url();
+        let objs = self
+            .client
+            .get::<Vec<T>>(&format!("availability/{resource}/{start}/{end}"))
+            .send()
+            .await
+            .inspect_err(|err| {
+                tracing::info!(%client_url, %err, start, end, "failed to fetch {resource} range");
+            })
+            .ok()?;
+
+        if objs.len() as u64 != end.saturating_sub(start)
+            || objs.iter().zip(start..).any(|(obj, h)| obj.height() != h)
+        {
+            tracing::error!(
+                %client_url,
+                start,
+                end,
+                len = objs.len(),
+                "received {resource} range with the wrong heights"
+            );
+            return None;
+        }
+        Some(objs)
+    }
+}
+
 // These tests run the `postgres` Docker image, which doesn't work on Windows.
 #[cfg(all(test, not(target_os = "windows")))]
 mod test {
@@ -536,7 +630,7 @@ mod test {
             },
             AvailabilityProvider, FetchingDataSource, Transaction, VersionedDataSource,
         },
-        fetching::provider::{NoFetching, Provider as ProviderTrait, TestProvider},
+        fetching::provider::{AnyProvider, NoFetching, Provider as ProviderTrait, TestProvider},
         node::{data_source::NodeDataSource, SyncStatus},
         task::BackgroundTask,
         testing::{
@@ -1686,6 +1780,123 @@ mod test {
         );
     }
 
+    async fn test_backfill_helper(range_requests: bool) {
+        setup_test();
+
+        // Create the consensus network.
+        let mut network = MockNetwork::<MockDataSource, MockVersions>::init().await;
+
+        // Start a web server that the non-consensus node can use to fetch blocks.
+        let port = pick_unused_port().unwrap();
+        let mut app = App::<_, Error>::with_state(ApiState::from(network.data_source()));
+        app.register_module(
+            "availability",
+            define_api(
+                &Default::default(),
+                MockBase::instance(),
+                "1.0.0".parse().unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        network.spawn(
+            "server",
+            app.serve(format!("0.0.0.0:{port}"), MockBase::instance()),
+        );
+
+        // Start a data source which is not receiving events from consensus, only from a peer. Add
+        // a provider which always fails, so that some range requests have to fall back to the
+        // next provider. If `range_requests` is false, wrap the peer in a provider which does not
+        // implement range requests, so that backfill has to fetch each object individually.
+        let db = TmpDb::init().await;
+        let peer = QueryServiceProvider::new(
+            format!("http://localhost:{port}").parse().unwrap(),
+            MockBase::instance(),
+        );
+        let provider = AnyProvider::<MockTypes>::default().with_provider(NoFetching);
+        let provider = if range_requests {
+            provider.with_provider(peer)
+        } else {
+            provider.with_provider(TestProvider::new(peer))
+        };
+        let data_source = builder(&db, &provider)
+            .await
+            .enable_backfill()
+            .with_backfill_window_size(3)
+            .with_backfill_request_size(2)
+            .with_backfill_interval(Duration::from_secs(1))
+            .build()
+            .await
+            .unwrap();
+
+        // Start consensus.
+        network.start().await;
+
+        // Wait until a few blocks are produced.
+        let leaves = network.data_source().subscribe_leaves(1).await;
+        let leaves = leaves.take(6).collect::<Vec<_>>().await;
+        let last_leaf = leaves.last().unwrap();
+
+        // Give the node the last leaf so it learns the block height. Everything else should be
+        // backfilled.
+        data_source.append(last_leaf.clone().into()).await.unwrap();
+        loop {
+            let sync_status = data_source.sync_status().await.unwrap();
+
+            // VID shares are never fetched from a peer, so ignore them.
+            if (SyncStatus {
+                missing_vid_shares: 0,
+                backfill: None,
+                ..sync_status
+            })
+            .is_fully_synced()
+            {
+                break;
+            }
+            tracing::info!(?sync_status, "waiting for backfill");
+            sleep(Duration::from_secs(1)).await;
+        }
+
+        // Everything is now available locally.
+        for leaf in &leaves {
+            let height = leaf.height() as usize;
+            tracing::info!(height, "checking backfilled data");
+            assert_eq!(
+                data_source
+                    .get_leaf(height)
+                    .await
+                    .try_resolve()
+                    .ok()
+                    .unwrap(),
+                *leaf
+            );
+            let block = data_source
+                .get_block(height)
+                .await
+                .try_resolve()
+                .ok()
+                .unwrap();
+            assert_eq!(block.hash(), leaf.block_hash());
+            let vid = data_source
+                .get_vid_common(height)
+                .await
+                .try_resolve()
+                .ok()
+                .unwrap();
+            assert_eq!(vid.block_hash(), leaf.block_hash());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_backfill() {
+        test_backfill_helper(true).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_backfill_without_range_requests() {
+        test_backfill_helper(false).await;
+    }
+
     #[derive(Clone, Copy, Debug)]
     enum FailureType {
         Begin,
@@ -27,7 +27,7 @@ use hotshot_types::traits::node_implementation::NodeType;
 use tokio::sync::broadcast;
 
 use super::Provider;
-use crate::fetching::Request;
+use crate::{data_source::AvailabilityProvider, fetching::Request};
 
 /// Adaptor to add test-only functionality to an existing [`Provider`].
 ///
@@ -118,3 +118,12 @@ where
         self.inner.fetch(req).await
     }
 }
+
+// Range requests use the default implementations, which go through the single-object requests
+// above, so that blocking and failing requests also applies to range requests.
+impl<Types, P> AvailabilityProvider<Types> for TestProvider<P>
+where
+    Types: NodeType,
+    P: AvailabilityProvider<Types>,
+{
+}
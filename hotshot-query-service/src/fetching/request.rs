@@ -12,13 +12,20 @@
 
 //! Requests for fetching resources.
 
-use std::{fmt::Debug, hash::Hash};
+use std::{fmt::Debug, hash::Hash, ops::Range};
 
+use committable::Committable;
 use derive_more::{From, Into};
-use hotshot_types::{data::VidCommitment, traits::node_implementation::NodeType};
+use hotshot_types::{
+    data::VidCommitment, traits::node_implementation::NodeType, vid::advz::ADVZScheme,
+};
+use jf_vid::VidScheme;
 
 use crate::{
-    availability::{LeafHash, LeafQueryData, QcHash, StateCertQueryData},
+    availability::{
+        BlockQueryData, LeafHash, LeafQueryData, QcHash, StateCertQueryData, VidCommonQueryData,
+    },
+    types::HeightIndexed,
     Payload, VidCommon,
 };
 
@@ -26,6 +33,26 @@ use crate::{
 pub trait Request<Types>: Copy + Debug + Eq + Hash + Send {
     /// The type of resource that will be returned as a successful response to this request.
     type Response: Clone + Send;
+
+    /// Check whether `response` answers this request.
+    ///
+    /// This is a cheap check which can be used to detect providers returning bad data. Some
+    /// responses cannot be fully verified from the request alone, in which case this only checks
+    /// what it can, and the caller is still responsible for verifying the response.
+    fn is_valid_response(&self, _response: &Self::Response) -> bool {
+        true
+    }
+}
+
+/// Check that the objects in a range response are consecutive, starting from `start`.
+///
+/// A provider may return fewer objects than requested, but not objects outside the range.
+fn is_valid_range<T: HeightIndexed>(start: u64, end: u64, response: &[T]) -> bool {
+    response.len() as u64 <= end.saturating_sub(start)
+        && response
+            .iter()
+            .zip(start..)
+            .all(|(obj, height)| obj.height() == height)
 }
 
 /// A request for a payload with a given commitment.
@@ -42,6 +69,17 @@ pub struct VidCommonRequest(pub VidCommitment);
 
 impl<Types: NodeType> Request<Types> for VidCommonRequest {
     type Response = VidCommon;
+
+    fn is_valid_response(&self, common: &VidCommon) -> bool {
+        match (self.0, common) {
+            (VidCommitment::V0(commit), VidCommon::V0(common)) => {
+                ADVZScheme::is_consistent(&commit, common).is_ok()
+            },
+            // AvidM common data cannot be checked against the commitment on its own.
+            (VidCommitment::V1(_), VidCommon::V1(_)) => true,
+            _ => false,
+        }
+    }
 }
 
 /// A request for a leaf with a given height.
@@ -67,6 +105,12 @@ impl<Types: NodeType> LeafRequest<Types> {
 
 impl<Types: NodeType> Request<Types> for LeafRequest<Types> {
     type Response = LeafQueryData<Types>;
+
+    fn is_valid_response(&self, leaf: &LeafQueryData<Types>) -> bool {
+        leaf.height() == self.height
+            && leaf.hash() == self.expected_leaf
+            && leaf.qc().commit() == self.expected_qc
+    }
 }
 
 /// A request for a light client state update certificate with a given epoch.
@@ -76,3 +120,85 @@ pub struct StateCertRequest(pub u64);
 impl<Types: NodeType> Request<Types> for StateCertRequest {
     type Response = StateCertQueryData<Types>;
 }
+
+/// A request for a contiguous range of leaves, `[start, end)`.
+///
+/// Unlike [`LeafRequest`], this request does not say what the expected leaves are, so a response
+/// from an untrusted provider must be checked by the caller, e.g. by checking that the leaves form
+/// a chain ending in a known leaf. Range requests are made through
+/// [`AvailabilityProvider`](crate::data_source::fetching::AvailabilityProvider), which can fall
+/// back to a [`LeafRequest`] for each leaf, working down from the last one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LeafRangeRequest {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl From<Range<u64>> for LeafRangeRequest {
+    fn from(range: Range<u64>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+impl<Types: NodeType> Request<Types> for LeafRangeRequest {
+    type Response = Vec<LeafQueryData<Types>>;
+
+    fn is_valid_response(&self, response: &Self::Response) -> bool {
+        is_valid_range(self.start, self.end, response)
+    }
+}
+
+/// A request for a contiguous range of blocks, `[start, end)`.
+///
+/// A response from an untrusted provider must be checked by the caller against known headers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BlockRangeRequest {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl From<Range<u64>> for BlockRangeRequest {
+    fn from(range: Range<u64>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+impl<Types: NodeType> Request<Types> for BlockRangeRequest {
+    type Response = Vec<BlockQueryData<Types>>;
+
+    fn is_valid_response(&self, response: &Self::Response) -> bool {
+        is_valid_range(self.start, self.end, response)
+    }
+}
+
+/// A request for VID common data for a contiguous range of blocks, `[start, end)`.
+///
+/// A response from an untrusted provider must be checked by the caller against known headers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VidCommonRangeRequest {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl From<Range<u64>> for VidCommonRangeRequest {
+    fn from(range: Range<u64>) -> Self {
+        Self {
+            start: range.start,
+            end: range.end,
+        }
+    }
+}
+
+impl<Types: NodeType> Request<Types> for VidCommonRangeRequest {
+    type Response = Vec<VidCommonQueryData<Types>>;
+
+    fn is_valid_response(&self, response: &Self::Response) -> bool {
+        is_valid_range(self.start, self.end, response)
+    }
+}
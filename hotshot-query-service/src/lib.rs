@@ -422,6 +422,7 @@ pub mod fetching;
 pub mod merklized_state;
 pub mod metrics;
 pub mod node;
+pub mod openapi;
 mod resolvable;
 pub mod status;
 pub mod task;
@@ -975,7 +976,8 @@ mod test {
                 missing_leaves: 0,
                 missing_vid_common: 1,
                 missing_vid_shares: 1,
-                pruned_height: None
+                pruned_height: None,
+                backfill: None,
             }
         );
         assert_eq!(
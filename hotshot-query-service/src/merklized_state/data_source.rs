@@ -27,10 +27,10 @@ use jf_merkle_tree::{
     prelude::MerkleProof, DigestAlgorithm, Element, ForgetableMerkleTreeScheme, Index,
     MerkleCommitment, NodeValue, ToTraversalPath,
 };
-use serde::{de::DeserializeOwned, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use tagged_base64::TaggedBase64;
 
-use crate::QueryResult;
+use crate::{QueryError, QueryResult};
 
 /// This trait defines methods that a data source should implement
 /// It enables retrieval of the membership path for a leaf node, which can be used to reconstruct the Merkle tree state.
@@ -45,6 +45,32 @@ where
         snapshot: Snapshot<Types, State, ARITY>,
         key: State::Key,
     ) -> QueryResult<MerkleProof<State::Entry, State::Key, State::T, ARITY>>;
+
+    /// Get every change to the entry at `key` in blocks `from..=to`, in order of block height.
+    ///
+    /// The default implementation fails, for data sources which do not keep the history of the
+    /// state.
+    async fn get_entry_history(
+        &self,
+        _key: State::Key,
+        _from: u64,
+        _to: u64,
+    ) -> QueryResult<Vec<EntryUpdate<State::Entry>>> {
+        Err(QueryError::Error {
+            message: "entry history is not supported by this data source".into(),
+        })
+    }
+}
+
+/// A change to a single entry of a merklized state.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryUpdate<Entry> {
+    /// The height of the block in which the entry changed.
+    pub height: u64,
+    /// The value of the entry before `height`, or [`None`] if it was not in the state.
+    pub old: Option<Entry>,
+    /// The value of the entry as of `height`, or [`None`] if it was removed.
+    pub new: Option<Entry>,
 }
 
 /// This trait defines methods for updating the storage with the merkle tree state.
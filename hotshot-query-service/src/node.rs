@@ -24,13 +24,18 @@ use std::{fmt::Display, ops::Bound, path::PathBuf};
 
 use derive_more::From;
 use futures::FutureExt;
-use hotshot_types::traits::node_implementation::NodeType;
+use hotshot_types::traits::{block_contents::BlockHeader, node_implementation::NodeType};
 use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
 use tide_disco::{api::ApiError, method::ReadState, Api, RequestError, StatusCode};
 use vbs::version::StaticVersionType;
 
-use crate::{api::load_api, availability::QueryableHeader, Header, QueryError};
+use crate::{
+    api::load_api,
+    availability::{AvailabilityDataSource, Cursor, Page, QueryableHeader, QueryablePayload},
+    data_source::storage::pruning::PrunedData,
+    Header, Payload, QueryError,
+};
 
 pub(crate) mod data_source;
 pub(crate) mod query_data;
@@ -86,6 +91,12 @@ pub enum Error {
         start: String,
         end: u64,
     },
+    #[snafu(display("data at height {height} has been pruned (pruned height {pruned_height})"))]
+    #[from(ignore)]
+    Pruned {
+        height: u64,
+        pruned_height: u64,
+    },
     #[snafu(display("error {status}: {message}"))]
     Custom {
         message: String,
@@ -107,6 +118,7 @@ impl Error {
             Self::Query { source, .. }
             | Self::QueryVid { source, .. }
             | Self::QueryWindow { source, .. } => source.status(),
+            Self::Pruned { .. } => StatusCode::GONE,
             Self::Custom { status, .. } => *status,
         }
     }
@@ -120,8 +132,10 @@ pub fn define_api<State, Types, Ver: StaticVersionType + 'static>(
 where
     Types: NodeType,
     Header<Types>: QueryableHeader<Types>,
+    Payload<Types>: QueryablePayload<Types>,
     State: 'static + Send + Sync + ReadState,
-    <State as ReadState>::State: NodeDataSource<Types> + Send + Sync,
+    <State as ReadState>::State:
+        NodeDataSource<Types> + AvailabilityDataSource<Types> + Send + Sync,
 {
     let mut api = load_api::<State, Error, Ver>(
         options.api_path.as_ref(),
@@ -209,6 +223,55 @@ where
             }
             .boxed()
         })?
+        .get("get_header_window_page", move |req, state| {
+            async move {
+                let end = req.integer_param("end")?;
+                let cursor = req.opt_blob_param::<_, Cursor>("cursor")?;
+                let start = match cursor {
+                    Some(cursor) => {
+                        // A cursor points to a block which existed when the cursor was created.
+                        // If its header has since been pruned, say so, rather than silently
+                        // starting the window later.
+                        let pruned_height = state.pruned_height(PrunedData::Leaf).await?;
+                        if let Some(pruned_height) = pruned_height {
+                            if cursor.height() <= pruned_height {
+                                return Err(Error::Pruned {
+                                    height: cursor.height(),
+                                    pruned_height,
+                                });
+                            }
+                        }
+                        WindowStart::Height(cursor.height())
+                    },
+                    None => WindowStart::Time(req.integer_param("start")?),
+                };
+                let window = state
+                    .get_header_window(start, end, window_limit)
+                    .await
+                    .context(QueryWindowSnafu {
+                        start: format!("{start:?}"),
+                        end,
+                    })?;
+
+                // The window is complete once it includes the first block after `end`. Otherwise,
+                // the next page picks up after the last block we have.
+                let next = if window.next.is_some() {
+                    None
+                } else {
+                    window
+                        .window
+                        .last()
+                        .or(window.prev.as_ref())
+                        .map(|header| Cursor::new(header.block_number() + 1))
+                        .or(cursor)
+                };
+                Ok(Page {
+                    items: window.window,
+                    next,
+                })
+            }
+            .boxed()
+        })?
         .get("get_limits", move |_req, _state| {
             async move { Ok(Limits { window_limit }) }.boxed()
         })?;
@@ -419,6 +482,32 @@ mod test {
                 .unwrap()
         );
 
+        // Following cursors through the paginated version of the same window yields the same
+        // headers, and ends once the window is complete.
+        let end = last_header.timestamp + 1;
+        let mut paged = vec![];
+        let mut route = format!("page/header/window/{}/{end}", first_header.timestamp);
+        loop {
+            let page: Page<Header<MockTypes>> = client.get(&route).send().await.unwrap();
+            paged.extend(page.items);
+            match page.next {
+                Some(cursor) => route = format!("page/header/window/from/{cursor}/{end}"),
+                None => break,
+            }
+        }
+        assert_eq!(paged, window.window);
+
+        // A page can also start at a cursor handed out earlier.
+        let page: Page<Header<MockTypes>> = client
+            .get(&format!(
+                "page/header/window/from/{}/{end}",
+                Cursor::new(window.window[0].block_number)
+            ))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(page.items, window.window);
+
         // In this simple test, the node should be fully synchronized.
         let sync_status = client
             .get::<SyncStatus>("sync-status")
@@ -23,6 +23,9 @@ pub struct SyncStatus {
     pub missing_vid_common: usize,
     pub missing_vid_shares: usize,
     pub pruned_height: Option<usize>,
+    /// Progress of the range backfill, if one is running.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backfill: Option<BackfillStatus>,
 }
 
 impl SyncStatus {
@@ -33,6 +36,7 @@ impl SyncStatus {
             missing_vid_common: 0,
             missing_vid_shares: 0,
             pruned_height: None,
+            backfill: None,
         }
     }
 
@@ -41,6 +45,21 @@ impl SyncStatus {
     }
 }
 
+/// Progress of a bulk backfill of missing data.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub struct BackfillStatus {
+    /// The lowest block height which will be checked in this backfill.
+    pub start: u64,
+    /// The highest block height (exclusive) which will be checked in this backfill.
+    pub end: u64,
+    /// The number of blocks checked so far, starting from `end` and working backwards.
+    pub processed: u64,
+    /// The number of missing objects (leaves, payloads and VID common data) fetched so far.
+    pub fetched: u64,
+    /// Estimated time until the backfill completes, in seconds.
+    pub eta_secs: Option<u64>,
+}
+
 /// Response to a `/:resource/window` query.
 #[derive(Clone, Debug, Derivative, PartialEq, Eq, Serialize, Deserialize)]
 #[derivative(Default(bound = ""))]
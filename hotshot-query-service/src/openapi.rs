@@ -0,0 +1,477 @@
+// Copyright (c) 2022 Espresso Systems (espressosys.com)
+// This file is part of the HotShot Query Service library.
+//
+// This program is free software: you can redistribute it and/or modify it under the terms of the GNU
+// General Public License as published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+// This program is distributed in the hope that it will be useful, but WITHOUT ANY WARRANTY; without
+// even the implied warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the GNU
+// General Public License for more details.
+// You should have received a copy of the GNU General Public License along with this program. If not,
+// see <https://www.gnu.org/licenses/>.
+
+//! Machine-readable API descriptions.
+//!
+//! Every API module is specified by a tide-disco TOML file, listing its routes, their path
+//! parameters and their documentation. [`OpenApi`] turns a set of these specifications into an
+//! [OpenAPI 3](https://spec.openapis.org/oas/v3.0.3) document, which clients in other languages can
+//! use to generate bindings. Since the TOML files say nothing about response types, response
+//! schemas are registered separately; [`OpenApi::with_query_service_schemas`] registers the schemas
+//! of the availability API.
+
+use std::collections::BTreeMap;
+
+use anyhow::{ensure, Context};
+use serde_json::{json, Map, Value as Json};
+use toml::Value;
+
+use crate::api::merge_toml;
+
+/// The default specification of the availability API.
+pub const AVAILABILITY_API: &str = include_str!("../api/availability.toml");
+/// The default specification of the explorer API.
+pub const EXPLORER_API: &str = include_str!("../api/explorer.toml");
+/// The default specification of the node API.
+pub const NODE_API: &str = include_str!("../api/node.toml");
+/// The default specification of the merklized state API.
+pub const STATE_API: &str = include_str!("../api/state.toml");
+/// The default specification of the status API.
+pub const STATUS_API: &str = include_str!("../api/status.toml");
+
+/// Parse an API specification and merge extensions into it, the same way the API modules do.
+pub fn load_spec<'a>(
+    default: &str,
+    extensions: impl IntoIterator<Item = &'a str>,
+) -> anyhow::Result<Value> {
+    let mut spec = toml::from_str(default).context("malformed API specification")?;
+    for extension in extensions {
+        merge_toml(
+            &mut spec,
+            toml::from_str(extension).context("malformed API extension")?,
+        );
+    }
+    Ok(spec)
+}
+
+/// Builder for an OpenAPI document describing a set of API modules.
+#[derive(Clone, Debug)]
+pub struct OpenApi {
+    title: String,
+    version: String,
+    server: Option<String>,
+    modules: Vec<(String, Value)>,
+    schemas: Map<String, Json>,
+    responses: BTreeMap<(String, String), Json>,
+}
+
+impl OpenApi {
+    pub fn new(title: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            version: version.into(),
+            server: None,
+            modules: vec![],
+            schemas: Map::new(),
+            responses: BTreeMap::new(),
+        }
+    }
+
+    /// Set the base URL, relative to the server root, at which all modules are served.
+    pub fn server(mut self, url: impl Into<String>) -> Self {
+        self.server = Some(url.into());
+        self
+    }
+
+    /// Describe the routes of the API module `spec`, served under `/{name}`.
+    pub fn module(mut self, name: impl Into<String>, spec: Value) -> Self {
+        self.modules.push((name.into(), spec));
+        self
+    }
+
+    /// Add a schema which responses can refer to by `name`.
+    pub fn schema(mut self, name: impl Into<String>, schema: Json) -> Self {
+        self.schemas.insert(name.into(), schema);
+        self
+    }
+
+    /// Declare the schema of successful responses from `route` in `module`.
+    ///
+    /// For WebSocket routes, this is the schema of each message in the stream.
+    pub fn response(
+        mut self,
+        module: impl Into<String>,
+        route: impl Into<String>,
+        schema: Json,
+    ) -> Self {
+        self.responses.insert((module.into(), route.into()), schema);
+        self
+    }
+
+    /// Register schemas for the query data types of the availability API.
+    ///
+    /// Chain-specific types, such as headers and payloads, are described as opaque objects.
+    pub fn with_query_service_schemas(self) -> Self {
+        let opaque = |description: &str| json!({ "type": "object", "description": description });
+        let integer = json!({ "type": "integer", "minimum": 0 });
+
+        let mut api = self
+            .schema(
+                "TaggedBase64",
+                json!({
+                    "type": "string",
+                    "pattern": "^[A-Za-z0-9_-]+~[A-Za-z0-9_=-]*$",
+                    "description": "A tagged base64 string, like `BLOCK~...`. Used for hashes and commitments.",
+                }),
+            )
+            .schema("Header", opaque("A block header."))
+            .schema("Payload", opaque("A block payload."))
+            .schema("Leaf", opaque("A consensus leaf."))
+            .schema("QuorumCertificate", opaque("A quorum certificate."))
+            .schema("Transaction", opaque("A transaction."))
+            .schema(
+                "TransactionInclusionProof",
+                opaque("Proof that a transaction is included in a block."),
+            )
+            .schema("VidCommon", opaque("VID common data for a block payload."))
+            .schema(
+                "LeafQueryData",
+                object([("leaf", reference("Leaf")), ("qc", reference("QuorumCertificate"))]),
+            )
+            .schema(
+                "BlockQueryData",
+                object([
+                    ("header", reference("Header")),
+                    ("payload", reference("Payload")),
+                    ("hash", reference("TaggedBase64")),
+                    ("size", integer.clone()),
+                    ("num_transactions", integer.clone()),
+                ]),
+            )
+            .schema(
+                "PayloadQueryData",
+                object([
+                    ("height", integer.clone()),
+                    ("block_hash", reference("TaggedBase64")),
+                    ("hash", reference("TaggedBase64")),
+                    ("size", integer.clone()),
+                    ("data", reference("Payload")),
+                ]),
+            )
+            .schema(
+                "VidCommonQueryData",
+                object([
+                    ("height", integer.clone()),
+                    ("block_hash", reference("TaggedBase64")),
+                    ("payload_hash", reference("TaggedBase64")),
+                    ("common", reference("VidCommon")),
+                ]),
+            )
+            .schema(
+                "TransactionQueryData",
+                object([
+                    ("transaction", reference("Transaction")),
+                    ("hash", reference("TaggedBase64")),
+                    ("index", integer.clone()),
+                    ("proof", reference("TransactionInclusionProof")),
+                    ("block_hash", reference("TaggedBase64")),
+                    ("block_height", integer.clone()),
+                    ("namespace", integer.clone()),
+                    ("pos_in_namespace", integer.clone()),
+                ]),
+            )
+            .schema(
+                "BlockSummaryQueryData",
+                object([
+                    ("header", reference("Header")),
+                    ("hash", reference("TaggedBase64")),
+                    ("size", integer.clone()),
+                    ("num_transactions", integer.clone()),
+                    (
+                        "namespaces",
+                        json!({
+                            "type": "object",
+                            "description": "Size and transaction count of each namespace in the block, by namespace ID.",
+                            "additionalProperties": object([
+                                ("num_transactions", integer.clone()),
+                                ("size", integer),
+                            ]),
+                        }),
+                    ),
+                ]),
+            );
+
+        for (routes, schema) in [
+            (&["get_leaf", "stream_leaves"][..], "LeafQueryData"),
+            (&["get_header", "stream_headers"][..], "Header"),
+            (&["get_block", "stream_blocks"][..], "BlockQueryData"),
+            (&["get_payload", "stream_payloads"][..], "PayloadQueryData"),
+            (
+                &["get_vid_common", "stream_vid_common"][..],
+                "VidCommonQueryData",
+            ),
+            (
+                &["get_transaction", "stream_transactions"][..],
+                "TransactionQueryData",
+            ),
+            (&["get_block_summary"][..], "BlockSummaryQueryData"),
+        ] {
+            for route in routes {
+                api = api.response("availability", *route, reference(schema));
+            }
+        }
+        for (route, schema) in [
+            ("get_leaf_range", "LeafQueryData"),
+            ("get_header_range", "Header"),
+            ("get_block_range", "BlockQueryData"),
+            ("get_payload_range", "PayloadQueryData"),
+            ("get_vid_common_range", "VidCommonQueryData"),
+            ("get_block_summary_range", "BlockSummaryQueryData"),
+        ] {
+            api = api.response(
+                "availability",
+                route,
+                json!({ "type": "array", "items": reference(schema) }),
+            );
+        }
+        api
+    }
+
+    /// Build the OpenAPI document.
+    pub fn build(&self) -> anyhow::Result<Json> {
+        let mut paths = Map::new();
+        let mut tags = vec![];
+        for (module, spec) in &self.modules {
+            let mut tag = json!({ "name": module });
+            if let Some(description) = spec
+                .get("meta")
+                .and_then(|meta| meta.get("DESCRIPTION"))
+                .and_then(Value::as_str)
+            {
+                tag["description"] = description.trim().into();
+            }
+            tags.push(tag);
+
+            let Some(routes) = spec.get("route").and_then(Value::as_table) else {
+                continue;
+            };
+            for (name, route) in routes {
+                self.add_route(&mut paths, module, name, route)
+                    .with_context(|| format!("invalid route {module}/{name}"))?;
+            }
+        }
+
+        let mut doc = json!({
+            "openapi": "3.0.3",
+            "info": { "title": self.title, "version": self.version },
+            "tags": tags,
+            "paths": paths,
+            "components": { "schemas": self.schemas },
+        });
+        if let Some(server) = &self.server {
+            doc["servers"] = json!([{ "url": server }]);
+        }
+        Ok(doc)
+    }
+
+    fn add_route(
+        &self,
+        paths: &mut Map<String, Json>,
+        module: &str,
+        name: &str,
+        route: &Value,
+    ) -> anyhow::Result<()> {
+        let patterns = route
+            .get("PATH")
+            .and_then(Value::as_array)
+            .context("missing PATH")?;
+        let method = route.get("METHOD").and_then(Value::as_str).unwrap_or("GET");
+        let doc = route
+            .get("DOC")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .trim();
+        let summary = doc.lines().next().unwrap_or_default();
+        let schema = self
+            .responses
+            .get(&(module.to_string(), name.to_string()))
+            .cloned()
+            .unwrap_or_else(|| json!({}));
+
+        let (method, responses) = match method {
+            "GET" | "POST" | "PUT" | "DELETE" => (
+                method.to_lowercase(),
+                json!({
+                    "200": {
+                        "description": "Success",
+                        "content": {
+                            "application/json": { "schema": schema },
+                            "application/octet-stream": {},
+                        },
+                    },
+                    "default": error_response(),
+                }),
+            ),
+            "SOCKET" => (
+                "get".to_string(),
+                json!({
+                    "101": {
+                        "description": "WebSocket stream. Each message has the response schema.",
+                        "content": { "application/json": { "schema": schema } },
+                    },
+                    "default": error_response(),
+                }),
+            ),
+            "METRICS" => (
+                "get".to_string(),
+                json!({
+                    "200": {
+                        "description": "Metrics in the Prometheus text format",
+                        "content": { "text/plain": {} },
+                    },
+                }),
+            ),
+            _ => anyhow::bail!("unsupported METHOD {method}"),
+        };
+
+        for (i, pattern) in patterns.iter().enumerate() {
+            let pattern = pattern.as_str().context("PATH must be a list of strings")?;
+            let mut path = format!("/{module}");
+            let mut parameters = vec![];
+            for segment in pattern.split('/').filter(|segment| !segment.is_empty()) {
+                path.push('/');
+                match segment.strip_prefix(':') {
+                    Some(param) => {
+                        let ty = route
+                            .get(segment)
+                            .and_then(Value::as_str)
+                            .with_context(|| format!("missing type for parameter {segment}"))?;
+                        parameters.push(json!({
+                            "name": param,
+                            "in": "path",
+                            "required": true,
+                            "schema": param_schema(ty),
+                        }));
+                        path.push('{');
+                        path.push_str(param);
+                        path.push('}');
+                    },
+                    None => path.push_str(segment),
+                }
+            }
+
+            let mut operation_id = format!("{module}_{name}").replace('-', "_");
+            if i > 0 {
+                operation_id = format!("{operation_id}_{i}");
+            }
+            let mut operation = json!({
+                "operationId": operation_id,
+                "tags": [module],
+                "summary": summary,
+                "description": doc,
+                "parameters": parameters,
+                "responses": responses.clone(),
+            });
+            if method == "post" || method == "put" {
+                operation["requestBody"] = json!({
+                    "content": {
+                        "application/json": {},
+                        "application/octet-stream": {},
+                    },
+                });
+            }
+            if route.get("METHOD").and_then(Value::as_str) == Some("SOCKET") {
+                operation["x-websocket"] = true.into();
+            }
+
+            let item = paths
+                .entry(path.clone())
+                .or_insert_with(|| json!({}))
+                .as_object_mut()
+                .context("path item is not an object")?;
+            ensure!(
+                !item.contains_key(&method),
+                "duplicate {method} route at {path}"
+            );
+            item.insert(method.clone(), operation);
+        }
+        Ok(())
+    }
+}
+
+fn reference(schema: &str) -> Json {
+    json!({ "$ref": format!("#/components/schemas/{schema}") })
+}
+
+fn object<const N: usize>(properties: [(&str, Json); N]) -> Json {
+    let required = properties.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+    let properties = properties
+        .into_iter()
+        .map(|(name, schema)| (name.to_string(), schema))
+        .collect::<Map<_, _>>();
+    json!({ "type": "object", "required": required, "properties": properties })
+}
+
+fn error_response() -> Json {
+    json!({
+        "description": "Error",
+        "content": { "application/json": { "schema": { "type": "object" } } },
+    })
+}
+
+fn param_schema(ty: &str) -> Json {
+    match ty {
+        "Integer" => json!({ "type": "integer" }),
+        "Boolean" => json!({ "type": "boolean" }),
+        "TaggedBase64" => reference("TaggedBase64"),
+        "Hexadecimal" => json!({ "type": "string", "pattern": "^(0x)?[0-9a-fA-F]*$" }),
+        _ => json!({ "type": "string" }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_availability_openapi() {
+        let doc = OpenApi::new("test", "0.1.0")
+            .server("/v1")
+            .with_query_service_schemas()
+            .module("availability", load_spec(AVAILABILITY_API, []).unwrap())
+            .module("status", load_spec(STATUS_API, []).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(doc["servers"][0]["url"], "/v1");
+
+        // Path parameters are translated to OpenAPI syntax, with types.
+        let get_leaf = &doc["paths"]["/availability/leaf/{height}"]["get"];
+        assert_eq!(get_leaf["operationId"], "availability_get_leaf");
+        assert_eq!(get_leaf["parameters"][0]["name"], "height");
+        assert_eq!(get_leaf["parameters"][0]["schema"]["type"], "integer");
+        assert_eq!(
+            get_leaf["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/LeafQueryData"
+        );
+        let by_hash = &doc["paths"]["/availability/leaf/hash/{hash}"]["get"];
+        assert_eq!(by_hash["operationId"], "availability_get_leaf_1");
+        assert_eq!(
+            by_hash["parameters"][0]["schema"]["$ref"],
+            "#/components/schemas/TaggedBase64"
+        );
+
+        // Streams are marked as WebSockets.
+        let stream = &doc["paths"]["/availability/stream/blocks/{height}"]["get"];
+        assert_eq!(stream["x-websocket"], true);
+
+        // Ranges return arrays.
+        assert_eq!(
+            doc["paths"]["/availability/block/{from}/{until}"]["get"]["responses"]["200"]
+                ["content"]["application/json"]["schema"]["type"],
+            "array"
+        );
+
+        // Modules become tags.
+        assert_eq!(doc["tags"][0]["name"], "availability");
+        assert_eq!(doc["tags"][1]["name"], "status");
+    }
+}
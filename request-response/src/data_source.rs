@@ -12,4 +12,12 @@ use super::request::Request;
 pub trait DataSource<R: Request>: Send + Sync + 'static + Clone {
     /// Calculate/derive the response for a specific request
     async fn derive_response_for(&self, request: &R) -> Result<R::Response>;
+
+    /// Whether this data source is able to respond to a specific request at all
+    ///
+    /// Requests for which this returns `false` are dropped before they are validated, instead of
+    /// failing in [`derive_response_for`](Self::derive_response_for). Defaults to `true`.
+    fn can_respond_to(&self, _request: &R) -> bool {
+        true
+    }
 }
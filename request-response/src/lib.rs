@@ -553,6 +553,15 @@ impl<
     ) {
         trace!("Handling request {:?}", request_message);
 
+        // Don't spend a permit (or a validation) on a request we could never respond to
+        if !self.data_source.can_respond_to(&request_message.request) {
+            trace!(
+                "Skipping request we cannot respond to: {:?}",
+                request_message
+            );
+            return;
+        }
+
         // Spawn a task to:
         // - Validate the request
         // - Derive the response data (check if we have it)
@@ -4,7 +4,8 @@ use ark_ff::{BigInteger, PrimeField};
 use ark_serialize::CanonicalSerialize;
 use committable::{Commitment, Committable};
 use espresso_types::{
-    BlockMerkleCommitment, BlockMerkleTree, Header, NsProof, NsTable, Transaction,
+    BlockMerkleCommitment, BlockMerkleTree, Header, NamespaceFinalityProof, NsProof, NsTable,
+    Transaction,
 };
 use ethers::types::U256;
 use hotshot_query_service::VidCommon;
@@ -187,6 +188,45 @@ pub extern "C" fn verify_namespace_helper(
     VerificationResult::success()
 }
 
+// Helper function to verify a namespace finality proof bundle, as returned by the
+// `block-state/finality-proof` endpoint, against a light client state read from L1.
+//
+// bundle_bytes: Byte representation of a JSON NamespaceFinalityProof string.
+// circuit_block_bytes: Circuit representation of the block Merkle tree root committed to by the light
+// client contract, in little-endian order.
+// tx_comm_bytes: Byte representation of a hex encoded Sha256 digest that the transaction set commits to.
+#[no_mangle]
+pub extern "C" fn verify_finality_proof_helper(
+    namespace: u64,
+    bundle_ptr: *const u8,
+    bundle_len: usize,
+    circuit_block_ptr: *const u8,
+    circuit_block_len: usize,
+    tx_comm_ptr: *const u8,
+    tx_comm_len: usize,
+) -> VerificationResult {
+    let bundle_bytes = handle_result!(slice_from_raw_parts(bundle_ptr, bundle_len));
+    let circuit_block_bytes =
+        handle_result!(slice_from_raw_parts(circuit_block_ptr, circuit_block_len));
+    let tx_comm_bytes = handle_result!(slice_from_raw_parts(tx_comm_ptr, tx_comm_len));
+
+    let txn_comm_str = handle_result!(std::str::from_utf8(tx_comm_bytes));
+    let bundle: NamespaceFinalityProof = handle_result!(serde_json::from_slice(bundle_bytes));
+    let block_comm_root = CircuitField::from_le_bytes_mod_order(circuit_block_bytes);
+    let namespace: u32 = handle_result!(namespace.try_into());
+
+    let txns = handle_result!(bundle.verify(block_comm_root, namespace.into()));
+
+    let txns_comm = hash_txns(namespace, &txns);
+    if txns_comm != txn_comm_str {
+        return VerificationResult::err(&format!(
+            "commitment mismatch: proven {txns_comm} != expected {txn_comm_str}"
+        ));
+    }
+
+    VerificationResult::success()
+}
+
 fn hash_txns(namespace: u32, txns: &[Transaction]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(namespace.to_le_bytes());
@@ -220,9 +260,18 @@ fn slice_from_raw_parts<'a>(ptr: *const u8, len: usize) -> Result<&'a [u8], ()>
 
 #[cfg(test)]
 mod test {
-    use std::ffi::CString;
+    use std::ffi::{CStr, CString};
 
-    use crate::free_error_string;
+    use ark_ff::{BigInteger, PrimeField};
+    use ark_serialize::CanonicalSerialize;
+    use espresso_types::NamespaceFinalityProof;
+    use hotshot_types::light_client::hash_bytes_to_field;
+    use serde_json::Value;
+
+    use crate::{
+        free_error_string, hash_txns, verify_finality_proof_helper, CircuitField,
+        VerificationResult,
+    };
 
     #[test]
     fn test_free_error_str() {
@@ -235,4 +284,67 @@ mod test {
         // sanity check that the function doesn't panic if the pointer is null
         unsafe { free_error_string(std::ptr::null_mut()) };
     }
+
+    fn check_finality_proof(
+        namespace: u32,
+        bundle: &[u8],
+        circuit_block: &[u8],
+        tx_comm: &str,
+    ) -> Result<(), String> {
+        let VerificationResult { success, error } = verify_finality_proof_helper(
+            namespace.into(),
+            bundle.as_ptr(),
+            bundle.len(),
+            circuit_block.as_ptr(),
+            circuit_block.len(),
+            tx_comm.as_ptr(),
+            tx_comm.len(),
+        );
+        let msg = unsafe { CStr::from_ptr(error) }
+            .to_string_lossy()
+            .into_owned();
+        unsafe { free_error_string(error) };
+        if success {
+            Ok(())
+        } else {
+            Err(msg)
+        }
+    }
+
+    #[test]
+    fn test_verify_finality_proof() {
+        let data: Value = serde_json::from_str(include_str!(
+            "../../go/verification/finality_proof_test_data.json"
+        ))
+        .unwrap();
+        let bundle = serde_json::to_vec(&data["bundle"]).unwrap();
+        let absent = data["absent_namespace"].as_u64().unwrap() as u32;
+        let present = data["present_namespace"].as_u64().unwrap() as u32;
+
+        // The light client commits to a hash of the anchor's block Merkle tree root.
+        let proof: NamespaceFinalityProof = serde_json::from_slice(&bundle).unwrap();
+        let mut root_bytes = vec![];
+        proof
+            .anchor
+            .block_merkle_tree_root()
+            .serialize_compressed(&mut root_bytes)
+            .unwrap();
+        let root = hash_bytes_to_field::<CircuitField>(&root_bytes).unwrap();
+        let circuit_block = root.into_bigint().to_bytes_le();
+
+        check_finality_proof(absent, &bundle, &circuit_block, &hash_txns(absent, &[])).unwrap();
+
+        // The bundle has no namespace proof, so it cannot prove a namespace which is in the block.
+        let err = check_finality_proof(present, &bundle, &circuit_block, &hash_txns(present, &[]))
+            .unwrap_err();
+        assert!(err.contains("missing proof for namespace"), "{err}");
+
+        // A different light client state is rejected.
+        let tampered = (root + CircuitField::from(1u64))
+            .into_bigint()
+            .to_bytes_le();
+        let err =
+            check_finality_proof(absent, &bundle, &tampered, &hash_txns(absent, &[])).unwrap_err();
+        assert!(err.contains("does not match light client state"), "{err}");
+    }
 }
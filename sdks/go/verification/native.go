@@ -29,6 +29,12 @@ extern VerificationResult verify_namespace_helper(
     const uint8_t* tx_comm_ptr, size_t tx_comm_len,
     const uint8_t* common_data_ptr, size_t common_data_len
 );
+extern VerificationResult verify_finality_proof_helper(
+    uint64_t namespace,
+    const uint8_t* bundle_ptr, size_t bundle_len,
+    const uint8_t* circuit_block_ptr, size_t circuit_block_len,
+    const uint8_t* tx_comm_ptr, size_t tx_comm_len
+);
 */
 import "C"
 import (
@@ -90,3 +96,26 @@ func verifyMerkleProof(proof []byte, header []byte, blockComm []byte, circuitBlo
 	msg := C.GoString(result.error)
 	return false, errors.New(msg)
 }
+
+func verifyFinalityProof(namespace uint64, bundle []byte, circuitBlock []byte, txComm []byte) (bool, error) {
+	c_namespace := C.uint64_t(namespace)
+
+	bundlePtr := (*C.uint8_t)(unsafe.Pointer(&bundle[0]))
+	bundleLen := C.size_t(len(bundle))
+
+	circuitBlockPtr := (*C.uint8_t)(unsafe.Pointer(&circuitBlock[0]))
+	circuitBlockLen := C.size_t(len(circuitBlock))
+
+	txCommPtr := (*C.uint8_t)(unsafe.Pointer(&txComm[0]))
+	txCommLen := C.size_t(len(txComm))
+
+	result := C.verify_finality_proof_helper(c_namespace, bundlePtr, bundleLen, circuitBlockPtr, circuitBlockLen, txCommPtr, txCommLen)
+	defer C.free_error_string(result.error)
+	if bool(result.success) {
+		return true, nil
+	}
+	// Allocate a new string in go, so we can free the C string
+	// See https://go.dev/wiki/cgo#go-strings-and-c-strings
+	msg := C.GoString(result.error)
+	return false, errors.New(msg)
+}
@@ -43,6 +43,21 @@ func VerifyMerkleProof(
 	return verifyMerkleProof(proof, header, []byte(blockComm.String()), circuit_comm_bytes[:])
 }
 
+// VerifyFinalityProof checks a namespace finality proof bundle, as returned by the
+// `block-state/finality-proof` endpoint, against the block commitment root of a light client
+// state read from L1, and checks that the proven namespace contains exactly `txs`.
+func VerifyFinalityProof(
+	namespace uint64,
+	bundle json.RawMessage,
+	circuit_comm_bytes espressoTypes.Commitment,
+	txs []espressoTypes.Bytes,
+) (bool, error) {
+	// G115: integer overflow conversion uint64 -> uint32 (gosec)
+	// #nosec G115
+	var txnComm = hashTxns(uint32(namespace), txs)
+	return verifyFinalityProof(namespace, bundle, circuit_comm_bytes[:], []byte(txnComm))
+}
+
 func hashTxns(namespace uint32, txns []espressoTypes.Bytes) string {
 	hasher := sha256.New()
 	ns_buf := make([]byte, 4)
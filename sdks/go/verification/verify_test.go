@@ -4,6 +4,7 @@ import (
 	"encoding/json"
 	"io"
 	"os"
+	"strings"
 	"testing"
 
 	"github.com/EspressoSystems/espresso-network/sdks/go/types"
@@ -55,6 +56,62 @@ func TestVerifyNamespaceWithRealData(t *testing.T) {
 	}
 }
 
+type finalityProofTestData struct {
+	Bundle            json.RawMessage `json:"bundle"`
+	HotShotCommitment []uint8         `json:"hotshot_commitment"`
+	AbsentNamespace   uint64          `json:"absent_namespace"`
+	PresentNamespace  uint64          `json:"present_namespace"`
+}
+
+func TestVerifyFinalityProof(t *testing.T) {
+	bytes, err := readResponse("./finality_proof_test_data.json")
+	if err != nil {
+		t.Fatalf("Failed to read file: %v", err)
+	}
+	var data finalityProofTestData
+	if err := json.Unmarshal(bytes, &data); err != nil {
+		t.Fatalf("Failed to unmarshal: %v", err)
+	}
+	var root types.Commitment
+	copy(root[:], data.HotShotCommitment)
+
+	// The bundle proves that block 7 is finalized, and that it has no transactions in a namespace
+	// which is not in its namespace table.
+	success, err := VerifyFinalityProof(data.AbsentNamespace, data.Bundle, root, nil)
+	if !success {
+		t.Fatalf("Failed to verify finality proof: %v", err)
+	}
+
+	// It does not prove any transactions in that namespace.
+	success, _ = VerifyFinalityProof(data.AbsentNamespace, data.Bundle, root, []types.Bytes{[]byte("test")})
+	if success {
+		t.Fatalf("Verified transactions which are not in the block")
+	}
+
+	// The bundle has no namespace proof, so it cannot prove the contents of a namespace which is in
+	// the block.
+	success, err = VerifyFinalityProof(data.PresentNamespace, data.Bundle, root, nil)
+	if success {
+		t.Fatalf("Verified a namespace without a namespace proof")
+	}
+	msg := "missing proof for namespace"
+	if err == nil || !strings.Contains(err.Error(), msg) {
+		t.Fatalf("Expected error message to contain '%v', got: %v", msg, err)
+	}
+
+	// A light client state with a different block commitment root is rejected.
+	tampered := root
+	tampered[0] ^= 1
+	success, err = VerifyFinalityProof(data.AbsentNamespace, data.Bundle, tampered, nil)
+	if success {
+		t.Fatalf("Verified a finality proof against the wrong light client state")
+	}
+	msg = "does not match light client state"
+	if err == nil || !strings.Contains(err.Error(), msg) {
+		t.Fatalf("Expected error message to contain '%v', got: %v", msg, err)
+	}
+}
+
 func readResponse(path string) (json.RawMessage, error) {
 	file, err := os.Open(path)
 	if err != nil {
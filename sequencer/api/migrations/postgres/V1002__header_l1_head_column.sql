@@ -0,0 +1,8 @@
+-- Index headers by their L1 head, so that the first Espresso block referencing a given L1 block can
+-- be found without scanning. As with the Merkle root columns, the field is nested under `fields` in
+-- all header versions after 0.1.
+ALTER TABLE header
+ADD column l1_head BIGINT
+GENERATED ALWAYS AS ((coalesce(data->'fields'->>'l1_head', data->>'l1_head'))::bigint) STORED NOT NULL;
+
+CREATE INDEX header_l1_head_idx ON header (l1_head);
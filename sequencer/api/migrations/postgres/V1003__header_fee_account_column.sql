@@ -0,0 +1,8 @@
+-- Index headers by the account which paid the builder fee, so that the blocks paid for by a given
+-- account can be listed without scanning. As with the L1 head column, the field is nested under
+-- `fields` in all header versions after 0.1. Accounts are serialized as lowercase hex strings.
+ALTER TABLE header
+ADD column fee_account TEXT
+GENERATED ALWAYS AS (coalesce(data->'fields'->'fee_info'->>'account', data->'fee_info'->>'account')) STORED;
+
+CREATE INDEX header_fee_account_idx ON header (fee_account, height);
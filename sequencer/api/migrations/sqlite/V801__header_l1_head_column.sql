@@ -0,0 +1,9 @@
+-- Index headers by their L1 head, so that the first Espresso block referencing a given L1 block can
+-- be found without scanning. As with the Merkle root columns, the field is nested under `fields` in
+-- all header versions after 0.1. SQLite cannot add a stored generated column to an existing table,
+-- so this column is virtual, and only materialized in the index.
+ALTER TABLE header
+ADD COLUMN l1_head BIGINT
+GENERATED ALWAYS AS (coalesce(json_extract(data, '$.fields.l1_head'), json_extract(data, '$.l1_head'))) VIRTUAL;
+
+CREATE INDEX header_l1_head_idx ON header (l1_head);
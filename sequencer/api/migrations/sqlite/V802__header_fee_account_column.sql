@@ -0,0 +1,9 @@
+-- Index headers by the account which paid the builder fee, so that the blocks paid for by a given
+-- account can be listed without scanning. As with the L1 head column, the field is nested under
+-- `fields` in all header versions after 0.1, and the column is virtual, only materialized in the
+-- index. Accounts are serialized as lowercase hex strings.
+ALTER TABLE header
+ADD COLUMN fee_account TEXT
+GENERATED ALWAYS AS (coalesce(json_extract(data, '$.fields.fee_info.account'), json_extract(data, '$.fee_info.account'))) VIRTUAL;
+
+CREATE INDEX header_fee_account_idx ON header (fee_account, height);
@@ -4,7 +4,7 @@ use anyhow::{bail, Context};
 use async_lock::RwLock;
 use async_once_cell::Lazy;
 use async_trait::async_trait;
-use committable::Commitment;
+use committable::{Commitment, Committable};
 use data_source::{
     CatchupDataSource, RequestResponseDataSource, StakeTableDataSource, StakeTableWithEpochNumber,
     SubmitDataSource,
@@ -17,7 +17,7 @@ use espresso_types::{
     v0_1::{RewardAccount, RewardAmount, RewardMerkleTree},
     v0_3::ChainConfig,
     AccountQueryData, BlockMerkleTree, FeeAccount, FeeMerkleTree, Leaf2, NodeState, PubKey,
-    Transaction, ValidatorMap,
+    Transaction, TransactionStatus, ValidatorMap,
 };
 use futures::{
     future::{BoxFuture, Future, FutureExt},
@@ -27,7 +27,10 @@ use hotshot_events_service::events_source::{
     EventFilterSet, EventsSource, EventsStreamer, StartupInfo,
 };
 use hotshot_query_service::{
-    availability::VidCommonQueryData, data_source::ExtensibleDataSource, VidCommon,
+    availability::VidCommonQueryData,
+    data_source::ExtensibleDataSource,
+    fetching::{Provider, Request as FetchRequest},
+    VidCommon,
 };
 use hotshot_types::{
     data::{VidCommitment, VidShare, ViewNumber},
@@ -46,26 +49,35 @@ use itertools::Itertools;
 use jf_merkle_tree::MerkleTreeScheme;
 use rand::Rng;
 use request_response::RequestType;
-use tokio::time::timeout;
+use tokio::{sync::Semaphore, time::timeout};
 
-use self::data_source::{HotShotConfigDataSource, NodeStateDataSource, StateSignatureDataSource};
+use self::{
+    data_source::{HotShotConfigDataSource, NodeStateDataSource, StateSignatureDataSource},
+    tx_status::TxStatusTracker,
+    validator_activity::ValidatorActivityCache,
+};
 use crate::{
     catchup::{add_fee_accounts_to_state, add_reward_accounts_to_state, CatchupStorage},
     context::Consensus,
     request_response::{
         data_source::retain_reward_accounts,
         request::{Request, Response},
+        RequestResponseProtocol,
     },
     state_signature::StateSigner,
-    SeqTypes, SequencerApiVersion, SequencerContext,
+    Node, SeqTypes, SequencerApiVersion, SequencerContext,
 };
 
 pub mod data_source;
 pub mod endpoints;
 pub mod fs;
+mod openapi;
 pub mod options;
+pub mod quota;
 pub mod sql;
+mod tx_status;
 mod update;
+mod validator_activity;
 
 pub use options::Options;
 
@@ -104,15 +116,31 @@ struct ApiState<N: ConnectedNetwork<PubKey>, P: SequencerPersistence, V: Version
     // without waiting.
     #[derivative(Debug = "ignore")]
     sequencer_context: BoxLazy<SequencerContext<N, P, V>>,
+    tx_status: TxStatusTracker,
+    validator_activity: ValidatorActivityCache,
+    /// Limits the number of account pages searching L1 for deposits at once.
+    account_deposit_lookups: Arc<Semaphore>,
 }
 
 impl<N: ConnectedNetwork<PubKey>, P: SequencerPersistence, V: Versions> ApiState<N, P, V> {
     fn new(context_init: impl Future<Output = SequencerContext<N, P, V>> + Send + 'static) -> Self {
         Self {
             sequencer_context: Arc::pin(Lazy::from_future(context_init.boxed())),
+            tx_status: Default::default(),
+            validator_activity: Default::default(),
+            account_deposit_lookups: Arc::new(Semaphore::new(
+                endpoints::MAX_ACCOUNT_DEPOSIT_LOOKUPS,
+            )),
         }
     }
 
+    /// Report submitted transactions as dropped if they are not decided within `timeout`, and
+    /// track at most `capacity` transactions at once.
+    fn with_tx_status(mut self, timeout: Duration, capacity: usize) -> Self {
+        self.tx_status = TxStatusTracker::new(timeout, capacity);
+        self
+    }
+
     async fn state_signer(&self) -> Arc<RwLock<StateSigner<SequencerApiVersion>>> {
         self.sequencer_context
             .as_ref()
@@ -199,6 +227,21 @@ impl<N: ConnectedNetwork<PubKey>, D: Send + Sync, V: Versions, P: SequencerPersi
     async fn submit(&self, tx: Transaction) -> anyhow::Result<()> {
         self.as_ref().submit(tx).await
     }
+
+    async fn submit_batch(&self, txs: Vec<Transaction>) -> Vec<anyhow::Result<()>> {
+        self.as_ref().submit_batch(txs).await
+    }
+
+    async fn tx_status(&self, tx: Commitment<Transaction>) -> Option<TransactionStatus> {
+        self.as_ref().tx_status(tx).await
+    }
+
+    async fn subscribe_tx_status(
+        &self,
+        tx: Commitment<Transaction>,
+    ) -> Option<BoxStream<'static, TransactionStatus>> {
+        self.as_ref().subscribe_tx_status(tx).await
+    }
 }
 
 impl<N: ConnectedNetwork<PubKey>, D: Sync, V: Versions, P: SequencerPersistence>
@@ -228,6 +271,14 @@ impl<N: ConnectedNetwork<PubKey>, D: Sync, V: Versions, P: SequencerPersistence>
     async fn get_block_reward(&self) -> anyhow::Result<RewardAmount> {
         self.as_ref().get_block_reward().await
     }
+
+    async fn get_leaders(
+        &self,
+        epoch: Option<<SeqTypes as NodeType>::Epoch>,
+        views: Vec<ViewNumber>,
+    ) -> anyhow::Result<Vec<PubKey>> {
+        self.as_ref().get_leaders(epoch, views).await
+    }
 }
 
 impl<N: ConnectedNetwork<PubKey>, V: Versions, P: SequencerPersistence>
@@ -288,6 +339,27 @@ impl<N: ConnectedNetwork<PubKey>, V: Versions, P: SequencerPersistence>
         Ok(membership.block_reward())
     }
 
+    async fn get_leaders(
+        &self,
+        epoch: Option<<SeqTypes as NodeType>::Epoch>,
+        views: Vec<ViewNumber>,
+    ) -> anyhow::Result<Vec<PubKey>> {
+        let mem = self
+            .consensus()
+            .await
+            .read()
+            .await
+            .membership_coordinator
+            .membership_for_epoch(epoch)
+            .await?;
+
+        let mut leaders = Vec::with_capacity(views.len());
+        for view in views {
+            leaders.push(mem.leader(view).await?);
+        }
+        Ok(leaders)
+    }
+
     /// Get the whole validators map
     async fn get_validators(
         &self,
@@ -426,31 +498,52 @@ impl<N: ConnectedNetwork<PubKey>, V: Versions, P: SequencerPersistence>
     }
 }
 
-impl<N: ConnectedNetwork<PubKey>, V: Versions, P: SequencerPersistence> SubmitDataSource<N, P>
-    for ApiState<N, P, V>
+/// Fetch missing query service data from staked peers over the request-response protocol.
+///
+/// The protocol is only available once consensus has been initialized. Until then, every fetch
+/// fails immediately, so that the query service falls back to its other providers.
+#[async_trait]
+impl<N, P, V, T> Provider<SeqTypes, T> for ApiState<N, P, V>
+where
+    N: ConnectedNetwork<PubKey>,
+    P: SequencerPersistence,
+    V: Versions,
+    T: FetchRequest<SeqTypes> + Send + 'static,
+    RequestResponseProtocol<Node<N, P>, V, N, P>: Provider<SeqTypes, T>,
 {
-    async fn submit(&self, tx: Transaction) -> anyhow::Result<()> {
-        let handle = self.consensus().await;
-
-        let consensus_read_lock = handle.read().await;
+    async fn fetch(&self, req: T) -> Option<T::Response> {
+        let Some(ctx) = self.sequencer_context.try_get() else {
+            tracing::debug!("cannot fetch from peers before consensus is initialized");
+            return None;
+        };
+        ctx.request_response_protocol.fetch(req).await
+    }
+}
 
+impl<N: ConnectedNetwork<PubKey>, V: Versions, P: SequencerPersistence> ApiState<N, P, V> {
+    /// The chain config against which submitted transactions are validated.
+    async fn submission_chain_config(&self, consensus: &Consensus<N, P, V>) -> ChainConfig {
         // Fetch full chain config from the validated state, if present.
         // This is necessary because we support chain config upgrades,
         // so the updated chain config is found in the validated state.
-        let cf = consensus_read_lock
-            .decided_state()
-            .await
-            .chain_config
-            .resolve();
+        let cf = consensus.decided_state().await.chain_config.resolve();
 
         // Use the chain config from the validated state if available,
         // otherwise, use the node state's chain config
         // The node state's chain config is the node's base version chain config
-        let cf = match cf {
+        match cf {
             Some(cf) => cf,
             None => self.node_state().await.chain_config,
-        };
+        }
+    }
 
+    /// Validate a transaction and submit it to consensus.
+    async fn validate_and_submit(
+        &self,
+        consensus: &Consensus<N, P, V>,
+        cf: &ChainConfig,
+        tx: Transaction,
+    ) -> anyhow::Result<()> {
         let max_block_size: u64 = cf.max_block_size.into();
         let txn_size = tx.payload().len() as u64;
 
@@ -459,11 +552,61 @@ impl<N: ConnectedNetwork<PubKey>, V: Versions, P: SequencerPersistence> SubmitDa
             bail!("transaction size ({txn_size}) is greater than max_block_size ({max_block_size})")
         }
 
-        consensus_read_lock.submit_transaction(tx).await?;
+        // Start tracking the transaction before submitting it, so we don't miss any events.
+        let hash = tx.commit();
+        self.tx_status.pending(hash);
+        if let Err(err) = consensus.submit_transaction(tx).await {
+            self.tx_status.forget(hash);
+            return Err(err.into());
+        }
         Ok(())
     }
 }
 
+impl<N: ConnectedNetwork<PubKey>, V: Versions, P: SequencerPersistence> SubmitDataSource<N, P>
+    for ApiState<N, P, V>
+{
+    async fn submit(&self, tx: Transaction) -> anyhow::Result<()> {
+        let handle = self.consensus().await;
+        let consensus_read_lock = handle.read().await;
+        let cf = self.submission_chain_config(&consensus_read_lock).await;
+        self.validate_and_submit(&consensus_read_lock, &cf, tx)
+            .await
+    }
+
+    async fn submit_batch(&self, txs: Vec<Transaction>) -> Vec<anyhow::Result<()>> {
+        let handle = self.consensus().await;
+        let cf = {
+            let consensus_read_lock = handle.read().await;
+            self.submission_chain_config(&consensus_read_lock).await
+        };
+
+        // Each transaction is validated and submitted independently, so that one bad transaction
+        // does not cause the rest of the batch to be rejected. The consensus lock is released
+        // between transactions, so that a large batch does not hold up consensus.
+        let mut results = Vec::with_capacity(txs.len());
+        for tx in txs {
+            let consensus_read_lock = handle.read().await;
+            results.push(
+                self.validate_and_submit(&consensus_read_lock, &cf, tx)
+                    .await,
+            );
+        }
+        results
+    }
+
+    async fn tx_status(&self, tx: Commitment<Transaction>) -> Option<TransactionStatus> {
+        self.tx_status.status(tx)
+    }
+
+    async fn subscribe_tx_status(
+        &self,
+        tx: Commitment<Transaction>,
+    ) -> Option<BoxStream<'static, TransactionStatus>> {
+        self.tx_status.subscribe(tx)
+    }
+}
+
 impl<N, P, D, V> NodeStateDataSource for StorageState<N, P, D, V>
 where
     N: ConnectedNetwork<PubKey>,
@@ -1291,6 +1434,29 @@ pub mod test_helpers {
 
         // Wait for a Decide event containing transaction matching the one we sent
         wait_for_decide_on_handle(&mut events, &txn).await;
+
+        // The status API should eventually report the transaction as decided.
+        let mut statuses = client
+            .socket(&format!("submit/stream/status/{hash}"))
+            .subscribe::<TransactionStatus>()
+            .await
+            .unwrap();
+        let mut status = statuses.next().await.unwrap().unwrap();
+        while !status.is_final() {
+            status = statuses.next().await.unwrap().unwrap();
+        }
+        let TransactionStatus::Decided(position) = status else {
+            panic!("transaction was not decided: {status:?}");
+        };
+        assert_eq!(position.namespace, txn.namespace());
+        assert_eq!(
+            client
+                .get::<TransactionStatus>(&format!("submit/status/{hash}"))
+                .send()
+                .await
+                .unwrap(),
+            status
+        );
     }
 
     /// Test the state signature API.
@@ -1496,9 +1662,13 @@ mod api_tests {
     use data_source::testing::TestableSequencerDataSource;
     use espresso_types::{
         traits::{EventConsumer, PersistenceOptions},
-        Header, Leaf2, MockSequencerVersions, NamespaceId, NamespaceProofQueryData, ValidatedState,
+        Header, Leaf2, MockSequencerVersions, NamespaceId, NamespaceProofQueryData,
+        NamespaceStreamEntry, ValidatedState,
+    };
+    use futures::{
+        future,
+        stream::{StreamExt, TryStreamExt},
     };
-    use futures::{future, stream::StreamExt};
     use hotshot_example_types::node_types::{EpochsTestVersions, TestVersions};
     use hotshot_query_service::availability::{
         AvailabilityDataSource, BlockQueryData, StateCertQueryData, VidCommonQueryData,
@@ -1511,7 +1681,10 @@ mod api_tests {
         event::LeafInfo,
         message::Proposal,
         simple_certificate::QuorumCertificate2,
-        traits::{node_implementation::ConsensusTime, signature_key::SignatureKey, EncodeBytes},
+        traits::{
+            block_contents::BlockHeader, node_implementation::ConsensusTime,
+            signature_key::SignatureKey, EncodeBytes,
+        },
         utils::EpochTransitionIndicator,
         vid::avidm::{init_avidm_param, AvidMScheme},
     };
@@ -1643,6 +1816,113 @@ mod api_tests {
         }
         assert!(found_txn);
         assert!(found_empty_block);
+
+        // The namespace stream yields the same data, with an entry for every block.
+        let mut entries = client
+            .socket(&format!("availability/stream/namespace/{ns_id}/0"))
+            .subscribe::<NamespaceStreamEntry>()
+            .await
+            .unwrap();
+        let mut found_txn = false;
+        for block_num in 0..=block_height {
+            let entry = entries.next().await.unwrap().unwrap();
+            assert_eq!(entry.height, block_num as u64);
+
+            let header: Header = client
+                .get(&format!("availability/header/{block_num}"))
+                .send()
+                .await
+                .unwrap();
+            match (entry.proof, entry.common) {
+                (Some(proof), Some(common)) => {
+                    let (txs, ns) = proof
+                        .verify(header.ns_table(), &header.payload_commitment(), &common)
+                        .unwrap();
+                    assert_eq!(ns, ns_id);
+                    assert_eq!(txs, entry.transactions);
+                },
+                (None, None) => {
+                    assert!(header.ns_table().find_ns_id(&ns_id).is_none());
+                    assert!(entry.transactions.is_empty());
+                },
+                (proof, common) => panic!("inconsistent entry: {proof:?}, {common:?}"),
+            }
+            found_txn = found_txn || entry.transactions.contains(&txn);
+        }
+        assert!(found_txn);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    pub(crate) async fn test_header_lookup<D: TestableSequencerDataSource>() {
+        setup_test();
+
+        // Start query service.
+        let port = pick_unused_port().expect("No ports free");
+        let storage = D::create_storage().await;
+        let network_config = TestConfigBuilder::default().build();
+        let config = TestNetworkConfigBuilder::default()
+            .api_config(D::options(&storage, Options::with_port(port)))
+            .network_config(network_config)
+            .build();
+        let _network = TestNetwork::new(config, MockSequencerVersions::new()).await;
+
+        // Connect client.
+        let client: Client<ServerError, StaticVersion<0, 1>> =
+            Client::new(format!("http://localhost:{port}").parse().unwrap());
+        client.connect(None).await;
+
+        // Wait for a few blocks to be produced.
+        let headers: Vec<Header> = client
+            .socket("availability/stream/headers/0")
+            .subscribe()
+            .await
+            .unwrap()
+            .take(5)
+            .try_collect()
+            .await
+            .unwrap();
+
+        for header in &headers {
+            // The result of each lookup is the first header which satisfies the query, which is
+            // not necessarily `header` itself, since consecutive headers may share a timestamp or
+            // L1 head.
+            let res: Header = client
+                .get(&format!(
+                    "availability/header/timestamp/{}",
+                    header.timestamp()
+                ))
+                .send()
+                .await
+                .unwrap();
+            assert!(res.height() <= header.height());
+            assert_eq!(res.timestamp(), header.timestamp());
+            if res.height() > 0 {
+                let prev = &headers[res.height() as usize - 1];
+                assert!(prev.timestamp() < header.timestamp());
+            }
+
+            let res: Header = client
+                .get(&format!(
+                    "availability/header/l1-block/{}",
+                    header.l1_head()
+                ))
+                .send()
+                .await
+                .unwrap();
+            assert!(res.height() <= header.height());
+            assert_eq!(res.l1_head(), header.l1_head());
+            if res.height() > 0 {
+                let prev = &headers[res.height() as usize - 1];
+                assert!(prev.l1_head() < header.l1_head());
+            }
+        }
+
+        // Lookups past the end of the chain fail.
+        client
+            .get::<Header>(&format!("availability/header/timestamp/{}", u64::MAX))
+            .send()
+            .await
+            .unwrap_err();
     }
 
     #[tokio::test(flavor = "multi_thread")]
@@ -2031,7 +2311,7 @@ mod test {
     use alloy::{
         eips::BlockId,
         network::EthereumWallet,
-        primitives::U256,
+        primitives::{Address, U256},
         providers::{Provider, ProviderBuilder},
     };
     use async_lock::Mutex;
@@ -2045,8 +2325,10 @@ mod test {
         traits::{NullEventConsumer, PersistenceOptions},
         v0_1::{RewardAmount, COMMISSION_BASIS_POINTS},
         v0_3::Fetcher,
-        validators_from_l1_events, EpochVersion, FeeAmount, FeeVersion, Header, L1ClientOptions,
-        MockSequencerVersions, NamespaceId, RewardDistributor, SequencerVersions, ValidatedState,
+        validators_from_l1_events, AccountDetailResponse, BalanceChange, BalanceChangeCause,
+        EpochVersion, FeeAmount, FeeVersion, Header, L1ClientOptions, MockSequencerVersions,
+        NamespaceId, RewardDistributor, SequencerVersions, SubmitResult, ValidatedState,
+        ValidatorDetailResponse, ValidatorSummariesResponse,
     };
     use futures::{
         future::{self, join_all},
@@ -2061,13 +2343,16 @@ mod test {
             VidCommonQueryData,
         },
         data_source::{sql::Config, storage::SqlStorage, VersionedDataSource},
-        explorer::TransactionSummariesResponse,
+        explorer::{SearchResultResponse, TransactionSummariesResponse},
         types::HeightIndexed,
     };
     use hotshot_types::{
         data::EpochNumber,
         event::LeafInfo,
-        traits::{election::Membership, metrics::NoMetrics, node_implementation::ConsensusTime},
+        traits::{
+            election::Membership, metrics::NoMetrics, node_implementation::ConsensusTime,
+            signature_key::SignatureKey,
+        },
         utils::epoch_from_block_number,
         ValidatorConfig,
     };
@@ -2081,7 +2366,7 @@ mod test {
         catchup_test_helper, state_signature_test_helper, status_test_helper, submit_test_helper,
         TestNetwork, TestNetworkConfigBuilder,
     };
-    use tide_disco::{app::AppHealth, error::ServerError, healthcheck::HealthStatus};
+    use tide_disco::{app::AppHealth, error::ServerError, healthcheck::HealthStatus, StatusCode};
     use tokio::time::sleep;
     use vbs::version::{StaticVersion, StaticVersionType};
 
@@ -2131,6 +2416,74 @@ mod test {
         submit_test_helper(|opt| opt).await
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_submit_batch() {
+        setup_test();
+
+        let port = pick_unused_port().expect("No ports free");
+        let url = format!("http://localhost:{port}").parse().unwrap();
+        let client: Client<ServerError, StaticVersion<0, 1>> = Client::new(url);
+
+        let options = Options::with_port(port).submit(options::Submit {
+            max_batch_size: 3,
+            ..Default::default()
+        });
+        let network_config = TestConfigBuilder::default().build();
+        let config = TestNetworkConfigBuilder::default()
+            .api_config(options)
+            .network_config(network_config)
+            .build();
+        let network = TestNetwork::new(config, MockSequencerVersions::new()).await;
+        // Use a separate event stream to wait for each transaction, since they may be decided in
+        // the same block.
+        let events = [
+            network.server.event_stream().await,
+            network.server.event_stream().await,
+        ];
+        client.connect(None).await;
+
+        // Submit a batch where the second transaction has an invalid namespace. The first and third
+        // transactions should still be submitted.
+        let txs = [
+            Transaction::new(NamespaceId::from(1_u32), vec![1, 2, 3]),
+            Transaction::new(NamespaceId::from(2_u32), vec![4, 5, 6]),
+        ];
+        let body = serde_json::json!([
+            txs[0],
+            { "namespace": u64::MAX, "payload": "" },
+            txs[1],
+        ]);
+        let results: Vec<SubmitResult> = client
+            .post("submit/batch")
+            .body_json(&body)
+            .unwrap()
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0], SubmitResult::Submitted(txs[0].commit()));
+        assert!(
+            matches!(&results[1], SubmitResult::Rejected(_)),
+            "{:?}",
+            results[1]
+        );
+        assert_eq!(results[2], SubmitResult::Submitted(txs[1].commit()));
+
+        // A batch larger than the limit is rejected as a whole.
+        let err = client
+            .post::<Vec<SubmitResult>>("submit/batch")
+            .body_json(&vec![txs[0].clone(); 4])
+            .unwrap()
+            .send()
+            .await
+            .unwrap_err();
+        assert_eq!(err.status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        for (mut events, tx) in events.into_iter().zip(&txs) {
+            wait_for_decide_on_handle(&mut events, tx).await;
+        }
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn state_signature_test_without_query_module() {
         state_signature_test_helper(|opt| opt).await
@@ -2217,6 +2570,46 @@ mod test {
             .unwrap();
         let expected = U256::MAX;
         assert_eq!(expected, amount.0);
+
+        // testing fee balance history: the builder pays a fee in each block, so its balance only
+        // goes down, and each change starts where the previous one left off.
+        let history = client
+            .get::<Vec<BalanceChange<FeeAmount>>>(&format!("fee-state/history/{account}"))
+            .send()
+            .await
+            .unwrap();
+        tracing::info!(?history, "fee balance history");
+        for change in &history {
+            assert_eq!(change.cause, BalanceChangeCause::FeePayment);
+            assert!(change.new < change.old, "{change:?}");
+        }
+        for pair in history.windows(2) {
+            assert!(pair[0].height < pair[1].height);
+            assert_eq!(pair[0].new, pair[1].old);
+        }
+
+        // Restricting the range returns a subset of the history.
+        let to = client
+            .get::<u64>("fee-state/block-height")
+            .send()
+            .await
+            .unwrap();
+        let partial = client
+            .get::<Vec<BalanceChange<FeeAmount>>>(&format!("fee-state/history/{account}/1/{to}"))
+            .send()
+            .await
+            .unwrap();
+        assert!(partial
+            .iter()
+            .all(|change| (1..=to).contains(&change.height)));
+        assert!(partial.iter().all(|change| history.contains(change)));
+
+        // Backwards ranges are rejected.
+        client
+            .get::<Vec<BalanceChange<FeeAmount>>>(&format!("fee-state/history/{account}/2/1"))
+            .send()
+            .await
+            .unwrap_err();
     }
 
     #[tokio::test(flavor = "multi_thread")]
@@ -2308,6 +2701,48 @@ mod test {
             .unwrap_err();
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_client_paginate() {
+        setup_test();
+
+        let port = pick_unused_port().expect("No ports free");
+        let storage = SqlDataSource::create_storage().await;
+        let options = SqlDataSource::options(&storage, Options::with_port(port));
+        let config = TestNetworkConfigBuilder::default()
+            .api_config(options)
+            .network_config(TestConfigBuilder::default().build())
+            .build();
+        let mut network = TestNetwork::new(config, MockSequencerVersions::new()).await;
+        let client =
+            client::SequencerClient::new(format!("http://localhost:{port}").parse().unwrap());
+
+        // Wait until some blocks have been decided, then stop consensus so that the paginated
+        // streams have a fixed tip to catch up with.
+        let mut height = 0;
+        while height < 5 {
+            sleep(Duration::from_secs(1)).await;
+            height = client.get_height().await.unwrap_or(0);
+        }
+        network.stop_consensus().await;
+
+        // Following cursors from genesis yields every header, in order, matching the headers we
+        // get one at a time.
+        let headers: Vec<Header> = client.paginate_headers(0).try_collect().await.unwrap();
+        assert!(headers.len() >= 5);
+        for (i, header) in headers.iter().enumerate() {
+            assert_eq!(header.height(), i as u64);
+            assert_eq!(*header, client.get_header(i as u64).await.unwrap());
+        }
+
+        // A time window which covers the whole chain yields the same headers, page by page.
+        let window: Vec<Header> = client
+            .paginate_header_window(0, u64::MAX)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(window, headers);
+    }
+
     async fn run_catchup_test(url_suffix: &str) {
         setup_test();
 
@@ -3251,6 +3686,120 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_explorer_validators() {
+        setup_test();
+        let epoch_height = 20;
+
+        type PosVersion = SequencerVersions<StaticVersion<0, 3>, StaticVersion<0, 0>>;
+
+        let network_config = TestConfigBuilder::default()
+            .epoch_height(epoch_height)
+            .build();
+
+        let api_port = pick_unused_port().expect("No ports free for query service");
+
+        const NUM_NODES: usize = 5;
+        let storage = join_all((0..NUM_NODES).map(|_| SqlDataSource::create_storage())).await;
+        let persistence: [_; NUM_NODES] = storage
+            .iter()
+            .map(<SqlDataSource as TestableSequencerDataSource>::persistence_options)
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+
+        let config = TestNetworkConfigBuilder::with_num_nodes()
+            .api_config(
+                SqlDataSource::options(&storage[0], Options::with_port(api_port))
+                    .explorer(Default::default()),
+            )
+            .network_config(network_config)
+            .persistences(persistence.clone())
+            .catchups(std::array::from_fn(|_| {
+                StatePeers::<StaticVersion<0, 1>>::from_urls(
+                    vec![format!("http://localhost:{api_port}").parse().unwrap()],
+                    Default::default(),
+                    &NoMetrics,
+                )
+            }))
+            .pos_hook::<PosVersion>(DelegationConfig::MultipleDelegators, Default::default())
+            .await
+            .unwrap()
+            .build();
+
+        let _network = TestNetwork::new(config, PosVersion::new()).await;
+        let client: Client<ServerError, SequencerApiVersion> =
+            Client::new(format!("http://localhost:{api_port}").parse().unwrap());
+
+        // Wait until epoch 2 (blocks 21 through 40) has been decided.
+        client
+            .socket("availability/stream/blocks/0")
+            .subscribe::<BlockQueryData<SeqTypes>>()
+            .await
+            .unwrap()
+            .take(45)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+
+        // The directory lists the same validators as the node API.
+        let expected = client
+            .get::<ValidatorMap>("node/validators/2")
+            .send()
+            .await
+            .unwrap();
+        let directory = client
+            .get::<ValidatorSummariesResponse>("explorer/validators/2")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(directory.epoch, 2);
+        assert_eq!(directory.validators.len(), expected.len());
+        assert!(directory
+            .validators
+            .windows(2)
+            .all(|pair| pair[0].stake >= pair[1].stake));
+        for summary in &directory.validators {
+            let validator = &expected[&summary.account];
+            assert_eq!(summary.stake, validator.stake);
+            assert_eq!(summary.commission, validator.commission);
+            assert_eq!(summary.num_delegators, validator.delegators.len());
+        }
+
+        // Every decided block in the epoch was proposed by at most one validator.
+        let mut blocks_proposed = HashSet::new();
+        for summary in &directory.validators {
+            let detail = client
+                .get::<ValidatorDetailResponse>(&format!(
+                    "explorer/validator/{}/epoch/2",
+                    summary.stake_table_key
+                ))
+                .send()
+                .await
+                .unwrap()
+                .validator_detail;
+            tracing::info!(?detail, "validator detail");
+            assert_eq!(&detail.summary, summary);
+            assert_eq!(detail.delegators.len(), summary.num_delegators);
+            assert_eq!(detail.activity.epoch, 2);
+            assert!(detail.activity.votes <= detail.activity.eligible_votes);
+            assert_eq!(detail.activity.missing_blocks, 0);
+            for height in detail.activity.blocks_proposed {
+                assert!((21..=40).contains(&height), "{height}");
+                assert!(blocks_proposed.insert(height), "{height}");
+            }
+        }
+        assert!(!blocks_proposed.is_empty());
+
+        // Unknown validators are not found.
+        let key = PubKey::generated_from_seed_indexed([1; 32], 1000).0;
+        client
+            .get::<ValidatorDetailResponse>(&format!("explorer/validator/{key}/epoch/2"))
+            .send()
+            .await
+            .unwrap_err();
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_cumulative_pos_rewards() -> anyhow::Result<()> {
         // This test registers 5 validators and multiple delegators for each validator.
@@ -4136,6 +4685,7 @@ mod test {
         let opt = Options::with_port(node_0_port).query_sql(
             Query {
                 peers: vec![format!("http://localhost:{api_port}").parse().unwrap()],
+                ..Default::default()
             },
             tmp_options(node_0_storage),
         );
@@ -4269,6 +4819,140 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fetch_missing_block_from_staked_peer() {
+        // This test verifies that a query node with no `peers` configured can still recover
+        // missing blocks, by fetching them from its staked peers over request-response.
+        //
+        // Steps:
+        // 1. Start a test network with 5 sequencer nodes. Only node 0 stores query data.
+        // 2. Take node 1 offline for an epoch.
+        // 3. Restart node 1 with the query module enabled, but without any `peers`.
+        // 4. Check that node 1 serves the blocks it missed, matching those of node 0.
+        setup_test();
+        const EPOCH_HEIGHT: u64 = 10;
+
+        type PosVersion = SequencerVersions<StaticVersion<0, 3>, StaticVersion<0, 0>>;
+
+        let network_config = TestConfigBuilder::default()
+            .epoch_height(EPOCH_HEIGHT)
+            .build();
+
+        let api_port = pick_unused_port().expect("No ports free for query service");
+        const NUM_NODES: usize = 5;
+
+        let storage = join_all((0..NUM_NODES).map(|_| SqlDataSource::create_storage())).await;
+        let persistence: [_; NUM_NODES] = storage
+            .iter()
+            .map(<SqlDataSource as TestableSequencerDataSource>::persistence_options)
+            .collect::<Vec<_>>()
+            .try_into()
+            .unwrap();
+
+        let config = TestNetworkConfigBuilder::with_num_nodes()
+            .api_config(SqlDataSource::options(
+                &storage[0],
+                Options::with_port(api_port),
+            ))
+            .network_config(network_config)
+            .persistences(persistence.clone())
+            .catchups(std::array::from_fn(|_| {
+                StatePeers::<StaticVersion<0, 1>>::from_urls(
+                    vec![format!("http://localhost:{api_port}").parse().unwrap()],
+                    Default::default(),
+                    &NoMetrics,
+                )
+            }))
+            .pos_hook::<PosVersion>(
+                DelegationConfig::MultipleDelegators,
+                hotshot_contract_adapter::stake_table::StakeTableContractVersion::V2,
+            )
+            .await
+            .unwrap()
+            .build();
+        let state = config.states()[0].clone();
+        let mut network = TestNetwork::new(config, PosVersion::new()).await;
+
+        // Take node 1 offline, and let the network make progress without it.
+        network.peers.remove(0);
+        let mut events = network.peers[1].event_stream().await;
+        wait_for_epochs(&mut events, EPOCH_HEIGHT, 1).await;
+
+        // Restart node 1 with the query module enabled, but with no peers to fetch from.
+        let node_port = pick_unused_port().expect("No ports free for query service");
+        let opt = Options::with_port(node_port).query_sql(
+            Query {
+                peers: vec![],
+                ..Default::default()
+            },
+            tmp_options(&storage[1]),
+        );
+        let node_persistence = persistence[1].clone();
+        let _node = opt
+            .serve(|metrics, consumer, storage| {
+                let cfg = network.cfg.clone();
+                async move {
+                    Ok(cfg
+                        .init_node(
+                            1,
+                            state,
+                            node_persistence,
+                            Some(StatePeers::<StaticVersion<0, 1>>::from_urls(
+                                vec![format!("http://localhost:{api_port}").parse().unwrap()],
+                                Default::default(),
+                                &NoMetrics,
+                            )),
+                            storage,
+                            &*metrics,
+                            test_helpers::STAKE_TABLE_CAPACITY_FOR_TEST,
+                            consumer,
+                            PosVersion::new(),
+                            Default::default(),
+                        )
+                        .await)
+                }
+                .boxed()
+            })
+            .await
+            .unwrap();
+
+        let peer: Client<ServerError, SequencerApiVersion> =
+            Client::new(format!("http://localhost:{api_port}").parse().unwrap());
+        peer.connect(None).await;
+        let client: Client<ServerError, SequencerApiVersion> =
+            Client::new(format!("http://localhost:{node_port}").parse().unwrap());
+        client.connect(None).await;
+
+        // Node 1 was offline for all of these blocks, so it can only get them from a staked peer.
+        for height in 1..EPOCH_HEIGHT {
+            let expected: BlockQueryData<SeqTypes> = peer
+                .get(&format!("availability/block/{height}"))
+                .send()
+                .await
+                .unwrap();
+
+            let mut retries = 0;
+            let block = loop {
+                match client
+                    .get::<BlockQueryData<SeqTypes>>(&format!("availability/block/{height}"))
+                    .send()
+                    .await
+                {
+                    Ok(block) => break block,
+                    Err(err) => {
+                        tracing::info!(height, "block not available yet: {err:#}");
+                        retries += 1;
+                        if retries > 120 {
+                            panic!("max retries reached. failed to fetch block {height}");
+                        }
+                        sleep(Duration::from_secs(1)).await;
+                    },
+                }
+            };
+            assert_eq!(block, expected);
+        }
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn test_state_reconstruction() -> anyhow::Result<()> {
         // This test verifies that a query node can successfully reconstruct its state
@@ -4344,6 +5028,7 @@ mod test {
         let opt = Options::with_port(node_0_port).query_sql(
             Query {
                 peers: vec![format!("http://localhost:{api_port}").parse().unwrap()],
+                ..Default::default()
             },
             tmp_options(node_0_storage),
         );
@@ -4835,6 +5520,90 @@ mod test {
         }
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn slow_test_explorer_account_detail() {
+        setup_test();
+
+        let port = pick_unused_port().expect("No ports free");
+
+        let url = format!("http://localhost:{port}").parse().unwrap();
+        let client: Client<ServerError, StaticVersion<0, 1>> = Client::new(url);
+
+        let storage = SqlDataSource::create_storage().await;
+        let network_config = TestConfigBuilder::default().build();
+        let config = TestNetworkConfigBuilder::default()
+            .api_config(
+                SqlDataSource::options(&storage, Options::with_port(port))
+                    .explorer(Default::default()),
+            )
+            .network_config(network_config)
+            .build();
+        let _network = TestNetwork::new(config, MockSequencerVersions::new()).await;
+
+        client.connect(None).await;
+
+        // Wait until some blocks have been decided, and give the state a moment to be stored.
+        client
+            .socket("availability/stream/blocks/0")
+            .subscribe::<BlockQueryData<SeqTypes>>()
+            .await
+            .unwrap()
+            .take(4)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        sleep(Duration::from_secs(5)).await;
+
+        // The builder pays for every block, so its account should have a balance and a list of
+        // blocks it paid for.
+        let account = TestConfig::<5>::builder_key().fee_account();
+        let detail = client
+            .get::<AccountDetailResponse>(&format!("explorer/account/{account}"))
+            .send()
+            .await
+            .unwrap()
+            .account_detail;
+        assert_eq!(detail.address, account.0);
+        assert!(detail.fee_balance > 0.into(), "{detail:?}");
+        assert!(!detail.fee_payer_blocks.is_empty(), "{detail:?}");
+        assert!(detail
+            .fee_payer_blocks
+            .windows(2)
+            .all(|pair| pair[0] > pair[1]));
+        assert!(detail
+            .fee_payer_blocks
+            .iter()
+            .all(|height| *height <= detail.height));
+
+        // Searching for the address should find the account.
+        let search = client
+            .get::<SearchResultResponse<SeqTypes>>(&format!("explorer/search/address/{account}"))
+            .send()
+            .await
+            .unwrap()
+            .search_results;
+        assert_eq!(search.accounts, vec![vec![account.0]]);
+
+        // An unknown account has an empty history.
+        let unknown = Address::random();
+        let detail = client
+            .get::<AccountDetailResponse>(&format!("explorer/account/{unknown}"))
+            .send()
+            .await
+            .unwrap()
+            .account_detail;
+        assert_eq!(detail.fee_balance, 0.into());
+        assert!(detail.fee_payer_blocks.is_empty());
+        assert_eq!(detail.deposits, Some(vec![]));
+
+        // An invalid address is rejected.
+        client
+            .get::<AccountDetailResponse>("explorer/account/not-an-address")
+            .send()
+            .await
+            .unwrap_err();
+    }
+
     use std::time::Instant;
 
     use rand::thread_rng;
@@ -4,6 +4,8 @@ use alloy::primitives::Address;
 use anyhow::Context;
 use async_trait::async_trait;
 use committable::Commitment;
+// re-exported here to avoid breaking changes in consumers
+pub use espresso_types::StakeTableWithEpochNumber;
 use espresso_types::{
     config::PublicNetworkConfig,
     v0::traits::{PersistenceOptions, SequencerPersistence},
@@ -11,9 +13,10 @@ use espresso_types::{
         RewardAccount, RewardAccountProof, RewardAccountQueryData, RewardAmount, RewardMerkleTree,
     },
     v0_3::{ChainConfig, Validator},
-    FeeAccount, FeeAccountProof, FeeMerkleTree, Leaf2, NodeState, PubKey, Transaction,
+    FeeAccount, FeeAccountProof, FeeMerkleTree, Header, Leaf2, NodeState, PubKey, Transaction,
+    TransactionStatus,
 };
-use futures::future::Future;
+use futures::{future::Future, stream::BoxStream};
 use hotshot::types::BLSPubKey;
 use hotshot_query_service::{
     availability::{AvailabilityDataSource, VidCommonQueryData},
@@ -21,18 +24,19 @@ use hotshot_query_service::{
     fetching::provider::{AnyProvider, QueryServiceProvider},
     node::NodeDataSource,
     status::StatusDataSource,
+    QueryError, QueryResult,
 };
 use hotshot_types::{
-    data::{EpochNumber, VidShare, ViewNumber},
+    data::{VidShare, ViewNumber},
     light_client::StateSignatureRequestBody,
     traits::{
+        block_contents::BlockHeader,
         network::ConnectedNetwork,
         node_implementation::{NodeType, Versions},
     },
     PeerConfig,
 };
 use indexmap::IndexMap;
-use serde::{Deserialize, Serialize};
 use tide_disco::Url;
 
 use super::{
@@ -81,6 +85,76 @@ pub trait SequencerDataSource:
 
     /// Instantiate a data source from command line options.
     async fn create(opt: Self::Options, provider: Provider, reset: bool) -> anyhow::Result<Self>;
+
+    /// Get the first header with a timestamp (in seconds) at or after `timestamp`.
+    ///
+    /// The default implementation binary searches over block heights, which works because header
+    /// timestamps never decrease. Backends with an index on header timestamps should override it.
+    async fn get_header_by_timestamp(&self, timestamp: u64) -> QueryResult<Header> {
+        search_headers(self, |header| header.timestamp() >= timestamp).await
+    }
+
+    /// Get the first header whose L1 head is at or after L1 block `number`.
+    ///
+    /// The default implementation binary searches over block heights, which works because the L1
+    /// head never decreases. Backends with an index on the L1 head should override it.
+    async fn get_header_by_l1_block(&self, number: u64) -> QueryResult<Header> {
+        search_headers(self, |header| header.l1_head() >= number).await
+    }
+    /// Get the heights of the most recent blocks below `before` in which `account` paid the builder
+    /// fee, newest first, up to `limit` of them.
+    ///
+    /// The default implementation scans headers backwards from `before`, which is slow for accounts
+    /// which rarely pay fees. Backends with an index on the fee account should override it.
+    async fn get_fee_payer_blocks(
+        &self,
+        account: FeeAccount,
+        before: u64,
+        limit: usize,
+    ) -> QueryResult<Vec<u64>> {
+        let mut height = before.min(NodeDataSource::block_height(self).await? as u64);
+        let mut heights = vec![];
+        while height > 0 && heights.len() < limit {
+            height -= 1;
+            let header = self
+                .get_header(height as usize)
+                .await
+                .try_resolve()
+                .map_err(|_| QueryError::Missing)?;
+            if header.fee_info().iter().any(|info| info.account == account) {
+                heights.push(height);
+            }
+        }
+        Ok(heights)
+    }
+}
+
+/// Find the first header satisfying `pred`, which must be monotonic in block height.
+async fn search_headers<D>(
+    ds: &D,
+    pred: impl Fn(&Header) -> bool + Send + Sync,
+) -> QueryResult<Header>
+where
+    D: AvailabilityDataSource<SeqTypes> + NodeDataSource<SeqTypes> + Sync,
+{
+    let mut lo = 0;
+    let mut hi = ds.block_height().await?;
+    let mut found = None;
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let header = ds
+            .get_header(mid)
+            .await
+            .try_resolve()
+            .map_err(|_| QueryError::Missing)?;
+        if pred(&header) {
+            hi = mid;
+            found = Some(header);
+        } else {
+            lo = mid + 1;
+        }
+    }
+    found.ok_or(QueryError::NotFound)
 }
 
 /// Provider for fetching missing data for the query service.
@@ -101,6 +175,26 @@ pub fn provider<V: Versions>(
 
 pub(crate) trait SubmitDataSource<N: ConnectedNetwork<PubKey>, P: SequencerPersistence> {
     fn submit(&self, tx: Transaction) -> impl Send + Future<Output = anyhow::Result<()>>;
+
+    /// Submit a batch of transactions, validating each one independently.
+    ///
+    /// Returns one result per transaction, in the same order as `txs`.
+    fn submit_batch(
+        &self,
+        txs: Vec<Transaction>,
+    ) -> impl Send + Future<Output = Vec<anyhow::Result<()>>>;
+
+    /// Get the status of a transaction submitted through this node, if it is being tracked.
+    fn tx_status(
+        &self,
+        tx: Commitment<Transaction>,
+    ) -> impl Send + Future<Output = Option<TransactionStatus>>;
+
+    /// Subscribe to status updates for a transaction submitted through this node.
+    fn subscribe_tx_status(
+        &self,
+        tx: Commitment<Transaction>,
+    ) -> impl Send + Future<Output = Option<BoxStream<'static, TransactionStatus>>>;
 }
 
 pub(crate) trait HotShotConfigDataSource {
@@ -116,13 +210,6 @@ pub(crate) trait NodeStateDataSource {
     fn node_state(&self) -> impl Send + Future<Output = NodeState>;
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(bound = "T: NodeType")]
-pub struct StakeTableWithEpochNumber<T: NodeType> {
-    pub epoch: Option<EpochNumber>,
-    pub stake_table: Vec<PeerConfig<T>>,
-}
-
 pub(crate) trait StakeTableDataSource<T: NodeType> {
     /// Get the stake table for a given epoch
     fn get_stake_table(
@@ -142,6 +229,13 @@ pub(crate) trait StakeTableDataSource<T: NodeType> {
     ) -> impl Send + Future<Output = anyhow::Result<IndexMap<Address, Validator<BLSPubKey>>>>;
 
     fn get_block_reward(&self) -> impl Send + Future<Output = anyhow::Result<RewardAmount>>;
+
+    /// Get the leaders of the given views, all of which must belong to `epoch`
+    fn get_leaders(
+        &self,
+        epoch: Option<<T as NodeType>::Epoch>,
+        views: Vec<<T as NodeType>::View>,
+    ) -> impl Send + Future<Output = anyhow::Result<Vec<<T as NodeType>::SignatureKey>>>;
 }
 
 pub(crate) trait CatchupDataSource: Sync {
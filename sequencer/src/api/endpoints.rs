@@ -1,16 +1,22 @@
 //! Sequencer-specific API endpoint handlers.
 
 use std::{
+    cmp::min,
     collections::{BTreeSet, HashMap},
     env,
     time::Duration,
 };
 
+use alloy::primitives::Address;
 use anyhow::Result;
-use committable::Committable;
+use committable::{Commitment, Committable};
 use espresso_types::{
     v0_1::{ADVZNsProof, RewardAccount, RewardMerkleTree},
-    FeeAccount, FeeMerkleTree, NamespaceId, NsProof, PubKey, Transaction,
+    AccountDetail, AccountDetailResponse, BalanceChange, BalanceChangeCause, BlockMerkleTree,
+    DelegatorStake, FeeAccount, FeeMerkleTree, L1Client, NamespaceFinalityProof, NamespaceId,
+    NamespaceStreamEntry, NsProof, PubKey, SubmitResult, Transaction, TransactionProofQueryData,
+    TxInclusionProof, ValidatorDetail, ValidatorDetailResponse, ValidatorEpochActivity,
+    ValidatorSummariesResponse, ValidatorSummary,
 };
 // re-exported here to avoid breaking changes in consumers
 // "deprecated" does not work with "pub use": https://github.com/rust-lang/rust/issues/30827
@@ -19,18 +25,22 @@ pub type ADVZNamespaceProofQueryData = espresso_types::ADVZNamespaceProofQueryDa
 #[deprecated(note = "use espresso_types::NamespaceProofQueryData")]
 pub type NamespaceProofQueryData = espresso_types::NamespaceProofQueryData;
 
-use futures::{try_join, FutureExt};
+use futures::{try_join, FutureExt, StreamExt, TryFutureExt};
+use hotshot_contract_adapter::sol_types::{LightClientStateSol, LightClientV2};
 use hotshot_query_service::{
-    availability::{self, AvailabilityDataSource, CustomSnafu, FetchBlockSnafu},
-    explorer::{self, ExplorerDataSource},
-    merklized_state::{
-        self, MerklizedState, MerklizedStateDataSource, MerklizedStateHeightPersistence, Snapshot,
+    availability::{
+        self, AvailabilityDataSource, BlockQueryData, CustomSnafu, FetchBlockSnafu,
+        InvalidTransactionIndexSnafu, QueryablePayload, VidCommonQueryData,
     },
+    explorer::{self, ExplorerDataSource, GetAccountDetailError, GetValidatorError},
+    merklized_state::{self, MerklizedStateDataSource, MerklizedStateHeightPersistence, Snapshot},
     node::{self, NodeDataSource},
-    ApiState, Error, VidCommon,
+    types::HeightIndexed,
+    ApiState, Error, QueryError, VidCommon,
 };
 use hotshot_types::{
     data::{EpochNumber, VidCommitment, VidShare, ViewNumber},
+    light_client::LightClientState,
     traits::{
         network::ConnectedNetwork,
         node_implementation::{ConsensusTime, Versions},
@@ -38,10 +48,9 @@ use hotshot_types::{
     vid::avidm::AvidMShare,
 };
 use jf_merkle_tree::MerkleTreeScheme;
-use serde::de::Error as _;
+use serde::{de::Error as _, Deserialize};
 use snafu::OptionExt;
-use tagged_base64::TaggedBase64;
-use tide_disco::{method::ReadState, Api, Error as _, StatusCode};
+use tide_disco::{method::ReadState, Api, Error as _, RequestParams, StatusCode};
 use tracing::warn;
 use vbs::version::{StaticVersion, StaticVersionType};
 use vid::avid_m::namespaced::NsAvidMScheme;
@@ -51,6 +60,7 @@ use super::{
         CatchupDataSource, HotShotConfigDataSource, NodeStateDataSource, RequestResponseDataSource,
         SequencerDataSource, StakeTableDataSource, StateSignatureDataSource, SubmitDataSource,
     },
+    validator_activity::EpochActivity,
     StorageState,
 };
 use crate::{SeqTypes, SequencerApiVersion, SequencerPersistence};
@@ -63,6 +73,7 @@ where
     Ver: 'static + StaticVersionType,
     <State as ReadState>::State: Send
         + Sync
+        + AvailabilityDataSource<SeqTypes>
         + MerklizedStateDataSource<SeqTypes, FeeMerkleTree, { FeeMerkleTree::ARITY }>
         + MerklizedStateHeightPersistence,
 {
@@ -88,6 +99,50 @@ where
             Ok(path.elem().copied())
         }
         .boxed()
+    })?
+    .get("get_fee_history", move |req, state| {
+        async move {
+            let address = req.string_param("address")?;
+            let key: FeeAccount = address
+                .parse()
+                .map_err(|_| merklized_state::Error::Custom {
+                    message: "failed to parse address".to_string(),
+                    status: StatusCode::BAD_REQUEST,
+                })?;
+            let (from, to) = balance_history_range(&req, state).await?;
+
+            let mut history = vec![];
+            for update in state.get_entry_history(key, from, to).await? {
+                // Balances only go down when an account pays a fee, but they can go up either
+                // because of a deposit or because the account is the fee recipient. Look at the
+                // header to tell which.
+                let header = state
+                    .get_header(update.height as usize)
+                    .await
+                    .try_resolve()
+                    .map_err(|_| merklized_state::Error::Custom {
+                        message: format!("header {} not available", update.height),
+                        status: StatusCode::NOT_FOUND,
+                    })?;
+                let paid = header.fee_info().iter().any(|info| info.account == key);
+                let cause = match header.chain_config().resolve() {
+                    _ if paid => BalanceChangeCause::FeePayment,
+                    Some(config) if config.fee_recipient == key => BalanceChangeCause::FeePayment,
+                    Some(_) => BalanceChangeCause::Deposit,
+                    // Without the full chain config we can't tell a deposit from a fee received
+                    // as the fee recipient.
+                    None => BalanceChangeCause::Unknown,
+                };
+                history.push(BalanceChange {
+                    height: update.height,
+                    old: update.old.unwrap_or_default(),
+                    new: update.new.unwrap_or_default(),
+                    cause,
+                });
+            }
+            Ok(history)
+        }
+        .boxed()
     })?;
     Ok(api)
 }
@@ -146,10 +201,83 @@ where
             Ok(path.elem().copied())
         }
         .boxed()
+    })?
+    .get("get_reward_history", move |req, state| {
+        async move {
+            let address = req.string_param("address")?;
+            let key: RewardAccount =
+                address
+                    .parse()
+                    .map_err(|_| merklized_state::Error::Custom {
+                        message: "failed to parse reward address".to_string(),
+                        status: StatusCode::BAD_REQUEST,
+                    })?;
+            let (from, to) = balance_history_range(&req, state).await?;
+
+            // Reward balances only ever change by accruing rewards.
+            let history = state
+                .get_entry_history(key, from, to)
+                .await?
+                .into_iter()
+                .map(|update| BalanceChange {
+                    height: update.height,
+                    old: update.old.unwrap_or_default(),
+                    new: update.new.unwrap_or_default(),
+                    cause: BalanceChangeCause::RewardAccrual,
+                })
+                .collect::<Vec<_>>();
+            Ok(history)
+        }
+        .boxed()
     })?;
     Ok(api)
 }
 
+/// The maximum number of blocks covered by a single balance history request.
+const MAX_BALANCE_HISTORY_BLOCKS: u64 = 10_000;
+
+/// Get the range of blocks covered by a balance history request.
+///
+/// If no range is given, this defaults to the most recent blocks for which state is available. If
+/// only one bound is given, it is kept and the other bound defaults to cover as many blocks as
+/// allowed, without going past the most recent block for which state is available.
+async fn balance_history_range<S>(
+    req: &RequestParams,
+    state: &S,
+) -> Result<(u64, u64), merklized_state::Error>
+where
+    S: MerklizedStateHeightPersistence + Sync,
+{
+    let (from, to) = match (
+        req.opt_integer_param::<_, u64>("from")?,
+        req.opt_integer_param::<_, u64>("to")?,
+    ) {
+        (Some(from), Some(to)) => (from, to),
+        (Some(from), None) => {
+            let last = state.get_last_state_height().await? as u64;
+            (
+                from,
+                min(from.saturating_add(MAX_BALANCE_HISTORY_BLOCKS - 1), last),
+            )
+        },
+        (None, Some(to)) => (to.saturating_sub(MAX_BALANCE_HISTORY_BLOCKS - 1), to),
+        (None, None) => {
+            let to = state.get_last_state_height().await? as u64;
+            (to.saturating_sub(MAX_BALANCE_HISTORY_BLOCKS - 1), to)
+        },
+    };
+    if to < from || to - from >= MAX_BALANCE_HISTORY_BLOCKS {
+        return Err(merklized_state::Error::Custom {
+            message: format!(
+                "invalid range {from}..={to}: range must be non-empty and cover at most \
+                 {MAX_BALANCE_HISTORY_BLOCKS} blocks"
+            ),
+            status: StatusCode::BAD_REQUEST,
+        });
+    }
+    Ok((from, to))
+}
+
 pub(super) type AvailState<N, P, D, ApiVer> = ApiState<StorageState<N, P, D, ApiVer>>;
 
 type AvailabilityApi<N, P, D, V, ApiVer> = Api<AvailState<N, P, D, V>, availability::Error, ApiVer>;
@@ -495,26 +623,504 @@ where
         })?;
     }
 
+    api.get("getheaderbytimestamp", |req, state| {
+        async move {
+            let timestamp = req.integer_param("timestamp")?;
+            Ok(state.inner().get_header_by_timestamp(timestamp).await?)
+        }
+        .boxed()
+    })?
+    .get("getheaderbyl1block", |req, state| {
+        async move {
+            let number = req.integer_param("number")?;
+            Ok(state.inner().get_header_by_l1_block(number).await?)
+        }
+        .boxed()
+    })?
+    .get("gettransactionproof", move |req, state| {
+        async move {
+            let height: usize = req.integer_param("height")?;
+            let i: u64 = req.integer_param("index")?;
+            let (block, common) = try_join!(
+                async move {
+                    state
+                        .get_block(height)
+                        .await
+                        .with_timeout(timeout)
+                        .await
+                        .context(FetchBlockSnafu {
+                            resource: height.to_string(),
+                        })
+                },
+                async move {
+                    state
+                        .get_vid_common(height)
+                        .await
+                        .with_timeout(timeout)
+                        .await
+                        .context(FetchBlockSnafu {
+                            resource: height.to_string(),
+                        })
+                }
+            )?;
+
+            // AVID-M commits to each namespace as a whole, so it has no proof for a single
+            // transaction.
+            if let VidCommon::V1(_) = common.common() {
+                return Err(availability::Error::Custom {
+                    message: format!(
+                        "block {height} uses AvidM, which cannot prove a single transaction; use \
+                         block/{height}/namespace/:namespace instead"
+                    ),
+                    status: StatusCode::NOT_IMPLEMENTED,
+                });
+            }
+            let index = block.payload().nth(block.metadata(), i as usize).context(
+                InvalidTransactionIndexSnafu {
+                    height: height as u64,
+                    index: i,
+                },
+            )?;
+            let (transaction, proof) = TxInclusionProof::new(
+                &index,
+                block.payload(),
+                common.common(),
+            )
+            .context(CustomSnafu {
+                message: format!("failed to make proof for transaction {i} in block {height}"),
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+            })?;
+            Ok(TransactionProofQueryData { proof, transaction })
+        }
+        .boxed()
+    })?;
+
+    let v1_1 = api_ver.major == 1 && api_ver.minor >= 1;
+    api.stream("stream_namespace", move |req, state| {
+        async move {
+            let height: usize = req.integer_param("height")?;
+            let ns_id = NamespaceId::from(req.integer_param::<_, u32>("namespace")?);
+            state
+                .read(|state| {
+                    async move {
+                        let blocks = state.subscribe_blocks(height).await;
+                        let common = state.subscribe_vid_common(height).await;
+                        Ok(blocks.zip(common).map(move |(block, common)| {
+                            namespace_stream_entry(&block, &common, ns_id, v1_1)
+                        }))
+                    }
+                    .boxed()
+                })
+                .await
+        }
+        .try_flatten_stream()
+        .boxed()
+    })?;
+
     Ok(api)
 }
 
+/// Build the entry for one block of a namespace stream.
+///
+/// Only proofs of correct encoding are generated here, since a proof of incorrect encoding
+/// requires collecting VID shares from the network. If the block was incorrectly encoded, the
+/// stream ends with an error, and the proof can be obtained from `block/:height/namespace/:namespace`.
+fn namespace_stream_entry(
+    block: &BlockQueryData<SeqTypes>,
+    common: &VidCommonQueryData<SeqTypes>,
+    ns_id: NamespaceId,
+    v1_1: bool,
+) -> Result<NamespaceStreamEntry, availability::Error> {
+    let height = block.height();
+    let Some(ns_index) = block.payload().ns_table().find_ns_id(&ns_id) else {
+        return Ok(NamespaceStreamEntry {
+            height,
+            proof: None,
+            common: None,
+            transactions: vec![],
+        });
+    };
+    let proof = if v1_1 {
+        NsProof::v1_1_new_with_correct_encoding(block.payload(), &ns_index, common.common())
+    } else {
+        NsProof::new(block.payload(), &ns_index, common.common())
+    }
+    .context(CustomSnafu {
+        message: format!("failed to make proof for namespace {ns_id} in block {height}"),
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+    })?;
+    Ok(NamespaceStreamEntry {
+        height,
+        transactions: proof.export_all_txs(&ns_id),
+        proof: Some(proof),
+        common: Some(common.common().clone()),
+    })
+}
+
 type ExplorerApi<N, P, D, V, ApiVer> = Api<AvailState<N, P, D, V>, explorer::Error, ApiVer>;
 
+/// The number of recent fee-paying blocks shown on an account page.
+const ACCOUNT_FEE_PAYER_BLOCKS: usize = 20;
+
+/// The number of recent finalized L1 blocks searched for deposits on an account page.
+const ACCOUNT_DEPOSITS_L1_BLOCKS: u64 = 100_000;
+
+/// The maximum number of account pages searching L1 for deposits at once.
+pub(super) const MAX_ACCOUNT_DEPOSIT_LOOKUPS: usize = 4;
+
+/// How long an account page waits for its deposits to be loaded from L1.
+const ACCOUNT_DEPOSITS_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub(super) fn explorer<N, P, D, V: Versions>(
     api_ver: semver::Version,
 ) -> Result<ExplorerApi<N, P, D, V, SequencerApiVersion>>
 where
     N: ConnectedNetwork<PubKey>,
-    D: ExplorerDataSource<SeqTypes> + Send + Sync + 'static,
+    D: ExplorerDataSource<SeqTypes>
+        + SequencerDataSource
+        + MerklizedStateDataSource<SeqTypes, FeeMerkleTree, { FeeMerkleTree::ARITY }>
+        + MerklizedStateDataSource<SeqTypes, RewardMerkleTree, { RewardMerkleTree::ARITY }>
+        + MerklizedStateHeightPersistence
+        + Send
+        + Sync
+        + 'static,
     P: SequencerPersistence,
 {
-    let api = explorer::define_api::<AvailState<N, P, D, V>, SeqTypes, _>(
+    let mut options = explorer::Options::default();
+    let extension = toml::from_str(include_str!("../../api/explorer.toml"))?;
+    options.extensions.push(extension);
+
+    let mut api = explorer::define_api_with_options::<AvailState<N, P, D, V>, SeqTypes, _>(
+        &options,
         SequencerApiVersion::instance(),
         api_ver,
     )?;
+
+    api.get("get_account_detail", |req, state| {
+        async move {
+            let address = req
+                .string_param("address")
+                .ok()
+                .and_then(|address| address.parse().ok())
+                .ok_or(GetAccountDetailError::InvalidAddress(explorer::BadQuery {}))
+                .map_err(explorer::Error::GetAccountDetail)?;
+            let account_detail = get_account_detail(state, address)
+                .await
+                .map_err(explorer::Error::GetAccountDetail)?;
+            Ok(AccountDetailResponse { account_detail })
+        }
+        .boxed()
+    })?
+    .get("get_validators", |req, state| {
+        async move {
+            let epoch = req
+                .opt_integer_param("epoch")
+                .map_err(|_| GetValidatorError::InvalidQuery(explorer::BadQuery {}))
+                .map_err(explorer::Error::GetValidator)?;
+            get_validator_summaries(state, epoch)
+                .await
+                .map_err(explorer::Error::GetValidator)
+        }
+        .boxed()
+    })?
+    .get("get_validator_detail", |req, state| {
+        async move {
+            let key = req
+                .string_param("bls_key")
+                .ok()
+                .and_then(|key| key.parse().ok())
+                .ok_or(GetValidatorError::InvalidQuery(explorer::BadQuery {}))
+                .map_err(explorer::Error::GetValidator)?;
+            let epoch = req
+                .opt_integer_param("epoch")
+                .map_err(|_| GetValidatorError::InvalidQuery(explorer::BadQuery {}))
+                .map_err(explorer::Error::GetValidator)?;
+            let validator_detail = get_validator_detail(state, key, epoch)
+                .await
+                .map_err(explorer::Error::GetValidator)?;
+            Ok(ValidatorDetailResponse { validator_detail })
+        }
+        .boxed()
+    })?;
     Ok(api)
 }
 
+async fn get_account_detail<N, P, D, V>(
+    state: &StorageState<N, P, D, V>,
+    address: Address,
+) -> Result<AccountDetail, GetAccountDetailError>
+where
+    N: ConnectedNetwork<PubKey>,
+    P: SequencerPersistence,
+    D: SequencerDataSource
+        + MerklizedStateDataSource<SeqTypes, FeeMerkleTree, { FeeMerkleTree::ARITY }>
+        + MerklizedStateDataSource<SeqTypes, RewardMerkleTree, { RewardMerkleTree::ARITY }>
+        + MerklizedStateHeightPersistence
+        + Send
+        + Sync,
+    V: Versions,
+{
+    let height = state.get_last_state_height().await? as u64;
+    let header = state
+        .get_header(height as usize)
+        .await
+        .try_resolve()
+        .map_err(|_| QueryError::Missing)?;
+
+    let fee_balance =
+        MerklizedStateDataSource::<SeqTypes, FeeMerkleTree, { FeeMerkleTree::ARITY }>::get_path(
+            state,
+            Snapshot::Index(height),
+            FeeAccount(address),
+        )
+        .await?
+        .elem()
+        .copied()
+        .unwrap_or_default();
+    let reward_balance = MerklizedStateDataSource::<
+        SeqTypes,
+        RewardMerkleTree,
+        { RewardMerkleTree::ARITY },
+    >::get_path(state, Snapshot::Index(height), RewardAccount(address))
+    .await?
+    .elem()
+    .copied()
+    .unwrap_or_default();
+
+    // Scanning the whole history of the fee contract would let a single request trigger an
+    // unbounded number of L1 queries, so only recent L1 blocks are searched for deposits. Each
+    // distinct address still costs an L1 search, so the number of searches running at once is
+    // limited, and the deposits are reported as unavailable rather than failing the whole page
+    // when L1 is busy or unreachable.
+    let node_state = state.node_state().await;
+    let (deposits, deposits_from_l1_block) =
+        match (node_state.chain_config.fee_contract, header.l1_finalized()) {
+            (Some(fee_contract), Some(finalized)) => {
+                let to = finalized.number();
+                let from = to.saturating_sub(ACCOUNT_DEPOSITS_L1_BLOCKS - 1);
+                let deposits = match state.as_ref().account_deposit_lookups.try_acquire() {
+                    Ok(_permit) => {
+                        let lookup = node_state.l1_client.get_account_deposits(
+                            fee_contract,
+                            address,
+                            from,
+                            to,
+                        );
+                        match tokio::time::timeout(ACCOUNT_DEPOSITS_TIMEOUT, lookup).await {
+                            Ok(Ok(deposits)) => Some(deposits),
+                            Ok(Err(err)) => {
+                                warn!(%address, "failed to fetch deposits from L1: {err:#}");
+                                None
+                            },
+                            Err(_) => {
+                                warn!(%address, "timed out fetching deposits from L1");
+                                None
+                            },
+                        }
+                    },
+                    Err(_) => {
+                        warn!(%address, "too many deposit lookups in progress");
+                        None
+                    },
+                };
+                (deposits, from)
+            },
+            _ => (Some(vec![]), 0),
+        };
+
+    let fee_payer_blocks = state
+        .inner()
+        .get_fee_payer_blocks(FeeAccount(address), height + 1, ACCOUNT_FEE_PAYER_BLOCKS)
+        .await?;
+
+    Ok(AccountDetail {
+        address,
+        height,
+        fee_balance,
+        reward_balance,
+        deposits,
+        deposits_from_l1_block,
+        fee_payer_blocks,
+    })
+}
+
+/// Resolve the epoch requested by a validator endpoint, defaulting to the current epoch.
+async fn validator_epoch<S: StakeTableDataSource<SeqTypes>>(
+    state: &S,
+    epoch: Option<u64>,
+) -> Result<EpochNumber, GetValidatorError> {
+    if let Some(epoch) = epoch {
+        return Ok(EpochNumber::new(epoch));
+    }
+    state
+        .get_stake_table_current()
+        .await
+        .ok()
+        .and_then(|stake_table| stake_table.epoch)
+        .ok_or_else(|| {
+            GetValidatorError::NotFound(explorer::NotFound {
+                key: "current epoch".into(),
+            })
+        })
+}
+
+async fn get_validator_summaries<S: StakeTableDataSource<SeqTypes>>(
+    state: &S,
+    epoch: Option<u64>,
+) -> Result<ValidatorSummariesResponse, GetValidatorError> {
+    let epoch = validator_epoch(state, epoch).await?;
+    let validators = state.get_validators(epoch).await.map_err(|err| {
+        tracing::info!(%epoch, "validators not available: {err:#}");
+        GetValidatorError::NotFound(explorer::NotFound {
+            key: format!("epoch {epoch}"),
+        })
+    })?;
+
+    let mut validators = validators
+        .values()
+        .map(ValidatorSummary::from)
+        .collect::<Vec<_>>();
+    validators.sort_by(|a, b| b.stake.cmp(&a.stake));
+    Ok(ValidatorSummariesResponse {
+        epoch: *epoch,
+        validators,
+    })
+}
+
+async fn get_validator_detail<N, P, D, V>(
+    state: &StorageState<N, P, D, V>,
+    key: PubKey,
+    epoch: Option<u64>,
+) -> Result<ValidatorDetail, GetValidatorError>
+where
+    N: ConnectedNetwork<PubKey>,
+    P: SequencerPersistence,
+    D: SequencerDataSource + Send + Sync,
+    V: Versions,
+{
+    let epoch = validator_epoch(state, epoch).await?;
+    let not_found = || {
+        GetValidatorError::NotFound(explorer::NotFound {
+            key: format!("validator {key} in epoch {epoch}"),
+        })
+    };
+    let validators = state.get_validators(epoch).await.map_err(|err| {
+        tracing::info!(%epoch, "validators not available: {err:#}");
+        not_found()
+    })?;
+    let validator = validators
+        .values()
+        .find(|validator| validator.stake_table_key == key)
+        .ok_or_else(not_found)?;
+
+    let mut delegators = validator
+        .delegators
+        .iter()
+        .map(|(address, stake)| DelegatorStake {
+            address: *address,
+            stake: *stake,
+        })
+        .collect::<Vec<_>>();
+    delegators.sort_by(|a, b| b.stake.cmp(&a.stake));
+
+    Ok(ValidatorDetail {
+        summary: validator.into(),
+        delegators,
+        activity: get_validator_activity(state, key, epoch).await?,
+    })
+}
+
+/// Compute the proposals and votes of a validator over the decided blocks of an epoch.
+///
+/// Activity is counted for all validators at once and cached, so each request only loads the
+/// leaves decided since the last request for the same epoch. Leaves which are not available yet
+/// are reported as missing, and counted by a later request once they have been fetched.
+async fn get_validator_activity<N, P, D, V>(
+    state: &StorageState<N, P, D, V>,
+    key: PubKey,
+    epoch: EpochNumber,
+) -> Result<ValidatorEpochActivity, GetValidatorError>
+where
+    N: ConnectedNetwork<PubKey>,
+    P: SequencerPersistence,
+    D: SequencerDataSource + Send + Sync,
+    V: Versions,
+{
+    let Some(epoch_height) = state.node_state().await.epoch_height.filter(|h| *h > 0) else {
+        return Ok(ValidatorEpochActivity {
+            epoch: *epoch,
+            ..Default::default()
+        });
+    };
+
+    let stake_table = state.get_stake_table(Some(epoch)).await.map_err(|err| {
+        tracing::info!(%epoch, "stake table not available: {err:#}");
+        GetValidatorError::NotFound(explorer::NotFound {
+            key: format!("epoch {epoch}"),
+        })
+    })?;
+    let index = stake_table
+        .iter()
+        .position(|peer| peer.stake_table_entry.stake_key == key);
+
+    // Epoch `e` contains blocks `(e - 1) * epoch_height + 1` through `e * epoch_height`.
+    let last = (*epoch).saturating_mul(epoch_height);
+    let first = last.saturating_sub(epoch_height) + 1;
+    let end = (last + 1).min(NodeDataSource::block_height(state).await? as u64);
+
+    let cache = &state.as_ref().validator_activity;
+    let mut activity = cache
+        .get(epoch)
+        .unwrap_or_else(|| EpochActivity::new(epoch, first, stake_table.len()));
+    let start = activity.next_height();
+    if start >= end {
+        return Ok(activity.validator(key, index, 0));
+    }
+
+    let leaves = state
+        .get_leaf_range(start as usize..end as usize)
+        .await
+        .map(|fetch| fetch.try_resolve().ok())
+        .collect::<Vec<_>>()
+        .await;
+    let views = leaves
+        .iter()
+        .flatten()
+        .map(|leaf| leaf.leaf().view_number())
+        .collect::<Vec<_>>();
+    let leaders = if views.is_empty() {
+        vec![]
+    } else {
+        state.get_leaders(Some(epoch), views).await.map_err(|err| {
+            tracing::warn!(%epoch, "failed to compute leaders: {err:#}");
+            GetValidatorError::from(QueryError::Error {
+                message: format!("failed to compute leaders for epoch {epoch}"),
+            })
+        })?
+    };
+
+    // Count the leaves up to the first missing one and cache the result, then count the rest for
+    // this response only.
+    let mut leaders = leaders.into_iter();
+    let mut missing_blocks = 0;
+    let mut rest = vec![];
+    for leaf in &leaves {
+        match (leaf, missing_blocks) {
+            (Some(leaf), 0) => activity.add_leaf(leaf, leaders.next().unwrap()),
+            (Some(leaf), _) => rest.push((leaf, leaders.next().unwrap())),
+            (None, _) => missing_blocks += 1,
+        }
+    }
+    if activity.next_height() > start {
+        cache.insert(activity.clone());
+    }
+    for (leaf, leader) in rest {
+        activity.add_leaf(leaf, leader);
+    }
+
+    Ok(activity.validator(key, index, missing_blocks))
+}
+
 pub(super) fn node<S>(api_ver: semver::Version) -> Result<Api<S, node::Error, StaticVersion<0, 1>>>
 where
     S: 'static + Send + Sync + ReadState,
@@ -602,8 +1208,34 @@ where
 
     Ok(api)
 }
+/// A transaction in a batch submission, whose namespace has not yet been validated.
+///
+/// This has the same serialization as [`Transaction`], but accepts any namespace ID, so that a
+/// transaction with an invalid namespace can be rejected on its own instead of failing the whole
+/// batch.
+#[derive(Debug, Deserialize)]
+struct UncheckedTransaction {
+    namespace: u64,
+    #[serde(with = "base64_bytes")]
+    payload: Vec<u8>,
+}
+
+impl TryFrom<UncheckedTransaction> for Transaction {
+    type Error = anyhow::Error;
+
+    fn try_from(tx: UncheckedTransaction) -> Result<Self> {
+        let namespace = u32::try_from(tx.namespace)
+            .map_err(|_| anyhow::anyhow!("invalid namespace {}: at most u32::MAX", tx.namespace))?;
+        Ok(Transaction::new(namespace.into(), tx.payload))
+    }
+}
+
+/// The default maximum number of transactions in a single `submit_batch` request.
+pub(super) const DEFAULT_MAX_SUBMIT_BATCH_SIZE: usize = 100;
+
 pub(super) fn submit<N, P, S, ApiVer: StaticVersionType + 'static>(
     api_ver: semver::Version,
+    max_batch_size: usize,
 ) -> Result<Api<S, Error, ApiVer>>
 where
     N: ConnectedNetwork<PubKey>,
@@ -614,21 +1246,96 @@ where
     let toml = toml::from_str::<toml::Value>(include_str!("../../api/submit.toml"))?;
     let mut api = Api::<S, Error, ApiVer>::new(toml)?;
 
-    api.with_version(api_ver).at("submit", |req, state| {
-        async move {
-            let tx = req
-                .body_auto::<Transaction, ApiVer>(ApiVer::instance())
-                .map_err(Error::from_request_error)?;
+    api.with_version(api_ver)
+        .at("submit", |req, state| {
+            async move {
+                let tx = req
+                    .body_auto::<Transaction, ApiVer>(ApiVer::instance())
+                    .map_err(Error::from_request_error)?;
 
-            let hash = tx.commit();
-            state
-                .read(|state| state.submit(tx).boxed())
-                .await
-                .map_err(|err| Error::internal(err.to_string()))?;
-            Ok(hash)
-        }
-        .boxed()
-    })?;
+                let hash = tx.commit();
+                state
+                    .read(|state| state.submit(tx).boxed())
+                    .await
+                    .map_err(|err| Error::internal(err.to_string()))?;
+                Ok(hash)
+            }
+            .boxed()
+        })?
+        .at("submit_batch", |req, state| {
+            async move {
+                let txs = req
+                    .body_auto::<Vec<UncheckedTransaction>, ApiVer>(ApiVer::instance())
+                    .map_err(Error::from_request_error)?;
+                if txs.len() > max_batch_size {
+                    return Err(Error::catch_all(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        format!(
+                            "batch of {} transactions is larger than the maximum of \
+                             {max_batch_size}",
+                            txs.len()
+                        ),
+                    ));
+                }
+
+                // Check namespaces up front. Transactions which pass are submitted together, and the
+                // results are merged back into the original order.
+                let mut results = Vec::with_capacity(txs.len());
+                let mut valid = vec![];
+                for tx in txs {
+                    match Transaction::try_from(tx) {
+                        Ok(tx) => {
+                            results.push(SubmitResult::Submitted(tx.commit()));
+                            valid.push((results.len() - 1, tx));
+                        },
+                        Err(err) => results.push(SubmitResult::Rejected(format!("{err:#}"))),
+                    }
+                }
+                let (indices, valid): (Vec<_>, Vec<_>) = valid.into_iter().unzip();
+                let submitted = state.read(|state| state.submit_batch(valid).boxed()).await;
+                for (i, res) in indices.into_iter().zip(submitted) {
+                    if let Err(err) = res {
+                        results[i] = SubmitResult::Rejected(format!("{err:#}"));
+                    }
+                }
+                Ok(results)
+            }
+            .boxed()
+        })?
+        .at("status", |req, state| {
+            async move {
+                let hash: Commitment<Transaction> =
+                    req.blob_param("hash").map_err(Error::from_request_error)?;
+                state
+                    .read(|state| state.tx_status(hash).boxed())
+                    .await
+                    .ok_or_else(|| {
+                        Error::catch_all(
+                            StatusCode::NOT_FOUND,
+                            format!("transaction {hash} is not being tracked"),
+                        )
+                    })
+            }
+            .boxed()
+        })?
+        .stream("stream_status", |req, state| {
+            async move {
+                let hash: Commitment<Transaction> =
+                    req.blob_param("hash").map_err(Error::from_request_error)?;
+                let updates = state
+                    .read(|state| state.subscribe_tx_status(hash).boxed())
+                    .await
+                    .ok_or_else(|| {
+                        Error::catch_all(
+                            StatusCode::NOT_FOUND,
+                            format!("transaction {hash} is not being tracked"),
+                        )
+                    })?;
+                Ok::<_, Error>(updates.map(Ok))
+            }
+            .try_flatten_stream()
+            .boxed()
+        })?;
 
     Ok(api)
 }
@@ -850,30 +1557,140 @@ where
 
 type MerklizedStateApi<N, P, D, V, ApiVer> =
     Api<AvailState<N, P, D, V>, merklized_state::Error, ApiVer>;
-pub(super) fn merklized_state<N, P, D, S, V: Versions, const ARITY: usize>(
+
+pub(super) fn block_state<N, P, D, V: Versions>(
     api_ver: semver::Version,
+    light_client: Option<Address>,
 ) -> Result<MerklizedStateApi<N, P, D, V, SequencerApiVersion>>
 where
     N: ConnectedNetwork<PubKey>,
-    D: MerklizedStateDataSource<SeqTypes, S, ARITY>
+    D: SequencerDataSource
+        + MerklizedStateDataSource<SeqTypes, BlockMerkleTree, { BlockMerkleTree::ARITY }>
+        + MerklizedStateHeightPersistence
         + Send
         + Sync
-        + MerklizedStateHeightPersistence
         + 'static,
-    S: MerklizedState<SeqTypes, ARITY>,
     P: SequencerPersistence,
-    for<'a> <S::Commit as TryFrom<&'a TaggedBase64>>::Error: std::fmt::Display,
 {
-    let api = merklized_state::define_api::<
+    let mut options = merklized_state::Options::default();
+    let extension = toml::from_str(include_str!("../../api/block_state.toml"))?;
+    options.extensions.push(extension);
+
+    let mut api = merklized_state::define_api::<
         AvailState<N, P, D, V>,
         SeqTypes,
-        S,
+        BlockMerkleTree,
         SequencerApiVersion,
-        ARITY,
-    >(&Default::default(), api_ver)?;
+        { BlockMerkleTree::ARITY },
+    >(&options, api_ver.clone())?;
+
+    let v1_1 = api_ver.major == 1 && api_ver.minor >= 1;
+    api.get("get_namespace_finality_proof", move |req, state| {
+        async move {
+            let height = req.integer_param("height")?;
+            let ns_id = NamespaceId::from(req.integer_param::<_, u32>("namespace")?);
+            let (anchor, light_client_state) = match req.opt_integer_param("anchor")? {
+                Some(anchor) => (anchor, None),
+                None => {
+                    // Only read the configured contract, so that callers cannot make this node
+                    // query arbitrary L1 contracts.
+                    let address = light_client.ok_or_else(|| merklized_state::Error::Custom {
+                        message: "no light client contract is configured".to_string(),
+                        status: StatusCode::NOT_IMPLEMENTED,
+                    })?;
+                    let light_client_state =
+                        read_light_client_state(&state.node_state().await.l1_client, address)
+                            .await
+                            .map_err(|err| merklized_state::Error::Custom {
+                                message: format!("{err:#}"),
+                                status: StatusCode::BAD_GATEWAY,
+                            })?;
+                    (light_client_state.block_height, Some(light_client_state))
+                },
+            };
+            namespace_finality_proof(state, height, ns_id, anchor, light_client_state, v1_1).await
+        }
+        .boxed()
+    })?;
     Ok(api)
 }
 
+/// Read the finalized state of the light client contract at `address`.
+async fn read_light_client_state(
+    l1: &L1Client,
+    address: Address,
+) -> anyhow::Result<LightClientState> {
+    let contract = LightClientV2::new(address, &l1.provider);
+    let state: LightClientStateSol = contract
+        .finalizedState()
+        .call()
+        .await
+        .map_err(|err| anyhow::anyhow!("reading finalized light client state: {err}"))?
+        .into();
+    Ok(state.into())
+}
+
+async fn namespace_finality_proof<N, P, D, V>(
+    state: &StorageState<N, P, D, V>,
+    height: u64,
+    ns_id: NamespaceId,
+    anchor: u64,
+    light_client_state: Option<LightClientState>,
+    v1_1: bool,
+) -> Result<NamespaceFinalityProof, merklized_state::Error>
+where
+    N: ConnectedNetwork<PubKey>,
+    P: SequencerPersistence,
+    D: SequencerDataSource
+        + MerklizedStateDataSource<SeqTypes, BlockMerkleTree, { BlockMerkleTree::ARITY }>
+        + Send
+        + Sync,
+    V: Versions,
+{
+    if height >= anchor {
+        return Err(merklized_state::Error::Custom {
+            message: format!("block {height} is not finalized as of anchor {anchor}"),
+            status: StatusCode::BAD_REQUEST,
+        });
+    }
+
+    let missing = |what: &str, height: u64| merklized_state::Error::Custom {
+        message: format!("{what} {height} not available"),
+        status: StatusCode::NOT_FOUND,
+    };
+    let anchor_header = state
+        .get_header(anchor as usize)
+        .await
+        .try_resolve()
+        .map_err(|_| missing("header", anchor))?;
+    let block = state
+        .get_block(height as usize)
+        .await
+        .try_resolve()
+        .map_err(|_| missing("block", height))?;
+    let common = state
+        .get_vid_common(height as usize)
+        .await
+        .try_resolve()
+        .map_err(|_| missing("VID common", height))?;
+    let block_merkle_proof = state.get_path(Snapshot::Index(anchor), height).await?;
+
+    let entry = namespace_stream_entry(&block, &common, ns_id, v1_1).map_err(|err| {
+        merklized_state::Error::Custom {
+            message: err.to_string(),
+            status: err.status(),
+        }
+    })?;
+    Ok(NamespaceFinalityProof {
+        light_client_state,
+        anchor: anchor_header,
+        block_merkle_proof,
+        header: block.header().clone(),
+        namespace_proof: entry.proof,
+        vid_common: common.common().clone(),
+    })
+}
+
 pub(super) fn config<S, ApiVer: StaticVersionType + 'static>(
     _: ApiVer,
     api_ver: semver::Version,
@@ -0,0 +1,115 @@
+//! OpenAPI description of the sequencer API, served at `/v1/openapi.json`.
+
+use anyhow::{bail, Result};
+use futures::FutureExt;
+use hotshot_query_service::openapi::{
+    load_spec, OpenApi, AVAILABILITY_API, EXPLORER_API, NODE_API, STATE_API, STATUS_API,
+};
+use serde_json::json;
+use tide_disco::{method::ReadState, Api};
+use vbs::version::StaticVersionType;
+
+/// The path at which the OpenAPI document is served, under `/v1`.
+pub(super) const OPENAPI_MODULE: &str = "openapi.json";
+
+/// The specification of an API module, including sequencer-specific extensions.
+fn module_spec(module: &str) -> Result<toml::Value> {
+    let (default, extensions): (&str, &[&str]) = match module {
+        "status" => (STATUS_API, &[]),
+        "availability" => (
+            AVAILABILITY_API,
+            &[include_str!("../../api/availability.toml")],
+        ),
+        "node" => (NODE_API, &[include_str!("../../api/node.toml")]),
+        "explorer" => (EXPLORER_API, &[include_str!("../../api/explorer.toml")]),
+        "block-state" => (STATE_API, &[include_str!("../../api/block_state.toml")]),
+        "fee-state" => (STATE_API, &[include_str!("../../api/fee.toml")]),
+        "reward-state" => (STATE_API, &[include_str!("../../api/reward.toml")]),
+        "submit" => (include_str!("../../api/submit.toml"), &[]),
+        "catchup" => (include_str!("../../api/catchup.toml"), &[]),
+        "state-signature" => (include_str!("../../api/state_signature.toml"), &[]),
+        "config" => (include_str!("../../api/config.toml"), &[]),
+        _ => bail!("no API specification for module {module}"),
+    };
+    load_spec(default, extensions.iter().copied())
+}
+
+/// Build an OpenAPI document describing `modules`.
+pub(super) fn document(modules: &[&str]) -> Result<serde_json::Value> {
+    let integer = json!({ "type": "integer", "minimum": 0 });
+    let mut api = OpenApi::new("Espresso Sequencer", env!("CARGO_PKG_VERSION"))
+        .server("/v1")
+        .with_query_service_schemas()
+        .response(
+            "submit",
+            "submit",
+            json!({ "$ref": "#/components/schemas/TaggedBase64" }),
+        )
+        .response("status", "block_height", integer.clone())
+        .response("node", "block_height", integer.clone())
+        .response("fee-state", "block_height", integer.clone())
+        .response("reward-state", "block_height", integer.clone())
+        .response("block-state", "block_height", integer);
+    for module in modules {
+        api = api.module(*module, module_spec(module)?);
+    }
+    api.build()
+}
+
+/// Define the API module serving the OpenAPI document for `modules`.
+pub(super) fn define_api<S, E, ApiVer>(modules: &[&str]) -> Result<Api<S, E, ApiVer>>
+where
+    S: 'static + Send + Sync + ReadState,
+    S::State: Send + Sync,
+    E: 'static + Send + Sync + tide_disco::Error,
+    ApiVer: StaticVersionType + 'static,
+{
+    let doc = document(modules)?;
+    let toml = toml::from_str::<toml::Value>(include_str!("../../api/openapi.toml"))?;
+    let mut api = Api::<S, E, ApiVer>::new(toml)?;
+    api.with_version("1.0.0".parse().unwrap());
+    api.get("openapi", move |_, _| {
+        {
+            let doc = doc.clone();
+            async move { Ok(doc) }
+        }
+        .boxed()
+    })?;
+    Ok(api)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_openapi_document() {
+        let modules = [
+            "status",
+            "availability",
+            "node",
+            "explorer",
+            "block-state",
+            "fee-state",
+            "reward-state",
+            "submit",
+            "catchup",
+            "state-signature",
+            "config",
+        ];
+        let doc = document(&modules).unwrap();
+        assert_eq!(doc["openapi"], "3.0.3");
+        assert_eq!(doc["tags"].as_array().unwrap().len(), modules.len());
+
+        // Sequencer-specific extensions are included.
+        let proof = &doc["paths"]["/availability/block/{height}/namespace/{namespace}"]["get"];
+        assert_eq!(proof["parameters"][1]["name"], "namespace");
+        let submit = &doc["paths"]["/submit/submit"]["post"];
+        assert_eq!(
+            submit["responses"]["200"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/TaggedBase64"
+        );
+
+        document(&["nonexistent"]).unwrap_err();
+    }
+}
@@ -1,12 +1,15 @@
 //! Sequencer-specific API options and initialization.
 
-use std::sync::Arc;
+use std::{net::IpAddr, path::PathBuf, sync::Arc, time::Duration};
 
+use alloy::primitives::Address;
 use anyhow::{bail, Context};
-use clap::Parser;
+use clap::{ArgGroup, Parser};
+use derivative::Derivative;
 use espresso_types::{
-    v0::traits::{EventConsumer, NullEventConsumer, PersistenceOptions, SequencerPersistence},
-    BlockMerkleTree, PubKey,
+    parse_duration,
+    v0::traits::{EventConsumer, PersistenceOptions, SequencerPersistence},
+    PubKey,
 };
 use futures::{
     channel::oneshot,
@@ -32,7 +35,10 @@ use super::{
         provider, CatchupDataSource, HotShotConfigDataSource, NodeStateDataSource, Provider,
         SequencerDataSource, StateSignatureDataSource, SubmitDataSource,
     },
-    endpoints, fs, sql,
+    endpoints, fs, openapi,
+    quota::{KeySource, QuotaLimiter, QuotaListener, DEFAULT_MAX_CLIENTS},
+    sql,
+    tx_status::{DEFAULT_TX_STATUS_CAPACITY, DEFAULT_TX_STATUS_TIMEOUT},
     update::ApiEventConsumer,
     ApiState, StorageState,
 };
@@ -55,6 +61,7 @@ pub struct Options {
     pub config: Option<Config>,
     pub hotshot_events: Option<HotshotEvents>,
     pub explorer: Option<Explorer>,
+    pub quotas: Option<Quotas>,
     pub storage_fs: Option<persistence::fs::Options>,
     pub storage_sql: Option<persistence::sql::Options>,
 }
@@ -70,6 +77,7 @@ impl From<Http> for Options {
             config: None,
             hotshot_events: None,
             explorer: None,
+            quotas: None,
             storage_fs: None,
             storage_sql: None,
         }
@@ -132,6 +140,12 @@ impl Options {
         self
     }
 
+    /// Enforce per-client quotas on the HTTP API.
+    pub fn quotas(mut self, opt: Quotas) -> Self {
+        self.quotas = Some(opt);
+        self
+    }
+
     /// Whether these options will run the query API.
     pub fn has_query_module(&self) -> bool {
         self.query.is_some() && (self.storage_fs.is_some() || self.storage_sql.is_some())
@@ -159,11 +173,14 @@ impl Options {
         // allows the web server to start before initialization can complete, since initialization
         // can take a long time (and is dependent on other nodes).
         let (send_ctx, recv_ctx) = oneshot::channel();
-        let state = ApiState::new(async move {
+        let mut state = ApiState::new(async move {
             recv_ctx
                 .await
                 .expect("context initialized and sent over channel")
         });
+        if let Some(submit) = &self.submit {
+            state = state.with_tx_status(submit.tx_status_timeout, submit.tx_status_capacity);
+        }
         let mut tasks = TaskList::default();
 
         // The server state type depends on whether we are running a query or status API or not, so
@@ -205,6 +222,8 @@ impl Options {
                 ds,
                 state.clone(),
             )));
+            let consumer = Box::new(state.tx_status.clone());
+            let mut modules = vec!["status"];
 
             // Initialize v0 and v1 status API.
             register_api("status", &mut app, move |ver| {
@@ -212,18 +231,22 @@ impl Options {
                     .context("failed to define status api")
             })?;
 
-            self.init_hotshot_modules(&mut app)?;
+            self.init_hotshot_modules(&mut app, &mut modules)?;
 
             if self.hotshot_events.is_some() {
                 self.init_and_spawn_hotshot_event_streaming_module(state, &mut tasks)?;
             }
 
-            tasks.spawn(
-                "API server",
-                self.listen(self.http.port, app, SequencerApiVersion::instance()),
-            );
+            self.spawn_server(
+                app,
+                SequencerApiVersion::instance(),
+                &*metrics,
+                &modules,
+                &mut tasks,
+            )
+            .await?;
 
-            (metrics, Box::new(NullEventConsumer), None)
+            (metrics, consumer, None)
         } else {
             // If no status or availability API is requested, we don't need metrics or a query
             // service data source. The only app state is the HotShot handle, which we use to
@@ -232,19 +255,25 @@ impl Options {
             // If we have no availability API, we cannot load a saved leaf from local storage,
             // so we better have been provided the leaf ahead of time if we want it at all.
             let mut app = App::<_, Error>::with_state(AppState::from(state.clone()));
+            let consumer = Box::new(state.tx_status.clone());
+            let mut modules = vec![];
 
-            self.init_hotshot_modules(&mut app)?;
+            self.init_hotshot_modules(&mut app, &mut modules)?;
 
             if self.hotshot_events.is_some() {
                 self.init_and_spawn_hotshot_event_streaming_module(state, &mut tasks)?;
             }
 
-            tasks.spawn(
-                "API server",
-                self.listen(self.http.port, app, SequencerApiVersion::instance()),
-            );
+            self.spawn_server(
+                app,
+                SequencerApiVersion::instance(),
+                &NoMetrics,
+                &modules,
+                &mut tasks,
+            )
+            .await?;
 
-            (Box::new(NoMetrics), Box::new(NullEventConsumer), None)
+            (Box::new(NoMetrics), consumer, None)
         };
 
         let ctx = init_context(metrics, consumer, storage.clone()).await?;
@@ -260,6 +289,7 @@ impl Options {
         ds: D,
         state: ApiState<N, P, V>,
         bind_version: SequencerApiVersion,
+        modules: &mut Vec<&'static str>,
     ) -> anyhow::Result<(
         Box<dyn Metrics>,
         Arc<StorageState<N, P, D, V>>,
@@ -276,6 +306,7 @@ impl Options {
         let mut app = App::<_, Error>::with_state(api_state);
 
         // Initialize v0 and v1 status API.
+        modules.push("status");
         register_api("status", &mut app, move |ver| {
             status::define_api(&Default::default(), SequencerApiVersion::instance(), ver)
                 .context("failed to define status api")
@@ -287,34 +318,40 @@ impl Options {
         // - `availability/v0/leaf/0` returns the old `Leaf1` type for backward compatibility.
         // - `availability/v1/leaf/0` returns the new `Leaf2` type
 
+        modules.push("availability");
         register_api("availability", &mut app, move |ver| {
             endpoints::availability(ver).context("failed to define availability api")
         })?;
 
+        modules.push("node");
         register_api("node", &mut app, move |ver| {
             endpoints::node(ver).context("failed to define node api")
         })?;
 
         // Initialize submit API
-        if self.submit.is_some() {
+        if let Some(submit) = self.submit {
+            modules.push("submit");
             register_api("submit", &mut app, move |ver| {
-                endpoints::submit::<_, _, _, SequencerApiVersion>(ver)
+                endpoints::submit::<_, _, _, SequencerApiVersion>(ver, submit.max_batch_size)
                     .context("failed to define submit api")
             })?;
         }
 
         tracing::info!("initializing catchup API");
 
+        modules.push("catchup");
         register_api("catchup", &mut app, move |ver| {
             endpoints::catchup(bind_version, ver).context("failed to define catchup api")
         })?;
 
+        modules.push("state-signature");
         register_api("state-signature", &mut app, move |ver| {
             endpoints::state_signature(bind_version, ver)
                 .context("failed to define state signature api")
         })?;
 
         if self.config.is_some() {
+            modules.push("config");
             register_api("config", &mut app, move |ver| {
                 endpoints::config(bind_version, ver).context("failed to define config api")
             })?;
@@ -338,25 +375,28 @@ impl Options {
         N: ConnectedNetwork<PubKey>,
         P: SequencerPersistence,
     {
-        let ds = <fs::DataSource as SequencerDataSource>::create(
-            mod_opt,
-            provider::<V>(query_opt.peers, bind_version),
-            false,
-        )
-        .await?;
+        let mut provider = provider::<V>(query_opt.peers, bind_version);
+        if !query_opt.disable_p2p_fetching {
+            provider = with_p2p_provider(provider, &state);
+        }
+        let ds = <fs::DataSource as SequencerDataSource>::create(mod_opt, provider.clone(), false)
+            .await?;
 
         // Get the inner storage from the data source
         let inner_storage = ds.inner();
 
+        let mut modules = vec![];
         let (metrics, ds, app) = self
-            .init_app_modules(ds, state.clone(), bind_version)
+            .init_app_modules(ds, state.clone(), bind_version, &mut modules)
             .await?;
+        provider.register_metrics(&*metrics);
 
         if self.hotshot_events.is_some() {
             self.init_and_spawn_hotshot_event_streaming_module(state, tasks)?;
         }
 
-        tasks.spawn("API server", self.listen(self.http.port, app, bind_version));
+        self.spawn_server(app, bind_version, &*metrics, &modules, tasks)
+            .await?;
         Ok((
             metrics,
             Box::new(ApiEventConsumer::from(ds)),
@@ -380,6 +420,7 @@ impl Options {
         N: ConnectedNetwork<PubKey>,
         P: SequencerPersistence,
     {
+        let light_client_address = query_opt.light_client_address;
         let mut provider = Provider::default();
 
         // Use the database itself as a fetching provider: sometimes we can fetch data that is
@@ -390,14 +431,21 @@ impl Options {
             tracing::info!("will fetch missing data from {peer}");
             provider = provider.with_provider(QueryServiceProvider::new(peer, bind_version));
         }
+        // Finally, fetch missing data from staked peers over the consensus network.
+        if !query_opt.disable_p2p_fetching {
+            provider = with_p2p_provider(provider, &state);
+        }
 
-        let ds = sql::DataSource::create(mod_opt.clone(), provider, false).await?;
+        let ds = sql::DataSource::create(mod_opt.clone(), provider.clone(), false).await?;
         let inner_storage = ds.inner();
+        let mut modules = vec![];
         let (metrics, ds, mut app) = self
-            .init_app_modules(ds, state.clone(), bind_version)
+            .init_app_modules(ds, state.clone(), bind_version, &mut modules)
             .await?;
+        provider.register_metrics(&*metrics);
 
         if self.explorer.is_some() {
+            modules.push("explorer");
             register_api("explorer", &mut app, move |ver| {
                 endpoints::explorer(ver).context("failed to define explorer api")
             })?;
@@ -405,17 +453,20 @@ impl Options {
 
         // Initialize merklized state module for block merkle tree
 
+        modules.push("block-state");
         register_api("block-state", &mut app, move |ver| {
-            endpoints::merklized_state::<N, P, _, BlockMerkleTree, _, 3>(ver)
+            endpoints::block_state(ver, light_client_address)
                 .context("failed to define block-state api")
         })?;
 
         // Initialize merklized state module for fee merkle tree
 
+        modules.push("fee-state");
         register_api("fee-state", &mut app, move |ver| {
             endpoints::fee::<_, SequencerApiVersion>(ver).context("failed to define fee-state api")
         })?;
 
+        modules.push("reward-state");
         register_api("reward-state", &mut app, move |ver| {
             endpoints::reward::<_, SequencerApiVersion>(ver)
                 .context("failed to define reward-state api")
@@ -433,10 +484,14 @@ impl Options {
             self.init_and_spawn_hotshot_event_streaming_module(state, tasks)?;
         }
 
-        tasks.spawn(
-            "API server",
-            self.listen(self.http.port, app, SequencerApiVersion::instance()),
-        );
+        self.spawn_server(
+            app,
+            SequencerApiVersion::instance(),
+            &*metrics,
+            &modules,
+            tasks,
+        )
+        .await?;
         Ok((
             metrics,
             Box::new(ApiEventConsumer::from(ds)),
@@ -449,7 +504,11 @@ impl Options {
     /// This function adds the `submit`, `state`, and `state_signature` API modules to the given
     /// app. These modules only require a HotShot handle as state, and thus they work with any data
     /// source, so initialization is the same no matter what mode the service is running in.
-    fn init_hotshot_modules<N, P, S>(&self, app: &mut App<S, Error>) -> anyhow::Result<()>
+    fn init_hotshot_modules<N, P, S>(
+        &self,
+        app: &mut App<S, Error>,
+        modules: &mut Vec<&'static str>,
+    ) -> anyhow::Result<()>
     where
         S: 'static + Send + Sync + ReadState,
         P: SequencerPersistence,
@@ -464,9 +523,10 @@ impl Options {
     {
         let bind_version = SequencerApiVersion::instance();
         // Initialize submit API
-        if self.submit.is_some() {
+        if let Some(submit) = self.submit {
+            modules.push("submit");
             register_api("submit", app, move |ver| {
-                endpoints::submit::<_, _, _, SequencerApiVersion>(ver)
+                endpoints::submit::<_, _, _, SequencerApiVersion>(ver, submit.max_batch_size)
                     .context("failed to define submit api")
             })?;
         }
@@ -475,17 +535,20 @@ impl Options {
         if self.catchup.is_some() {
             tracing::info!("initializing state API");
 
+            modules.push("catchup");
             register_api("catchup", app, move |ver| {
                 endpoints::catchup(bind_version, ver).context("failed to define catchup api")
             })?;
         }
 
+        modules.push("state-signature");
         register_api("state-signature", app, move |ver| {
             endpoints::state_signature(bind_version, ver)
                 .context("failed to define state signature api")
         })?;
 
         if self.config.is_some() {
+            modules.push("config");
             register_api("config", app, move |ver| {
                 endpoints::config(bind_version, ver).context("failed to define config api")
             })?;
@@ -530,17 +593,67 @@ impl Options {
                 self.hotshot_events.unwrap().events_service_port,
                 app,
                 SequencerApiVersion::instance(),
+                None,
             ),
         );
 
         Ok(())
     }
 
+    /// Spawn the main API server, enforcing quotas if requested.
+    ///
+    /// `modules` lists the API modules registered in `app`, which are described in the OpenAPI
+    /// document served by the app.
+    async fn spawn_server<S, E, ApiVer>(
+        &self,
+        mut app: App<S, E>,
+        bind_version: ApiVer,
+        metrics: &dyn Metrics,
+        modules: &[&'static str],
+        tasks: &mut TaskList,
+    ) -> anyhow::Result<()>
+    where
+        S: Send + Sync + 'static + ReadState,
+        S::State: Send + Sync,
+        E: Send + Sync + tide_disco::Error,
+        ApiVer: StaticVersionType + 'static,
+    {
+        app.register_module(
+            openapi::OPENAPI_MODULE,
+            openapi::define_api::<_, E, ApiVer>(modules)
+                .context("failed to define OpenAPI document")?,
+        )?;
+
+        let quotas = match &self.quotas {
+            Some(opt) => {
+                let source = opt.source()?;
+                let keys = source.load().await?;
+                let quotas = Arc::new(
+                    QuotaLimiter::new(keys, metrics)
+                        .with_trusted_proxies(opt.trusted_proxies.iter().copied())
+                        .with_max_clients(opt.max_anonymous_clients),
+                );
+                tasks.spawn(
+                    "API key reload",
+                    quotas.clone().maintain(source, opt.reload_interval),
+                );
+                Some(quotas)
+            },
+            None => None,
+        };
+        tasks.spawn(
+            "API server",
+            self.listen(self.http.port, app, bind_version, quotas),
+        );
+        Ok(())
+    }
+
     fn listen<S, E, ApiVer>(
         &self,
         port: u16,
         app: App<S, E>,
         bind_version: ApiVer,
+        quotas: Option<Arc<QuotaLimiter>>,
     ) -> impl Future<Output = anyhow::Result<()>>
     where
         S: Send + Sync + 'static,
@@ -550,7 +663,13 @@ impl Options {
         let max_connections = self.http.max_connections;
 
         async move {
-            if let Some(limit) = max_connections {
+            if let Some(quotas) = quotas {
+                app.serve(
+                    QuotaListener::with_port(port, quotas, max_connections),
+                    bind_version,
+                )
+                .await?;
+            } else if let Some(limit) = max_connections {
                 app.serve(RateLimitListener::with_port(port, limit), bind_version)
                     .await?;
             } else {
@@ -591,8 +710,51 @@ impl Http {
 }
 
 /// Options for the submission API module.
-#[derive(Parser, Clone, Copy, Debug, Default)]
-pub struct Submit;
+#[derive(Parser, Clone, Copy, Debug)]
+pub struct Submit {
+    /// How long to wait for a submitted transaction to be decided before reporting it as dropped.
+    ///
+    /// Dropped transactions are tracked for another timeout period, and reported as decided if they
+    /// are finalized in that time.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_TX_STATUS_TIMEOUT",
+        default_value = "5m",
+        value_parser = parse_duration
+    )]
+    pub tx_status_timeout: Duration,
+
+    /// The maximum number of submitted transactions to track the status of at once.
+    ///
+    /// When this many transactions are tracked, the one closest to expiring is forgotten to make
+    /// room for each newly submitted transaction.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_TX_STATUS_CAPACITY",
+        default_value_t = DEFAULT_TX_STATUS_CAPACITY
+    )]
+    pub tx_status_capacity: usize,
+
+    /// The maximum number of transactions which can be submitted in a single batch.
+    ///
+    /// Larger batches are rejected as a whole.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_MAX_SUBMIT_BATCH_SIZE",
+        default_value_t = endpoints::DEFAULT_MAX_SUBMIT_BATCH_SIZE
+    )]
+    pub max_batch_size: usize,
+}
+
+impl Default for Submit {
+    fn default() -> Self {
+        Self {
+            tx_status_timeout: DEFAULT_TX_STATUS_TIMEOUT,
+            tx_status_capacity: DEFAULT_TX_STATUS_CAPACITY,
+            max_batch_size: endpoints::DEFAULT_MAX_SUBMIT_BATCH_SIZE,
+        }
+    }
+}
 
 /// Options for the status API module.
 #[derive(Parser, Clone, Copy, Debug, Default)]
@@ -612,6 +774,20 @@ pub struct Query {
     /// Peers for fetching missing data for the query service.
     #[clap(long, env = "ESPRESSO_SEQUENCER_API_PEERS", value_delimiter = ',')]
     pub peers: Vec<Url>,
+
+    /// Do not fetch missing data for the query service from consensus peers.
+    ///
+    /// By default, missing payloads, VID common data and leaves are also requested from staked
+    /// peers over the request-response protocol, which works even if no `peers` are configured.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_API_DISABLE_P2P_FETCHING")]
+    pub disable_p2p_fetching: bool,
+
+    /// Address of the light client contract on L1.
+    ///
+    /// If set, namespace finality proofs can be anchored at the latest finalized state of this
+    /// contract. Otherwise, callers must choose the anchor height themselves.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_LIGHT_CLIENT_PROXY_ADDRESS")]
+    pub light_client_address: Option<Address>,
 }
 
 /// Options for the state API module.
@@ -630,6 +806,77 @@ pub struct HotshotEvents {
 #[derive(Parser, Clone, Copy, Debug, Default)]
 pub struct Explorer;
 
+/// Options for per-client API quotas.
+///
+/// Clients present an API key in the `X-Api-Key` header, or as a bearer token in the
+/// `Authorization` header. Each key has a budget of requests and bytes per second for each route
+/// group: `submit` (the submit API), `streams` (all WebSocket streams) and `availability`
+/// (everything else). Clients without a key get the budgets of the anonymous tier, separately for
+/// each IP address. Requests over budget get a 429 response with a `Retry-After` header.
+#[derive(Parser, Clone, Derivative)]
+#[derivative(Debug)]
+#[clap(group(ArgGroup::new("api-keys").required(true).args(["api_keys_file", "api_keys_db"])))]
+pub struct Quotas {
+    /// TOML file containing API keys and their budgets.
+    ///
+    /// The file has an optional `[anonymous]` table and a `[[keys]]` array, where each key has a
+    /// `name` (used in metrics) and a `key`. Budgets are given per route group, like
+    /// `submit = { requests_per_second = 10, bytes_per_second = 1000000 }`. Missing budgets are
+    /// unlimited.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_API_KEYS_FILE")]
+    pub api_keys_file: Option<PathBuf>,
+
+    /// URL of a Postgres database containing API keys and their budgets.
+    ///
+    /// Keys are read from a table `api_keys` with columns `name TEXT`, `key TEXT`,
+    /// `route_group TEXT`, `requests_per_second DOUBLE PRECISION` and `bytes_per_second BIGINT`,
+    /// with one row per key and route group. Rows with a `NULL` key define the anonymous tier.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_API_KEYS_DB")]
+    #[derivative(Debug = "ignore")]
+    pub api_keys_db: Option<String>,
+
+    /// How often to reload API keys.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_API_KEYS_RELOAD_INTERVAL",
+        default_value = "1m",
+        value_parser = parse_duration
+    )]
+    pub reload_interval: Duration,
+
+    /// Addresses of reverse proxies in front of the API.
+    ///
+    /// Anonymous clients connecting through one of these proxies are identified by the
+    /// `X-Forwarded-For` header instead of the address of the proxy. The header is ignored for all
+    /// other connections.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_API_TRUSTED_PROXIES",
+        value_delimiter = ','
+    )]
+    pub trusted_proxies: Vec<IpAddr>,
+
+    /// Maximum number of anonymous clients given their own budget.
+    ///
+    /// Beyond this, new anonymous clients share one budget until idle clients are forgotten.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_API_MAX_ANONYMOUS_CLIENTS",
+        default_value_t = DEFAULT_MAX_CLIENTS
+    )]
+    pub max_anonymous_clients: usize,
+}
+
+impl Quotas {
+    fn source(&self) -> anyhow::Result<KeySource> {
+        match (&self.api_keys_file, &self.api_keys_db) {
+            (Some(path), None) => Ok(KeySource::File(path.clone())),
+            (None, Some(url)) => Ok(KeySource::Postgres(url.clone())),
+            _ => bail!("exactly one of API keys file or API keys database must be given"),
+        }
+    }
+}
+
 /// Registers two versions (v0 and v1) of the same API module under the given path.
 fn register_api<E, S, F, ModuleError, ModuleVersion>(
     path: &'static str,
@@ -653,3 +900,17 @@ where
 
     Ok(())
 }
+
+/// Add consensus peers, reached over the request-response protocol, as a fetching provider.
+fn with_p2p_provider<N, P, V>(provider: Provider, state: &ApiState<N, P, V>) -> Provider
+where
+    N: ConnectedNetwork<PubKey>,
+    P: SequencerPersistence,
+    V: Versions + 'static,
+{
+    tracing::info!("will fetch missing data from consensus peers");
+    provider
+        .with_block_provider(state.clone())
+        .with_leaf_provider(state.clone())
+        .with_vid_common_provider(state.clone())
+}
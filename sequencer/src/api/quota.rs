@@ -0,0 +1,1221 @@
+//! Per-client quotas for the public HTTP API.
+//!
+//! Clients identify themselves with an API key, sent either in the `X-Api-Key` header or as a
+//! bearer token in the `Authorization` header. Each key has a budget of requests per second and
+//! bytes per second for each [`RouteGroup`]. Clients without a key fall into the anonymous tier,
+//! whose budgets apply separately to each client IP address. Requests over budget are refused with
+//! a 429 response and a `Retry-After` header, before they reach the API.
+//!
+//! Byte budgets are charged for everything the server writes to a client's connection, including
+//! chunked responses and WebSocket streams whose size is not known up front.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    io,
+    net::IpAddr,
+    path::PathBuf,
+    pin::Pin,
+    str::FromStr,
+    sync::Arc,
+    task::{Context as TaskContext, Poll},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, ensure, Context};
+use async_std::{
+    net::{TcpListener, TcpStream},
+    task,
+};
+use async_trait::async_trait;
+use derivative::Derivative;
+use futures::{
+    io::{AsyncRead, AsyncWrite},
+    StreamExt,
+};
+use hotshot_types::traits::metrics::{CounterFamily, Metrics};
+use parking_lot::{Mutex, RwLock};
+use serde::Deserialize;
+use sqlx::{postgres::PgConnection, Connection};
+use tide::{
+    http::{Request, Response, StatusCode},
+    listener::{ListenInfo, Listener, ToListener},
+    Server,
+};
+use tokio::sync::Semaphore;
+
+/// The label used in metrics for clients without an API key.
+const ANONYMOUS: &str = "anonymous";
+
+/// The label used in metrics for requests with an API key we don't know.
+const UNKNOWN_KEY: &str = "unknown";
+
+/// The default limit on the number of anonymous clients tracked individually.
+pub const DEFAULT_MAX_CLIENTS: usize = 100_000;
+
+/// A group of routes which share a budget.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    /// The `submit` API module.
+    Submit,
+    /// Streaming (WebSocket) endpoints of any module.
+    Streams,
+    /// All other requests.
+    Availability,
+}
+
+impl RouteGroup {
+    fn classify(req: &Request) -> Self {
+        // All streaming endpoints are served over WebSockets.
+        if req
+            .header("Upgrade")
+            .is_some_and(|upgrade| upgrade.last().as_str().eq_ignore_ascii_case("websocket"))
+        {
+            return Self::Streams;
+        }
+
+        // Routes look like `/<module>/...`, optionally prefixed by an API version `/v0` or `/v1`.
+        let mut segments = req
+            .url()
+            .path_segments()
+            .into_iter()
+            .flatten()
+            .filter(|segment| !segment.is_empty());
+        let mut module = segments.next();
+        if matches!(module, Some("v0" | "v1")) {
+            module = segments.next();
+        }
+        match module {
+            Some("submit") => Self::Submit,
+            _ => Self::Availability,
+        }
+    }
+}
+
+impl Display for RouteGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Submit => write!(f, "submit"),
+            Self::Streams => write!(f, "streams"),
+            Self::Availability => write!(f, "availability"),
+        }
+    }
+}
+
+impl FromStr for RouteGroup {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "submit" => Ok(Self::Submit),
+            "streams" => Ok(Self::Streams),
+            "availability" => Ok(Self::Availability),
+            _ => bail!("unknown route group {s}"),
+        }
+    }
+}
+
+/// Sustained usage allowed for one route group.
+///
+/// Clients may burst up to one second's worth of usage at once. A missing limit means unlimited.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Budget {
+    pub requests_per_second: Option<f64>,
+    /// Combined size of request and response bodies.
+    pub bytes_per_second: Option<u64>,
+}
+
+impl Budget {
+    fn validate(&self) -> anyhow::Result<()> {
+        if let Some(rate) = self.requests_per_second {
+            ensure!(
+                rate.is_finite() && rate > 0.,
+                "requests_per_second must be positive"
+            );
+        }
+        ensure!(
+            self.bytes_per_second != Some(0),
+            "bytes_per_second must be positive"
+        );
+        Ok(())
+    }
+}
+
+/// Budgets for each route group.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+pub struct Tier {
+    #[serde(default)]
+    pub submit: Budget,
+    #[serde(default)]
+    pub availability: Budget,
+    #[serde(default)]
+    pub streams: Budget,
+}
+
+impl Tier {
+    fn budget(&self, group: RouteGroup) -> Budget {
+        match group {
+            RouteGroup::Submit => self.submit,
+            RouteGroup::Streams => self.streams,
+            RouteGroup::Availability => self.availability,
+        }
+    }
+
+    fn budget_mut(&mut self, group: RouteGroup) -> &mut Budget {
+        match group {
+            RouteGroup::Submit => &mut self.submit,
+            RouteGroup::Streams => &mut self.streams,
+            RouteGroup::Availability => &mut self.availability,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        self.submit.validate().context("submit")?;
+        self.availability.validate().context("availability")?;
+        self.streams.validate().context("streams")?;
+        Ok(())
+    }
+}
+
+/// A client API key and its budgets.
+#[derive(Clone, Derivative, Deserialize)]
+#[derivative(Debug)]
+pub struct KeyConfig {
+    /// Name identifying the client in logs and metrics.
+    pub name: String,
+    #[derivative(Debug = "ignore")]
+    pub key: String,
+    #[serde(flatten)]
+    pub tier: Tier,
+}
+
+/// The full set of API keys known to the server.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct KeyStore {
+    /// Budgets for each IP address from which clients connect without an API key.
+    #[serde(default)]
+    pub anonymous: Tier,
+    #[serde(default)]
+    pub keys: Vec<KeyConfig>,
+}
+
+impl KeyStore {
+    fn validate(&self) -> anyhow::Result<()> {
+        self.anonymous.validate().context("anonymous tier")?;
+        let mut names = HashSet::new();
+        let mut keys = HashSet::new();
+        for key in &self.keys {
+            ensure!(!key.key.is_empty(), "empty API key for {}", key.name);
+            ensure!(
+                key.name != ANONYMOUS && key.name != UNKNOWN_KEY,
+                "API key name {} is reserved",
+                key.name
+            );
+            ensure!(
+                names.insert(&key.name),
+                "duplicate API key name {}",
+                key.name
+            );
+            ensure!(
+                keys.insert(&key.key),
+                "API key for {} is not unique",
+                key.name
+            );
+            key.tier.validate().with_context(|| key.name.clone())?;
+        }
+        Ok(())
+    }
+}
+
+/// Where to load API keys from.
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
+pub enum KeySource {
+    /// A TOML file in the format of [`KeyStore`].
+    File(PathBuf),
+    /// A Postgres database with an `api_keys` table.
+    Postgres(#[derivative(Debug = "ignore")] String),
+}
+
+impl KeySource {
+    pub async fn load(&self) -> anyhow::Result<KeyStore> {
+        let store = match self {
+            Self::File(path) => {
+                let contents = std::fs::read_to_string(path)
+                    .with_context(|| format!("reading API keys from {}", path.display()))?;
+                toml::from_str(&contents)
+                    .with_context(|| format!("parsing API keys from {}", path.display()))?
+            },
+            Self::Postgres(url) => load_db(url).await?,
+        };
+        store.validate().context("invalid API key configuration")?;
+        Ok(store)
+    }
+}
+
+/// Load API keys from the `api_keys` table, which has one row per key and route group.
+///
+/// Rows with a `NULL` key define the anonymous tier.
+async fn load_db(url: &str) -> anyhow::Result<KeyStore> {
+    let mut conn = PgConnection::connect(url)
+        .await
+        .context("connecting to API key database")?;
+    let rows: Vec<(String, Option<String>, String, Option<f64>, Option<i64>)> = sqlx::query_as(
+        "SELECT name, key, route_group, requests_per_second, bytes_per_second FROM api_keys",
+    )
+    .fetch_all(&mut conn)
+    .await
+    .context("reading API keys")?;
+    conn.close().await?;
+
+    let mut store = KeyStore::default();
+    let mut keys = BTreeMap::new();
+    for (name, key, group, requests_per_second, bytes_per_second) in rows {
+        let group = group.parse::<RouteGroup>()?;
+        let budget = Budget {
+            requests_per_second,
+            bytes_per_second: bytes_per_second
+                .map(u64::try_from)
+                .transpose()
+                .with_context(|| format!("negative bytes_per_second for {name}"))?,
+        };
+        let tier = match key {
+            Some(key) => {
+                &mut keys
+                    .entry(key.clone())
+                    .or_insert_with(|| KeyConfig {
+                        name,
+                        key,
+                        tier: Default::default(),
+                    })
+                    .tier
+            },
+            None => &mut store.anonymous,
+        };
+        *tier.budget_mut(group) = budget;
+    }
+    store.keys = keys.into_values().collect();
+    Ok(store)
+}
+
+/// A token bucket.
+#[derive(Clone, Debug)]
+struct Bucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// A full bucket.
+    fn new(rate: f64, now: Instant) -> Self {
+        // Always allow at least one request, even if the rate is less than one per second.
+        let capacity = rate.max(1.);
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// How long until the bucket holds at least `amount` tokens.
+    fn wait_for(&self, amount: f64) -> Duration {
+        if self.tokens >= amount {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((amount - self.tokens) / self.rate)
+        }
+    }
+
+    /// Take tokens from the bucket.
+    ///
+    /// This may leave the bucket in debt, which must be paid off before the bucket admits anything
+    /// else.
+    fn take(&mut self, amount: f64) {
+        self.tokens -= amount;
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= self.capacity
+    }
+}
+
+/// Bring a bucket in line with the current budget.
+fn sync_bucket(bucket: &mut Option<Bucket>, rate: Option<f64>, now: Instant) {
+    match (bucket.as_mut(), rate) {
+        (Some(bucket), Some(rate)) if bucket.rate == rate => bucket.refill(now),
+        (_, Some(rate)) => *bucket = Some(Bucket::new(rate, now)),
+        (_, None) => *bucket = None,
+    }
+}
+
+/// Usage of one route group by one client.
+#[derive(Clone, Debug, Default)]
+struct Limits {
+    requests: Option<Bucket>,
+    bytes: Option<Bucket>,
+}
+
+impl Limits {
+    /// Try to admit a request, returning how long to wait if it is over budget.
+    fn admit(
+        &mut self,
+        budget: Budget,
+        request_bytes: usize,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        sync_bucket(&mut self.requests, budget.requests_per_second, now);
+        sync_bucket(
+            &mut self.bytes,
+            budget.bytes_per_second.map(|rate| rate as f64),
+            now,
+        );
+
+        // Check both budgets before charging either, so that a refused request costs nothing.
+        // Since we don't know how large the response will be, we only require that the byte
+        // budget is not in debt.
+        let wait = self
+            .requests
+            .iter()
+            .map(|bucket| bucket.wait_for(1.))
+            .chain(self.bytes.iter().map(|bucket| bucket.wait_for(0.)))
+            .max()
+            .unwrap_or_default();
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+
+        if let Some(bucket) = &mut self.requests {
+            bucket.take(1.);
+        }
+        self.charge(request_bytes);
+        Ok(())
+    }
+
+    fn charge(&mut self, bytes: usize) {
+        if let Some(bucket) = &mut self.bytes {
+            bucket.take(bytes as f64);
+        }
+    }
+
+    /// Whether these limits are back to their initial state, so they can be forgotten.
+    fn is_idle(&mut self, now: Instant) -> bool {
+        self.requests
+            .iter_mut()
+            .chain(&mut self.bytes)
+            .all(|bucket| {
+                bucket.refill(now);
+                bucket.is_full()
+            })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum ClientId {
+    Key(Arc<str>),
+    /// An anonymous client, by IP address.
+    ///
+    /// Clients whose address is unknown, or who arrive when we are already tracking too many
+    /// clients, share the budget of `Anonymous(None)`.
+    Anonymous(Option<IpAddr>),
+}
+
+impl ClientId {
+    fn label(&self) -> &str {
+        match self {
+            Self::Key(name) => name,
+            Self::Anonymous(_) => ANONYMOUS,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Keys {
+    anonymous: Tier,
+    by_key: HashMap<String, (Arc<str>, Tier)>,
+}
+
+impl From<KeyStore> for Keys {
+    fn from(store: KeyStore) -> Self {
+        Self {
+            anonymous: store.anonymous,
+            by_key: store
+                .keys
+                .into_iter()
+                .map(|key| (key.key, (key.name.into(), key.tier)))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct QuotaMetrics {
+    requests: Box<dyn CounterFamily>,
+    rejected: Box<dyn CounterFamily>,
+    bytes: Box<dyn CounterFamily>,
+}
+
+impl QuotaMetrics {
+    fn new(metrics: &dyn Metrics) -> Self {
+        let metrics = metrics.subgroup("api_quota".into());
+        let labels = || vec!["key".into(), "group".into()];
+        Self {
+            requests: metrics.counter_family("requests".into(), labels()),
+            rejected: metrics.counter_family("rejected_requests".into(), labels()),
+            bytes: metrics.counter_family("bytes".into(), labels()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Usage {
+    clients: HashMap<(ClientId, RouteGroup), Limits>,
+    pruned: Instant,
+}
+
+impl Usage {
+    fn prune(&mut self, now: Instant) {
+        self.clients.retain(|_, limits| !limits.is_idle(now));
+        self.pruned = now;
+    }
+}
+
+/// Tracks usage of the API by each client and enforces their quotas.
+#[derive(Debug)]
+pub struct QuotaLimiter {
+    keys: RwLock<Keys>,
+    usage: Mutex<Usage>,
+    trusted_proxies: HashSet<IpAddr>,
+    max_clients: usize,
+    metrics: QuotaMetrics,
+}
+
+impl QuotaLimiter {
+    pub fn new(keys: KeyStore, metrics: &dyn Metrics) -> Self {
+        Self {
+            keys: RwLock::new(keys.into()),
+            usage: Mutex::new(Usage {
+                clients: Default::default(),
+                pruned: Instant::now(),
+            }),
+            trusted_proxies: Default::default(),
+            max_clients: DEFAULT_MAX_CLIENTS,
+            metrics: QuotaMetrics::new(metrics),
+        }
+    }
+
+    /// Identify anonymous clients connecting through `proxies` by the `X-Forwarded-For` header.
+    ///
+    /// Without trusted proxies, anonymous clients are identified by the address they connect from,
+    /// and the header is ignored, since any client could set it.
+    pub fn with_trusted_proxies(mut self, proxies: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.trusted_proxies = proxies.into_iter().collect();
+        self
+    }
+
+    /// Limit the number of anonymous clients with their own budget.
+    ///
+    /// Once this many clients are being tracked, new anonymous clients share a single budget
+    /// until idle clients are pruned.
+    pub fn with_max_clients(mut self, max_clients: usize) -> Self {
+        self.max_clients = max_clients;
+        self
+    }
+
+    /// Replace the set of known API keys.
+    ///
+    /// Usage so far is kept, so reloading keys does not reset anyone's budget.
+    pub fn update_keys(&self, keys: KeyStore) {
+        *self.keys.write() = keys.into();
+    }
+
+    /// Periodically reload API keys from `source` and forget idle clients.
+    pub async fn maintain(self: Arc<Self>, source: KeySource, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            match source.load().await {
+                Ok(keys) => self.update_keys(keys),
+                Err(err) => tracing::warn!("failed to reload API keys, keeping old keys: {err:#}"),
+            }
+            self.prune(Instant::now());
+        }
+    }
+
+    fn prune(&self, now: Instant) {
+        self.usage.lock().prune(now);
+    }
+
+    /// Figure out who sent a request, or [`None`] if it has an API key we don't know.
+    fn identify(&self, req: &Request, peer: Option<IpAddr>) -> Option<(ClientId, Tier)> {
+        let keys = self.keys.read();
+        match api_key(req) {
+            Some(key) => {
+                let (name, tier) = keys.by_key.get(key)?;
+                Some((ClientId::Key(name.clone()), *tier))
+            },
+            None => Some((
+                ClientId::Anonymous(self.client_ip(req, peer)),
+                keys.anonymous,
+            )),
+        }
+    }
+
+    /// The address of the client that sent a request through `peer`.
+    ///
+    /// If `peer` is a trusted proxy, this is the last address in `X-Forwarded-For` which was not
+    /// added by one of our own proxies.
+    fn client_ip(&self, req: &Request, peer: Option<IpAddr>) -> Option<IpAddr> {
+        let mut ip = peer?;
+        let Some(forwarded) = req.header("X-Forwarded-For") else {
+            return Some(ip);
+        };
+        let hops = forwarded
+            .iter()
+            .flat_map(|value| value.as_str().split(','))
+            .collect::<Vec<_>>();
+        for hop in hops.into_iter().rev() {
+            if !self.trusted_proxies.contains(&ip) {
+                break;
+            }
+            match hop.trim().parse() {
+                Ok(hop) => ip = hop,
+                // If a trusted proxy gave us garbage, the best we can do is blame the proxy.
+                Err(_) => break,
+            }
+        }
+        Some(ip)
+    }
+
+    /// Try to admit a request, returning the client it is charged to.
+    fn admit(
+        &self,
+        client: &ClientId,
+        tier: Tier,
+        group: RouteGroup,
+        request_bytes: usize,
+    ) -> Result<ClientId, Duration> {
+        let labels = vec![client.label().to_string(), group.to_string()];
+        let now = Instant::now();
+        let mut usage = self.usage.lock();
+        let mut id = (client.clone(), group);
+        if matches!(client, ClientId::Anonymous(Some(_)))
+            && !usage.clients.contains_key(&id)
+            && usage.clients.len() >= self.max_clients
+        {
+            // Make room by forgetting idle clients, but not so often that a flood of new clients
+            // makes every request scan the whole table.
+            if now.saturating_duration_since(usage.pruned) >= Duration::from_secs(1) {
+                usage.prune(now);
+            }
+            if usage.clients.len() >= self.max_clients {
+                id.0 = ClientId::Anonymous(None);
+            }
+        }
+        let res = usage.clients.entry(id.clone()).or_default().admit(
+            tier.budget(group),
+            request_bytes,
+            now,
+        );
+        drop(usage);
+        match res {
+            Ok(()) => {
+                self.metrics.requests.create(labels.clone()).add(1);
+                self.metrics.bytes.create(labels).add(request_bytes);
+                Ok(id.0)
+            },
+            Err(wait) => {
+                self.metrics.rejected.create(labels).add(1);
+                Err(wait)
+            },
+        }
+    }
+
+    fn charge(&self, client: &ClientId, group: RouteGroup, bytes: usize) {
+        if let Some(limits) = self.usage.lock().clients.get_mut(&(client.clone(), group)) {
+            limits.charge(bytes);
+        }
+        self.metrics
+            .bytes
+            .create(vec![client.label().to_string(), group.to_string()])
+            .add(bytes);
+    }
+
+    /// Handle a request from `peer`, forwarding it to `app` if it is within the client's quota.
+    ///
+    /// If the request is admitted, `meter` is pointed at the client, so that the response is
+    /// charged to them as it is written to the connection.
+    async fn respond<State>(
+        &self,
+        app: &Server<State>,
+        req: Request,
+        peer: Option<IpAddr>,
+        meter: &Meter,
+    ) -> tide::http::Result<Response>
+    where
+        State: Clone + Send + Sync + 'static,
+    {
+        *meter.lock() = None;
+        let group = RouteGroup::classify(&req);
+        let Some((client, tier)) = self.identify(&req, peer) else {
+            self.metrics
+                .rejected
+                .create(vec![UNKNOWN_KEY.into(), group.to_string()])
+                .add(1);
+            let mut res = Response::new(StatusCode::Unauthorized);
+            res.set_body("unknown API key");
+            return Ok(res);
+        };
+        let client = match self.admit(&client, tier, group, req.len().unwrap_or(0)) {
+            Ok(client) => client,
+            Err(retry_after) => return Ok(too_many_requests(retry_after)),
+        };
+
+        *meter.lock() = Some((client, group));
+        app.respond(req).await
+    }
+}
+
+/// The client to charge for bytes written to a connection.
+type Meter = Arc<Mutex<Option<(ClientId, RouteGroup)>>>;
+
+/// A TCP connection which charges everything written to it against the current client's quota.
+///
+/// Since the charge happens as bytes are actually sent, this covers streamed and chunked bodies as
+/// well as upgraded connections, such as WebSockets, which outlive the request that opened them.
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
+struct MeteredStream {
+    stream: TcpStream,
+    #[derivative(Debug = "ignore")]
+    quotas: Arc<QuotaLimiter>,
+    meter: Meter,
+}
+
+impl AsyncRead for MeteredStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for MeteredStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let res = Pin::new(&mut self.stream).poll_write(cx, buf);
+        if let Poll::Ready(Ok(bytes)) = &res {
+            if let Some((client, group)) = &*self.meter.lock() {
+                self.quotas.charge(client, *group, *bytes);
+            }
+        }
+        res
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_close(cx)
+    }
+}
+
+/// Get the API key presented with a request, if any.
+fn api_key(req: &Request) -> Option<&str> {
+    if let Some(key) = req.header("X-Api-Key") {
+        return Some(key.last().as_str().trim());
+    }
+    req.header("Authorization")?
+        .last()
+        .as_str()
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+fn too_many_requests(retry_after: Duration) -> Response {
+    let mut res = Response::new(StatusCode::TooManyRequests);
+    let secs = retry_after.as_secs_f64().ceil().max(1.) as u64;
+    res.insert_header("Retry-After", secs.to_string());
+    res.set_body(format!(
+        "request quota exceeded, retry after {secs} seconds"
+    ));
+    res
+}
+
+/// A TCP listener which enforces per-client quotas before handing requests to the server.
+///
+/// This optionally also enforces a global limit on concurrent requests, like
+/// [`RateLimitListener`](tide_disco::listener::RateLimitListener).
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct QuotaListener<State> {
+    addr: String,
+    listener: Option<TcpListener>,
+    #[derivative(Debug = "ignore")]
+    server: Option<Server<State>>,
+    info: Option<ListenInfo>,
+    quotas: Arc<QuotaLimiter>,
+    permit: Option<Arc<Semaphore>>,
+}
+
+impl<State> QuotaListener<State> {
+    /// Listen on all interfaces at `port`.
+    pub fn with_port(port: u16, quotas: Arc<QuotaLimiter>, max_connections: Option<usize>) -> Self {
+        Self {
+            addr: format!("0.0.0.0:{port}"),
+            listener: None,
+            server: None,
+            info: None,
+            quotas,
+            permit: max_connections.map(|limit| Arc::new(Semaphore::new(limit))),
+        }
+    }
+}
+
+#[async_trait]
+impl<State> Listener<State> for QuotaListener<State>
+where
+    State: Clone + Send + Sync + 'static,
+{
+    async fn bind(&mut self, server: Server<State>) -> io::Result<()> {
+        if self.server.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "`bind` should only be called once",
+            ));
+        }
+        self.server = Some(server);
+        self.listener = Some(TcpListener::bind(&self.addr).await?);
+        self.info = Some(ListenInfo::new(self.to_string(), "tcp".into(), false));
+        Ok(())
+    }
+
+    async fn accept(&mut self) -> io::Result<()> {
+        let server = self
+            .server
+            .take()
+            .expect("`Listener::bind` must be called before `Listener::accept`");
+        let listener = self
+            .listener
+            .take()
+            .expect("`Listener::bind` must be called before `Listener::accept`");
+
+        let mut incoming = listener.incoming();
+        while let Some(stream) = incoming.next().await {
+            match stream {
+                Ok(stream) => handle_tcp(
+                    server.clone(),
+                    stream,
+                    self.quotas.clone(),
+                    self.permit.clone(),
+                ),
+                Err(err) if is_transient_error(&err) => continue,
+                Err(err) => {
+                    tracing::error!("error accepting connection: {err}");
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn info(&self) -> Vec<ListenInfo> {
+        self.info.iter().cloned().collect()
+    }
+}
+
+impl<State> ToListener<State> for QuotaListener<State>
+where
+    State: Clone + Send + Sync + 'static,
+{
+    type Listener = Self;
+
+    fn to_listener(self) -> io::Result<Self::Listener> {
+        Ok(self)
+    }
+}
+
+impl<State> Display for QuotaListener<State> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.listener {
+            Some(listener) => match listener.local_addr() {
+                Ok(addr) => write!(f, "http://{addr}"),
+                Err(_) => write!(f, "http://{}", self.addr),
+            },
+            None => write!(f, "http://{}", self.addr),
+        }
+    }
+}
+
+fn is_transient_error(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+    )
+}
+
+fn handle_tcp<State>(
+    app: Server<State>,
+    stream: TcpStream,
+    quotas: Arc<QuotaLimiter>,
+    permit: Option<Arc<Semaphore>>,
+) where
+    State: Clone + Send + Sync + 'static,
+{
+    task::spawn(async move {
+        let local_addr = stream.local_addr().ok();
+        let peer_addr = stream.peer_addr().ok();
+        let meter = Meter::default();
+        let stream = MeteredStream {
+            stream,
+            quotas: quotas.clone(),
+            meter: meter.clone(),
+        };
+        let fut = async_h1::accept(stream, |mut req| async {
+            // Enforce the global connection limit first, if there is one.
+            let _guard = match &permit {
+                Some(permit) => match permit.try_acquire() {
+                    Ok(guard) => Some(guard),
+                    Err(_) => return Ok(Response::new(StatusCode::TooManyRequests)),
+                },
+                None => None,
+            };
+            req.set_local_addr(local_addr);
+            req.set_peer_addr(peer_addr);
+            quotas
+                .respond(&app, req, peer_addr.map(|addr| addr.ip()), &meter)
+                .await
+        });
+        if let Err(err) = fut.await {
+            tracing::debug!("HTTP connection error: {err}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use futures::io::Cursor;
+    use hotshot_types::traits::metrics::NoMetrics;
+    use portpicker::pick_unused_port;
+    use sequencer_utils::test_utils::setup_test;
+    use tide::{
+        http::{Method, Url},
+        Body,
+    };
+
+    use super::*;
+
+    fn request(path: &str) -> Request {
+        Request::new(
+            Method::Get,
+            Url::parse(&format!("http://localhost{path}")).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_route_group_classify() {
+        assert_eq!(
+            RouteGroup::classify(&request("/v1/submit/submit")),
+            RouteGroup::Submit
+        );
+        assert_eq!(
+            RouteGroup::classify(&request("/submit/submit")),
+            RouteGroup::Submit
+        );
+        assert_eq!(
+            RouteGroup::classify(&request("/v0/availability/block/1")),
+            RouteGroup::Availability
+        );
+        assert_eq!(
+            RouteGroup::classify(&request("/status/block-height")),
+            RouteGroup::Availability
+        );
+
+        let mut req = request("/v1/availability/stream/blocks/0");
+        req.insert_header("Upgrade", "websocket");
+        assert_eq!(RouteGroup::classify(&req), RouteGroup::Streams);
+    }
+
+    #[test]
+    fn test_key_store_parse() {
+        let store: KeyStore = toml::from_str(
+            r#"
+            [anonymous]
+            submit = { requests_per_second = 0.5 }
+
+            [[keys]]
+            name = "rollup"
+            key = "secret"
+            availability = { requests_per_second = 100, bytes_per_second = 1000000 }
+            "#,
+        )
+        .unwrap();
+        store.validate().unwrap();
+        assert_eq!(store.anonymous.submit.requests_per_second, Some(0.5));
+        assert_eq!(store.anonymous.availability, Budget::default());
+        assert_eq!(store.keys.len(), 1);
+        assert_eq!(store.keys[0].name, "rollup");
+        assert_eq!(
+            store.keys[0].tier.availability.bytes_per_second,
+            Some(1000000)
+        );
+
+        // Keys must be unique.
+        let mut dup = store.clone();
+        dup.keys.push(KeyConfig {
+            name: "other".into(),
+            key: "secret".into(),
+            tier: Default::default(),
+        });
+        dup.validate().unwrap_err();
+
+        // Budgets must be positive.
+        let mut zero = store;
+        zero.anonymous.streams.requests_per_second = Some(0.);
+        zero.validate().unwrap_err();
+    }
+
+    #[test]
+    fn test_limits() {
+        let budget = Budget {
+            requests_per_second: Some(2.),
+            bytes_per_second: Some(100),
+        };
+        let start = Instant::now();
+        let mut limits = Limits::default();
+
+        // We can burst up to one second's worth of requests.
+        limits.admit(budget, 0, start).unwrap();
+        limits.admit(budget, 0, start).unwrap();
+        let wait = limits.admit(budget, 0, start).unwrap_err();
+        assert_eq!(wait, Duration::from_millis(500));
+        limits
+            .admit(budget, 0, start + Duration::from_millis(500))
+            .unwrap();
+
+        // A large response puts the byte budget in debt.
+        let now = start + Duration::from_secs(10);
+        limits.admit(budget, 0, now).unwrap();
+        limits.charge(300);
+        let wait = limits.admit(budget, 0, now).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(2));
+        limits.admit(budget, 0, now + wait).unwrap();
+
+        // Eventually everything refills.
+        assert!(!limits.is_idle(now + wait));
+        assert!(limits.is_idle(now + Duration::from_secs(10)));
+
+        // Unlimited budgets never refuse anything.
+        let mut limits = Limits::default();
+        for _ in 0..1000 {
+            limits.admit(Budget::default(), 1000, start).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_identify() {
+        let limiter = QuotaLimiter::new(
+            KeyStore {
+                anonymous: Tier {
+                    submit: Budget {
+                        requests_per_second: Some(1.),
+                        bytes_per_second: None,
+                    },
+                    ..Default::default()
+                },
+                keys: vec![KeyConfig {
+                    name: "rollup".into(),
+                    key: "secret".into(),
+                    tier: Default::default(),
+                }],
+            },
+            &NoMetrics,
+        );
+        let peer = Some(IpAddr::from([127, 0, 0, 1]));
+
+        let req = request("/v1/submit/submit");
+        let (client, tier) = limiter.identify(&req, peer).unwrap();
+        assert_eq!(client, ClientId::Anonymous(peer));
+        limiter.admit(&client, tier, RouteGroup::Submit, 0).unwrap();
+        limiter
+            .admit(&client, tier, RouteGroup::Submit, 0)
+            .unwrap_err();
+
+        // A client with a key has its own budget.
+        let mut req = request("/v1/submit/submit");
+        req.insert_header("X-Api-Key", "secret");
+        let (client, tier) = limiter.identify(&req, peer).unwrap();
+        assert_eq!(client, ClientId::Key("rollup".into()));
+        limiter.admit(&client, tier, RouteGroup::Submit, 0).unwrap();
+        limiter.admit(&client, tier, RouteGroup::Submit, 0).unwrap();
+
+        let mut req = request("/v1/submit/submit");
+        req.insert_header("Authorization", "Bearer secret");
+        assert_eq!(limiter.identify(&req, peer).unwrap().0, client);
+
+        // Unknown keys are rejected.
+        let mut req = request("/v1/submit/submit");
+        req.insert_header("X-Api-Key", "wrong");
+        assert!(limiter.identify(&req, peer).is_none());
+    }
+
+    #[test]
+    fn test_client_ip() {
+        let proxy = IpAddr::from([10, 0, 0, 1]);
+        let inner_proxy = IpAddr::from([10, 0, 0, 2]);
+        let client = IpAddr::from([1, 2, 3, 4]);
+        let spoofed = IpAddr::from([5, 6, 7, 8]);
+        let limiter = QuotaLimiter::new(Default::default(), &NoMetrics)
+            .with_trusted_proxies([proxy, inner_proxy]);
+
+        // Without the header, clients are identified by the connection.
+        let req = request("/status/block-height");
+        assert_eq!(limiter.client_ip(&req, Some(proxy)), Some(proxy));
+        assert_eq!(limiter.client_ip(&req, None), None);
+
+        // The header is only trusted from our proxies, and only up to the first hop they did not
+        // add themselves.
+        let mut req = request("/status/block-height");
+        req.insert_header(
+            "X-Forwarded-For",
+            format!("{spoofed}, {client}, {inner_proxy}"),
+        );
+        assert_eq!(limiter.client_ip(&req, Some(proxy)), Some(client));
+        assert_eq!(limiter.client_ip(&req, Some(client)), Some(client));
+
+        // If a proxy sends something we can't parse, we charge the proxy.
+        let mut req = request("/status/block-height");
+        req.insert_header("X-Forwarded-For", "garbage");
+        assert_eq!(limiter.client_ip(&req, Some(proxy)), Some(proxy));
+    }
+
+    #[test]
+    fn test_max_clients() {
+        let limiter = QuotaLimiter::new(
+            KeyStore {
+                anonymous: Tier {
+                    submit: Budget {
+                        requests_per_second: Some(1.),
+                        bytes_per_second: None,
+                    },
+                    ..Default::default()
+                },
+                keys: vec![],
+            },
+            &NoMetrics,
+        )
+        .with_max_clients(2);
+        let tier = limiter.keys.read().anonymous;
+
+        // The first clients get their own budgets.
+        for i in 0..2 {
+            let client = ClientId::Anonymous(Some(IpAddr::from([127, 0, 0, i])));
+            assert_eq!(
+                limiter.admit(&client, tier, RouteGroup::Submit, 0).unwrap(),
+                client
+            );
+        }
+
+        // Beyond the limit, new clients share a budget.
+        let client = ClientId::Anonymous(Some(IpAddr::from([127, 0, 0, 2])));
+        assert_eq!(
+            limiter.admit(&client, tier, RouteGroup::Submit, 0).unwrap(),
+            ClientId::Anonymous(None)
+        );
+        let client = ClientId::Anonymous(Some(IpAddr::from([127, 0, 0, 3])));
+        limiter
+            .admit(&client, tier, RouteGroup::Submit, 0)
+            .unwrap_err();
+        assert_eq!(limiter.usage.lock().clients.len(), 3);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_quota_listener() {
+        setup_test();
+
+        let limiter = Arc::new(QuotaLimiter::new(
+            KeyStore {
+                anonymous: Tier {
+                    availability: Budget {
+                        requests_per_second: Some(0.1),
+                        bytes_per_second: None,
+                    },
+                    ..Default::default()
+                },
+                keys: vec![KeyConfig {
+                    name: "rollup".into(),
+                    key: "secret".into(),
+                    tier: Tier {
+                        availability: Budget {
+                            requests_per_second: None,
+                            bytes_per_second: Some(100),
+                        },
+                        ..Default::default()
+                    },
+                }],
+            },
+            &NoMetrics,
+        ));
+
+        // Serve a chunked response, whose size is not known until it has been sent.
+        let mut app = tide::new();
+        app.at("/availability/payload")
+            .get(|_| async { Ok(Body::from_reader(Cursor::new(vec![0u8; 1000]), None)) });
+        let port = pick_unused_port().expect("No ports free");
+        let mut listener = QuotaListener::with_port(port, limiter, None);
+        listener.bind(app).await.unwrap();
+        let _server = tokio::spawn(async move { listener.accept().await });
+
+        let url = format!("http://127.0.0.1:{port}/availability/payload");
+        let client = reqwest::Client::new();
+        let retry_after = |res: &reqwest::Response| -> u64 {
+            res.headers()["retry-after"]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap()
+        };
+
+        // Anonymous clients run out of requests.
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+        assert_eq!(res.bytes().await.unwrap().len(), 1000);
+        let res = client.get(&url).send().await.unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(retry_after(&res), 10);
+
+        // The client with a key runs out of bytes, even though the response was chunked.
+        let res = client
+            .get(&url)
+            .header("X-Api-Key", "secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::OK);
+        assert_eq!(res.bytes().await.unwrap().len(), 1000);
+        let res = client
+            .get(&url)
+            .header("X-Api-Key", "secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+        assert!(retry_after(&res) >= 9, "{}", retry_after(&res));
+    }
+}
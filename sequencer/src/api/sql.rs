@@ -7,11 +7,12 @@ use espresso_types::{
     get_l1_deposits,
     v0_1::{IterableFeeInfo, RewardAccount, RewardMerkleTree, REWARD_MERKLE_TREE_HEIGHT},
     v0_3::ChainConfig,
-    BlockMerkleTree, EpochVersion, FeeAccount, FeeMerkleTree, Leaf2, NodeState, ValidatedState,
+    BlockMerkleTree, EpochVersion, FeeAccount, FeeMerkleTree, Header, Leaf2, NodeState,
+    ValidatedState,
 };
 use hotshot::traits::ValidatedState as _;
 use hotshot_query_service::{
-    availability::LeafId,
+    availability::{AvailabilityDataSource, LeafId},
     data_source::{
         sql::{Config, SqlDataSource, Transaction},
         storage::{
@@ -21,7 +22,7 @@ use hotshot_query_service::{
         VersionedDataSource,
     },
     merklized_state::Snapshot,
-    Resolvable,
+    QueryError, QueryResult, Resolvable,
 };
 use hotshot_types::{
     data::{EpochNumber, QuorumProposalWrapper, ViewNumber},
@@ -86,8 +87,91 @@ impl SequencerDataSource for DataSource {
             builder = builder.with_types_migration_batch_size(batch_size);
         }
 
+        if opt.backfill {
+            builder = builder.enable_backfill();
+            if let Some(window_size) = opt.backfill_window_size {
+                builder = builder.with_backfill_window_size(window_size);
+            }
+            if let Some(request_size) = opt.backfill_request_size {
+                builder = builder.with_backfill_request_size(request_size);
+            }
+            if let Some(parallelism) = opt.backfill_parallelism {
+                builder = builder.with_backfill_parallelism(parallelism);
+            }
+        }
+
+        if opt.integrity_scan {
+            builder = builder.enable_integrity_scan();
+            if let Some(interval) = opt.integrity_scan_interval {
+                builder = builder.with_integrity_scan_interval(interval);
+            }
+        }
+
         builder.build().await
     }
+
+    async fn get_header_by_timestamp(&self, timestamp: u64) -> QueryResult<Header> {
+        get_first_header_where(self, "timestamp", timestamp).await
+    }
+
+    async fn get_header_by_l1_block(&self, number: u64) -> QueryResult<Header> {
+        get_first_header_where(self, "l1_head", number).await
+    }
+    async fn get_fee_payer_blocks(
+        &self,
+        account: FeeAccount,
+        before: u64,
+        limit: usize,
+    ) -> QueryResult<Vec<u64>> {
+        let mut tx = self.read().await.map_err(|err| QueryError::Error {
+            message: format!("{err:#}"),
+        })?;
+        // The `fee_account` column holds accounts as they are serialized in the header, which is
+        // as a lowercase, 0x-prefixed hex string.
+        let rows = query_as::<(i64,)>(
+            "SELECT height FROM header
+              WHERE fee_account = $1 AND height < $2
+              ORDER BY height DESC
+              LIMIT $3",
+        )
+        .bind(format!("{:#x}", account.0))
+        .bind(i64::try_from(before).unwrap_or(i64::MAX))
+        .bind(limit as i64)
+        .fetch_all(tx.as_mut())
+        .await?;
+        Ok(rows.into_iter().map(|(height,)| height as u64).collect())
+    }
+}
+
+/// Find the first header where `column`, which must be indexed and non-decreasing in block height,
+/// is at least `value`.
+async fn get_first_header_where(ds: &DataSource, column: &str, value: u64) -> QueryResult<Header> {
+    let mut tx = ds.read().await.map_err(|err| QueryError::Error {
+        message: format!("{err:#}"),
+    })?;
+    let (height,) = query_as::<(i64,)>(&format!(
+        "SELECT height FROM header WHERE {column} >= $1 ORDER BY {column}, height LIMIT 1"
+    ))
+    .bind(i64::try_from(value).unwrap_or(i64::MAX))
+    .fetch_one(tx.as_mut())
+    .await?;
+
+    // Headers which have not been fetched yet are missing from the database, and one of them could
+    // be the real answer. Since `column` is non-decreasing, it is enough to check that the header
+    // just before the one we found is present.
+    if height > 0 {
+        query_as::<(i64,)>("SELECT height FROM header WHERE height = $1")
+            .bind(height - 1)
+            .fetch_optional(tx.as_mut())
+            .await?
+            .ok_or(QueryError::Missing)?;
+    }
+    drop(tx);
+
+    ds.get_header(height as usize)
+        .await
+        .try_resolve()
+        .map_err(|_| QueryError::Missing)
 }
 
 impl CatchupStorage for SqlStorage {
@@ -0,0 +1,443 @@
+//! Tracking the status of transactions submitted through the API.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use committable::{Commitment, Committable};
+use espresso_types::{
+    v0::traits::EventConsumer, NsTable, Payload, Transaction, TransactionPosition,
+    TransactionStatus,
+};
+use futures::stream::{self, BoxStream, StreamExt};
+use hotshot::types::{Event, EventType};
+use hotshot_query_service::availability::QueryablePayload;
+use hotshot_types::{data::ViewNumber, traits::block_contents::BlockPayload};
+use parking_lot::Mutex;
+use tokio::sync::watch;
+
+use crate::SeqTypes;
+
+/// How long to wait for a submitted transaction to be decided before reporting it as dropped, if
+/// not otherwise configured.
+pub(crate) const DEFAULT_TX_STATUS_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The maximum number of transactions to track at once, if not otherwise configured.
+pub(crate) const DEFAULT_TX_STATUS_CAPACITY: usize = 100_000;
+
+/// Tracks submitted transactions as they move through consensus.
+///
+/// Transactions are registered when they are submitted, and their status is updated from the
+/// consensus events seen by this node: DA and quorum proposals mark a transaction as included, and
+/// decide events mark it as decided. Transactions which are not decided within the timeout are
+/// marked as dropped, but are still tracked for another timeout period in case they are decided
+/// late. Decided entries are likewise kept for another timeout period so that clients have a chance
+/// to observe them. After that, entries are forgotten.
+///
+/// At most `capacity` transactions are tracked at once. When a new transaction is submitted to a
+/// full tracker, the entry closest to expiring is forgotten early to make room.
+#[derive(Clone, Debug)]
+pub(crate) struct TxStatusTracker {
+    inner: Arc<Mutex<Inner>>,
+    timeout: Duration,
+    capacity: usize,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    txs: HashMap<Commitment<Transaction>, Entry>,
+    /// The deadlines of tracked transactions, in the order they expire.
+    ///
+    /// Since the timeout is fixed, deadlines are pushed in order. A transaction gets a new deadline
+    /// each time its entry is updated, which leaves its old deadline in the queue. Such stale
+    /// deadlines are skipped when they come up.
+    deadlines: VecDeque<(Instant, Commitment<Transaction>)>,
+    /// The number of tracked transactions which have not been decided yet.
+    undecided: usize,
+    /// Tracked transactions found in DA proposals, waiting for the corresponding quorum proposal
+    /// to tell us the block height.
+    proposed: BTreeMap<ViewNumber, Vec<(Commitment<Transaction>, TransactionPosition)>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    status: watch::Sender<TransactionStatus>,
+    /// When the transaction will be dropped, if it is still undecided, or forgotten otherwise.
+    deadline: Instant,
+}
+
+impl Default for TxStatusTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_TX_STATUS_TIMEOUT, DEFAULT_TX_STATUS_CAPACITY)
+    }
+}
+
+impl TxStatusTracker {
+    pub(crate) fn new(timeout: Duration, capacity: usize) -> Self {
+        Self {
+            inner: Default::default(),
+            timeout,
+            capacity,
+        }
+    }
+
+    /// Start tracking a newly submitted transaction.
+    pub(crate) fn pending(&self, tx: Commitment<Transaction>) {
+        let mut inner = self.inner.lock();
+        // If the same transaction is resubmitted, keep its current status.
+        if inner.txs.contains_key(&tx) {
+            return;
+        }
+        inner.expire(self.timeout);
+        while inner.txs.len() >= self.capacity && inner.evict() {}
+
+        let deadline = Instant::now() + self.timeout;
+        inner.txs.insert(
+            tx,
+            Entry {
+                status: watch::Sender::new(TransactionStatus::Pending),
+                deadline,
+            },
+        );
+        inner.deadlines.push_back((deadline, tx));
+        inner.undecided += 1;
+    }
+
+    /// Stop tracking a transaction which failed to submit.
+    pub(crate) fn forget(&self, tx: Commitment<Transaction>) {
+        let mut inner = self.inner.lock();
+        if let Some(entry) = inner.txs.get(&tx) {
+            if *entry.status.borrow() == TransactionStatus::Pending {
+                inner.remove(tx);
+            }
+        }
+    }
+
+    /// Get the current status of a transaction, if it is being tracked.
+    pub(crate) fn status(&self, tx: Commitment<Transaction>) -> Option<TransactionStatus> {
+        let mut inner = self.inner.lock();
+        inner.expire(self.timeout);
+        inner.txs.get(&tx).map(|entry| *entry.status.borrow())
+    }
+
+    /// Subscribe to status updates for a transaction, if it is being tracked.
+    ///
+    /// The stream yields the current status immediately, then each subsequent change. It ends after
+    /// yielding a final status, or once the tracker forgets about the transaction.
+    pub(crate) fn subscribe(
+        &self,
+        tx: Commitment<Transaction>,
+    ) -> Option<BoxStream<'static, TransactionStatus>> {
+        let mut rx = self.inner.lock().txs.get(&tx)?.status.subscribe();
+        let status = *rx.borrow_and_update();
+        Some(
+            stream::unfold(Some((Some(status), rx)), |state| async move {
+                let (status, mut rx) = state?;
+                let status = match status {
+                    Some(status) => status,
+                    None => {
+                        // Wait for the next change. If the entry is dropped before reaching a final
+                        // status, the tracker has forgotten about it, and we end the stream.
+                        rx.changed().await.ok()?;
+                        *rx.borrow_and_update()
+                    },
+                };
+                let next = (!status.is_final()).then_some((None, rx));
+                Some((status, next))
+            })
+            .boxed(),
+        )
+    }
+
+    fn has_pending(&self) -> bool {
+        self.inner.lock().has_pending()
+    }
+
+    fn handle_da_proposal(&self, view: ViewNumber, payload: &[u8], ns_table: &NsTable) {
+        if !self.has_pending() {
+            return;
+        }
+        // Decode the payload before taking the lock, so we don't block API requests.
+        let payload = Payload::from_bytes(payload, ns_table);
+        let txs = positions(&payload, ns_table, 0);
+
+        let mut inner = self.inner.lock();
+        let found = inner.find(txs);
+        if !found.is_empty() {
+            inner.proposed.insert(view, found);
+        }
+    }
+
+    fn handle_quorum_proposal(&self, view: ViewNumber, height: u64) {
+        let mut inner = self.inner.lock();
+        let Some(found) = inner.proposed.remove(&view) else {
+            return;
+        };
+        for (tx, mut pos) in found {
+            pos.height = height;
+            inner.update(tx, TransactionStatus::Included(pos), self.timeout);
+        }
+    }
+
+    fn handle_decide(&self, event: &Event<SeqTypes>) {
+        let EventType::Decide { leaf_chain, .. } = &event.event else {
+            return;
+        };
+        // Decode the payloads before taking the lock, so we don't block API requests.
+        let txs = if self.has_pending() {
+            leaf_chain
+                .iter()
+                .filter_map(|info| {
+                    let payload = info.leaf.block_payload()?;
+                    let header = info.leaf.block_header();
+                    Some(positions(&payload, header.ns_table(), header.height()))
+                })
+                .flatten()
+                .collect()
+        } else {
+            vec![]
+        };
+
+        let mut inner = self.inner.lock();
+        for (tx, pos) in inner.find(txs) {
+            inner.update(tx, TransactionStatus::Decided(pos), self.timeout);
+        }
+        // Proposals for decided views (or earlier) can no longer lead to inclusion.
+        if let Some(info) = leaf_chain.first() {
+            let decided = info.leaf.view_number();
+            inner.proposed.retain(|view, _| *view > decided);
+        }
+        inner.expire(self.timeout);
+    }
+}
+
+impl Inner {
+    fn has_pending(&self) -> bool {
+        self.undecided > 0
+    }
+
+    /// Filter a list of transactions down to the tracked, undecided ones.
+    fn find(
+        &self,
+        txs: Vec<(Commitment<Transaction>, TransactionPosition)>,
+    ) -> Vec<(Commitment<Transaction>, TransactionPosition)> {
+        txs.into_iter()
+            .filter(|(hash, _)| {
+                self.txs
+                    .get(hash)
+                    .is_some_and(|entry| !entry.status.borrow().is_final())
+            })
+            .collect()
+    }
+
+    fn update(
+        &mut self,
+        tx: Commitment<Transaction>,
+        status: TransactionStatus,
+        timeout: Duration,
+    ) {
+        let Some(entry) = self.txs.get_mut(&tx) else {
+            return;
+        };
+        if entry.status.borrow().is_final() {
+            return;
+        }
+        if status.is_final() {
+            // Keep decided transactions for another timeout period, so clients can observe them.
+            entry.deadline = Instant::now() + timeout;
+            self.deadlines.push_back((entry.deadline, tx));
+            self.undecided -= 1;
+        }
+        entry.status.send_replace(status);
+    }
+
+    /// Mark transactions which have been pending too long as dropped, and forget transactions
+    /// which have been decided or dropped for too long.
+    fn expire(&mut self, timeout: Duration) {
+        let now = Instant::now();
+        while let Some(&(deadline, tx)) = self.deadlines.front() {
+            if deadline > now {
+                break;
+            }
+            self.deadlines.pop_front();
+            let Some(entry) = self.txs.get_mut(&tx) else {
+                continue;
+            };
+            if entry.deadline != deadline {
+                continue;
+            }
+            let status = *entry.status.borrow();
+            if status.is_final() || status == TransactionStatus::Dropped {
+                self.remove(tx);
+                continue;
+            }
+            entry.deadline = now + timeout;
+            self.deadlines.push_back((entry.deadline, tx));
+            entry.status.send_replace(TransactionStatus::Dropped);
+        }
+    }
+
+    /// Forget the transaction with the earliest deadline, to make room for a new one.
+    ///
+    /// Returns `false` if there are no transactions left to forget.
+    fn evict(&mut self) -> bool {
+        while let Some((deadline, tx)) = self.deadlines.pop_front() {
+            if self
+                .txs
+                .get(&tx)
+                .is_some_and(|entry| entry.deadline == deadline)
+            {
+                self.remove(tx);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn remove(&mut self, tx: Commitment<Transaction>) {
+        if let Some(entry) = self.txs.remove(&tx) {
+            if !entry.status.borrow().is_final() {
+                self.undecided -= 1;
+            }
+        }
+    }
+}
+
+/// The positions of all the transactions in a payload.
+fn positions(
+    payload: &Payload,
+    ns_table: &NsTable,
+    height: u64,
+) -> Vec<(Commitment<Transaction>, TransactionPosition)> {
+    payload
+        .enumerate(ns_table)
+        .map(|(index, tx)| {
+            let pos = TransactionPosition {
+                height,
+                namespace: tx.namespace(),
+                ns_index: i64::from(index.ns_index) as u32,
+                offset: index.position,
+            };
+            (tx.commit(), pos)
+        })
+        .collect()
+}
+
+#[async_trait]
+impl EventConsumer for TxStatusTracker {
+    async fn handle_event(&self, event: &Event<SeqTypes>) -> anyhow::Result<()> {
+        match &event.event {
+            EventType::DaProposal { proposal, .. } => self.handle_da_proposal(
+                proposal.data.view_number,
+                &proposal.data.encoded_transactions,
+                &proposal.data.metadata,
+            ),
+            EventType::QuorumProposal { proposal, .. } => self.handle_quorum_proposal(
+                proposal.data.view_number(),
+                proposal.data.block_header().height(),
+            ),
+            EventType::Decide { .. } => self.handle_decide(event),
+            _ => {},
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use espresso_types::NamespaceId;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tx_status_expiry() {
+        let tracker = TxStatusTracker::new(Duration::from_millis(100), DEFAULT_TX_STATUS_CAPACITY);
+        let tx = Transaction::new(NamespaceId::from(1_u32), vec![1, 2, 3]).commit();
+        assert_eq!(tracker.status(tx), None);
+
+        tracker.pending(tx);
+        let mut updates = tracker.subscribe(tx).unwrap();
+        assert_eq!(updates.next().await, Some(TransactionStatus::Pending));
+
+        // After the timeout, the transaction is dropped.
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(tracker.status(tx), Some(TransactionStatus::Dropped));
+        assert_eq!(updates.next().await, Some(TransactionStatus::Dropped));
+
+        // After another timeout, it is forgotten, which ends the stream.
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(tracker.status(tx), None);
+        assert_eq!(updates.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_tx_status_decided_after_dropped() {
+        let tracker = TxStatusTracker::new(Duration::from_millis(100), DEFAULT_TX_STATUS_CAPACITY);
+        let tx = Transaction::new(NamespaceId::from(1_u32), vec![1, 2, 3]);
+        let hash = tx.commit();
+        tracker.pending(hash);
+        let mut updates = tracker.subscribe(hash).unwrap();
+        assert_eq!(updates.next().await, Some(TransactionStatus::Pending));
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        assert_eq!(tracker.status(hash), Some(TransactionStatus::Dropped));
+        assert_eq!(updates.next().await, Some(TransactionStatus::Dropped));
+
+        // The transaction is decided late, which is still reported.
+        let pos = TransactionPosition {
+            height: 1,
+            namespace: tx.namespace(),
+            ns_index: 0,
+            offset: 0,
+        };
+        {
+            let mut inner = tracker.inner.lock();
+            let found = inner.find(vec![(hash, pos)]);
+            assert_eq!(found, vec![(hash, pos)]);
+            inner.update(hash, TransactionStatus::Decided(pos), tracker.timeout);
+        }
+        assert_eq!(tracker.status(hash), Some(TransactionStatus::Decided(pos)));
+        assert_eq!(updates.next().await, Some(TransactionStatus::Decided(pos)));
+        assert_eq!(updates.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_tx_status_capacity() {
+        let tracker = TxStatusTracker::new(Duration::from_secs(60), 2);
+        let txs = (0..3u8)
+            .map(|i| Transaction::new(NamespaceId::from(1_u32), vec![i]))
+            .collect::<Vec<_>>();
+        let pos = TransactionPosition {
+            height: 1,
+            namespace: NamespaceId::from(1_u32),
+            ns_index: 0,
+            offset: 0,
+        };
+
+        tracker.pending(txs[0].commit());
+        tracker.pending(txs[1].commit());
+        tracker.inner.lock().update(
+            txs[0].commit(),
+            TransactionStatus::Decided(pos),
+            tracker.timeout,
+        );
+        assert_eq!(tracker.inner.lock().undecided, 1);
+
+        // Tracking a third transaction forgets the one closest to expiring. The decided
+        // transaction got a new deadline when it was decided, so that is the second one.
+        tracker.pending(txs[2].commit());
+        assert_eq!(
+            tracker.status(txs[0].commit()),
+            Some(TransactionStatus::Decided(pos))
+        );
+        assert_eq!(tracker.status(txs[1].commit()), None);
+        assert_eq!(
+            tracker.status(txs[2].commit()),
+            Some(TransactionStatus::Pending)
+        );
+        assert_eq!(tracker.inner.lock().undecided, 1);
+    }
+}
@@ -37,6 +37,8 @@ where
         if let Err(height) = self.inner.update(event).await {
             bail!("failed to update API state after {height}: {event:?}",);
         }
-        Ok(())
+        // Update transaction statuses only after the API state, so that once a transaction is
+        // reported as decided, its block is available from the query service.
+        self.inner.as_ref().tx_status.handle_event(event).await
     }
 }
@@ -0,0 +1,157 @@
+//! Caching the validator activity shown by the explorer.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use espresso_types::{PubKey, ValidatorEpochActivity};
+use hotshot_query_service::{availability::LeafQueryData, types::HeightIndexed};
+use hotshot_types::data::EpochNumber;
+use parking_lot::Mutex;
+
+use crate::SeqTypes;
+
+/// The maximum number of epochs whose activity is cached at once.
+const MAX_CACHED_EPOCHS: usize = 16;
+
+/// The proposals and votes of every validator over the first decided blocks of an epoch.
+#[derive(Clone, Debug)]
+pub(crate) struct EpochActivity {
+    epoch: EpochNumber,
+    /// The height of the first block which has not been counted yet.
+    next_height: u64,
+    /// The number of quorum certificates counted, each of which every validator could have signed.
+    eligible_votes: u64,
+    /// The number of counted certificates signed by each validator, indexed by position in the
+    /// epoch's stake table.
+    votes: Vec<u64>,
+    /// The height and proposer of each counted block.
+    proposals: Vec<(u64, PubKey)>,
+}
+
+impl EpochActivity {
+    /// Start counting activity in `epoch` from the block at `first_height`.
+    pub(crate) fn new(epoch: EpochNumber, first_height: u64, num_validators: usize) -> Self {
+        Self {
+            epoch,
+            next_height: first_height,
+            eligible_votes: 0,
+            votes: vec![0; num_validators],
+            proposals: vec![],
+        }
+    }
+
+    /// The height of the first block which has not been counted yet.
+    pub(crate) fn next_height(&self) -> u64 {
+        self.next_height
+    }
+
+    /// Count a decided leaf from this epoch, which was proposed by `leader`.
+    ///
+    /// Votes are counted from the signer bitmap of the quorum certificate stored with the leaf.
+    pub(crate) fn add_leaf(&mut self, leaf: &LeafQueryData<SeqTypes>, leader: PubKey) {
+        self.next_height = leaf.height() + 1;
+        self.proposals.push((leaf.height(), leader));
+
+        let qc = leaf.leaf().justify_qc();
+        if qc.data.epoch != Some(self.epoch) {
+            return;
+        }
+        let Some((_, signers)) = &qc.signatures else {
+            return;
+        };
+        self.eligible_votes += 1;
+        for (votes, signed) in self.votes.iter_mut().zip(signers.iter()) {
+            if *signed {
+                *votes += 1;
+            }
+        }
+    }
+
+    /// The activity of the validator with the given key and position in the stake table.
+    ///
+    /// `missing_blocks` is the number of decided blocks in the epoch which could not be counted.
+    pub(crate) fn validator(
+        &self,
+        key: PubKey,
+        index: Option<usize>,
+        missing_blocks: u64,
+    ) -> ValidatorEpochActivity {
+        let (votes, eligible_votes) = match index.and_then(|i| self.votes.get(i)) {
+            Some(votes) => (*votes, self.eligible_votes),
+            None => (0, 0),
+        };
+        ValidatorEpochActivity {
+            epoch: *self.epoch,
+            blocks_proposed: self
+                .proposals
+                .iter()
+                .filter(|(_, leader)| *leader == key)
+                .map(|(height, _)| *height)
+                .collect(),
+            votes,
+            eligible_votes,
+            missing_blocks,
+        }
+    }
+}
+
+/// Epoch activity counted so far, shared between explorer requests.
+///
+/// Decided blocks never change, so once a prefix of an epoch has been counted, later requests only
+/// need to load the blocks decided since. Only contiguous prefixes are cached, so blocks which are
+/// missing when one request is served are counted by a later request once they are available.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ValidatorActivityCache {
+    epochs: Arc<Mutex<BTreeMap<EpochNumber, EpochActivity>>>,
+}
+
+impl ValidatorActivityCache {
+    pub(crate) fn get(&self, epoch: EpochNumber) -> Option<EpochActivity> {
+        self.epochs.lock().get(&epoch).cloned()
+    }
+
+    /// Cache the activity counted for an epoch, unless more of the epoch is already cached.
+    pub(crate) fn insert(&self, activity: EpochActivity) {
+        let mut epochs = self.epochs.lock();
+        if epochs
+            .get(&activity.epoch)
+            .is_some_and(|cached| cached.next_height >= activity.next_height)
+        {
+            return;
+        }
+        epochs.insert(activity.epoch, activity);
+        // Evict the oldest epochs, which are the least likely to be requested.
+        while epochs.len() > MAX_CACHED_EPOCHS {
+            epochs.pop_first();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hotshot_types::traits::node_implementation::ConsensusTime;
+
+    use super::*;
+
+    #[test]
+    fn test_validator_activity_cache() {
+        let cache = ValidatorActivityCache::default();
+        let epoch = EpochNumber::new(1);
+        assert!(cache.get(epoch).is_none());
+
+        let mut activity = EpochActivity::new(epoch, 1, 5);
+        activity.next_height = 10;
+        cache.insert(activity);
+        assert_eq!(cache.get(epoch).unwrap().next_height(), 10);
+
+        // A request which counted less of the epoch does not replace the cached activity.
+        cache.insert(EpochActivity::new(epoch, 1, 5));
+        assert_eq!(cache.get(epoch).unwrap().next_height(), 10);
+
+        // Once the cache is full, the oldest epochs are evicted.
+        for i in 2..=(MAX_CACHED_EPOCHS as u64 + 1) {
+            cache.insert(EpochActivity::new(EpochNumber::new(i), 1, 5));
+        }
+        assert!(cache.get(epoch).is_none());
+        assert!(cache.get(EpochNumber::new(2)).is_some());
+    }
+}
@@ -927,6 +927,7 @@ mod tests {
         node_bindings::{Anvil, AnvilInstance},
         primitives::U256,
     };
+    use client::SequencerClient;
     use committable::{Commitment, Committable};
     use escargot::CargoBuild;
     use espresso_types::{BlockMerkleTree, Header, NamespaceProofQueryData, SeqTypes, Transaction};
@@ -1201,6 +1202,34 @@ mod tests {
                 sleep(Duration::from_secs(3)).await;
             }
 
+            // Check data from the query service against the light client state.
+            tracing::info!("checking the verified client");
+            let mut verified =
+                SequencerClient::new(format!("http://localhost:{api_port}").parse().unwrap())
+                    .verified(
+                        ProviderBuilder::new().on_http(l1_url.clone()),
+                        light_client_address,
+                    )
+                    .await
+                    .unwrap();
+            while verified.anchor().block_height <= tx_block_height {
+                tracing::info!("waiting for light client to finalize block {tx_block_height}");
+                sleep(Duration::from_secs(3)).await;
+                let prev = verified.anchor().block_height;
+                verified.refresh_anchor().await.unwrap();
+                assert!(verified.anchor().block_height >= prev);
+            }
+            let finalized = verified.anchor().block_height;
+            let header = verified.get_header(tx_block_height).await.unwrap();
+            assert_eq!(header.height(), tx_block_height);
+            let txs = verified
+                .get_namespace(tx_block_height, 100_u32.into())
+                .await
+                .unwrap();
+            assert!(txs.iter().any(|tx| tx.commit() == tx_hash));
+            // Blocks which the light client has not finalized cannot be verified.
+            verified.get_header(finalized).await.unwrap_err();
+
             let height = provider.get_block_number().await.unwrap();
             dev_node_client
                 .post::<()>("api/set-hotshot-down")
@@ -0,0 +1,144 @@
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context};
+use clap::{Parser, Subcommand};
+use espresso_types::SeqTypes;
+use hotshot_query_service::{
+    availability::LeafHash,
+    data_source::{
+        storage::{
+            archive,
+            sql::{Config, SqlStorage},
+            AvailabilityStorage, FileSystemStorage, NodeStorage, UpdateAvailabilityStorage,
+        },
+        VersionedDataSource,
+    },
+};
+use sequencer::persistence;
+use tagged_base64::TaggedBase64;
+
+/// Export or import portable archives of chain data.
+///
+/// Archives can be used to bootstrap a new archival query node much faster than fetching the full
+/// history from peers. Do not import into storage which is in use by a running sequencer.
+#[derive(Clone, Debug, Subcommand)]
+pub enum Commands {
+    /// Export a range of blocks from storage into an archive.
+    Export(ExportOptions),
+    /// Verify an archive and load it into storage.
+    Import(ImportOptions),
+}
+
+#[derive(Clone, Debug, Parser)]
+pub struct ExportOptions {
+    /// Directory to write the archive to.
+    #[clap(long)]
+    dir: PathBuf,
+
+    /// First block to export.
+    #[clap(long, default_value = "0")]
+    from_block: u64,
+
+    /// Last block to export (inclusive).
+    ///
+    /// If not specified, will export until the end of the chain in storage.
+    #[clap(long)]
+    to_block: Option<u64>,
+
+    /// Number of blocks in each chunk of the archive.
+    #[clap(long, default_value = "10000")]
+    chunk_size: u64,
+
+    #[command(subcommand)]
+    storage: Storage,
+}
+
+#[derive(Clone, Debug, Parser)]
+pub struct ImportOptions {
+    /// Directory containing the archive.
+    #[clap(long)]
+    dir: PathBuf,
+
+    /// Hash of the last leaf in the archive, obtained from a trusted source.
+    ///
+    /// This authenticates the contents of the archive. It may be omitted only if storage already
+    /// contains the leaf following the archive, which then serves the same purpose.
+    #[clap(long, value_parser = parse_leaf_hash)]
+    trusted_leaf_hash: Option<LeafHash<SeqTypes>>,
+
+    #[command(subcommand)]
+    storage: Storage,
+}
+
+fn parse_leaf_hash(s: &str) -> anyhow::Result<LeafHash<SeqTypes>> {
+    let tb64 = TaggedBase64::parse(s).context("malformed leaf hash")?;
+    tb64.try_into().context("malformed leaf hash")
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Storage {
+    /// Use file system storage.
+    Fs(persistence::fs::Options),
+    /// Use SQL storage.
+    Sql(Box<persistence::sql::Options>),
+}
+
+pub async fn run(opt: Commands) -> anyhow::Result<()> {
+    match opt {
+        Commands::Export(opt) => match &opt.storage {
+            Storage::Fs(fs) => {
+                let storage = FileSystemStorage::<SeqTypes>::open(fs.path()).await?;
+                export(&storage, &opt).await
+            },
+            Storage::Sql(sql) => {
+                let storage = SqlStorage::connect(Config::try_from(&**sql)?).await?;
+                export(&storage, &opt).await
+            },
+        },
+        Commands::Import(opt) => match &opt.storage {
+            Storage::Fs(fs) => {
+                let storage = FileSystemStorage::<SeqTypes>::open(fs.path()).await?;
+                import(&storage, &opt).await
+            },
+            Storage::Sql(sql) => {
+                let storage = SqlStorage::connect(Config::try_from(&**sql)?).await?;
+                import(&storage, &opt).await
+            },
+        },
+    }
+}
+
+async fn export<S>(storage: &S, opt: &ExportOptions) -> anyhow::Result<()>
+where
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<SeqTypes> + NodeStorage<SeqTypes>,
+{
+    // Convert the closed [from, to] interval to a semi-open [start, end) interval.
+    let start = opt.from_block;
+    let end = match opt.to_block {
+        Some(to) => to + 1,
+        None => storage.read().await?.block_height().await? as u64,
+    };
+    ensure!(start < end, "no blocks to export in [{start}, {end})");
+
+    tracing::info!(start, end, dir = ?opt.dir, "exporting archive");
+    let manifest = archive::export(storage, &opt.dir, start..end, opt.chunk_size).await?;
+    tracing::info!(chunks = manifest.chunks.len(), "export complete");
+    Ok(())
+}
+
+async fn import<S>(storage: &S, opt: &ImportOptions) -> anyhow::Result<()>
+where
+    S: VersionedDataSource,
+    for<'a> S::Transaction<'a>: UpdateAvailabilityStorage<SeqTypes>,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<SeqTypes>,
+{
+    tracing::info!(dir = ?opt.dir, "importing archive");
+    let manifest = archive::import(storage, &opt.dir, opt.trusted_leaf_hash).await?;
+    tracing::info!(
+        start = manifest.start,
+        end = manifest.end,
+        "import complete"
+    );
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use anyhow::{bail, ensure};
+use clap::{Parser, Subcommand};
+use espresso_types::{v0_1::RewardMerkleTree, BlockMerkleTree, FeeMerkleTree, SeqTypes};
+use hotshot_query_service::data_source::{
+    storage::{
+        integrity::{self, Inconsistency, ObjectKind},
+        sql::{Config, SqlStorage},
+        AvailabilityStorage, FileSystemStorage, NodeStorage,
+    },
+    VersionedDataSource,
+};
+use jf_merkle_tree::MerkleTreeScheme;
+use sequencer::persistence;
+
+/// Check the integrity of query service data in storage.
+///
+/// Leaves are checked against their QCs and the hash chain, and payloads, VID common data and
+/// payload metadata against the commitments in their headers. With SQL storage, the roots of the
+/// block, fee and reward Merkle trees are also checked against the state commitments in each
+/// header.
+///
+/// Inconsistencies are logged. With `--repair`, the affected blocks are deleted, so that a
+/// sequencer running on this storage will fetch them again from its peers. Problems in the
+/// Merklized state are never repaired. Do not repair storage which is in use by a running
+/// sequencer.
+#[derive(Clone, Debug, Parser)]
+pub struct Options {
+    /// First block to check.
+    #[clap(long, default_value = "0")]
+    from_block: u64,
+
+    /// Last block to check (inclusive).
+    ///
+    /// If not specified, will check until the end of the chain in storage.
+    #[clap(long)]
+    to_block: Option<u64>,
+
+    /// Number of blocks to check in each transaction.
+    #[clap(long, default_value = "1000")]
+    batch_size: u64,
+
+    /// Skip checking the Merklized state, which is much slower than checking blocks.
+    #[clap(long)]
+    skip_state: bool,
+
+    /// Delete blocks which are found to be inconsistent.
+    ///
+    /// Only supported for SQL storage.
+    #[clap(long)]
+    repair: bool,
+
+    #[command(subcommand)]
+    storage: Storage,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Storage {
+    /// Use file system storage.
+    Fs(persistence::fs::Options),
+    /// Use SQL storage.
+    Sql(Box<persistence::sql::Options>),
+}
+
+pub async fn run(opt: Options) -> anyhow::Result<()> {
+    match &opt.storage {
+        Storage::Fs(fs) => {
+            if opt.repair {
+                bail!("file system storage is append-only and cannot be repaired");
+            }
+            let storage = FileSystemStorage::<SeqTypes>::open(fs.path()).await?;
+            let problems = scan_blocks(&storage, &opt).await?;
+            report(&problems)
+        },
+        Storage::Sql(sql) => {
+            let storage = SqlStorage::connect(Config::try_from(&**sql)?).await?;
+            let mut problems = scan_blocks(&storage, &opt).await?;
+            if !opt.skip_state {
+                problems.extend(scan_state(&storage, &opt).await?);
+            }
+            if opt.repair && !problems.is_empty() {
+                log(&problems);
+                let deleted = integrity::repair(&storage, &problems).await?;
+                tracing::warn!(?deleted, "deleted inconsistent blocks");
+                ensure!(
+                    problems
+                        .iter()
+                        .all(|problem| problem.kind != ObjectKind::MerklizedState),
+                    "Merklized state is inconsistent and cannot be repaired"
+                );
+                return Ok(());
+            }
+            report(&problems)
+        },
+    }
+}
+
+/// Convert the closed [from, to] interval to a semi-open [start, end) interval.
+async fn range<S>(storage: &S, opt: &Options) -> anyhow::Result<(u64, u64)>
+where
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: NodeStorage<SeqTypes>,
+{
+    let start = opt.from_block;
+    let end = match opt.to_block {
+        Some(to) => to + 1,
+        None => storage.read().await?.block_height().await? as u64,
+    };
+    ensure!(start < end, "no blocks to check in [{start}, {end})");
+    Ok((start, end))
+}
+
+async fn scan_blocks<S>(storage: &S, opt: &Options) -> anyhow::Result<Vec<Inconsistency>>
+where
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<SeqTypes> + NodeStorage<SeqTypes>,
+{
+    let (start, end) = range(storage, opt).await?;
+    tracing::info!(start, end, "checking blocks");
+    integrity::scan::<SeqTypes, _>(storage, start..end, opt.batch_size).await
+}
+
+async fn scan_state(storage: &SqlStorage, opt: &Options) -> anyhow::Result<Vec<Inconsistency>> {
+    // Headers from before the reward Merkle tree was introduced do not commit to it, so those
+    // heights are skipped for that tree.
+    let (start, end) = range(storage, opt).await?;
+    tracing::info!(start, end, "checking Merklized state");
+
+    let mut problems = integrity::scan_merklized_state::<
+        SeqTypes,
+        BlockMerkleTree,
+        _,
+        { BlockMerkleTree::ARITY },
+    >(storage, start..end)
+    .await?;
+    problems.extend(
+        integrity::scan_merklized_state::<SeqTypes, FeeMerkleTree, _, { FeeMerkleTree::ARITY }>(
+            storage,
+            start..end,
+        )
+        .await?,
+    );
+    problems.extend(
+        integrity::scan_merklized_state::<
+            SeqTypes,
+            RewardMerkleTree,
+            _,
+            { RewardMerkleTree::ARITY },
+        >(storage, start..end)
+        .await?,
+    );
+    Ok(problems)
+}
+
+fn log(problems: &[Inconsistency]) {
+    for problem in problems {
+        tracing::error!(%problem, "inconsistent data in storage");
+    }
+}
+
+fn report(problems: &[Inconsistency]) -> anyhow::Result<()> {
+    log(problems);
+    ensure!(
+        problems.is_empty(),
+        "found {} inconsistencies in storage",
+        problems.len()
+    );
+    tracing::info!("storage is consistent");
+    Ok(())
+}
@@ -2,7 +2,10 @@
 
 use clap::{Parser, Subcommand};
 use sequencer_utils::logging;
+mod archive;
+mod integrity;
 mod keygen;
+mod migrate_storage;
 mod ns_aggregator;
 mod pubkey;
 mod reset_storage;
@@ -23,6 +26,10 @@ enum Command {
     #[command(subcommand)]
     ResetStorage(reset_storage::Commands),
     NsAggregator(ns_aggregator::Options),
+    #[command(subcommand)]
+    Archive(archive::Commands),
+    MigrateStorage(migrate_storage::Options),
+    Integrity(integrity::Options),
 }
 
 #[tokio::main]
@@ -38,5 +45,8 @@ async fn main() -> anyhow::Result<()> {
         },
         Command::ResetStorage(opt) => reset_storage::run(opt).await,
         Command::NsAggregator(opt) => ns_aggregator::run(opt).await,
+        Command::Archive(opt) => archive::run(opt).await,
+        Command::MigrateStorage(opt) => migrate_storage::run(opt).await,
+        Command::Integrity(opt) => integrity::run(opt).await,
     }
 }
@@ -0,0 +1,178 @@
+use std::path::PathBuf;
+
+use anyhow::ensure;
+use clap::{Parser, Subcommand};
+use espresso_types::{v0_1::RewardMerkleTree, BlockMerkleTree, FeeMerkleTree, SeqTypes};
+#[cfg(feature = "sqlite-source")]
+use hotshot_query_service::data_source::storage::sql::SqliteSource;
+use hotshot_query_service::{
+    data_source::{
+        storage::{
+            migration::{self, MerklizedStateSource, MigrationCfg, MigrationSource},
+            sql::{Config, SqlStorage},
+            AggregatesStorage, AvailabilityStorage, FileSystemStorage, NodeStorage,
+            UpdateAggregatesStorage, UpdateAvailabilityStorage,
+        },
+        VersionedDataSource,
+    },
+    merklized_state::MerklizedState,
+};
+use sequencer::persistence;
+
+/// Migrate query service data from one storage backend to another.
+///
+/// This copies leaves, blocks, VID data and state certificates, and recomputes aggregate
+/// statistics in the destination. When both sides are SQL databases, merklized state is copied as
+/// well. Otherwise, it is rebuilt from the migrated leaves when the sequencer is started on the new
+/// storage.
+///
+/// The SQL backend (SQLite or Postgres) is determined by how this program was built. To move from
+/// SQLite to Postgres, use a Postgres build with the `sqlite-source` feature and the `sqlite`
+/// source, which reads the SQLite database directly.
+///
+/// Do not run this program while a sequencer is using the destination storage.
+#[derive(Clone, Debug, Parser)]
+pub struct Options {
+    /// File in which to record progress.
+    ///
+    /// If the file exists, the migration resumes from the progress recorded in it.
+    #[clap(long)]
+    checkpoint: Option<PathBuf>,
+
+    /// Number of blocks to copy in each transaction.
+    #[clap(long, default_value = "1000")]
+    batch_size: u64,
+
+    /// Skip the consistency check after migrating.
+    #[clap(long)]
+    skip_verify: bool,
+
+    #[command(subcommand)]
+    from: Source,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Source {
+    /// Migrate from file system storage.
+    Fs {
+        #[clap(flatten)]
+        opt: persistence::fs::Options,
+        #[command(subcommand)]
+        to: Destination,
+    },
+    /// Migrate from SQL storage.
+    Sql {
+        #[clap(flatten)]
+        opt: Box<persistence::sql::Options>,
+        #[command(subcommand)]
+        to: Destination,
+    },
+    /// Migrate from a SQLite database, regardless of the SQL backend of this build.
+    #[cfg(feature = "sqlite-source")]
+    Sqlite {
+        /// Path to the SQLite database file.
+        #[clap(long)]
+        path: PathBuf,
+        #[command(subcommand)]
+        to: Destination,
+    },
+}
+
+#[derive(Clone, Debug, Subcommand)]
+pub enum Destination {
+    /// Migrate to file system storage.
+    Fs(persistence::fs::Options),
+    /// Migrate to SQL storage.
+    Sql(Box<persistence::sql::Options>),
+}
+
+pub async fn run(opt: Options) -> anyhow::Result<()> {
+    match &opt.from {
+        Source::Fs { opt: src, to } => {
+            let src = FileSystemStorage::<SeqTypes>::open(src.path()).await?;
+            migrate_to(&src, None, to, &opt).await
+        },
+        Source::Sql { opt: src, to } => {
+            let src = SqlStorage::connect(Config::try_from(&**src)?).await?;
+            migrate_to(&src, Some(&src), to, &opt).await
+        },
+        #[cfg(feature = "sqlite-source")]
+        Source::Sqlite { path, to } => {
+            let src = SqliteSource::open(path).await?;
+            migrate_to(&src, Some(&src), to, &opt).await
+        },
+    }
+}
+
+async fn migrate_to<Src>(
+    src: &Src,
+    state: Option<&dyn MerklizedStateSource>,
+    to: &Destination,
+    opt: &Options,
+) -> anyhow::Result<()>
+where
+    Src: MigrationSource<SeqTypes>,
+{
+    match to {
+        Destination::Fs(dst) => {
+            let dst = FileSystemStorage::<SeqTypes>::open(dst.path()).await?;
+            if state.is_some() {
+                tracing::warn!(
+                    "file system storage does not hold merklized state, it will not be migrated"
+                );
+            }
+            migrate(src, &dst, opt).await
+        },
+        Destination::Sql(dst) => {
+            let dst = SqlStorage::connect(Config::try_from(&**dst)?).await?;
+            migrate(src, &dst, opt).await?;
+            if let Some(state) = state {
+                let tables = [
+                    BlockMerkleTree::state_type(),
+                    FeeMerkleTree::state_type(),
+                    RewardMerkleTree::state_type(),
+                ];
+                let nodes =
+                    migration::migrate_merklized_state(state, &dst, &tables, opt.batch_size)
+                        .await?;
+                tracing::info!(nodes, "merklized state migration complete");
+            }
+            Ok(())
+        },
+    }
+}
+
+async fn migrate<Src, Dst>(src: &Src, dst: &Dst, opt: &Options) -> anyhow::Result<()>
+where
+    Src: MigrationSource<SeqTypes>,
+    Dst: VersionedDataSource,
+    for<'a> Dst::Transaction<'a>:
+        UpdateAvailabilityStorage<SeqTypes> + UpdateAggregatesStorage<SeqTypes>,
+    for<'a> Dst::ReadOnly<'a>:
+        AvailabilityStorage<SeqTypes> + NodeStorage<SeqTypes> + AggregatesStorage<SeqTypes>,
+{
+    let mut cfg = MigrationCfg::default().with_batch_size(opt.batch_size);
+    if let Some(path) = &opt.checkpoint {
+        cfg = cfg.with_checkpoint(path);
+    }
+
+    let report = migration::migrate::<SeqTypes, _, _>(src, dst, &cfg).await?;
+    tracing::info!(?report, "migration complete");
+
+    if !opt.skip_verify {
+        // Check everything up to the end of the migration, including anything migrated by previous
+        // runs which were resumed from the checkpoint.
+        let mismatches =
+            migration::verify::<SeqTypes, _, _>(src, dst, 0..report.range.end, opt.batch_size)
+                .await?;
+        ensure!(
+            mismatches.is_empty(),
+            "destination is inconsistent with source at {} heights, starting from {}",
+            mismatches.len(),
+            mismatches[0]
+        );
+        tracing::info!("destination is consistent with source");
+    }
+
+    Ok(())
+}
@@ -32,6 +32,19 @@ pub struct Options {
     #[clap(short, long, default_value = "1")]
     jobs: usize,
 
+    /// Use the aggregate statistics maintained by the query service, instead of downloading every
+    /// block in the range.
+    ///
+    /// This is much faster, but not every query service supports it: the file system backend only
+    /// tracks statistics for the whole chain, not for a range of blocks. If the query service
+    /// cannot answer, we fall back to downloading every block.
+    ///
+    /// Note that the byte counts differ between the two modes: a scan counts only the bytes of
+    /// transaction payloads, while the aggregate statistics count the full size of the namespace in
+    /// each block payload, including its transaction table.
+    #[clap(long)]
+    aggregate: bool,
+
     /// Espresso query service URL.
     url: Url,
 }
@@ -51,6 +64,22 @@ pub async fn run(opt: Options) -> anyhow::Result<()> {
     };
     ensure!(end > start, "to-block < from-block");
 
+    if opt.aggregate {
+        match aggregate(&client, ns, start, end).await {
+            Ok((num_txs, bytes)) => {
+                println!("Aggregated range [{start}, {end}) for namespace {ns}");
+                println!("{num_txs} transactions");
+                println!("{bytes} bytes (including transaction tables)");
+                return Ok(());
+            },
+            Err(err) => {
+                tracing::warn!(
+                    "aggregate statistics unavailable, scanning blocks instead: {err:#}"
+                );
+            },
+        }
+    }
+
     let tasks = (0..opt.jobs).map(|i| {
         let chunk_size = (end - start) / opt.jobs;
         let chunk_start = start + i * chunk_size;
@@ -73,6 +102,26 @@ pub async fn run(opt: Options) -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn aggregate(
+    client: &surf_disco::Client<hotshot_query_service::Error, SequencerApiVersion>,
+    ns: NamespaceId,
+    start: usize,
+    end: usize,
+) -> anyhow::Result<(usize, usize)> {
+    let range = format!("{ns}/{start}/{}", end - 1);
+    let num_txs = client
+        .get(&format!("node/transactions/count/namespace/{range}"))
+        .send()
+        .await
+        .context("requesting transaction count")?;
+    let bytes = client
+        .get(&format!("node/payloads/size/namespace/{range}"))
+        .send()
+        .await
+        .context("requesting payload size")?;
+    Ok((num_txs, bytes))
+}
+
 async fn process_chunk(
     url: Url,
     ns: NamespaceId,
@@ -524,6 +524,7 @@ impl ModuleArgs {
                 SequencerModule::Explorer(m) => {
                     curr = m.add(&mut modules.explorer, &mut provided)?
                 },
+                SequencerModule::Quotas(m) => curr = m.add(&mut modules.quotas, &mut provided)?,
             }
         }
 
@@ -558,6 +559,7 @@ module!("catchup", api::options::Catchup, requires: "http");
 module!("config", api::options::Config, requires: "http");
 module!("hotshot-events", api::options::HotshotEvents, requires: "http");
 module!("explorer", api::options::Explorer, requires: "http", "storage-sql");
+module!("quotas", api::options::Quotas, requires: "http");
 
 #[derive(Clone, Debug, Args)]
 struct Module<Options: ModuleInfo> {
@@ -638,6 +640,10 @@ enum SequencerModule {
     ///
     /// This module requires the http and storage-sql modules to be started.
     Explorer(Module<api::options::Explorer>),
+    /// Enforce per-client quotas on the HTTP API, using API keys.
+    ///
+    /// This module requires the http module to be started.
+    Quotas(Module<api::options::Quotas>),
 }
 
 #[derive(Clone, Debug, Default)]
@@ -652,4 +658,5 @@ pub struct Modules {
     pub config: Option<api::options::Config>,
     pub hotshot_events: Option<api::options::HotshotEvents>,
     pub explorer: Option<api::options::Explorer>,
+    pub quotas: Option<api::options::Quotas>,
 }
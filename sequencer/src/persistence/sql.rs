@@ -17,8 +17,8 @@ use espresso_types::{
     traits::{EventsPersistenceRead, MembershipPersistence},
     v0::traits::{EventConsumer, PersistenceOptions, SequencerPersistence, StateCatchup},
     v0_3::{EventKey, IndexedStake, StakeTableEvent},
-    BackoffParams, BlockMerkleTree, FeeMerkleTree, Leaf, Leaf2, NetworkConfig, Payload,
-    ValidatorMap,
+    BackoffParams, BlockMerkleTree, FeeMerkleTree, Leaf, Leaf2, NetworkConfig, ParseDurationError,
+    Payload, ValidatorMap,
 };
 use futures::stream::StreamExt;
 use hotshot::InitializerEpochInfo;
@@ -29,13 +29,13 @@ use hotshot_query_service::{
     availability::LeafQueryData,
     data_source::{
         storage::{
-            pruning::PrunerCfg,
+            pruning::{PrunedData, PrunerCfg, Retention},
             sql::{
-                include_migrations, query_as, syntax_helpers::MAX_FN, Config, Db, SqlStorage,
-                Transaction, TransactionMode, Write,
+                include_migrations, query_as, syntax_helpers::MAX_FN, CacheCfg, ColdStorageCfg,
+                Config, Db, SqlStorage, Transaction, TransactionMode, Write,
             },
         },
-        Transaction as _, VersionedDataSource,
+        AvailabilityProvider, Transaction as _, VersionedDataSource,
     },
     fetching::{
         request::{LeafRequest, PayloadRequest, VidCommonRequest},
@@ -66,6 +66,7 @@ use hotshot_types::{
 };
 use itertools::Itertools;
 use sqlx::{query, Executor, Row};
+use url::Url;
 
 use crate::{
     catchup::SqlStateCatchup, persistence::persistence_metrics::PersistenceMetricsValue, NodeType,
@@ -180,6 +181,14 @@ pub struct Options {
     #[clap(flatten)]
     pub(crate) consensus_pruning: ConsensusPruningOptions,
 
+    /// Cold storage parameters.
+    #[clap(flatten)]
+    pub(crate) cold_storage: ColdStorageOptions,
+
+    /// In-memory cache parameters.
+    #[clap(flatten)]
+    pub(crate) cache: CacheOptions,
+
     /// Specifies the maximum number of concurrent fetch requests allowed from peers.
     #[clap(long, env = "ESPRESSO_SEQUENCER_FETCH_RATE_LIMIT")]
     pub(crate) fetch_rate_limit: Option<usize>,
@@ -192,6 +201,37 @@ pub struct Options {
     #[clap(long, env = "ESPRESSO_SEQUENCER_CHUNK_FETCH_DELAY", value_parser = parse_duration)]
     pub(crate) chunk_fetch_delay: Option<Duration>,
 
+    /// Backfill missing data in bulk, by requesting ranges of objects from several peers at once.
+    ///
+    /// This is useful for a node which is missing a lot of data, such as a new archival node.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_BACKFILL")]
+    pub(crate) backfill: bool,
+
+    /// Number of blocks to verify and store at a time when backfilling.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_BACKFILL_WINDOW_SIZE")]
+    pub(crate) backfill_window_size: Option<usize>,
+
+    /// Number of objects to request from a peer at a time when backfilling.
+    ///
+    /// This should not exceed the range limits of the peers.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_BACKFILL_REQUEST_SIZE")]
+    pub(crate) backfill_request_size: Option<usize>,
+
+    /// Number of simultaneous requests to peers when backfilling.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_BACKFILL_PARALLELISM")]
+    pub(crate) backfill_parallelism: Option<usize>,
+
+    /// Periodically check the integrity of the query service data in storage.
+    ///
+    /// Inconsistencies are logged and reported in metrics. They can be repaired offline with the
+    /// `utils integrity` command.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_INTEGRITY_SCAN")]
+    pub(crate) integrity_scan: bool,
+
+    /// How often to run an integrity scan.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_INTEGRITY_SCAN_INTERVAL", value_parser = parse_duration)]
+    pub(crate) integrity_scan_interval: Option<Duration>,
+
     /// Disable pruning and reconstruct previously pruned data.
     ///
     /// While running without pruning is the default behavior, the default will not try to
@@ -427,6 +467,12 @@ impl TryFrom<&Options> for Config {
         if opt.archive {
             cfg = cfg.archive();
         }
+        if let Some(cold_cfg) = opt.cold_storage.cfg() {
+            cfg = cfg.cold_storage(cold_cfg)?;
+        }
+        if let Some(cache_cfg) = opt.cache.cfg() {
+            cfg = cfg.cache(cache_cfg)?;
+        }
 
         Ok(cfg)
     }
@@ -485,6 +531,75 @@ pub struct PruningOptions {
     /// This value corresponds to `N` in the SQLite PRAGMA `incremental_vacuum(N)`,
     #[clap(long, env = "ESPRESSO_SEQUENCER_PRUNER_INCREMENTAL_VACUUM_PAGES")]
     pages: Option<u64>,
+
+    /// Retention period for leaves and headers, or "forever".
+    ///
+    /// Defaults to the target retention period. Payloads, VID data and the transactions index
+    /// cannot be kept longer than leaves.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_PRUNER_LEAF_RETENTION",
+        value_parser = parse_retention,
+    )]
+    leaf_retention: Option<Retention>,
+
+    /// Retention period for block payloads, or "forever".
+    ///
+    /// Defaults to the target retention period.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_PRUNER_PAYLOAD_RETENTION",
+        value_parser = parse_retention,
+    )]
+    payload_retention: Option<Retention>,
+
+    /// Retention period for VID data, or "forever".
+    ///
+    /// Defaults to the target retention period.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_PRUNER_VID_RETENTION",
+        value_parser = parse_retention,
+    )]
+    vid_retention: Option<Retention>,
+
+    /// Retention period for the index of transactions by hash, or "forever".
+    ///
+    /// Defaults to the target retention period.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_PRUNER_TRANSACTIONS_RETENTION",
+        value_parser = parse_retention,
+    )]
+    transactions_retention: Option<Retention>,
+
+    /// Retention period for historical merklized state, or "forever".
+    ///
+    /// Defaults to the target retention period.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_PRUNER_STATE_RETENTION",
+        value_parser = parse_retention,
+    )]
+    state_retention: Option<Retention>,
+
+    /// Retention period for aggregate statistics, or "forever".
+    ///
+    /// Defaults to "forever".
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_PRUNER_AGGREGATES_RETENTION",
+        value_parser = parse_retention,
+    )]
+    aggregates_retention: Option<Retention>,
+}
+
+fn parse_retention(s: &str) -> Result<Retention, ParseDurationError> {
+    if s == "forever" {
+        Ok(Retention::Forever)
+    } else {
+        parse_duration(s).map(Retention::Duration)
+    }
 }
 
 impl From<PruningOptions> for PrunerCfg {
@@ -513,6 +628,19 @@ impl From<PruningOptions> for PrunerCfg {
             cfg = cfg.with_incremental_vacuum_pages(pages)
         }
 
+        for (data, retention) in [
+            (PrunedData::Leaf, opt.leaf_retention),
+            (PrunedData::Payload, opt.payload_retention),
+            (PrunedData::VidCommon, opt.vid_retention),
+            (PrunedData::Transactions, opt.transactions_retention),
+            (PrunedData::MerklizedState, opt.state_retention),
+            (PrunedData::Aggregates, opt.aggregates_retention),
+        ] {
+            if let Some(retention) = retention {
+                cfg = cfg.with_retention(data, retention);
+            }
+        }
+
         cfg = cfg.with_state_tables(vec![
             BlockMerkleTree::state_type().to_string(),
             FeeMerkleTree::state_type().to_string(),
@@ -522,6 +650,90 @@ impl From<PruningOptions> for PrunerCfg {
     }
 }
 
+/// Parameters for moving old payloads and VID common data to an object store.
+#[derive(Parser, Clone, Debug)]
+pub struct ColdStorageOptions {
+    /// Object store to move old payloads and VID common data to.
+    ///
+    /// This can be an S3 URL (`s3://bucket/prefix`) or a local directory (`file:///path`). For S3
+    /// and S3-compatible services, credentials and endpoint are taken from the standard `AWS_*`
+    /// environment variables, e.g. `AWS_ENDPOINT` and `AWS_ALLOW_HTTP` for a local MinIO server.
+    /// If not set, all data is kept in the database.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_COLD_STORAGE_URL")]
+    cold_storage_url: Option<Url>,
+
+    /// Age at which data is moved to cold storage.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_COLD_STORAGE_AGE",
+        value_parser = parse_duration,
+    )]
+    cold_storage_age: Option<Duration>,
+
+    /// Number of blocks worth of data to move in a single transaction.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_COLD_STORAGE_BATCH_SIZE")]
+    cold_storage_batch_size: Option<u64>,
+
+    /// Interval for checking for data to move to cold storage.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_COLD_STORAGE_INTERVAL",
+        value_parser = parse_duration,
+    )]
+    cold_storage_interval: Option<Duration>,
+}
+
+impl ColdStorageOptions {
+    fn cfg(&self) -> Option<ColdStorageCfg> {
+        let mut cfg = ColdStorageCfg::new(self.cold_storage_url.clone()?);
+        if let Some(age) = self.cold_storage_age {
+            cfg = cfg.with_age(age);
+        }
+        if let Some(batch_size) = self.cold_storage_batch_size {
+            cfg = cfg.with_batch_size(batch_size);
+        }
+        if let Some(interval) = self.cold_storage_interval {
+            cfg = cfg.with_interval(interval);
+        }
+        Some(cfg)
+    }
+}
+
+/// Parameters for caching recent query service data in memory.
+#[derive(Parser, Clone, Copy, Debug)]
+pub struct CacheOptions {
+    /// Keep recently inserted leaves, headers, payload summaries and VID common data in memory.
+    ///
+    /// Requests for these objects by height or hash are answered from memory when possible,
+    /// instead of querying the database.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_QUERY_CACHE")]
+    query_cache: bool,
+
+    /// Maximum number of objects to keep in the cache.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_QUERY_CACHE_MAX_OBJECTS")]
+    query_cache_max_objects: Option<usize>,
+
+    /// Maximum total size of the objects in the cache, in bytes.
+    #[clap(long, env = "ESPRESSO_SEQUENCER_QUERY_CACHE_MAX_BYTES")]
+    query_cache_max_bytes: Option<usize>,
+}
+
+impl CacheOptions {
+    fn cfg(&self) -> Option<CacheCfg> {
+        if !self.query_cache {
+            return None;
+        }
+        let mut cfg = CacheCfg::default();
+        if let Some(max_objects) = self.query_cache_max_objects {
+            cfg = cfg.with_max_objects(max_objects);
+        }
+        if let Some(max_bytes) = self.query_cache_max_bytes {
+            cfg = cfg.with_max_bytes(max_bytes);
+        }
+        Some(cfg)
+    }
+}
+
 /// Pruning parameters for ephemeral consensus storage.
 #[derive(Parser, Clone, Copy, Debug)]
 pub struct ConsensusPruningOptions {
@@ -2527,6 +2739,10 @@ impl Provider<SeqTypes, LeafRequest<SeqTypes>> for Persistence {
     }
 }
 
+// Consensus storage only holds recent, undecided data, so range requests fall back to fetching
+// each object individually, and will usually fail.
+impl AvailabilityProvider<SeqTypes> for Persistence {}
+
 async fn fetch_leaf_from_proposals<Mode: TransactionMode>(
     tx: &mut Transaction<Mode>,
     req: LeafRequest<SeqTypes>,
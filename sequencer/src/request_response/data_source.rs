@@ -10,18 +10,20 @@ use espresso_types::{
     retain_accounts,
     traits::SequencerPersistence,
     v0_1::{RewardAccount, RewardMerkleTree},
-    NodeState, PubKey, SeqTypes,
+    NodeState, Payload, PubKey, SeqTypes,
 };
 use hotshot::{traits::NodeImplementation, SystemContext};
 use hotshot_query_service::{
+    availability::{LeafId, LeafQueryData},
     data_source::{
-        storage::{FileSystemStorage, NodeStorage, SqlStorage},
+        storage::{AvailabilityStorage, FileSystemStorage, NodeStorage, SqlStorage},
         VersionedDataSource,
     },
     node::BlockId,
+    VidCommon,
 };
 use hotshot_types::{
-    data::ViewNumber,
+    data::{VidCommitment, ViewNumber},
     traits::{
         network::ConnectedNetwork,
         node_implementation::{ConsensusTime, Versions},
@@ -284,8 +286,98 @@ impl<
 
                 Ok(Response::VidShare(vid_share))
             },
+
+            Request::Payload(commit) => {
+                // Load the payload and the VID common data needed to verify it from storage
+                let (payload, common) = match &self.storage {
+                    Some(Storage::Sql(storage)) => load_payload(&**storage, *commit).await,
+                    Some(Storage::Fs(storage)) => load_payload(&**storage, *commit).await,
+                    _ => bail!("storage was not initialized"),
+                }
+                .with_context(|| format!("failed to get payload {commit}"))?;
+
+                Ok(Response::Payload(payload, common))
+            },
+
+            Request::VidCommon(commit) => {
+                // Load the VID common data from storage
+                let common = match &self.storage {
+                    Some(Storage::Sql(storage)) => load_vid_common(&**storage, *commit).await,
+                    Some(Storage::Fs(storage)) => load_vid_common(&**storage, *commit).await,
+                    _ => bail!("storage was not initialized"),
+                }
+                .with_context(|| format!("failed to get vid common {commit}"))?;
+
+                Ok(Response::VidCommon(common))
+            },
+
+            Request::DecidedLeaf(height) => {
+                // Load the leaf and its QC from storage
+                let leaf = match &self.storage {
+                    Some(Storage::Sql(storage)) => load_leaf(&**storage, *height).await,
+                    Some(Storage::Fs(storage)) => load_leaf(&**storage, *height).await,
+                    _ => bail!("storage was not initialized"),
+                }
+                .with_context(|| format!("failed to get decided leaf {height}"))?;
+
+                Ok(Response::DecidedLeaf(leaf))
+            },
         }
     }
+
+    fn can_respond_to(&self, request: &Request) -> bool {
+        match request {
+            // These can only be answered from storage, so a node without any should not even try
+            Request::VidShare(..)
+            | Request::Payload(_)
+            | Request::VidCommon(_)
+            | Request::DecidedLeaf(_) => self.storage.is_some(),
+            // Everything else may be answered from memory
+            _ => true,
+        }
+    }
+}
+
+/// Load a payload and its VID common data from query service storage
+async fn load_payload<S>(storage: &S, commit: VidCommitment) -> Result<(Payload, VidCommon)>
+where
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<SeqTypes>,
+{
+    let mut tx = storage
+        .read()
+        .await
+        .with_context(|| "failed to open storage transaction")?;
+    let payload = tx.get_payload(BlockId::PayloadHash(commit)).await?;
+    let common = tx.get_vid_common(BlockId::PayloadHash(commit)).await?;
+    Ok((payload.data().clone(), common.common().clone()))
+}
+
+/// Load VID common data from query service storage
+async fn load_vid_common<S>(storage: &S, commit: VidCommitment) -> Result<VidCommon>
+where
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<SeqTypes>,
+{
+    let mut tx = storage
+        .read()
+        .await
+        .with_context(|| "failed to open storage transaction")?;
+    let common = tx.get_vid_common(BlockId::PayloadHash(commit)).await?;
+    Ok(common.common().clone())
+}
+
+/// Load a decided leaf from query service storage
+async fn load_leaf<S>(storage: &S, height: u64) -> Result<LeafQueryData<SeqTypes>>
+where
+    S: VersionedDataSource,
+    for<'a> S::ReadOnly<'a>: AvailabilityStorage<SeqTypes>,
+{
+    let mut tx = storage
+        .read()
+        .await
+        .with_context(|| "failed to open storage transaction")?;
+    Ok(tx.get_leaf(LeafId::Number(height as usize)).await?)
 }
 
 /// Get a partial snapshot of the given reward state, which contains only the specified accounts.
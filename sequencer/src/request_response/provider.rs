@@ -0,0 +1,252 @@
+//! This file implements the query service [`Provider`] trait for the [`RequestResponseProtocol`],
+//! so that a query node can fetch missing payloads, VID common data and leaves from its staked
+//! peers, without needing any HTTP peers.
+
+use anyhow::{anyhow, ensure, Context};
+use async_trait::async_trait;
+use espresso_types::{traits::SequencerPersistence, Payload, PubKey, SeqTypes};
+use hotshot::traits::NodeImplementation;
+use hotshot_query_service::{
+    availability::LeafQueryData,
+    fetching::{
+        request::{LeafRequest, PayloadRequest, VidCommonRequest},
+        Provider, Request as FetchRequest,
+    },
+    VidCommon,
+};
+use hotshot_types::{
+    data::{ns_table::parse_ns_table, VidCommitment},
+    traits::{network::ConnectedNetwork, node_implementation::Versions, EncodeBytes},
+    vid::{
+        advz::{advz_scheme, ADVZScheme},
+        avidm::{init_avidm_param, AvidMScheme},
+    },
+};
+use jf_vid::VidScheme;
+use request_response::RequestType;
+use tokio::time::timeout;
+
+use crate::request_response::{
+    request::{Request, Response},
+    RequestResponseProtocol,
+};
+
+#[async_trait]
+impl<
+        I: NodeImplementation<SeqTypes>,
+        V: Versions,
+        N: ConnectedNetwork<PubKey>,
+        P: SequencerPersistence,
+    > Provider<SeqTypes, PayloadRequest> for RequestResponseProtocol<I, V, N, P>
+{
+    async fn fetch(&self, req: PayloadRequest) -> Option<Payload> {
+        self.fetch_availability(Request::Payload(req.0), move |response| {
+            // Make sure the response is a payload response
+            let Response::Payload(payload, common) = response else {
+                return Err(anyhow!("expected payload response"));
+            };
+
+            // Verify the payload against the requested commitment
+            verify_payload(req.0, &payload, &common)?;
+            Ok(payload)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl<
+        I: NodeImplementation<SeqTypes>,
+        V: Versions,
+        N: ConnectedNetwork<PubKey>,
+        P: SequencerPersistence,
+    > Provider<SeqTypes, VidCommonRequest> for RequestResponseProtocol<I, V, N, P>
+{
+    async fn fetch(&self, req: VidCommonRequest) -> Option<VidCommon> {
+        self.fetch_availability(Request::VidCommon(req.0), move |response| {
+            // Make sure the response is a VID common response
+            let Response::VidCommon(common) = response else {
+                return Err(anyhow!("expected vid common response"));
+            };
+
+            // Verify the VID common data against the requested commitment
+            ensure!(
+                <VidCommonRequest as FetchRequest<SeqTypes>>::is_valid_response(&req, &common),
+                "vid common is inconsistent with commitment {}",
+                req.0
+            );
+            Ok(common)
+        })
+        .await
+    }
+}
+
+#[async_trait]
+impl<
+        I: NodeImplementation<SeqTypes>,
+        V: Versions,
+        N: ConnectedNetwork<PubKey>,
+        P: SequencerPersistence,
+    > Provider<SeqTypes, LeafRequest<SeqTypes>> for RequestResponseProtocol<I, V, N, P>
+{
+    async fn fetch(&self, req: LeafRequest<SeqTypes>) -> Option<LeafQueryData<SeqTypes>> {
+        self.fetch_availability(Request::DecidedLeaf(req.height), move |response| {
+            // Make sure the response is a leaf response
+            let Response::DecidedLeaf(leaf) = response else {
+                return Err(anyhow!("expected decided leaf response"));
+            };
+
+            // Verify the leaf and QC against the hashes we expect
+            ensure!(
+                req.is_valid_response(&leaf),
+                "leaf {} does not match the requested leaf and QC",
+                req.height
+            );
+            Ok(leaf)
+        })
+        .await
+    }
+}
+
+impl<
+        I: NodeImplementation<SeqTypes>,
+        V: Versions,
+        N: ConnectedNetwork<PubKey>,
+        P: SequencerPersistence,
+    > RequestResponseProtocol<I, V, N, P>
+{
+    /// Request an object from our peers, accepting the first response which passes
+    /// `response_validation_fn`.
+    ///
+    /// Returns [`None`] if no valid response is received within a few batch intervals, so that the
+    /// query service can try another provider.
+    async fn fetch_availability<F, O>(
+        &self,
+        request: Request,
+        response_validation_fn: F,
+    ) -> Option<O>
+    where
+        F: Fn(Response) -> anyhow::Result<O> + Send + Sync + 'static + Clone,
+        O: Send + Sync + 'static + Clone,
+    {
+        tracing::debug!(?request, "fetching from peers");
+
+        // Timeout after a few batches
+        let timeout_duration = self.config.request_batch_interval * 3;
+
+        let response_validation_fn = move |_request: &Request, response: Response| {
+            // Clone the validation function so the future can own it
+            let response_validation_fn = response_validation_fn.clone();
+            async move { response_validation_fn(response) }
+        };
+
+        match timeout(
+            timeout_duration,
+            self.request_indefinitely(
+                request.clone(),
+                RequestType::Batched,
+                response_validation_fn,
+            ),
+        )
+        .await
+        {
+            Ok(Ok(object)) => {
+                tracing::debug!(?request, "fetched from peers");
+                Some(object)
+            },
+            Ok(Err(err)) => {
+                tracing::warn!(?request, "failed to fetch from peers: {err:#}");
+                None
+            },
+            Err(_) => {
+                tracing::warn!(?request, "timed out while fetching from peers");
+                None
+            },
+        }
+    }
+}
+
+/// Check that a payload matches the VID commitment it was requested by.
+///
+/// The VID parameters are taken from `common`, which is not itself trusted: parameters which do
+/// not match those used to compute `commit` lead to a different commitment.
+fn verify_payload(
+    commit: VidCommitment,
+    payload: &Payload,
+    common: &VidCommon,
+) -> anyhow::Result<()> {
+    let bytes = payload.encode();
+    let actual = match common {
+        VidCommon::V0(common) => {
+            let num_storage_nodes = ADVZScheme::get_num_storage_nodes(common) as usize;
+            advz_scheme(num_storage_nodes)
+                .commit_only(&bytes)
+                .map(VidCommitment::V0)
+                .map_err(|err| anyhow!("failed to compute VID commitment (V0): {err}"))?
+        },
+        VidCommon::V1(common) => {
+            let param = init_avidm_param(common.total_weights)
+                .with_context(|| "failed to initialize avidm param")?;
+            let ns_table = parse_ns_table(bytes.len(), &payload.ns_table().encode());
+            AvidMScheme::commit(&param, &bytes, ns_table)
+                .map(VidCommitment::V1)
+                .map_err(|err| anyhow!("failed to compute VID commitment (V1): {err}"))?
+        },
+    };
+    ensure!(
+        actual == commit,
+        "payload commitment mismatch: expected {commit}, got {actual}"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use espresso_types::{NodeState, Transaction, ValidatedState};
+    use sequencer_utils::test_utils::setup_test;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verify_payload() {
+        setup_test();
+
+        let instance_state = NodeState::mock();
+        let validated_state = ValidatedState::default();
+        let (payload, ns_table) = Payload::from_transactions(
+            [Transaction::of_size(10), Transaction::of_size(20)],
+            &validated_state,
+            &instance_state,
+        )
+        .await
+        .unwrap();
+
+        let bytes = payload.encode();
+        let param = init_avidm_param(10).unwrap();
+        let commit = AvidMScheme::commit(
+            &param,
+            &bytes,
+            parse_ns_table(bytes.len(), &ns_table.encode()),
+        )
+        .map(VidCommitment::V1)
+        .unwrap();
+        let common = VidCommon::V1(param);
+
+        // The payload matches its own commitment.
+        verify_payload(commit, &payload, &common).unwrap();
+
+        // A different payload does not.
+        let (other, _) = Payload::from_transactions(
+            [Transaction::of_size(10)],
+            &validated_state,
+            &instance_state,
+        )
+        .await
+        .unwrap();
+        verify_payload(commit, &other, &common).unwrap_err();
+
+        // Neither does the same payload with different VID parameters.
+        let common = VidCommon::V1(init_avidm_param(20).unwrap());
+        verify_payload(commit, &payload, &common).unwrap_err();
+    }
+}
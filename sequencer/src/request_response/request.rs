@@ -4,9 +4,10 @@ use committable::Commitment;
 use espresso_types::{
     v0_1::{RewardAccount, RewardMerkleTree},
     v0_3::ChainConfig,
-    FeeAccount, FeeMerkleTree, Leaf2,
+    FeeAccount, FeeMerkleTree, Leaf2, Payload, SeqTypes,
 };
-use hotshot_types::data::VidShare;
+use hotshot_query_service::{availability::LeafQueryData, VidCommon};
+use hotshot_types::data::{VidCommitment, VidShare};
 use request_response::{request::Request as RequestTrait, Serializable};
 use serde::{Deserialize, Serialize};
 
@@ -33,6 +34,12 @@ pub enum Request {
     RewardAccounts(Height, ViewNumber, Vec<RewardAccount>),
     /// A request for the VID share at the given block height
     VidShare(Height, RequestId),
+    /// A request for the block payload with a particular VID commitment
+    Payload(VidCommitment),
+    /// A request for the VID common data for the block with a particular VID commitment
+    VidCommon(VidCommitment),
+    /// A request for the decided leaf at the given block height, with the QC which decided it
+    DecidedLeaf(Height),
 }
 
 /// The outermost response type. This an enum that contains all the possible responses that the
@@ -51,6 +58,12 @@ pub enum Response {
     RewardAccounts(RewardMerkleTree),
     /// A response for a VID share at the given block height
     VidShare(VidShare),
+    /// A response for a block payload, with the VID common data needed to verify it
+    Payload(Payload, VidCommon),
+    /// A response for the VID common data of a block
+    VidCommon(VidCommon),
+    /// A response for a decided leaf at the given block height
+    DecidedLeaf(LeafQueryData<SeqTypes>),
 }
 
 /// Implement the `RequestTrait` trait for the `Request` type. This tells the request response
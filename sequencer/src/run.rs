@@ -248,6 +248,9 @@ where
             if let Some(config) = modules.config {
                 http_opt = http_opt.config(config);
             }
+            if let Some(quotas) = modules.quotas {
+                http_opt = http_opt.quotas(quotas);
+            }
 
             http_opt
                 .serve(move |metrics, consumer, storage| {
@@ -0,0 +1,72 @@
+use alloy::primitives::{Address, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::{v0_1::RewardAmount, FeeAmount};
+
+/// The state of a fee and reward account, as shown on its explorer page.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AccountDetail {
+    pub address: Address,
+    /// Height of the block as of which the balances and deposits are reported.
+    pub height: u64,
+    /// Balance in the fee state.
+    pub fee_balance: FeeAmount,
+    /// Balance in the reward state.
+    pub reward_balance: RewardAmount,
+    /// Deposits into the fee contract for this account, in L1 order, from L1 block
+    /// `deposits_from_l1_block` up to the L1 block finalized as of `height`.
+    ///
+    /// This is `None` if the deposits could not be loaded from L1, for example because the L1
+    /// provider is unavailable or too many deposit searches are already running. The rest of the
+    /// account details are still reported.
+    pub deposits: Option<Vec<AccountDeposit>>,
+    /// The first L1 block searched for deposits.
+    ///
+    /// Only recent L1 history is searched, so deposits made before this block are not reported.
+    pub deposits_from_l1_block: u64,
+    /// The most recent blocks in which this account paid the builder fee, newest first.
+    pub fee_payer_blocks: Vec<u64>,
+}
+
+/// A deposit into the L1 fee contract.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AccountDeposit {
+    pub amount: FeeAmount,
+    /// Number of the L1 block containing the deposit.
+    pub l1_block: u64,
+    /// Hash of the L1 block containing the deposit.
+    pub l1_block_hash: B256,
+    /// Hash of the L1 transaction which made the deposit.
+    pub l1_tx_hash: B256,
+}
+
+/// The response from the `explorer/account/:address` endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AccountDetailResponse {
+    pub account_detail: AccountDetail,
+}
+
+/// Why an account balance changed.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceChangeCause {
+    /// Funds were deposited into the fee contract on L1.
+    Deposit,
+    /// The account paid a builder fee, or received one as the chain's fee recipient.
+    FeePayment,
+    /// Rewards were distributed to the account.
+    RewardAccrual,
+    /// The cause could not be determined, because the block only commits to its chain config, so
+    /// we cannot tell whether the account was the fee recipient.
+    Unknown,
+}
+
+/// A change to an account balance in the fee or reward state.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BalanceChange<Amount> {
+    /// The height of the block in which the balance changed.
+    pub height: u64,
+    pub old: Amount,
+    pub new: Amount,
+    pub cause: BalanceChangeCause,
+}
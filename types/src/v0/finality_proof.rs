@@ -0,0 +1,279 @@
+use anyhow::{ensure, Context};
+use ark_serialize::CanonicalSerialize;
+use committable::{Commitment, Committable};
+use hotshot_query_service::VidCommon;
+use hotshot_types::light_client::{hash_bytes_to_field, CircuitField, LightClientState};
+use jf_merkle_tree::{
+    prelude::{MerkleProof, Sha3Node},
+    MerkleCommitment, MerkleTreeScheme,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{BlockMerkleTree, Header, NamespaceId, NsProof, Transaction};
+
+/// Proof that a namespace of a block was sequenced, checkable against the light client on L1.
+///
+/// The bundle is anchored at a light client state. The light client only stores a hash of the
+/// block Merkle tree root, so the bundle includes the header at the light client's block height,
+/// whose block Merkle tree root is that root. From there, a block Merkle proof shows that `header`
+/// was finalized, and a namespace proof shows which transactions `header` contains in the
+/// namespace.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NamespaceFinalityProof {
+    /// The light client state the proof is anchored at, if it was read from L1 by the server.
+    ///
+    /// If the caller chose the anchor by height, this is [`None`], and the caller is expected to
+    /// already have the corresponding light client state.
+    pub light_client_state: Option<LightClientState>,
+    /// The header at the block height of the light client state.
+    pub anchor: Header,
+    /// Proof of `header` in the block Merkle tree of `anchor`.
+    pub block_merkle_proof: MerkleProof<Commitment<Header>, u64, Sha3Node, 3>,
+    /// The header being proven.
+    pub header: Header,
+    /// Proof of the namespace in the payload of `header`, or [`None`] if the block does not contain
+    /// the namespace.
+    pub namespace_proof: Option<NsProof>,
+    /// VID common data for the payload of `header`, needed to check `namespace_proof`.
+    pub vid_common: VidCommon,
+}
+
+impl NamespaceFinalityProof {
+    /// Verify the bundle against the block commitment root of a finalized light client state.
+    ///
+    /// On success, returns the transactions of the proven block in `namespace`, which are empty if
+    /// the block does not contain the namespace.
+    pub fn verify(
+        &self,
+        block_comm_root: CircuitField,
+        namespace: NamespaceId,
+    ) -> anyhow::Result<Vec<Transaction>> {
+        // The anchor must be the header the light client committed to.
+        let root = self.anchor.block_merkle_tree_root();
+        let mut root_bytes = vec![];
+        root.serialize_compressed(&mut root_bytes)?;
+        ensure!(
+            hash_bytes_to_field::<CircuitField>(&root_bytes)? == block_comm_root,
+            "block Merkle tree root of anchor header {} does not match light client state",
+            self.anchor.height()
+        );
+        if let Some(state) = &self.light_client_state {
+            ensure!(
+                state.block_comm_root == block_comm_root
+                    && state.block_height == self.anchor.height(),
+                "bundled light client state does not match anchor"
+            );
+        }
+
+        // The header must be in the block Merkle tree of the anchor.
+        let height = self.header.height();
+        ensure!(
+            height < self.anchor.height(),
+            "block {height} is not finalized as of anchor {}",
+            self.anchor.height()
+        );
+        ensure!(
+            *self.block_merkle_proof.index() == height,
+            "block Merkle proof is for block {}, not {height}",
+            self.block_merkle_proof.index()
+        );
+        ensure!(
+            BlockMerkleTree::verify(root.digest(), height, &self.block_merkle_proof)?.is_ok(),
+            "invalid block Merkle proof for header {height}"
+        );
+        let proven = self
+            .block_merkle_proof
+            .elem()
+            .context("block Merkle proof is missing header commitment")?;
+        ensure!(
+            *proven == self.header.commit(),
+            "header commitment mismatch: proven {proven} != header {}",
+            self.header.commit()
+        );
+
+        // The namespace proof must be valid for the header.
+        let Some(proof) = &self.namespace_proof else {
+            ensure!(
+                self.header.ns_table().find_ns_id(&namespace).is_none(),
+                "missing proof for namespace {namespace} in block {height}"
+            );
+            return Ok(vec![]);
+        };
+        let (transactions, ns) = proof
+            .verify(
+                self.header.ns_table(),
+                &self.header.payload_commitment(),
+                &self.vid_common,
+            )
+            .with_context(|| {
+                format!("invalid proof for namespace {namespace} in block {height}")
+            })?;
+        ensure!(
+            ns == namespace,
+            "requested namespace {namespace} but got proof for namespace {ns}"
+        );
+        Ok(transactions)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use hotshot_query_service::testing::mocks::MockVersions;
+    use hotshot_types::{
+        data::{Leaf, VidCommitment},
+        traits::{BlockPayload, EncodeBytes},
+        vid::avidm::{init_avidm_param, AvidMScheme},
+    };
+    use jf_merkle_tree::AppendableMerkleTreeScheme;
+    use sequencer_utils::test_utils::setup_test;
+    use vbs::version::Version;
+
+    use super::*;
+    use crate::{NodeState, Payload, ValidatedState, BLOCK_MERKLE_TREE_HEIGHT};
+
+    /// Build a proof of namespace 1 in block 1, anchored at block 2, along with the block
+    /// commitment root the light client would store for block 2.
+    async fn finality_proof() -> (NamespaceFinalityProof, CircuitField) {
+        let instance = NodeState::mock();
+        let validated = ValidatedState::genesis(&instance).0;
+        let genesis = Leaf::genesis::<MockVersions>(&validated, &instance)
+            .await
+            .block_header()
+            .clone();
+
+        let txs = vec![
+            Transaction::new(1u32.into(), vec![1, 2, 3]),
+            Transaction::new(2u32.into(), vec![4, 5]),
+            Transaction::new(1u32.into(), vec![6]),
+        ];
+        let payload = Payload::from_transactions(txs, &validated, &instance)
+            .await
+            .unwrap()
+            .0;
+        let param = init_avidm_param(10).unwrap();
+        let payload_byte_len = payload.byte_len();
+        let ns_ranges = payload
+            .ns_table()
+            .iter()
+            .map(|index| payload.ns_table().ns_range(&index, &payload_byte_len).0)
+            .collect::<Vec<_>>();
+        let payload_commitment = AvidMScheme::commit(&param, &payload.encode(), ns_ranges).unwrap();
+        let vid_common = VidCommon::V1(param);
+
+        let header = Header::create(
+            instance.chain_config,
+            1,
+            genesis.timestamp_internal(),
+            genesis.timestamp_millis_internal(),
+            genesis.l1_head(),
+            genesis.l1_finalized(),
+            VidCommitment::V1(payload_commitment),
+            genesis.builder_commitment().clone(),
+            payload.ns_table().clone(),
+            genesis.fee_merkle_tree_root(),
+            genesis.block_merkle_tree_root(),
+            genesis.reward_merkle_tree_root(),
+            genesis.fee_info(),
+            vec![],
+            Version { major: 0, minor: 3 },
+        );
+
+        let mut tree = BlockMerkleTree::new(BLOCK_MERKLE_TREE_HEIGHT);
+        tree.push(genesis.commit()).unwrap();
+        tree.push(header.commit()).unwrap();
+        let (_, block_merkle_proof) = tree.lookup(1).expect_ok().unwrap();
+
+        let mut anchor = header.clone();
+        *anchor.height_mut() = 2;
+        *anchor.block_merkle_tree_root_mut() = tree.commitment();
+        let mut root_bytes = vec![];
+        tree.commitment()
+            .serialize_compressed(&mut root_bytes)
+            .unwrap();
+        let block_comm_root = hash_bytes_to_field::<CircuitField>(&root_bytes).unwrap();
+
+        let ns_index = payload.ns_table().find_ns_id(&1u32.into()).unwrap();
+        let namespace_proof = NsProof::new(&payload, &ns_index, &vid_common);
+        assert!(namespace_proof.is_some());
+
+        let proof = NamespaceFinalityProof {
+            light_client_state: None,
+            anchor,
+            block_merkle_proof,
+            header,
+            namespace_proof,
+            vid_common,
+        };
+        (proof, block_comm_root)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_namespace_finality_proof() {
+        setup_test();
+
+        let (proof, root) = finality_proof().await;
+        let txs = proof.verify(root, 1u32.into()).unwrap();
+        assert_eq!(
+            txs,
+            vec![
+                Transaction::new(1u32.into(), vec![1, 2, 3]),
+                Transaction::new(1u32.into(), vec![6]),
+            ]
+        );
+
+        // A namespace which is not in the block is proven empty without a namespace proof.
+        let mut absent = proof.clone();
+        absent.namespace_proof = None;
+        assert_eq!(absent.verify(root, 3u32.into()).unwrap(), vec![]);
+
+        // But a namespace which is in the block cannot be proven empty.
+        absent.verify(root, 1u32.into()).unwrap_err();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_namespace_finality_proof_tampered_root() {
+        setup_test();
+
+        let (proof, root) = finality_proof().await;
+        proof
+            .verify(root + CircuitField::from(1u64), 1u32.into())
+            .unwrap_err();
+
+        // A block Merkle tree root in the anchor which the light client did not commit to is
+        // rejected, even if the block Merkle proof is consistent with it.
+        let mut tampered = proof.clone();
+        *tampered.anchor.block_merkle_tree_root_mut() = proof.header.block_merkle_tree_root();
+        tampered.verify(root, 1u32.into()).unwrap_err();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_namespace_finality_proof_tampered_index() {
+        setup_test();
+
+        let (proof, root) = finality_proof().await;
+
+        // A proof for a different block than the header.
+        let mut tampered = proof.clone();
+        tampered.block_merkle_proof.pos = 0;
+        tampered.verify(root, 1u32.into()).unwrap_err();
+
+        // A header claiming a different height than the block Merkle proof.
+        let mut tampered = proof.clone();
+        *tampered.header.height_mut() = 0;
+        tampered.verify(root, 1u32.into()).unwrap_err();
+
+        // A header which is not finalized as of the anchor.
+        let mut tampered = proof.clone();
+        *tampered.anchor.height_mut() = 1;
+        tampered.verify(root, 1u32.into()).unwrap_err();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_namespace_finality_proof_wrong_namespace() {
+        setup_test();
+
+        let (proof, root) = finality_proof().await;
+        proof.verify(root, 2u32.into()).unwrap_err();
+        proof.verify(root, 3u32.into()).unwrap_err();
+    }
+}
@@ -1141,6 +1141,9 @@ impl ExplorerHeader<SeqTypes> for Header {
             .map(|i| self.ns_table().read_ns_id_unchecked(&i))
             .collect()
     }
+    fn parse_wallet_address(query: &str) -> Option<Self::WalletAddress> {
+        Some(vec![query.parse().ok()?])
+    }
 }
 
 #[cfg(test)]
@@ -41,10 +41,13 @@ use tracing::Instrument;
 use url::Url;
 
 use super::{
-    v0_1::{L1BlockInfoWithParent, SingleTransport, SingleTransportStatus, SwitchingTransport},
+    v0_1::{
+        AccountDeposits, L1BlockInfoWithParent, SingleTransport, SingleTransportStatus,
+        SwitchingTransport,
+    },
     L1BlockInfo, L1ClientMetrics, L1State, L1UpdateTask,
 };
-use crate::{FeeInfo, L1Client, L1ClientOptions, L1Event, L1Snapshot};
+use crate::{AccountDeposit, FeeInfo, L1Client, L1ClientOptions, L1Event, L1Snapshot};
 
 impl PartialOrd for L1BlockInfo {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
@@ -434,7 +437,10 @@ impl L1Client {
         Self {
             provider,
             transport,
-            state: Arc::new(Mutex::new(L1State::new(opt.l1_blocks_cache_size))),
+            state: Arc::new(Mutex::new(L1State::new(
+                opt.l1_blocks_cache_size,
+                opt.l1_deposits_cache_size,
+            ))),
             sender,
             receiver: receiver.deactivate(),
             update_task: Default::default(),
@@ -1000,6 +1006,96 @@ impl L1Client {
             .await
     }
 
+    /// Get the deposits into `account` made in the L1 blocks `from..=to`, in L1 order.
+    ///
+    /// Unlike [`get_finalized_deposits`](Self::get_finalized_deposits), this asks the L1 provider to
+    /// filter events by account, and fails instead of retrying indefinitely, so that it can be
+    /// used to serve user queries. Results are cached, so repeated queries for the same account
+    /// only fetch events from blocks which have not been scanned before. For this reason, `to`
+    /// must be finalized.
+    pub async fn get_account_deposits(
+        &self,
+        fee_contract_address: Address,
+        account: Address,
+        from: u64,
+        to: u64,
+    ) -> anyhow::Result<Vec<AccountDeposit>> {
+        let key = (fee_contract_address, account);
+        let cached = self.state.lock().await.deposits.get(&key).cloned();
+
+        // If the cache covers the start of the requested range, we only need to scan the blocks
+        // after the end of the cache. Otherwise, start over.
+        let (mut entry, start) = match cached {
+            Some(cached) if cached.from <= from && from <= cached.to + 1 => {
+                let start = cached.to + 1;
+                (cached, start)
+            },
+            _ => (
+                AccountDeposits {
+                    from,
+                    to,
+                    deposits: vec![],
+                },
+                from,
+            ),
+        };
+        if start <= to {
+            let deposits = self
+                .fetch_account_deposits(fee_contract_address, account, start, to)
+                .await?;
+            entry.deposits.extend(deposits);
+            entry.to = to;
+            self.state.lock().await.deposits.put(key, entry.clone());
+        }
+
+        Ok(entry
+            .deposits
+            .into_iter()
+            .filter(|deposit| (from..=to).contains(&deposit.l1_block))
+            .collect())
+    }
+
+    async fn fetch_account_deposits(
+        &self,
+        fee_contract_address: Address,
+        account: Address,
+        from: u64,
+        to: u64,
+    ) -> anyhow::Result<Vec<AccountDeposit>> {
+        let fee_contract = FeeContract::new(fee_contract_address, self.provider.clone());
+        let chunk_size = self.options().l1_events_max_block_range;
+
+        let mut deposits = vec![];
+        let mut start = from;
+        while start <= to {
+            let end = min(start + chunk_size - 1, to);
+            tracing::debug!(%account, start, end, "fetch account deposits in range");
+            let events = fee_contract
+                .Deposit_filter()
+                .address(*fee_contract.address())
+                .topic1(account.into_word())
+                .from_block(start)
+                .to_block(end)
+                .query()
+                .await
+                .with_context(|| format!("fetching deposits in L1 blocks {start}..={end}"))?;
+            for (deposit, log) in events {
+                deposits.push(AccountDeposit {
+                    amount: deposit.amount.into(),
+                    l1_block: log
+                        .block_number
+                        .context("deposit event missing block number")?,
+                    l1_block_hash: log.block_hash.context("deposit event missing block hash")?,
+                    l1_tx_hash: log
+                        .transaction_hash
+                        .context("deposit event missing transaction hash")?,
+                });
+            }
+            start = end + 1;
+        }
+        Ok(deposits)
+    }
+
     /// Check if the given address is a proxy contract.
     pub async fn is_proxy_contract(&self, proxy_address: Address) -> anyhow::Result<bool> {
         // confirm that the proxy_address is a proxy
@@ -1057,11 +1153,12 @@ impl L1Client {
 }
 
 impl L1State {
-    fn new(cache_size: NonZeroUsize) -> Self {
+    fn new(cache_size: NonZeroUsize, deposits_cache_size: NonZeroUsize) -> Self {
         Self {
             snapshot: Default::default(),
             finalized: LruCache::new(cache_size),
             last_finalized: None,
+            deposits: LruCache::new(deposits_cache_size),
         }
     }
 
@@ -1247,6 +1344,87 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_account_deposits() -> anyhow::Result<()> {
+        setup_test();
+
+        let anvil = Anvil::new().spawn();
+        let wallet = anvil.wallet().unwrap();
+        let deployer = wallet.default_signer().address();
+        let inner_provider = ProviderBuilder::new()
+            .wallet(wallet)
+            .on_http(anvil.endpoint_url());
+        let provider = AnvilProvider::new(inner_provider, Arc::new(anvil));
+        let mut contracts = Contracts::new();
+        let l1_client = new_l1_client(provider.anvil(), false).await;
+
+        let fee_proxy_addr = deploy_fee_contract_proxy(&provider, &mut contracts, deployer).await?;
+        let fee_proxy = FeeContract::new(fee_proxy_addr, &provider);
+        let start = provider.get_block_number().await?;
+
+        // Alternate deposits between two accounts.
+        let other = Address::random();
+        let mut receipts = vec![];
+        for account in [deployer, other, deployer, other] {
+            let receipt = fee_proxy
+                .deposit(account)
+                .value(parse_ether("0.1")?)
+                .send()
+                .await?
+                .get_receipt()
+                .await?;
+            assert!(receipt.inner.is_success());
+            receipts.push((account, receipt));
+        }
+        let end = provider.get_block_number().await?;
+
+        // Only deposits into the requested account are returned, with their L1 location.
+        let deposits = l1_client
+            .get_account_deposits(fee_proxy_addr, deployer, start, end)
+            .await?;
+        let expected = receipts
+            .iter()
+            .filter(|(account, _)| *account == deployer)
+            .map(|(_, receipt)| {
+                (
+                    receipt.block_number.unwrap(),
+                    receipt.block_hash.unwrap(),
+                    receipt.transaction_hash,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            deposits
+                .iter()
+                .map(|deposit| (deposit.l1_block, deposit.l1_block_hash, deposit.l1_tx_hash))
+                .collect::<Vec<_>>(),
+            expected
+        );
+        let amount = parse_ether("0.1")?;
+        assert!(deposits.iter().all(|deposit| deposit.amount.0 == amount));
+
+        // Querying a prefix of the range, then the whole range, gives the same result as querying
+        // the whole range at once, even though the second query only fetches new blocks.
+        let mid = receipts[1].1.block_number.unwrap();
+        let prefix = l1_client
+            .get_account_deposits(fee_proxy_addr, other, start, mid)
+            .await?;
+        assert_eq!(prefix.len(), 1);
+        let all = l1_client
+            .get_account_deposits(fee_proxy_addr, other, start, end)
+            .await?;
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0], prefix[0]);
+
+        // A range before all the deposits is empty.
+        assert!(l1_client
+            .get_account_deposits(fee_proxy_addr, deployer, 0, start)
+            .await?
+            .is_empty());
+
+        Ok(())
+    }
+
     async fn test_wait_for_finalized_block_helper(ws: bool) {
         setup_test();
 
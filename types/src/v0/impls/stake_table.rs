@@ -43,6 +43,7 @@ use hotshot_types::{
 };
 use humantime::format_duration;
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::{spawn, time::sleep};
 use tracing::Instrument;
@@ -62,6 +63,14 @@ use crate::{
 
 type Epoch = <SeqTypes as NodeType>::Epoch;
 pub type ValidatorMap = IndexMap<Address, Validator<BLSPubKey>>;
+
+/// A stake table together with the epoch it applies to.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "T: NodeType")]
+pub struct StakeTableWithEpochNumber<T: NodeType> {
+    pub epoch: Option<EpochNumber>,
+    pub stake_table: Vec<PeerConfig<T>>,
+}
 /// The result of applying a stake table event:
 /// - `Ok(Ok(()))`: success
 /// - `Ok(Err(...))`: expected error
@@ -10,12 +10,19 @@ use hotshot_types::{
 };
 use serde::{Deserialize, Serialize};
 
+mod account;
 pub mod config;
+mod finality_proof;
 mod header;
 mod impls;
 mod nsproof;
 pub mod traits;
+mod tx_status;
+mod txproof;
 mod utils;
+mod validator_info;
+pub use account::*;
+pub use finality_proof::*;
 pub use header::Header;
 #[cfg(any(test, feature = "testing"))]
 pub use impls::mock;
@@ -24,7 +31,10 @@ pub use impls::{
     EpochCommittees, FeeError, ProposalValidationError, StateValidationError,
 };
 pub use nsproof::*;
+pub use tx_status::*;
+pub use txproof::*;
 pub use utils::*;
+pub use validator_info::*;
 use vbs::version::{StaticVersion, StaticVersionType};
 
 // This is the single source of truth for minor versions supported by this major version.
@@ -190,7 +200,10 @@ pub type PrivKey = <PubKey as SignatureKey>::PrivateKey;
 
 pub type NetworkConfig = hotshot_types::network::NetworkConfig<SeqTypes>;
 
-pub use self::impls::{NodeState, RewardDistributor, UpgradeMap, ValidatedState, ValidatorMap};
+pub use self::impls::{
+    NodeState, RewardDistributor, StakeTableWithEpochNumber, UpgradeMap, ValidatedState,
+    ValidatorMap,
+};
 pub use crate::v0_1::{
     BLOCK_MERKLE_TREE_HEIGHT, FEE_MERKLE_TREE_HEIGHT, NS_ID_BYTE_LEN, NS_OFFSET_BYTE_LEN,
     NUM_NSS_BYTE_LEN, NUM_TXS_BYTE_LEN, TX_OFFSET_BYTE_LEN,
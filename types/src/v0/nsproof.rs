@@ -20,6 +20,19 @@ pub struct ADVZNamespaceProofQueryData {
     pub transactions: Vec<Transaction>,
 }
 
+/// One block's worth of a namespace stream.
+///
+/// Contains the transactions in the namespace, along with the proof and VID common data needed to
+/// verify them against the block header. If the block does not contain the namespace,
+/// `transactions` is empty and `proof` and `common` are `None`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NamespaceStreamEntry {
+    pub height: u64,
+    pub proof: Option<NsProof>,
+    pub common: Option<VidCommon>,
+    pub transactions: Vec<Transaction>,
+}
+
 /// Each variant represents a specific version of a namespace proof.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NsProof {
@@ -0,0 +1,61 @@
+use committable::Commitment;
+use serde::{Deserialize, Serialize};
+
+use crate::v0::{NamespaceId, Transaction};
+
+/// The location of a transaction within a block.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct TransactionPosition {
+    /// Height of the block containing the transaction.
+    pub height: u64,
+    /// Namespace of the transaction.
+    pub namespace: NamespaceId,
+    /// Index of the transaction's namespace in the block's namespace table.
+    pub ns_index: u32,
+    /// Index of the transaction within its namespace.
+    pub offset: u32,
+}
+
+/// The status of a transaction submitted through the sequencer API.
+///
+/// A transaction starts out [`Pending`](Self::Pending). It becomes
+/// [`Included`](Self::Included) once it has been seen in a proposed block, and
+/// [`Decided`](Self::Decided) once that block (or a later one containing the transaction) has been
+/// finalized. A transaction which is not decided within a server-configured timeout is reported as
+/// [`Dropped`](Self::Dropped). This is not final: a dropped transaction which is later finalized is
+/// still reported as `Decided`, until the server stops tracking it.
+///
+/// Not every node sees every proposal, so a transaction may go straight from `Pending` to
+/// `Decided`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum TransactionStatus {
+    Pending,
+    Included(TransactionPosition),
+    Decided(TransactionPosition),
+    Dropped,
+}
+
+impl TransactionStatus {
+    /// Whether this status will never change again.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Decided(_))
+    }
+
+    /// The position of the transaction, if it has been included in a block.
+    pub fn position(&self) -> Option<TransactionPosition> {
+        match self {
+            Self::Included(pos) | Self::Decided(pos) => Some(*pos),
+            Self::Pending | Self::Dropped => None,
+        }
+    }
+}
+
+/// The result of submitting one transaction in a batch.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SubmitResult {
+    /// The transaction passed validation and was submitted. Its status starts out
+    /// [`Pending`](TransactionStatus::Pending).
+    Submitted(Commitment<Transaction>),
+    /// The transaction was not submitted, for the given reason.
+    Rejected(String),
+}
@@ -0,0 +1,60 @@
+use hotshot_query_service::VidCommon;
+use hotshot_types::data::VidCommitment;
+use serde::{Deserialize, Serialize};
+
+use crate::v0::{Index, NsTable, Payload, Transaction, TxProof};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TransactionProofQueryData {
+    pub proof: TxInclusionProof,
+    pub transaction: Transaction,
+}
+
+/// Each variant represents a specific version of a transaction proof.
+///
+/// There is no variant for AVID-M, which cannot prove a transaction without its whole namespace.
+/// Use a namespace proof for blocks dispersed with AVID-M.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxInclusionProof {
+    /// V0 proof for ADVZ
+    V0(TxProof),
+}
+
+impl TxInclusionProof {
+    pub fn new(
+        index: &Index,
+        payload: &Payload,
+        common: &VidCommon,
+    ) -> Option<(Transaction, TxInclusionProof)> {
+        match common {
+            VidCommon::V0(common) => {
+                let (tx, proof) = TxProof::new(index, payload, common)?;
+                Some((tx, TxInclusionProof::V0(proof)))
+            },
+            VidCommon::V1(_) => {
+                tracing::info!("transaction proofs are not supported for AvidM payloads");
+                None
+            },
+        }
+    }
+
+    pub fn verify(
+        &self,
+        ns_table: &NsTable,
+        tx: &Transaction,
+        commit: &VidCommitment,
+        common: &VidCommon,
+    ) -> Option<bool> {
+        match (self, commit, common) {
+            (Self::V0(proof), VidCommitment::V0(commit), VidCommon::V0(common)) => {
+                proof.verify(ns_table, tx, commit, common)
+            },
+            _ => {
+                tracing::error!(
+                    "Incompatible version of VidCommitment, VidCommon and TxInclusionProof."
+                );
+                None
+            },
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use alloy::{
     network::Ethereum,
-    primitives::{B256, U256},
+    primitives::{Address, B256, U256},
     providers::{
         fillers::{FillProvider, JoinFill, RecommendedFillers},
         Identity, RootProvider,
@@ -26,7 +26,7 @@ use tokio::{
 };
 use url::Url;
 
-use crate::v0::utils::parse_duration;
+use crate::{v0::utils::parse_duration, AccountDeposit};
 
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Hash, PartialEq, Eq)]
 pub struct L1BlockInfo {
@@ -93,6 +93,14 @@ pub struct L1ClientOptions {
     )]
     pub l1_blocks_cache_size: NonZeroUsize,
 
+    /// Maximum number of accounts whose fee contract deposits are kept in cache at once.
+    #[clap(
+        long,
+        env = "ESPRESSO_SEQUENCER_L1_DEPOSITS_CACHE_SIZE",
+        default_value = "1000"
+    )]
+    pub l1_deposits_cache_size: NonZeroUsize,
+
     /// Number of L1 events to buffer before discarding.
     #[clap(
         long,
@@ -244,6 +252,16 @@ pub(crate) struct L1State {
     pub(crate) snapshot: L1Snapshot,
     pub(crate) finalized: LruCache<u64, L1BlockInfoWithParent>,
     pub(crate) last_finalized: Option<u64>,
+    /// Deposits already fetched for each (fee contract, account) pair.
+    pub(crate) deposits: LruCache<(Address, Address), AccountDeposits>,
+}
+
+/// Deposits into a single account, found by scanning the L1 blocks `from..=to`.
+#[derive(Clone, Debug)]
+pub(crate) struct AccountDeposits {
+    pub(crate) from: u64,
+    pub(crate) to: u64,
+    pub(crate) deposits: Vec<AccountDeposit>,
 }
 
 #[derive(Clone, Debug)]
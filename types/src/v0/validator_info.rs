@@ -0,0 +1,89 @@
+use alloy::primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::{v0_3::Validator, PubKey};
+
+/// A validator as listed in the explorer's validator directory.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValidatorSummary {
+    /// The Ethereum account which registered the validator.
+    pub account: Address,
+    pub stake_table_key: PubKey,
+    /// Total stake delegated to the validator, including self-delegation.
+    pub stake: U256,
+    /// Commission in basis points.
+    pub commission: u16,
+    pub num_delegators: usize,
+}
+
+impl From<&Validator<PubKey>> for ValidatorSummary {
+    fn from(validator: &Validator<PubKey>) -> Self {
+        Self {
+            account: validator.account,
+            stake_table_key: validator.stake_table_key,
+            stake: validator.stake,
+            commission: validator.commission,
+            num_delegators: validator.delegators.len(),
+        }
+    }
+}
+
+/// A single delegation to a validator.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DelegatorStake {
+    pub address: Address,
+    pub stake: U256,
+}
+
+/// What a validator did during (the decided part of) an epoch.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValidatorEpochActivity {
+    pub epoch: u64,
+    /// Heights of the decided blocks in this epoch which were proposed by the validator.
+    pub blocks_proposed: Vec<u64>,
+    /// The number of quorum certificates formed in this epoch which include a vote from the
+    /// validator.
+    pub votes: u64,
+    /// The number of quorum certificates formed in this epoch in which the validator could have
+    /// voted.
+    pub eligible_votes: u64,
+    /// The number of decided blocks in this epoch which are not yet available to this node.
+    ///
+    /// If this is not 0, the other counts only cover the blocks which are available.
+    pub missing_blocks: u64,
+}
+
+impl ValidatorEpochActivity {
+    /// The fraction of quorum certificates in this epoch which the validator signed.
+    ///
+    /// This is 0 if no certificates have been formed yet.
+    pub fn participation_rate(&self) -> f64 {
+        if self.eligible_votes == 0 {
+            return 0.;
+        }
+        self.votes as f64 / self.eligible_votes as f64
+    }
+}
+
+/// The details of a single validator, as shown on its explorer page.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValidatorDetail {
+    pub summary: ValidatorSummary,
+    /// Delegations to the validator, largest first.
+    pub delegators: Vec<DelegatorStake>,
+    pub activity: ValidatorEpochActivity,
+}
+
+/// The response from the `explorer/validators` endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValidatorSummariesResponse {
+    pub epoch: u64,
+    /// Validators in the epoch's stake table, by descending stake.
+    pub validators: Vec<ValidatorSummary>,
+}
+
+/// The response from the `explorer/validator/:bls_key` endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ValidatorDetailResponse {
+    pub validator_detail: ValidatorDetail,
+}